@@ -15,6 +15,7 @@
 //
 
 use imbl::{OrdMap, OrdSet, Vector};
+use rust_decimal::prelude::ToPrimitive;
 use js_sys::{Array, BigInt, Map, Set, Uint8Array};
 use wasm_bindgen::{JsCast, JsValue};
 use crate::{model::{Func, Graph, SId}, runtime::{Num, Type, Val, ValRef}};
@@ -62,6 +63,14 @@ pub fn to_graph_value(js: JsValue, doc: &Graph) -> Val {
     #[allow(irrefutable_let_patterns)]
     if js.is_bigint() {
         let bigint = BigInt::from(js);
+        if let Ok(digits) = bigint.to_string(10) {
+            if let Ok(big) = String::from(digits).parse::<num_bigint::BigInt>() {
+                if let Some(val) = big.to_i64() {
+                    return Val::Num(Num::Int(val));
+                }
+                return Val::Num(Num::from(big));
+            }
+        }
         if let Some(val) = bigint.as_f64() {
             return Val::Num(Num::Float(val));
         }
@@ -132,6 +141,14 @@ pub fn to_raw_value(js: JsValue) -> Val {
     #[allow(irrefutable_let_patterns)]
     if js.is_bigint() {
         let bigint = BigInt::from(js);
+        if let Ok(digits) = bigint.to_string(10) {
+            if let Ok(big) = String::from(digits).parse::<num_bigint::BigInt>() {
+                if let Some(val) = big.to_i64() {
+                    return Val::Num(Num::Int(val));
+                }
+                return Val::Num(Num::from(big));
+            }
+        }
         if let Some(val) = bigint.as_f64() {
             return Val::Num(Num::Float(val));
         }
@@ -191,11 +208,19 @@ impl From<Val> for JsValue {
             Val::Str(val) => Self::from_str(&val),
             Val::Prompt(prompt) => Self::from_str(&prompt.to_string()),
             Val::Ver(..) => Self::from_str(&value.to_string()),
+            Val::Time(..) => Self::from_str(&value.to_string()),
             Val::Num(num) => {
                 match num {
                     Num::Int(val) => Self::from(val as i32),
                     Num::Float(val) => Self::from(val),
                     Num::Units(val, _) => Self::from(val),
+                    Num::Decimal(val) => Self::from(val.to_f64().unwrap_or_default()),
+                    Num::BigInt(val) => {
+                        match BigInt::new(&Self::from_str(&val.to_string())) {
+                            Ok(big) => Self::from(big),
+                            Err(_) => Self::from(val.to_f64().unwrap_or_default()),
+                        }
+                    },
                 }
             },
             Val::Fn(ptr) => Self::from_str(ptr.as_ref()),
@@ -18,7 +18,7 @@ use arcstr::{literal, ArcStr};
 use indexmap::IndexMap;
 use rustc_hash::{FxHashMap, FxHashSet};
 use serde::{Deserialize, Serialize};
-use crate::{model::{DataRef, Graph, NodeRef, SPath, StofData, SELF_STR_KEYWORD, SUPER_STR_KEYWORD}, runtime::{Val, Variable}};
+use crate::{model::{val_size, DataRef, Func, Graph, NodeRef, Prototype, SId, SPath, StofData, SELF_STR_KEYWORD, SUPER_STR_KEYWORD}, runtime::{Val, Variable}};
 
 /// Marks a field as no export.
 /// Used in export formats.
@@ -33,6 +33,13 @@ pub const PRIVATE_FIELD_ATTR: ArcStr = literal!("private");
 /// Can this field be set or just read?
 pub const READ_ONLY_FIELD_ATTR: ArcStr = literal!("readonly");
 
+/// Function to call after this field is set Ex. #[on_change('recalculate')].
+pub const ON_CHANGE_FIELD_ATTR: ArcStr = literal!("on_change");
+
+/// Field value is the cached result of a function Ex. #[computed('calc_total')].
+/// Recomputed when any field the function read has changed.
+pub const COMPUTED_FIELD_ATTR: ArcStr = literal!("computed");
+
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 /// Field.
@@ -64,6 +71,11 @@ impl StofData for Field {
         }
     }
 
+    /// Size of this field's value (quotas).
+    fn value_size(&self) -> (usize, usize) {
+        val_size(&self.value.val.read())
+    }
+
     /// Deep copy.
     fn deep_copy(&self, graph: &mut Graph, context: Option<NodeRef>) -> Box::<dyn StofData> {
         // For fields that are objects, there could be a lot of extra allocated memory for this op...
@@ -110,7 +122,12 @@ impl Field {
 
     /// Can set this field?
     pub fn can_set(&self) -> bool {
-        !self.attributes.contains_key(READ_ONLY_FIELD_ATTR.as_str())
+        !self.attributes.contains_key(READ_ONLY_FIELD_ATTR.as_str()) && !self.is_computed()
+    }
+
+    /// Is this field computed?
+    pub fn is_computed(&self) -> bool {
+        self.attributes.contains_key(COMPUTED_FIELD_ATTR.as_str())
     }
 
     /// Can read this field?
@@ -118,6 +135,54 @@ impl Field {
         self.attributes.contains_key(PRIVATE_FIELD_ATTR.as_str())
     }
 
+    /// Get an accessor function (getter or setter) for a dot separated field path.
+    /// Accessors are functions named "{attr}_{field}" with an #[{attr}('{field}')] attribute, on the object or its prototypes.
+    /// Returns the object the accessor should be called on along with the function.
+    pub fn accessor(graph: &Graph, path: &str, start: Option<NodeRef>, attr: &str) -> Option<(NodeRef, DataRef)> {
+        let mut spath = SPath::from(path);
+        if spath.path.is_empty() { return None; }
+
+        let field_name = spath.path.pop().unwrap();
+        if let Some(node) = SPath::node(&graph, spath, start) {
+            let func_name = SId::from(format!("{attr}_{}", field_name.as_ref()));
+            let mut search = vec![node.clone()];
+            search.append(&mut Prototype::prototype_nodes(graph, &node, true));
+            for search_node in search {
+                if let Some(func_ref) = Func::func(graph, &search_node, &func_name) {
+                    if let Some(func) = graph.get_stof_data::<Func>(&func_ref) {
+                        if let Some(Val::Str(accessor)) = func.attributes.get(attr) {
+                            if accessor.as_str() == field_name.as_ref() {
+                                return Some((node, func_ref));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Function referenced by an attribute on this field (Ex. #[on_change('recalc')] or #[computed('calc')]).
+    /// The attribute can be a function or the name of a function on the object (or its prototypes).
+    pub fn attribute_func(&self, graph: &Graph, node: &NodeRef, attr: &str) -> Option<DataRef> {
+        match self.attributes.get(attr) {
+            Some(Val::Fn(func)) => Some(func.clone()),
+            Some(Val::Str(name)) => {
+                let func_name = SId::from(name.as_str());
+                if let Some(func) = Func::func(graph, node, &func_name) {
+                    return Some(func);
+                }
+                for proto in Prototype::prototype_nodes(graph, node, true) {
+                    if let Some(func) = Func::func(graph, &proto, &func_name) {
+                        return Some(func);
+                    }
+                }
+                None
+            },
+            _ => None,
+        }
+    }
+
     /// Get a field from a dot separated name path string.
     /// Ex. "root.hello" -> root object with a field named "hello". If hello is an object, a field might get created for it.
     pub fn field_from_path(graph: &mut Graph, path: &str, start: Option<NodeRef>) -> Option<DataRef> {
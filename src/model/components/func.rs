@@ -14,12 +14,12 @@
 // limitations under the License.
 //
 
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 use arcstr::{literal, ArcStr};
 use imbl::Vector;
 use rustc_hash::{FxHashMap, FxHashSet};
 use serde::{Deserialize, Serialize};
-use crate::{model::{DataRef, Graph, NodeRef, SId, SPath, StofData}, runtime::{instruction::{Instruction, Instructions}, Type, Val}};
+use crate::{model::{DataRef, Graph, NodeRef, SId, SPath, StofData}, runtime::{bytecode::Program, instruction::{Instruction, Instructions}, Type, Val}};
 
 
 /// Attribute used to denote a main function.
@@ -34,6 +34,27 @@ pub const ASYNC_FUNC_ATTR: ArcStr = literal!("async");
 /// If present, the function will not add its location to the self stack when called.
 pub const UNSELF_FUNC_ATTR: ArcStr = literal!("unself");
 
+/// Attribute used to denote an operator overload on a prototype Ex. #[op('+')].
+pub const OP_FUNC_ATTR: ArcStr = literal!("op");
+
+/// Attribute used to denote a custom equality function on a prototype.
+pub const EQ_FUNC_ATTR: ArcStr = literal!("eq");
+
+/// Attribute used to denote a custom ordering function on a prototype.
+pub const CMP_FUNC_ATTR: ArcStr = literal!("cmp");
+
+/// Attribute given to function signatures without a body (interface functions).
+pub const ABSTRACT_FUNC_ATTR: ArcStr = literal!("abstract");
+
+/// Attribute given to getter accessor functions Ex. get total() -> float { .. }.
+pub const GET_FUNC_ATTR: ArcStr = literal!("get");
+
+/// Attribute given to setter accessor functions Ex. set email(v: str) { .. }.
+pub const SET_FUNC_ATTR: ArcStr = literal!("set");
+
+/// Attribute given to functions called when a process running on their object is cancelled (see Promise.cancel).
+pub const CANCELLED_FUNC_ATTR: ArcStr = literal!("cancelled");
+
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 /// Function.
@@ -42,6 +63,14 @@ pub struct Func {
     pub return_type: Type,
     pub attributes: FxHashMap<String, Val>,
     pub instructions: Vector<Arc<dyn Instruction>>,
+
+    /// Instructions compiled on the first call.
+    #[serde(skip)]
+    program: OnceLock<Arc<Program>>,
+
+    /// Body of a trivial getter, inlined at call sites (set by the optimizer).
+    #[serde(skip)]
+    pub(crate) inline: Option<Arc<dyn Instruction>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -97,9 +126,20 @@ impl Func {
             return_type,
             instructions: instructions.instructions,
             attributes,
+            program: Default::default(),
+            inline: None,
         }
     }
 
+    /// Compiled program for this function's instructions.
+    /// Compiled once on first use and shared by every call.
+    pub fn program(&self) -> Arc<Program> {
+        self.program.get_or_init(|| {
+            let params = self.params.iter().map(|param| ArcStr::from(param.name.as_ref())).collect::<Vec<_>>();
+            Arc::new(Program::compile_func(&self.instructions, &params))
+        }).clone()
+    }
+
     /// Get a func from a dot separated name path string.
     /// Ex. "root.hello" -> root object with a func named "hello".
     pub fn func_from_path(graph: &Graph, path: &str, start: Option<NodeRef>) -> Option<DataRef> {
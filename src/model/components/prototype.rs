@@ -17,7 +17,7 @@
 use arcstr::{literal, ArcStr};
 use rustc_hash::FxHashSet;
 use serde::{Deserialize, Serialize};
-use crate::model::{DataRef, Graph, NodeRef, SPath, StofData};
+use crate::{model::{DataRef, Func, Graph, NodeRef, SPath, StofData, ABSTRACT_FUNC_ATTR}, runtime::{Error, Val}};
 
 
 /// Const prototype "type" literal.
@@ -26,6 +26,12 @@ pub const PROTOTYPE_TYPE_ATTR: ArcStr = literal!("type");
 /// Const prototype "extends" literal.
 pub const PROTOTYPE_EXTENDS_ATTR: ArcStr = literal!("extends");
 
+/// Const prototype "interface" literal.
+pub const PROTOTYPE_INTERFACE_ATTR: ArcStr = literal!("interface");
+
+/// Const prototype "implements" literal.
+pub const PROTOTYPE_IMPLEMENTS_ATTR: ArcStr = literal!("implements");
+
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 /// Prototype.
@@ -89,4 +95,83 @@ impl Prototype {
         }
         protos
     }
+
+    /// Find a function on the prototypes of a node that has the given attribute.
+    /// If a value is given, the attribute value must match as well (Ex. #[op('+')]).
+    /// Prototypes are searched in order, so sub-types override the types they extend.
+    pub fn attribute_func(graph: &Graph, node: &NodeRef, attr: &str, value: Option<&Val>) -> Option<DataRef> {
+        for proto in Self::prototype_nodes(graph, node, true) {
+            if let Some(proto) = proto.node(graph) {
+                for (_, dref) in &proto.data {
+                    if let Some(func) = graph.get_stof_data::<Func>(dref) {
+                        if let Some(attr_val) = func.attributes.get(attr) {
+                            if value.is_none() || value == Some(attr_val) {
+                                return Some(dref.clone());
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Is this node an interface (#[interface])?
+    pub fn is_interface(graph: &Graph, node: &NodeRef) -> bool {
+        if let Some(node) = node.node(graph) {
+            return node.attributes.contains_key(PROTOTYPE_INTERFACE_ATTR.as_str());
+        }
+        false
+    }
+
+    /// Check that a type conforms to an interface.
+    /// Every abstract function on the interface (and the interfaces it extends) must be implemented
+    /// by the type (or a type it extends) with the same parameter types and return type.
+    pub fn check_interface(graph: &Graph, ty: &NodeRef, interface: &NodeRef) -> Result<(), Error> {
+        let mut interfaces = vec![interface.clone()];
+        for proto in Self::prototype_nodes(graph, interface, true) {
+            if Self::is_interface(graph, &proto) { interfaces.push(proto); }
+        }
+
+        let mut implementors = vec![ty.clone()];
+        for proto in Self::prototype_nodes(graph, ty, true) {
+            if !Self::is_interface(graph, &proto) { implementors.push(proto); }
+        }
+
+        let type_name = ty.node(graph).map(|node| node.name.to_string()).unwrap_or_default();
+        for iface in interfaces {
+            let iface_name = iface.node(graph).map(|node| node.name.to_string()).unwrap_or_default();
+            for dref in Func::functions(graph, &iface, &None, false) {
+                if let Some(required) = graph.get_stof_data::<Func>(&dref) {
+                    if !required.attributes.contains_key(ABSTRACT_FUNC_ATTR.as_str()) { continue; }
+                    let name = dref.data_name(graph).unwrap_or_default();
+
+                    let mut implemented = None;
+                    for node in &implementors {
+                        if let Some(fref) = Func::func(graph, node, &name) {
+                            if let Some(func) = graph.get_stof_data::<Func>(&fref) {
+                                if !func.attributes.contains_key(ABSTRACT_FUNC_ATTR.as_str()) {
+                                    implemented = Some(func);
+                                    break;
+                                }
+                            }
+                        }
+                    }
+
+                    if let Some(func) = implemented {
+                        let mut matches = func.params.len() == required.params.len() && func.return_type == required.return_type;
+                        for (param, req) in func.params.iter().zip(required.params.iter()) {
+                            if param.param_type != req.param_type { matches = false; }
+                        }
+                        if !matches {
+                            return Err(Error::InterfaceNotImplemented(format!("{type_name}.{name} does not match the signature of {iface_name}.{name}")));
+                        }
+                    } else {
+                        return Err(Error::InterfaceNotImplemented(format!("{type_name} does not implement {iface_name}.{name}")));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
 }
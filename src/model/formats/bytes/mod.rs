@@ -55,7 +55,9 @@ impl Format for BytesFormat {
                 fvar = Some(field.value.clone());
             }
             if let Some(mut fvar) = fvar {
-                fvar.set(&Variable::val(Val::Blob(bytes)), graph, None)?;
+                let val = Val::Blob(bytes);
+                graph.quota_set(&field_ref, &val)?;
+                fvar.set(&Variable::val(val), graph, None)?;
                 if let Some(field) = graph.get_mut_stof_data::<Field>(&field_ref) {
                     field.value = fvar;
                 }
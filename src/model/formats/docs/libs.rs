@@ -36,6 +36,11 @@ pub fn insert_lib_documentation(graph: &mut Graph) {
     
     md_lib(graph);
     time_lib(graph);
+    promise_lib(graph);
+    chan_lib(graph);
+    rand_lib(graph);
+    regex_lib(graph);
+    crypto_lib(graph);
     fs_lib(graph);
     http_lib(graph);
     pdf_lib(graph);
@@ -63,7 +68,13 @@ fn main() {
 fn num_lib(graph: &mut Graph) {
     graph.insert_libdoc(literal!("Num"), 
 r#"# Number Library (Num)
-Library for manipulating and using numbers, automatically linked to the number types (int, float, & units).
+Library for manipulating and using numbers, automatically linked to the number types (int, float, decimal, & units).
+
+## Decimals
+Decimal numbers (type "decimal", literals like `19.99d`) are exact, for money and other values that must not pick up binary float error (0.1d + 0.2d == 0.3d). They hold up to 28 significant digits and keep their scale (19.90d prints as "19.90"). Math with ints and floats stays decimal, math with units gives units, and overflow throws a "NumOverflow" error (division by zero throws "NumDivideByZero"). Num.round takes an optional rounding mode. Decimals export as JSON strings, or as JSON numbers with the "json:numeric" format when the number keeps every digit.
+
+## Integers & BigInts
+Int math is checked: overflow (9223372036854775807 + 1), division by zero, and shifts outside 0..64 are errors instead of wrapping. Int literals too large for an int become big integers (type "bigint", or cast with `x as bigint`), which never overflow. Math with ints stays bigint, math with floats, decimals or units follows those types, and casting a bigint that does not fit back to an int is an error. Unsigned 64-bit JSON numbers import as bigints so large IDs stay exact (larger numbers are floats by the time the JSON parser hands them over). Bigints export as JSON and TOML numbers when they fit in 64 bits, and as strings otherwise.
 
 ## Example Usage
 ```rust
@@ -73,6 +84,14 @@ fn main() {
 
     const val = -5;
     assert_eq(val.abs(), 5);
+
+    const total = 19.99d * 3;
+    assert_eq(total, 59.97d);
+    assert_eq((total / 7).round(2, 'half_even'), 8.57d);
+
+    const id = 18446744073709551616;
+    assert_eq(typeof id, 'bigint');
+    assert_eq((id * 2) as str, '36893488147419103232');
 }
 ```
 "#.into());
@@ -84,11 +103,19 @@ fn str_lib(graph: &mut Graph) {
 r#"# String Library (Str)
 Library for manipulating strings, automatically linked to the 'str' type.
 
+## Format Specs
+Template strings accept a format spec after a colon, which is also the spec used by `Str.format`: `[[fill]align][+][0][width][,][.precision][type][units]`. Align is one of "<", ">", or "^", type is one of "e", "E", "x", "X", "b", or "o", and units converts the number before display. A spec starting with "%" formats a timestamp with strftime syntax.
+
 ## Example Usage
 ```rust
 #[main]
 fn main() {
     assert_eq("hello, world".split(", "), ['hello', 'world']);
+
+    const price = 3.14159;
+    assert_eq(`Total: ${price:.2}`, "Total: 3.14");
+    assert_eq(`${1572864bytes:.1MiB}`, "1.5MiB");
+    assert_eq(Str.format("{:>5}|{:08}", "ab", 42), "   ab|00000042");
 }
 ```
 "#.into());
@@ -155,6 +182,16 @@ fn main() {
     assert_eq(f(), 'hello');
 }
 ```
+
+## Async Priority
+Async functions run at "normal" priority. A "priority" attribute ('high', 'normal', or 'low') or the value of the "async" attribute sets a different one. Each round, every running process gets a time slice, with higher priorities going first and getting longer slices (low priority work is never starved).
+```rust
+#[priority('high')]
+async fn urgent() {}
+
+#[async('low')]
+fn background() {}
+```
 "#.into());
 }
 
@@ -222,7 +259,7 @@ fn main() {
 fn set_lib(graph: &mut Graph) {
     graph.insert_libdoc(literal!("Set"), 
 r#"# Set Library (Set)
-Library linked to the 'set' type.
+Library linked to the 'set' type. Sets are ordered, and objects in a set are ordered (and deduplicated) by object ID, even if their prototype defines a #[cmp] function (use a sorted list for that ordering).
 
 ## Example Usage
 ```rust
@@ -240,7 +277,7 @@ fn main() {
 fn map_lib(graph: &mut Graph) {
     graph.insert_libdoc(literal!("Map"), 
 r#"# Map Library (Map)
-Library linked to the 'map' type. Maps are ordered by keys.
+Library linked to the 'map' type. Maps are ordered by keys, and object keys are ordered by object ID (a prototype's #[cmp] function is not used for keys).
 
 ## Example Usage
 ```rust
@@ -287,6 +324,15 @@ fn time_lib(graph: &mut Graph) {
 r#"# Time Library (Time)
 Functions for working with time. Requires the "system" feature flag to be enabled. Includes timestamps (Time.now()) as well as common time formats (like RFC-3339) that are used in APIs and across systems.
 
+Calendar functions take an optional IANA time zone name (Ex. 'America/Denver') and default to UTC. Time.format and Time.parse use strftime-style format strings.
+
+## Time Values & Durations
+Timestamps can be plain numbers (ms since the Unix Epoch) or time values (type "time"), which are an instant with an optional time zone. Time values print as RFC-3339 strings (with a "[Zone]" suffix when they have a zone), and strings cast to them with "as time". Calendar functions use the zone of a time value when no tz is given, and return a time value when given one.
+
+Durations are numbers with time units. Subtracting two time values gives a duration in ms, adding a duration to a time value gives a time value (numbers without units are ms), and ISO-8601 durations convert with Time.duration and Time.iso_duration.
+
+TOML datetimes import as time values, as do RFC-3339 strings when importing with the "json:typed" or "yaml:typed" formats.
+
 ## Example Usage
 ```rust
 #[main]
@@ -294,6 +340,148 @@ fn main() {
     const now = Time.now(); // default units are ms
     sleep(50ms);
     pln(Time.diff(now) as seconds); // having units is really nice
+
+    const today = Time.start_of_day(now, 'America/Denver');
+    pln(Time.format(today, '%Y-%m-%d %H:%M %Z', 'America/Denver'));
+
+    const start = '2026-03-15T08:30:00[America/Denver]' as time;
+    const end = start + Time.duration('P1DT2H');
+    pln(end);               // 2026-03-16T10:30:00-06:00[America/Denver]
+    pln((end - start) as hr); // 26hr
+}
+```
+"#.into());
+}
+
+/// Promise.
+fn promise_lib(graph: &mut Graph) {
+    graph.insert_libdoc(literal!("Promise"), 
+r#"# Promise Library (Promise)
+Functions for controlling async processes through their promises: cancelling them, giving them deadlines, and waiting on groups of them. Cancelling a process also cancels the processes it spawned, unwinding each with a "Cancelled" error so that finally blocks still run.
+
+## Example Usage
+```rust
+#[main]
+fn main() {
+    const slow = async { sleep(10s); 'slow' };
+    const fast = async { sleep(10ms); 'fast' };
+    assert_eq(await Promise.race([slow, fast]), 'fast');
+    slow.cancel();
+
+    try {
+        await Http.fetch('https://example.com').timeout(5s);
+    } catch (error) {
+        pln(error); // timed out (the request was cancelled)
+    }
+}
+```
+"#.into());
+}
+
+/// Chan.
+fn chan_lib(graph: &mut Graph) {
+    graph.insert_libdoc(literal!("Chan"), 
+r#"# Channel Library (Chan)
+Channels pass values between processes, so that producers and consumers within a document don't have to share fields on the graph. A channel is data (Data<Chan>) that can be bounded (senders sleep while it is full) or unbounded. Receivers sleep while a channel is empty and are woken when a value is sent or the channel is closed.
+
+## Example Usage
+```rust
+#[main]
+fn main() {
+    const ch = Chan.new(2);
+    const producer = async {
+        for (const i in 10) ch.send(i);
+        ch.close();
+    };
+
+    let total = 0;
+    loop {
+        const value = ch.recv();
+        if (value == null) break; // closed
+        total += value;
+    }
+    await producer;
+    pln(total); // 45
+}
+```
+"#.into());
+}
+
+/// Rand.
+fn rand_lib(graph: &mut Graph) {
+    graph.insert_libdoc(literal!("Rand"), 
+r#"# Random Library (Rand)
+Random numbers, booleans, and list operations (choice, shuffle, sample), with uniform and normal distributions. Numbers with units give random values in those units. Each graph has its own generator, seeded from the OS (or the browser in JS) unless seeded with Rand.seed, which makes simulations and tests reproducible. Not for secrets (see the Crypto library).
+
+## Example Usage
+```rust
+#[main]
+fn main() {
+    Rand.seed(42); // same results every run
+
+    const roll = Rand.int(1, 6);
+    const length = Rand.float(1m, 5m);
+    const height = Rand.normal(170cm, 10cm);
+    const heads = Rand.bool();
+
+    const deck = [1, 2, 3, 4, 5];
+    Rand.shuffle(deck);
+    const hand = Rand.sample(deck, 2);
+    const card = Rand.choice(deck);
+}
+```
+"#.into());
+}
+
+/// Regex.
+fn regex_lib(graph: &mut Graph) {
+    graph.insert_libdoc(literal!("Regex"), 
+r#"# Regular Expression Library (Regex)
+Regular expressions (Rust regex syntax) compiled into data (Data<Regex>), with capture groups and named groups as maps, template or function replacements, and splitting. Every function also accepts a pattern string in place of a compiled regex. Compiled patterns are cached, so using the same pattern string again (including with Str.matches) doesn't compile it again.
+
+## Example Usage
+```rust
+date: Regex.new("(?<year>\\d{4})-(?<month>\\d{2})-(?<day>\\d{2})")
+
+#[main]
+fn main() {
+    const caps = self.date.captures("released 2025-06-01");
+    assert_eq(caps.get("year"), "2025");
+
+    const us = self.date.replace_all("2025-06-01", "${month}/${day}/${year}");
+    assert_eq(us, "06/01/2025");
+
+    const shouted = Regex.replace_all("\\b\\w", "hello world", (caps: map): str => caps.get(0).upper());
+    assert_eq(shouted, "Hello World");
+
+    assert_eq(Regex.split("\\s+", "a  b   c"), ["a", "b", "c"]);
+}
+```
+"#.into());
+}
+
+/// Crypto.
+fn crypto_lib(graph: &mut Graph) {
+    graph.insert_libdoc(literal!("Crypto"), 
+r#"# Cryptography Library (Crypto)
+Hashing (SHA-256, SHA-512, BLAKE3), HMAC, constant-time comparison, Ed25519 signatures, and secure random bytes & UUIDs. Functions take strings (as UTF-8) or blobs, and return blobs (see Blob.hex and Blob.base64 for encoding them). Requires the "crypto" feature (on by default).
+
+## Example Usage
+```rust
+#[main]
+fn main() {
+    // webhook signature verification
+    const body = '{"event": "push"}';
+    const header = Crypto.hmac('webhook-secret', body).hex();
+    assert(Crypto.eq(Crypto.hmac('webhook-secret', body).hex(), header));
+
+    // content-addressed caching
+    const key = Crypto.blake3(body).hex();
+
+    // signatures
+    const keys = Crypto.keygen();
+    const signature = Crypto.sign(keys.get('secret'), body);
+    assert(Crypto.verify(keys.get('public'), body, signature));
 }
 ```
 "#.into());
@@ -303,7 +491,21 @@ fn main() {
 fn fs_lib(graph: &mut Graph) {
     graph.insert_libdoc(literal!("fs"), 
 r#"# File System Library (fs)
-Functions for working with the file system. Requires the "system" feature to automatically be added, otherwise, remove this library to further sandbox your environment.
+Functions for working with the file system. Requires the "system" feature to automatically be added, otherwise, remove this library to further sandbox your environment. When a graph or runtime has a sandbox policy, paths are checked against its "read" and "write" allow-lists.
+
+## Example Usage
+```rust
+#[main]
+fn main() {
+    fs.mkdir("out");
+    for (const path in fs.glob("src/**/*.rs")) {
+        const meta = fs.metadata(path);
+        fs.append("out/sizes.txt", `${path}: ${meta.get("size") as KB}\n`);
+    }
+    for (const line in fs.read_lines("out/sizes.txt")) pln(line);
+    fs.remove("out", true);
+}
+```
 "#.into());
 }
 
@@ -339,6 +339,7 @@ impl Format for MdDocsFormat {
     }
     /// Takes a directory path and populates the directory with documentation for this graph & node.
     fn file_export(&self, graph: &Graph, _format: &str, path: &str, node: Option<NodeRef>) -> Result<(), Error> {
+        graph.sandbox_write(path)?;
         if let Some(_lib) = graph.libfunc(&FS_LIB, "write") {
             let buf = PathBuf::from(path);
             if buf.is_dir() {
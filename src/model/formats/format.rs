@@ -37,6 +37,7 @@ pub trait Format: std::fmt::Debug + Send + Sync {
     /// File import.
     #[allow(unused)]
     fn file_import(&self, graph: &mut Graph, format: &str, path: &str, node: Option<NodeRef>, profile: &Profile) -> Result<(), Error> {
+        graph.sandbox_read(path)?;
         if let Some(_lib) = graph.libfunc(&FS_LIB, "read_string") {
             #[cfg(not(feature = "system"))]
             {
@@ -101,6 +102,7 @@ pub trait Format: std::fmt::Debug + Send + Sync {
     /// File export.
     /// Try exporting a string (binary if failed) and write the output to a file located at 'path'.
     fn file_export(&self, graph: &Graph, format: &str, path: &str, node: Option<NodeRef>) -> Result<(), Error> {
+        graph.sandbox_write(path)?;
         if let Some(_lib) = graph.libfunc(&FS_LIB, "write") {
             // make sure a directory exists at the requested path before writing to it
             let buf = PathBuf::from(path);
@@ -15,26 +15,29 @@
 //
 
 use imbl::Vector;
+use num_traits::ToPrimitive;
+use rust_decimal::Decimal;
 use serde_json::{Map, Number, Value};
 use crate::{model::{Field, Graph, NodeRef, NOEXPORT_FIELD_ATTR}, runtime::{Num, Val, ValRef}};
 
 
 /// Export a serde_json Value from a node in the graph.
-pub(crate) fn json_value_from_node(graph: &Graph, node: &NodeRef) -> Value {
+/// Decimals are strings, unless "numeric" and they survive the trip through a JSON (f64) number.
+pub(crate) fn json_value_from_node(graph: &Graph, node: &NodeRef, numeric: bool) -> Value {
     let mut map = Map::new();
     if let Some(node) = node.node(graph) {
         for (name, dref) in &node.data {
             if let Some(field) = graph.get_stof_data::<Field>(dref) {
                 if !field.attributes.contains_key(NOEXPORT_FIELD_ATTR.as_str()) {
                     // could still be objects... just not child object fields (unles you create another reference...)
-                    map.insert(name.to_string(), json_value(graph, field.value.get()));
+                    map.insert(name.to_string(), json_value(graph, field.value.get(), numeric));
                 }
             }
         }
         for child in &node.children {
             if let Some(child) = child.node(graph) {
                 if child.is_field() && !child.attributes.contains_key(NOEXPORT_FIELD_ATTR.as_str()) {
-                    map.insert(child.name.to_string(), json_value(graph, Val::Obj(child.id.clone())));
+                    map.insert(child.name.to_string(), json_value(graph, Val::Obj(child.id.clone()), numeric));
                 }
             }
         }
@@ -43,7 +46,7 @@ pub(crate) fn json_value_from_node(graph: &Graph, node: &NodeRef) -> Value {
 }
 
 /// Get a JSON value from a Val.
-fn json_value(graph: &Graph, val: Val) -> Value {
+fn json_value(graph: &Graph, val: Val, numeric: bool) -> Value {
     match val {
         Val::Void |
         Val::Null => Value::Null,
@@ -51,19 +54,37 @@ fn json_value(graph: &Graph, val: Val) -> Value {
         Val::Bool(v) => Value::Bool(v),
         Val::Str(v) => Value::String(v.to_string()),
         Val::Prompt(v) => Value::String(v.to_string()),
+        Val::Num(Num::Decimal(v)) => {
+            if numeric {
+                if let Some(number) = Number::from_f64(v.to_f64().unwrap_or(f64::NAN)) {
+                    if number.to_string().parse::<Decimal>().is_ok_and(|exact| exact == v) {
+                        return Value::Number(number);
+                    }
+                }
+            }
+            Value::String(v.to_string())
+        },
+        Val::Num(Num::BigInt(v)) => {
+            // JSON numbers only keep 64-bit integers exact
+            if v.to_i64().is_some() || v.to_u64().is_some() {
+                return Value::Number(Number::from(Num::BigInt(v)));
+            }
+            Value::String(v.to_string())
+        },
         Val::Num(v) => Value::Number(Number::from(v)),
         Val::Blob(blob) => Value::from_iter(blob.into_iter()),
         Val::Fn(_dref) => Value::Null,
         Val::Data(_dref) => Value::Null, // TODO custom exports
-        Val::List(vals) => value_from_array(graph, vals),
-        Val::Tup(vals) => value_from_array(graph, vals),
+        Val::List(vals) => value_from_array(graph, vals, numeric),
+        Val::Tup(vals) => value_from_array(graph, vals, numeric),
         Val::Ver(..) => Value::String(val.to_string()),
-        Val::Set(vals) => value_from_array(graph, vals.into_iter().collect()),
-        Val::Obj(nref) => json_value_from_node(graph, &nref),
+        Val::Time(..) => Value::String(val.to_string()),
+        Val::Set(vals) => value_from_array(graph, vals.into_iter().collect(), numeric),
+        Val::Obj(nref) => json_value_from_node(graph, &nref, numeric),
         Val::Map(map) => {
             let mut value = Map::new();
             for (k, v) in map {
-                value.insert(k.read().to_string(), json_value(graph, v.read().clone()));
+                value.insert(k.read().to_string(), json_value(graph, v.read().clone(), numeric));
             }
             Value::Object(value)
         },
@@ -71,10 +92,10 @@ fn json_value(graph: &Graph, val: Val) -> Value {
 }
 
 /// Export value from an array of values.
-fn value_from_array(graph: &Graph, vals: Vector<ValRef<Val>>) -> Value {
+fn value_from_array(graph: &Graph, vals: Vector<ValRef<Val>>, numeric: bool) -> Value {
     let mut results: Vec<Value> = Vec::new();
     for val in vals {
-        results.push(json_value(graph, val.read().clone()));
+        results.push(json_value(graph, val.read().clone(), numeric));
     }
     Value::Array(results)
 }
@@ -90,6 +111,14 @@ impl From<Num> for Number {
             },
             Num::Units(v, _) => {
                 Number::from_f64(v).unwrap()
+            },
+            Num::Decimal(v) => {
+                Number::from_f64(v.to_f64().unwrap_or_default()).unwrap()
+            },
+            Num::BigInt(v) => {
+                if let Some(v) = v.to_i64() { Number::from(v) }
+                else if let Some(v) = v.to_u64() { Number::from(v) }
+                else { Number::from_f64(v.to_f64().unwrap_or_default()).unwrap_or(Number::from(0)) }
             }
         }
     }
@@ -14,31 +14,55 @@
 // limitations under the License.
 //
 
+use std::borrow::Cow;
 use imbl::Vector;
 use rustc_hash::FxHashMap;
+use num_bigint::BigInt;
 use serde_json::{Map, Value};
-use crate::{model::{Field, Graph, NodeRef, SId, NOEXPORT_FIELD_ATTR}, runtime::{Val, ValRef, Variable}};
+use crate::{model::{Field, Graph, NodeRef, SId, NOEXPORT_FIELD_ATTR}, parser::datetime::parse_rfc3339_time_alone, runtime::{Num, Val, ValRef, Variable}};
 
 
 /// Parse a serde_json Object value into a graph.
 pub(crate) fn parse_json_object_value(graph: &mut Graph, node: &NodeRef, value: Value) {
+    parse_json_object(graph, node, value, false, None);
+}
+
+/// Parse a serde_json Object value into a graph.
+/// When typed, RFC 3339 strings are parsed into time values.
+/// Strings starting with the bigint marker are integers from `mark_json_bigints`.
+pub(crate) fn parse_json_object(graph: &mut Graph, node: &NodeRef, value: Value, typed: bool, bigint_marker: Option<&str>) {
     match value {
         Value::Object(map) => {
             for (field, val) in map {
-                let jf = parse_json_field_value(graph, node, val, &field);
+                let jf = parse_json_field_value(graph, node, val, &field, typed, bigint_marker);
                 graph.insert_stof_data(node, &field, Box::new(jf), None);
             }
         },
         _ => {
             let mut map = Map::new();
             map.insert("field".into(), value);
-            parse_json_object_value(graph, node, Value::Object(map));
+            parse_json_object(graph, node, Value::Object(map), typed, bigint_marker);
         }
     }
 }
 
+/// Parse a serde_json string value (time values for RFC 3339 strings when typed).
+fn json_string_value(value: &str, typed: bool, bigint_marker: Option<&str>) -> Val {
+    if let Some(digits) = bigint_marker.and_then(|marker| value.strip_prefix(marker)) {
+        if let Ok(big) = digits.parse::<BigInt>() {
+            return Val::Num(Num::from(big));
+        }
+    }
+    if typed {
+        if let Some(time) = parse_rfc3339_time_alone(value) {
+            return time;
+        }
+    }
+    Val::from(value)
+}
+
 /// Parse a serde_json field value into the graph.
-pub(crate) fn parse_json_field_value(graph: &mut Graph, node: &NodeRef, value: Value, field: &str) -> Field {
+pub(crate) fn parse_json_field_value(graph: &mut Graph, node: &NodeRef, value: Value, field: &str, typed: bool, bigint_marker: Option<&str>) -> Field {
     match value {
         Value::Null => {
             Field::new(Variable::new(graph, true, Val::Null, false), None)
@@ -57,19 +81,25 @@ pub(crate) fn parse_json_field_value(graph: &mut Graph, node: &NodeRef, value: V
             Field::new(Variable::new(graph, true, val, false), None)
         },
         Value::String(v)  => {
-            Field::new(Variable::new(graph, true, Val::from(v.as_str()), false), None)
+            Field::new(Variable::new(graph, true, json_string_value(&v, typed, bigint_marker), false), None)
         },
         Value::Bool(v) => {
             Field::new(Variable::new(graph, true, Val::from(v), false), None)
         },
         Value::Array(vals) => {
             let mut jf_arr = Vector::default();
-            parse_json_array_values(graph, node, vals, &mut jf_arr);
+            parse_json_array_values(graph, node, vals, &mut jf_arr, typed, bigint_marker);
             Field::new(Variable::new(graph, true, Val::List(jf_arr), false), None)
         }
         Value::Object(_) => {
-            let child_node = graph.insert_node(field, Some(node.clone()), true);
-            parse_json_object_value(graph, &child_node, value);
+            let child_node = match graph.try_insert_node(field, Some(node.clone()), true) {
+                Ok(child_node) => child_node,
+                Err(error) => {
+                    graph.refuse(error); // raised by the import
+                    return Field::new(Variable::new(graph, true, Val::Null, false), None);
+                },
+            };
+            parse_json_object(graph, &child_node, value, typed, bigint_marker);
 
             let mut attrs = FxHashMap::default();
             attrs.insert(NOEXPORT_FIELD_ATTR.to_string(), Val::Null); // don't export object fields
@@ -79,7 +109,7 @@ pub(crate) fn parse_json_field_value(graph: &mut Graph, node: &NodeRef, value: V
 }
 
 /// Parse array values.
-pub(crate) fn parse_json_array_values(graph: &mut Graph, node: &NodeRef, vals: Vec<Value>, res: &mut Vector<ValRef<Val>>) {
+pub(crate) fn parse_json_array_values(graph: &mut Graph, node: &NodeRef, vals: Vec<Value>, res: &mut Vector<ValRef<Val>>, typed: bool, bigint_marker: Option<&str>) {
     for val in vals {
         match val {
             Value::Null => {
@@ -99,22 +129,82 @@ pub(crate) fn parse_json_array_values(graph: &mut Graph, node: &NodeRef, vals: V
                 res.push_back(ValRef::new(val));
             },
             Value::String(v)  => {
-                res.push_back(ValRef::new(Val::from(v.as_str())));
+                res.push_back(ValRef::new(json_string_value(&v, typed, bigint_marker)));
             },
             Value::Bool(v) => {
                 res.push_back(ValRef::new(Val::from(v)));
             },
             Value::Array(vals) => {
                 let mut jf_arr = Vector::default();
-                parse_json_array_values(graph, node, vals, &mut jf_arr);
+                parse_json_array_values(graph, node, vals, &mut jf_arr, typed, bigint_marker);
                 res.push_back(ValRef::new(Val::List(jf_arr)));
             }
             Value::Object(_) => {
                 let id = SId::default();
-                let child_node = graph.insert_node_id(&id, &id, Some(node.clone()), false);
-                parse_json_object_value(graph, &child_node, val);
-                res.push_back(ValRef::new(Val::Obj(child_node)));
+                match graph.try_insert_node_id(&id, &id, Some(node.clone()), false) {
+                    Ok(child_node) => {
+                        parse_json_object(graph, &child_node, val, typed, bigint_marker);
+                        res.push_back(ValRef::new(Val::Obj(child_node)));
+                    },
+                    Err(error) => graph.refuse(error), // raised by the import
+                }
             },
         }
     }
 }
+
+
+/// Rewrite JSON integers that don't fit into 64 bits as strings starting with the given marker.
+/// serde_json parses these as lossy floats, so they are kept as text and parsed into bigints on import.
+pub(crate) fn mark_json_bigints<'a>(src: &'a str, marker: &str) -> Cow<'a, str> {
+    let bytes = src.as_bytes();
+    let mut res = String::new();
+    let mut copied = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut i = 0;
+    while i < bytes.len() {
+        let byte = bytes[i];
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if byte == b'\\' {
+                escaped = true;
+            } else if byte == b'"' {
+                in_string = false;
+            }
+            i += 1;
+            continue;
+        }
+        if byte == b'"' {
+            in_string = true;
+            i += 1;
+            continue;
+        }
+        if byte != b'-' && !byte.is_ascii_digit() {
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        while i < bytes.len() && matches!(bytes[i], b'0'..=b'9' | b'.' | b'e' | b'E' | b'+' | b'-') { i += 1; }
+
+        // only valid integers (no fraction, exponent or leading zeros) that overflow 64 bits
+        let number = &src[start..i];
+        let digits = number.strip_prefix('-').unwrap_or(number);
+        let integer = !digits.is_empty() && !digits.starts_with('0') && digits.bytes().all(|byte| byte.is_ascii_digit());
+        if integer && number.parse::<i64>().is_err() && number.parse::<u64>().is_err() {
+            res.push_str(&src[copied..start]);
+            res.push('"');
+            res.push_str(marker);
+            res.push_str(number);
+            res.push('"');
+            copied = i;
+        }
+    }
+    if copied == 0 {
+        return Cow::Borrowed(src);
+    }
+    res.push_str(&src[copied..]);
+    Cow::Owned(res)
+}
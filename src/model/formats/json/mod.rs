@@ -17,28 +17,30 @@
 
 pub mod import;
 pub mod export;
+use nanoid::nanoid;
 use serde_json::Value;
-use crate::{model::{Format, Graph, NodeRef, Profile, json::{export::json_value_from_node, import::parse_json_object_value}}, runtime::Error};
+use crate::{model::{Format, Graph, NodeRef, Profile, json::{export::json_value_from_node, import::{mark_json_bigints, parse_json_object}}}, runtime::Error};
 
 
 #[derive(Debug)]
 pub struct JsonFormat;
 impl Format for JsonFormat {
     fn identifiers(&self) -> Vec<String> {
-        vec!["json".into()]
+        vec!["json".into(), "json:typed".into(), "json:numeric".into()]
     }
     fn content_type(&self) -> String {
         "application/json".into()
     }
-    fn string_import(&self, graph: &mut Graph, _format: &str, src: &str, node: Option<NodeRef>, _profile: &Profile) -> Result<(), Error> {
+    fn string_import(&self, graph: &mut Graph, format: &str, src: &str, node: Option<NodeRef>, _profile: &Profile) -> Result<(), Error> {
         if src.is_empty() { return Ok(()); }
-        match serde_json::from_str::<Value>(src) {
+        let bigint_marker = format!("bigint:{}:", nanoid!());
+        match serde_json::from_str::<Value>(&mark_json_bigints(src, &bigint_marker)) {
             Ok(value) => {
                 let mut parse_node = graph.ensure_main_root();
                 if let Some(nd) = node {
                     parse_node = nd;
                 }
-                parse_json_object_value(graph, &parse_node, value);
+                parse_json_object(graph, &parse_node, value, format.ends_with(":typed"), Some(&bigint_marker)); // typed: RFC 3339 strings are time values
                 Ok(())
             },
             Err(error) => {
@@ -46,14 +48,14 @@ impl Format for JsonFormat {
             }
         }
     }
-    fn string_export(&self, graph: &Graph, _format: &str, node: Option<NodeRef>) -> Result<String, Error> {
+    fn string_export(&self, graph: &Graph, format: &str, node: Option<NodeRef>) -> Result<String, Error> {
         let exp_node;
         if let Some(nd) = node {
             exp_node = nd;
         } else {
             exp_node = graph.main_root().expect("graph does not have a main 'root' node for default JSON export");
         }
-        let value = json_value_from_node(graph, &exp_node);
+        let value = json_value_from_node(graph, &exp_node, format.ends_with(":numeric")); // numeric: decimals as JSON numbers
         match serde_json::to_string(&value) {
             Ok(res) => {
                 Ok(res)
@@ -63,3 +63,8 @@ pub use image::*;
 pub mod docx;
 #[cfg(feature = "docx")]
 pub use docx::*;
+
+#[cfg(feature = "crypto")]
+pub mod signed;
+#[cfg(feature = "crypto")]
+pub use signed::*;
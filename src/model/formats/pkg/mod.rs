@@ -210,6 +210,7 @@ impl Format for StofPackageFormat {
         }
     }
     fn parser_import(&self, _format: &str, path: &str, context: &mut ParseContext) -> Result<(), Error> {
+        context.graph.sandbox_read(path)?;
         let mut package_path = path.to_string();
         let mut cleanup_dir = None;
 
@@ -261,6 +262,7 @@ impl Format for StofPackageFormat {
         Ok(())
     }
     fn file_import(&self, graph: &mut Graph, _format: &str, path: &str, node: Option<NodeRef>, profile: &Profile) -> Result<(), Error> {
+        graph.sandbox_read(path)?;
         let mut package_path = path.to_string();
         let mut cleanup_dir = None;
 
@@ -32,6 +32,9 @@ pub struct Profile {
 
     /// Excluded attributes (both functions and fields).
     pub exclude_attributes: FxHashSet<String>,
+
+    /// Optimize parsed functions?
+    pub optimize: bool,
 }
 impl Default for Profile {
     fn default() -> Self {
@@ -47,6 +50,7 @@ impl Profile {
             debug_info: true,
             docs: false,
             exclude_attributes: Default::default(),
+            optimize: false,
         }
     }
 
@@ -60,11 +64,12 @@ impl Profile {
             debug_info: false,
             docs: true,
             exclude_attributes,
+            optimize: false,
         }
     }
 
     /// Prod profile.
-    /// Test attributes are excluded.
+    /// Test attributes are excluded and functions are optimized.
     pub fn prod() -> Self {
         let mut exclude_attributes = FxHashSet::default();
         exclude_attributes.insert("test".into());
@@ -73,6 +78,7 @@ impl Profile {
             debug_info: false,
             docs: false,
             exclude_attributes,
+            optimize: true,
         }
     }
 }
@@ -0,0 +1,216 @@
+//
+// Copyright 2025 Formata, Inc. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use crate::{model::{crypto::{ed25519_sign, ed25519_verify, signing_key, verifying_key}, Format, Graph, NodeRef, Profile}, runtime::Error};
+
+
+/// Signature context, so that a document signature can't be confused with other Ed25519 signatures by the same key.
+const SIGNED_CONTEXT: &[u8] = b"stof:signed:v1";
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Signed document envelope ("stof:signed").
+/// Exported bytes of a document (in "format") with a detached Ed25519 signature and the ID of the signing key.
+pub struct SignedDocument {
+    pub format: String,
+    pub key_id: String,
+    pub signature: Vec<u8>,
+    pub payload: Vec<u8>,
+}
+impl SignedDocument {
+    /// Sign exported document bytes with an Ed25519 secret key.
+    pub fn sign(format: &str, payload: Vec<u8>, secret: &[u8]) -> Result<Self, Error> {
+        let public = signing_key(secret)?.verifying_key().to_bytes();
+        let mut document = Self {
+            format: format.to_string(),
+            key_id: key_id(&public),
+            signature: Default::default(),
+            payload,
+        };
+        document.signature = ed25519_sign(secret, &document.message())?;
+        Ok(document)
+    }
+
+    /// Message that gets signed (covers the format as well as the payload).
+    fn message(&self) -> Vec<u8> {
+        let mut message = Vec::with_capacity(SIGNED_CONTEXT.len() + self.format.len() + self.payload.len() + 2);
+        message.extend_from_slice(SIGNED_CONTEXT);
+        message.push(0);
+        message.extend_from_slice(self.format.as_bytes());
+        message.push(0);
+        message.extend_from_slice(&self.payload);
+        message
+    }
+
+    /// Verify this document's signature with a public key.
+    pub fn verify(&self, public: &[u8]) -> Result<bool, Error> {
+        ed25519_verify(public, &self.message(), &self.signature)
+    }
+
+    /// Serialize this envelope.
+    pub fn to_bytes(&self) -> Result<Bytes, Error> {
+        match bincode::serialize(self) {
+            Ok(bytes) => Ok(bytes.into()),
+            Err(error) => Err(Error::SignedExport(error.to_string())),
+        }
+    }
+
+    /// Deserialize an envelope (without verifying it).
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        match bincode::deserialize::<Self>(bytes) {
+            Ok(document) => Ok(document),
+            Err(error) => Err(Error::SignedImport(error.to_string())),
+        }
+    }
+}
+
+
+/// Key ID for an Ed25519 public key (hex of the first 8 bytes of its SHA-256 digest).
+pub fn key_id(public: &[u8]) -> String {
+    Sha256::digest(public)[..8].iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+
+impl Graph {
+    /// Trust an Ed25519 public key when importing signed documents.
+    /// Returns the key ID that signed documents reference.
+    pub fn trust_key(&mut self, public: impl AsRef<[u8]>) -> Result<String, Error> {
+        let public = verifying_key(public.as_ref())?.to_bytes();
+        let id = key_id(&public);
+        self.trusted_keys.insert(id.clone(), Bytes::copy_from_slice(&public));
+        Ok(id)
+    }
+
+    /// Stop trusting a key (by key ID).
+    pub fn untrust_key(&mut self, id: &str) -> bool {
+        self.trusted_keys.remove(id).is_some()
+    }
+
+    /// Export a node (or the whole graph) in a format and sign it, returning a "stof:signed" envelope.
+    pub fn sign(&self, format: &str, node: Option<NodeRef>, secret: impl AsRef<[u8]>) -> Result<Bytes, Error> {
+        let payload = self.binary_export(format, node)?;
+        SignedDocument::sign(format, payload.to_vec(), secret.as_ref())?.to_bytes()
+    }
+
+    /// Verify a "stof:signed" envelope against this graph's trusted keys.
+    /// Errors if the envelope is malformed, the signing key isn't trusted, or the signature doesn't match (tampered).
+    pub fn verify(&self, bytes: impl AsRef<[u8]>) -> Result<SignedDocument, Error> {
+        let document = SignedDocument::from_bytes(bytes.as_ref())?;
+        if let Some(public) = self.trusted_keys.get(&document.key_id) {
+            if document.verify(public)? {
+                return Ok(document);
+            }
+            return Err(Error::SignedInvalidSignature(document.key_id));
+        }
+        Err(Error::SignedUntrustedKey(document.key_id))
+    }
+}
+
+
+#[derive(Debug)]
+/// Signed document format.
+/// Imports only verified documents signed by a trusted key (see Graph::trust_key).
+/// Signing is host-only (see Graph::sign), so scripts can't get a host signature over content they choose (Ex. blobify with "stof:signed").
+pub struct SignedFormat;
+impl Format for SignedFormat {
+    fn identifiers(&self) -> Vec<String> {
+        vec!["stof:signed".into(), "bstf:signed".into()]
+    }
+    fn content_type(&self) -> String {
+        "application/stof+signed".into()
+    }
+    fn binary_export(&self, _graph: &Graph, _format: &str, _node: Option<NodeRef>) -> Result<Bytes, Error> {
+        Err(Error::SignedExport("signing is host-only (see Graph::sign)".into()))
+    }
+    fn binary_import(&self, graph: &mut Graph, _format: &str, bytes: Bytes, node: Option<NodeRef>, profile: &Profile) -> Result<(), Error> {
+        let document = graph.verify(&bytes)?;
+        if self.identifiers().contains(&document.format) {
+            return Err(Error::SignedImport("nested signed documents are not supported".into()));
+        }
+        graph.binary_import(&document.format, document.payload.into(), node, profile)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{model::{crypto::ed25519_keygen, Graph, Profile, SignedDocument}, runtime::{Error, Val}};
+
+    fn doc() -> Graph {
+        let mut graph = Graph::default();
+        graph.parse_stof_src(r#"
+            field: 42
+            fn answer() -> int { self.field }
+        "#, None, Profile::test()).unwrap();
+        graph
+    }
+
+    #[test]
+    fn sign_and_import() {
+        let (secret, public) = ed25519_keygen().unwrap();
+        let bytes = doc().sign("stof", None, &secret).unwrap();
+
+        let mut other = Graph::default();
+        assert!(matches!(other.binary_import("stof:signed", bytes.clone(), None, &Profile::test()), Err(Error::SignedUntrustedKey(_))));
+        assert_eq!(other.field_value("field", None), None);
+
+        other.trust_key(&public).unwrap();
+        other.binary_import("stof:signed", bytes, None, &Profile::test()).unwrap();
+        assert_eq!(other.field_value("field", None), Some(Val::from(42)));
+    }
+
+    #[test]
+    fn tampered_rejected() {
+        let (secret, public) = ed25519_keygen().unwrap();
+        let graph = doc();
+        let mut document = SignedDocument::from_bytes(&graph.sign("stof", None, &secret).unwrap()).unwrap();
+        document.payload.extend_from_slice(b"\nfield: 666");
+        let tampered = document.to_bytes().unwrap();
+
+        let mut other = Graph::default();
+        let key = other.trust_key(&public).unwrap();
+        assert!(matches!(other.verify(&tampered), Err(Error::SignedInvalidSignature(id)) if id == key));
+
+        // the format is part of what gets signed
+        let mut document = SignedDocument::from_bytes(&graph.sign("stof", None, &secret).unwrap()).unwrap();
+        document.format = "text".into();
+        assert!(matches!(other.verify(document.to_bytes().unwrap()), Err(Error::SignedInvalidSignature(_))));
+
+        assert!(matches!(other.verify(b"not signed"), Err(Error::SignedImport(_))));
+        assert!(other.untrust_key(&key));
+        assert!(matches!(other.verify(graph.sign("stof", None, &secret).unwrap()), Err(Error::SignedUntrustedKey(_))));
+    }
+
+    #[test]
+    fn export_host_only() {
+        let (secret, public) = ed25519_keygen().unwrap();
+        let mut graph = doc();
+        assert!(matches!(graph.binary_export("stof:signed", None), Err(Error::SignedExport(_))));
+
+        // scripts can't sign through the format either
+        graph.parse_stof_src(r#"fn sign() -> blob { blobify('bstf:signed') }"#, None, Profile::test()).unwrap();
+        assert!(graph.call("sign", None, vec![]).is_err());
+
+        let bytes = graph.sign("bstf", None, &secret).unwrap();
+        let mut other = Graph::default();
+        other.trust_key(public).unwrap();
+        other.binary_import("application/stof+signed", bytes, None, &Profile::test()).unwrap();
+        assert_eq!(other.field_value("field", None), Some(Val::from(42)));
+    }
+}
@@ -15,7 +15,7 @@
 //
 
 use rustc_hash::FxHashSet;
-use crate::{model::{Field, Func, Graph, NodeRef, NOEXPORT_FIELD_ATTR}, runtime::Val};
+use crate::{model::{Field, Func, Graph, NodeRef, NOEXPORT_FIELD_ATTR}, runtime::{Num, Val}};
 
 
 #[derive(Debug, Clone, Default)]
@@ -179,6 +179,8 @@ impl StofExportContext {
             Val::Bool(_) => self.stof.push_str(&val.to_string()),
             Val::Str(str) => self.stof.push_str(&format!("r#\"{str}\"#")),
             Val::Prompt(v) => self.stof.push_str(&format!("r#\"{}\"#", v.to_string())),
+            Val::Num(Num::Decimal(v)) => self.stof.push_str(&format!("{v}d")),
+            Val::Num(Num::BigInt(v)) => self.stof.push_str(&format!("({v} as bigint)")),
             Val::Num(v) => self.stof.push_str(&v.to_string()),
             Val::Blob(blob) => {
                 let str = format!("{blob:?}");
@@ -218,6 +220,7 @@ impl StofExportContext {
                 self.stof.push_str(&context.stof);
             },
             Val::Ver(..) => self.stof.push_str(&val.to_string()),
+            Val::Time(..) => self.stof.push_str(&format!("('{}' as time)", val.to_string())),
             Val::Set(set) => {
                 let mut context = Self::default();
                 context.push_text("{", false);
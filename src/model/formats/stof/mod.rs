@@ -212,8 +212,9 @@ impl Format for BstfFormat {
 
 #[cfg(test)]
 mod tests {
+    use bytes::Bytes;
     use colored::Colorize;
-    use crate::model::{Graph, Profile};
+    use crate::{model::{Graph, Profile}, runtime::Val};
 
     #[test]
     fn stof_suite() {
@@ -231,6 +232,23 @@ mod tests {
         }
     }
 
+    #[test]
+    fn bstf_old_layout() {
+        // serialized before time values were added (new value variants go at the end of the enum)
+        let bytes = include_bytes!("tests/formats/old_layout.bstf");
+        let mut graph = Graph::default();
+        graph.binary_import("bstf", Bytes::from_static(bytes), None, &Profile::test()).unwrap();
+
+        assert_eq!(graph.field_value("name", None), Some(Val::from("old")));
+        assert_eq!(graph.field_value("version", None), Some(Val::Ver(1, 2, 3, None, None)));
+        assert_eq!(graph.field_value("child.value", None), Some(Val::from(42)));
+        assert!(matches!(graph.field_value("list", None), Some(Val::List(list)) if list.len() == 3));
+        assert!(matches!(graph.field_value("tup", None), Some(Val::Tup(tup)) if tup.len() == 2));
+        assert!(matches!(graph.field_value("m", None), Some(Val::Map(map)) if map.len() == 1));
+        assert!(matches!(graph.field_value("ref", None), Some(Val::Obj(_))));
+        assert_eq!(graph.call("answer", None, vec![]).unwrap(), Val::from(42));
+    }
+
     #[test]
     fn stof_docs() {
         let mut graph = Graph::default();
@@ -38,7 +38,9 @@ impl Format for TextFormat {
                 fvar = Some(field.value.clone());
             }
             if let Some(mut fvar) = fvar {
-                fvar.set(&Variable::val(Val::Str(src.into())), graph, None)?;
+                let val = Val::Str(src.into());
+                graph.quota_set(&field_ref, &val)?;
+                fvar.set(&Variable::val(val), graph, None)?;
                 if let Some(field) = graph.get_mut_stof_data::<Field>(&field_ref) {
                     field.value = fvar;
                 }
@@ -88,7 +90,9 @@ impl Format for MdFormat {
                 fvar = Some(field.value.clone());
             }
             if let Some(mut fvar) = fvar {
-                fvar.set(&Variable::val(Val::Str(src.into())), graph, None)?;
+                let val = Val::Str(src.into());
+                graph.quota_set(&field_ref, &val)?;
+                fvar.set(&Variable::val(val), graph, None)?;
                 if let Some(field) = graph.get_mut_stof_data::<Field>(&field_ref) {
                     field.value = fvar;
                 }
@@ -15,8 +15,9 @@
 //
 
 use imbl::Vector;
-use toml::{Table, Value};
-use crate::{model::{Field, Graph, NodeRef, NOEXPORT_FIELD_ATTR}, runtime::{Num, Val, ValRef}};
+use num_traits::ToPrimitive;
+use toml::{value::Datetime, Table, Value};
+use crate::{model::{time::instant_rfc3339, Field, Graph, NodeRef, NOEXPORT_FIELD_ATTR}, runtime::{Num, Val, ValRef}};
 
 
 pub(super) fn toml_value_from_node(graph: &Graph, node: &NodeRef) -> Table {
@@ -58,6 +59,14 @@ pub(super) fn toml_value(graph: &Graph, val: Val) -> Option<Value> {
                 Num::Int(v) => Some(Value::Integer(v)),
                 Num::Float(v) => Some(Value::Float(v)),
                 Num::Units(v, _) => Some(Value::Float(v)),
+                Num::Decimal(v) => Some(Value::String(v.to_string())), // TOML floats are not exact
+                Num::BigInt(v) => {
+                    // TOML integers are 64-bit
+                    match v.to_i64() {
+                        Some(v) => Some(Value::Integer(v)),
+                        None => Some(Value::String(v.to_string())),
+                    }
+                },
             }
         },
         Val::Blob(blob) => {
@@ -72,6 +81,14 @@ pub(super) fn toml_value(graph: &Graph, val: Val) -> Option<Value> {
         Val::List(vals) => Some(value_from_array(graph, vals)),
         Val::Tup(vals) => Some(value_from_array(graph, vals)),
         Val::Ver(..) => Some(Value::String(val.to_string())),
+        Val::Time(ms, zone) => {
+            // TOML datetimes carry an offset, but not a zone name
+            let rfc3339 = instant_rfc3339(ms, zone.as_ref())?;
+            match rfc3339.parse::<Datetime>() {
+                Ok(datetime) => Some(Value::Datetime(datetime)),
+                Err(_) => Some(Value::String(rfc3339)),
+            }
+        },
         Val::Set(vals) => Some(value_from_array(graph, vals.into_iter().collect())),
         Val::Obj(nref) => {
             let map = toml_value_from_node(graph, &nref);
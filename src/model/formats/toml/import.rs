@@ -16,8 +16,8 @@
 
 use imbl::Vector;
 use rustc_hash::FxHashMap;
-use toml::{Table, Value};
-use crate::{model::{Field, Graph, NodeRef, SId, NOEXPORT_FIELD_ATTR}, runtime::{Val, ValRef, Variable}};
+use toml::{value::Datetime, Table, Value};
+use crate::{model::{Field, Graph, NodeRef, SId, NOEXPORT_FIELD_ATTR}, parser::datetime::parse_time_alone, runtime::{Val, ValRef, Variable}};
 
 
 pub(super) fn parse_toml_object_value(graph: &mut Graph, node: &NodeRef, table: Table) {
@@ -42,7 +42,7 @@ pub(super) fn parse_toml_field_value(graph: &mut Graph, node: &NodeRef, value: V
             Field::new(Variable::new(graph, true, Val::from(val), false), None)
         },
         Value::Datetime(val) => {
-            Field::new(Variable::new(graph, true, Val::Str(val.to_string().into()), false), None)
+            Field::new(Variable::new(graph, true, toml_datetime(&val), false), None)
         },
         Value::Array(vals) => {
             let mut tf_arr = Vector::default();
@@ -50,7 +50,13 @@ pub(super) fn parse_toml_field_value(graph: &mut Graph, node: &NodeRef, value: V
             Field::new(Variable::new(graph, true, Val::List(tf_arr), false), None)
         },
         Value::Table(value) => {
-            let child_node = graph.insert_node(field, Some(node.clone()), true);
+            let child_node = match graph.try_insert_node(field, Some(node.clone()), true) {
+                Ok(child_node) => child_node,
+                Err(error) => {
+                    graph.refuse(error); // raised by the import
+                    return Field::new(Variable::new(graph, true, Val::Null, false), None);
+                },
+            };
             parse_toml_object_value(graph, &child_node, value);
 
             let mut attrs = FxHashMap::default();
@@ -76,7 +82,7 @@ pub(super) fn parse_toml_array_values(graph: &mut Graph, node: &NodeRef, vals: V
                 res.push_back(ValRef::new(Val::Bool(val)));
             },
             Value::Datetime(val) => {
-                res.push_back(ValRef::new(Val::Str(val.to_string().into())));  
+                res.push_back(ValRef::new(toml_datetime(&val)));
             },
             Value::Array(vals) => {
                 let mut tf_arr = Vector::default();
@@ -85,10 +91,20 @@ pub(super) fn parse_toml_array_values(graph: &mut Graph, node: &NodeRef, vals: V
             },
             Value::Table(table) => {
                 let id = SId::default();
-                let child_node = graph.insert_node_id(&id, &id, Some(node.clone()), false);
-                parse_toml_object_value(graph, &child_node, table);
-                res.push_back(ValRef::new(Val::Obj(child_node)));
+                match graph.try_insert_node_id(&id, &id, Some(node.clone()), false) {
+                    Ok(child_node) => {
+                        parse_toml_object_value(graph, &child_node, table);
+                        res.push_back(ValRef::new(Val::Obj(child_node)));
+                    },
+                    Err(error) => graph.refuse(error), // raised by the import
+                }
             },
         }
     }
 }
+
+/// TOML datetime value (times without a date stay strings).
+fn toml_datetime(val: &Datetime) -> Val {
+    let val = val.to_string();
+    parse_time_alone(&val).unwrap_or(Val::Str(val.into()))
+}
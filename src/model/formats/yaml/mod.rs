@@ -15,19 +15,19 @@
 //
 
 use serde_json::Value;
-use crate::{model::{Format, Graph, NodeRef, Profile, export::json_value_from_node, import::parse_json_object_value}, runtime::Error};
+use crate::{model::{Format, Graph, NodeRef, Profile, export::json_value_from_node, import::parse_json_object}, runtime::Error};
 
 
 #[derive(Debug)]
 pub struct YamlFormat;
 impl Format for YamlFormat {
     fn identifiers(&self) -> Vec<String> {
-        vec!["yaml".into(), "yml".into()]
+        vec!["yaml".into(), "yml".into(), "yaml:typed".into(), "yml:typed".into()]
     }
     fn content_type(&self) -> String {
         "application/yaml".into()
     }
-    fn string_import(&self, graph: &mut Graph, _format: &str, src: &str, node: Option<NodeRef>, _profile: &Profile) -> Result<(), Error> {
+    fn string_import(&self, graph: &mut Graph, format: &str, src: &str, node: Option<NodeRef>, _profile: &Profile) -> Result<(), Error> {
         if src.is_empty() { return Ok(()); }
         match serde_yaml::from_str::<Value>(src) {
             Ok(value) => {
@@ -35,7 +35,7 @@ impl Format for YamlFormat {
                 if let Some(nd) = node {
                     parse_node = nd;
                 }
-                parse_json_object_value(graph, &parse_node, value);
+                parse_json_object(graph, &parse_node, value, format.ends_with(":typed"), None); // typed: RFC 3339 strings are time values
                 Ok(())
             },
             Err(error) => {
@@ -50,7 +50,7 @@ impl Format for YamlFormat {
         } else {
             exp_node = graph.main_root().expect("graph does not have a main 'root' node for default YAML export");
         }
-        let value = json_value_from_node(graph, &exp_node);
+        let value = json_value_from_node(graph, &exp_node, false);
         match serde_yaml::to_string(&value) {
             Ok(yaml) => {
                 Ok(yaml)
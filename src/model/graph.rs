@@ -20,7 +20,7 @@ use bytes::Bytes;
 use colored::Colorize;
 use rustc_hash::{FxHashMap, FxHashSet};
 use serde::{Deserialize, Serialize};
-use crate::{model::{BstfFormat, BytesFormat, Data, DataRef, Field, Format, INVALID_NODE_NEW, JsonFormat, LibFunc, MdDocsFormat, MdFormat, Node, NodeRef, Profile, SId, SPath, StofData, StofFormat, TextFormat, TomlFormat, UrlEncodedFormat, YamlFormat, blob::insert_blob_lib, libraries::{data::insert_data_lib, function::insert_fn_lib}, libs::insert_lib_documentation, list::insert_list_lib, map::insert_map_lib, md::insert_md_lib, num::insert_number_lib, obj::insert_obj_lib, prompt::insert_prompt_lib, set::insert_set_lib, stof_std::stof_std_lib, string::insert_string_lib, time::insert_time_lib, tup::insert_tup_lib, ver::insert_semver_lib}, parser::context::ParseContext, runtime::{Error, Runtime, Val, Variable, table::SymbolTable}};
+use crate::{model::{BstfFormat, BytesFormat, Data, DataRef, Field, Format, INVALID_NODE_NEW, JsonFormat, LibFunc, Limits, Quota, SandboxPolicy, Usage, val_size, MdDocsFormat, MdFormat, Node, NodeRef, Profile, SId, SPath, StofData, StofFormat, TextFormat, TomlFormat, UrlEncodedFormat, YamlFormat, blob::insert_blob_lib, chan::insert_chan_lib, libraries::{data::insert_data_lib, function::insert_fn_lib}, libs::insert_lib_documentation, list::insert_list_lib, map::insert_map_lib, md::insert_md_lib, num::insert_number_lib, obj::insert_obj_lib, prompt::insert_prompt_lib, promise::insert_promise_lib, rand::{insert_rand_lib, Rng}, regexp::insert_regex_lib, set::insert_set_lib, stof_std::stof_std_lib, string::insert_string_lib, time::insert_time_lib, tup::insert_tup_lib, ver::insert_semver_lib}, parser::context::ParseContext, runtime::{Error, Runtime, Val, Variable, instructions::computed::ComputedIns, table::SymbolTable}};
 
 #[cfg(feature = "system")]
 use crate::model::{filesys::fs_library};
@@ -42,6 +42,8 @@ use crate::model::docx::DocxFormat;
 
 #[cfg(feature = "age_encrypt")]
 use crate::model::age::insert_age_encrypt_library;
+#[cfg(feature = "crypto")]
+use crate::model::{crypto::insert_crypto_lib, formats::signed::SignedFormat};
 
 /// Root node name.
 pub const ROOT_NODE_NAME: ArcStr = literal!("root");
@@ -78,6 +80,42 @@ pub struct Graph {
     pub libdocs: FxHashMap<ArcStr, String>,
     #[serde(skip)]
     pub libfuncs: FxHashMap<ArcStr, FxHashMap<String, LibFunc>>,
+
+    /// Computed fields that are up to date, with the fields they read when computed.
+    #[serde(skip)]
+    pub computed: FxHashMap<DataRef, FxHashSet<DataRef>>,
+    /// Fields read by computed fields (field -> computed fields that depend on it).
+    #[serde(skip)]
+    pub dependents: FxHashMap<DataRef, FxHashSet<DataRef>>,
+
+    /// Resource limits (fuel & quotas) for this graph and the processes run on it.
+    #[serde(skip)]
+    pub limits: Limits,
+    /// String/blob bytes currently held by data in this graph (byte quotas).
+    /// Measured the first time a byte quota needs it, then kept current as data is inserted, set, and removed.
+    #[serde(skip)]
+    pub bytes: Option<usize>,
+    /// Quota & usage of the process currently running on this graph (set by the runtime).
+    #[serde(skip)]
+    pub process_quota: Option<(Quota, Usage)>,
+    /// Quota error from a data insert (or an import) that could not return it, raised by the runtime after the current instruction.
+    #[serde(skip)]
+    pub quota_error: Option<Error>,
+
+    /// Sandbox policy (capabilities) for this graph and the processes run on it.
+    #[serde(skip)]
+    pub sandbox: Option<Arc<SandboxPolicy>>,
+    /// Sandbox policy of the process currently running on this graph (set by the runtime).
+    #[serde(skip)]
+    pub process_sandbox: Option<Arc<SandboxPolicy>>,
+
+    /// Ed25519 public keys (by key ID) trusted when importing signed documents ("stof:signed").
+    #[serde(skip)]
+    pub trusted_keys: FxHashMap<String, Bytes>,
+
+    /// Random number generator (Rand library), created on first use unless seeded.
+    #[serde(skip)]
+    pub rng: Option<Rng>,
 }
 impl Default for Graph {
     fn default() -> Self {
@@ -93,6 +131,16 @@ impl Default for Graph {
             formats: Default::default(),
             libdocs: Default::default(),
             libfuncs: Default::default(),
+            computed: Default::default(),
+            dependents: Default::default(),
+            limits: Default::default(),
+            bytes: None,
+            process_quota: None,
+            quota_error: None,
+            sandbox: None,
+            process_sandbox: None,
+            trusted_keys: Default::default(),
+            rng: None,
         };
         graph.load_std_formats();
         graph.insert_std_lib();
@@ -138,6 +186,8 @@ impl Graph {
     /// Ensure main root.
     /// Make sure the main root exists in this graph.
     /// Will create a root node named "root" if not found.
+    /// Not checked against quotas: every graph has a main root, and imports and field lookups need it to exist.
+    /// It still counts towards the graph's node total, so a quota without room for it refuses the next node instead.
     pub fn ensure_main_root(&mut self) -> NodeRef {
         if let Some(nref) = self.main_root() {
             nref
@@ -164,12 +214,133 @@ impl Graph {
 
     #[inline(always)]
     /// Insert data deadpool.
+    /// Called with all data removed from this graph.
     pub fn insert_data_deadpool(&mut self, data: Data) {
+        if let Some(bytes) = &mut self.bytes {
+            *bytes = bytes.saturating_sub(data.data.value_size().0);
+        }
         if self.deadpools_enabled {
             self.data_deadpool.insert(data.id.clone(), data);
         }
     }
 
+
+    /*****************************************************************************
+     * Quotas.
+     *****************************************************************************/
+
+    #[inline(always)]
+    /// Are any quotas in effect (for this graph or the running process)?
+    pub fn quotas_enabled(&self) -> bool {
+        !self.limits.graph.is_empty() || self.process_quota.is_some()
+    }
+
+    #[inline]
+    /// Do the quotas in effect need the size of inserted values?
+    pub fn quotas_sized(&self) -> bool {
+        self.limits.graph.sized() || self.process_quota.is_some_and(|(quota, _)| quota.sized())
+    }
+
+    /// String/blob bytes currently held by data in this graph, measured if not already known.
+    pub fn graph_bytes(&mut self) -> usize {
+        if let Some(bytes) = self.bytes {
+            return bytes;
+        }
+        let bytes = self.data.values().map(|data| data.data.value_size().0).sum();
+        self.bytes = Some(bytes);
+        bytes
+    }
+
+    /// Check an insert against the graph and process quotas, recording it if allowed.
+    /// Freed bytes are held by a value the insert replaces (Ex. setting a field), so only the growth counts.
+    /// If a quota would be exceeded, returns the error (don't insert).
+    pub fn quota_insert(&mut self, insert: Usage, freed: usize, list_len: usize) -> Result<(), Error> {
+        if !self.quotas_enabled() { return Ok(()); }
+        if !self.quotas_sized() {
+            self.bytes = None; // sizes aren't being measured, so re-measure when needed
+        }
+
+        let mut growth = insert;
+        growth.bytes = insert.bytes.saturating_sub(freed);
+
+        let mut graph_usage = Usage {
+            nodes: self.nodes.len(),
+            data: self.data.len(),
+            bytes: 0,
+        };
+        if self.limits.graph.max_bytes.is_some() {
+            graph_usage.bytes = self.graph_bytes().saturating_sub(freed);
+        }
+        let mut res = self.limits.graph.check(&graph_usage, &insert, list_len);
+        if res.is_ok() {
+            if let Some((quota, usage)) = &self.process_quota {
+                res = quota.check(usage, &growth, list_len);
+            }
+        }
+        match res {
+            Ok(_) => {
+                if let Some(bytes) = &mut self.bytes {
+                    *bytes = bytes.saturating_sub(freed) + insert.bytes;
+                }
+                if let Some((_, usage)) = &mut self.process_quota {
+                    usage.add(&growth);
+                }
+                Ok(())
+            },
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Keep the error from an insert that can't return it (data inserts and imports), for the runtime or import to raise.
+    /// Only the first error is kept.
+    pub(crate) fn refuse(&mut self, error: Error) {
+        if self.quota_error.is_none() {
+            self.quota_error = Some(error);
+        }
+    }
+
+    /// Check setting a data component's value (Ex. assigning to a field) against the quotas.
+    /// The bytes held by the current value are freed by the set.
+    pub fn quota_set(&mut self, dref: &DataRef, val: &Val) -> Result<(), Error> {
+        if !self.quotas_enabled() { return Ok(()); }
+        let mut insert = Usage::default();
+        let mut freed = 0;
+        let mut list_len = 0;
+        if self.quotas_sized() {
+            (insert.bytes, list_len) = val_size(val);
+            if let Some(data) = dref.data(self) {
+                freed = data.data.value_size().0;
+            }
+        }
+        self.quota_insert(insert, freed, list_len)
+    }
+
+    /// Check a value growing in place (Ex. pushing onto a list or adding to a string) against the quotas.
+    /// Growth counts towards the process, and the graph total once the value is inserted or set.
+    pub fn quota_grow(&mut self, bytes: usize, list_len: usize) -> Result<(), Error> {
+        if !self.quotas_enabled() || (bytes == 0 && list_len == 0) { return Ok(()); }
+
+        let growth = Usage { bytes, ..Default::default() };
+        let mut res = Ok(());
+        if let Some(max) = self.limits.graph.max_bytes {
+            if bytes > 0 && self.graph_bytes() + bytes > max {
+                res = Err(Error::ByteQuota(max));
+            }
+        }
+        if res.is_ok() {
+            res = self.limits.graph.check(&Usage::default(), &Usage::default(), list_len);
+        }
+        if res.is_ok() {
+            if let Some((quota, usage)) = &mut self.process_quota {
+                res = quota.check(usage, &growth, list_len);
+                if res.is_ok() {
+                    usage.add(&growth);
+                }
+            }
+        }
+        res
+    }
+
     
     /*****************************************************************************
      * Types.
@@ -248,6 +419,10 @@ impl Graph {
             insert_prompt_lib(self);
             insert_md_lib(self);
             insert_time_lib(self);
+            insert_promise_lib(self);
+            insert_chan_lib(self);
+            insert_rand_lib(self);
+            insert_regex_lib(self);
             insert_profile_lib(self, &Profile::default());
         }
         
@@ -267,6 +442,10 @@ impl Graph {
         // Age lib
         #[cfg(feature = "age_encrypt")]
         insert_age_encrypt_library(self);
+
+        // Crypto lib
+        #[cfg(feature = "crypto")]
+        insert_crypto_lib(self);
     }
 
     /// Insert library documentation.
@@ -325,6 +504,7 @@ impl Graph {
      *****************************************************************************/
     
     /// Insert a root node directly.
+    /// Not checked against quotas (see try_insert_root).
     pub fn insert_root(&mut self, name: impl Into<SId>) -> NodeRef {
         let mut node = Node::new(name.into(), SId::default(), false);
         node.invalidate(INVALID_NODE_NEW);
@@ -335,14 +515,27 @@ impl Graph {
         nref
     }
 
+    /// Insert a root node, checked against the graph and process node quotas.
+    pub fn try_insert_root(&mut self, name: impl Into<SId>) -> Result<NodeRef, Error> {
+        self.quota_insert(Usage { nodes: 1, ..Default::default() }, 0, 0)?;
+        Ok(self.insert_root(name))
+    }
+
     #[inline(always)]
     /// Insert a child node directly.
     pub fn insert_child(&mut self, name: impl Into<SId>, parent: impl Into<NodeRef>, field: bool) -> NodeRef {
         self.insert_node(name, Some(parent.into()), field)
     }
+
+    #[inline(always)]
+    /// Insert a child node, checked against the graph and process node quotas.
+    pub fn try_insert_child(&mut self, name: impl Into<SId>, parent: impl Into<NodeRef>, field: bool) -> Result<NodeRef, Error> {
+        self.try_insert_node(name, Some(parent.into()), field)
+    }
     
     /// Insert a node.
     /// If a parent is not provided, the behavior is the same as insert root.
+    /// Not checked against quotas (see try_insert_node).
     pub fn insert_node(&mut self, name: impl Into<SId>, parent: Option<NodeRef>, field: bool) -> NodeRef {
         let node;
         if field && parent.is_some() {
@@ -361,7 +554,14 @@ impl Graph {
         self.insert_stof_node(node, parent)
     }
 
+    /// Insert a node, checked against the graph and process node quotas.
+    pub fn try_insert_node(&mut self, name: impl Into<SId>, parent: Option<NodeRef>, field: bool) -> Result<NodeRef, Error> {
+        self.quota_insert(Usage { nodes: 1, ..Default::default() }, 0, 0)?;
+        Ok(self.insert_node(name, parent, field))
+    }
+
     /// Insert a node with an ID.
+    /// Not checked against quotas (see try_insert_node_id).
     pub fn insert_node_id(&mut self, name: impl Into<SId>, id: impl Into<SId>, parent: Option<NodeRef>, field: bool) -> NodeRef {
         let node;
         if field && parent.is_some() {
@@ -380,8 +580,27 @@ impl Graph {
         self.insert_stof_node(node, parent)
     }
 
+    /// Insert a node with an ID, checked against the graph and process node quotas.
+    pub fn try_insert_node_id(&mut self, name: impl Into<SId>, id: impl Into<SId>, parent: Option<NodeRef>, field: bool) -> Result<NodeRef, Error> {
+        let id: SId = id.into();
+        if !self.nodes.contains_key(&id) {
+            self.quota_insert(Usage { nodes: 1, ..Default::default() }, 0, 0)?;
+        }
+        Ok(self.insert_node_id(name, id, parent, field))
+    }
+
+    /// Insert stof node, checked against the graph and process node quotas.
+    /// Don't call this with nodes that already exist in the graph (have a valid ID already).
+    pub fn try_insert_stof_node(&mut self, node: Node, parent: Option<NodeRef>) -> Result<NodeRef, Error> {
+        if self.quotas_enabled() && !self.nodes.contains_key(&node.id) {
+            self.quota_insert(Usage { nodes: 1, ..Default::default() }, 0, 0)?;
+        }
+        Ok(self.insert_stof_node(node, parent))
+    }
+
     /// Insert stof node.
     /// Don't call this with nodes that already exist in the graph (have a valid ID already).
+    /// Not checked against quotas (see try_insert_stof_node).
     pub fn insert_stof_node(&mut self, mut node: Node, parent: Option<NodeRef>) -> NodeRef {
         if let Some(parent) = &parent {
             if parent.node_exists(&self) {
@@ -429,7 +648,13 @@ impl Graph {
                 if let Some(custom) = &custom_insert {
                     current = Some(custom(self, &segment, current));
                 } else {
-                    current = Some(self.insert_node(&segment, current, fields));
+                    match self.try_insert_node(&segment, current, fields) {
+                        Ok(nref) => current = Some(nref),
+                        Err(error) => {
+                            self.refuse(error); // raised after the instruction or import
+                            return None;
+                        },
+                    }
                 }
             }
         }
@@ -592,7 +817,13 @@ impl Graph {
                 cloned.not_field();
             }
         }
-        let inserted = self.insert_stof_node(cloned, parent);
+        let inserted = match self.try_insert_stof_node(cloned, parent) {
+            Ok(nref) => nref,
+            Err(error) => {
+                self.refuse(error); // raised by the import
+                return false;
+            },
+        };
         for nref in &node.children {
             if let Some(child) = nref.node(other) {
                 self.insert_external_node(other, child, Some(inserted.clone()), None, field);
@@ -632,6 +863,25 @@ impl Graph {
     /// Data in the graph must be associated with a node.
     /// Will overwrite data with the same ID if already in the graph.
     pub fn insert_data(&mut self, node: &NodeRef, mut data: Data) -> Option<DataRef> {
+        if self.quotas_enabled() && node.node_exists(self) {
+            let mut insert = Usage::default();
+            let mut freed = 0;
+            let mut list_len = 0;
+            match self.data.get(&data.id) {
+                Some(old) => if self.quotas_sized() { freed = old.data.value_size().0; },
+                None => insert.data = 1,
+            }
+            if self.quotas_sized() {
+                (insert.bytes, list_len) = data.data.value_size();
+            }
+            if let Err(error) = self.quota_insert(insert, freed, list_len) {
+                self.refuse(error); // raised after the instruction or import
+                return None;
+            }
+        } else {
+            self.bytes = None; // not measured, so re-measure when needed
+        }
+
         let mut res = None;
         let mut replaced = None;
         if let Some(node) = node.node_mut(self) {
@@ -928,8 +1178,9 @@ impl Graph {
     }
 
     /// Flush this graph.
-    /// This operation clears both deadpools, validates all nodes, and validates all data.
+    /// This operation recomputes stale computed fields, clears both deadpools, validates all nodes, and validates all data.
     pub fn flush(&mut self) {
+        self.recompute_fields();
         self.clear_node_deadpool();
         self.clear_data_deadpool();
         for nref in self.dirty_nodes(None) {
@@ -1135,6 +1386,9 @@ impl Graph {
         
         #[cfg(feature = "docx")]
         self.load_format(Arc::new(DocxFormat{}));
+
+        #[cfg(feature = "crypto")]
+        self.load_format(Arc::new(SignedFormat{}));
     }
     
     /// Load a format.
@@ -1199,7 +1453,7 @@ impl Graph {
     /// Binary import into this graph, using a loaded format.
     pub fn binary_import(&mut self, format: &str, bytes: Bytes, node: Option<NodeRef>, profile: &Profile) -> Result<(), Error> {
         let id = format;
-        if let Some(format) = self.get_format(id) {
+        let res = if let Some(format) = self.get_format(id) {
             format.binary_import(self, id, bytes, node, profile)
         } else if let Some(format) = self.get_format_by_content_type(id) {
             format.binary_import(self, id, bytes, node, profile)
@@ -1207,13 +1461,14 @@ impl Graph {
             format.binary_import(self, id, bytes, node, profile)
         } else {
             Err(Error::GraphFormatNotFound)
-        }
+        };
+        self.import_quota(res)
     }
 
     /// Import a string into this graph, using a loaded format.
     pub fn string_import(&mut self, format: &str, src: &str, node: Option<NodeRef>, profile: &Profile) -> Result<(), Error> {
         let id = format;
-        if let Some(format) = self.get_format(id) {
+        let res = if let Some(format) = self.get_format(id) {
             format.string_import(self, id, src, node, profile)
         } else if let Some(format) = self.get_format_by_content_type(id) {
             format.string_import(self, id, src, node, profile)
@@ -1221,17 +1476,28 @@ impl Graph {
             format.string_import(self, id, src, node, profile)
         } else {
             Err(Error::GraphFormatNotFound)
-        }
+        };
+        self.import_quota(res)
     }
 
     /// File import into this graph, using a loaded format.
     pub fn file_import(&mut self, format: &str, path: &str, node: Option<NodeRef>, profile: &Profile) -> Result<(), Error> {
+        self.sandbox_read(path)?;
         let id = format;
-        if let Some(format) = self.get_format(id) {
+        let res = if let Some(format) = self.get_format(id) {
             format.file_import(self, id, path, node, profile)
         } else {
             Err(Error::GraphFormatNotFound)
+        };
+        self.import_quota(res)
+    }
+
+    /// Imports that had inserts refused by a quota error out.
+    fn import_quota(&mut self, res: Result<(), Error>) -> Result<(), Error> {
+        if let Some(error) = self.quota_error.take() {
+            return Err(error);
         }
+        res
     }
 
     /// String export.
@@ -1291,6 +1557,7 @@ impl Graph {
 
     /// File export from this graph, using a loaded format.
     pub fn file_export(&self, format: &str, path: &str, node: Option<NodeRef>) -> Result<(), Error> {
+        self.sandbox_write(path)?;
         let id = format;
         if let Some(format) = self.get_format(id) {
             format.file_export(self, id, path, node)
@@ -1313,8 +1580,16 @@ impl Graph {
         else { pth = path.to_string(); }
 
         if let Some(dref) = Field::field_from_path(self, &pth, start) {
+            let mut stale = false;
             if let Some(field) = self.get_stof_data::<Field>(&dref) {
-                return Some(field.value.get());
+                if !field.is_computed() || self.computed_valid(&dref) {
+                    return Some(field.value.get());
+                }
+                stale = true;
+            }
+            if stale {
+                // out of date computed field, so recompute it now
+                return Runtime::eval(self, Arc::new(ComputedIns::Load(dref))).ok();
             }
         }
         None
@@ -1333,7 +1608,18 @@ impl Graph {
 
     /// Set a field value by path.
     /// Meant to be a quick helper - does not create nodes, fields, etc.
+    /// Not checked against quotas (see try_set_field).
     pub fn set_field(&mut self, var: Variable, path: &str, start: Option<NodeRef>) -> bool {
+        self.set_field_value(var, path, start, false).unwrap_or(false)
+    }
+
+    /// Set a field value by path, checked against the graph and process byte quotas.
+    /// Returns false if the field doesn't exist or can't be set.
+    pub fn try_set_field(&mut self, var: Variable, path: &str, start: Option<NodeRef>) -> Result<bool, Error> {
+        self.set_field_value(var, path, start, true)
+    }
+
+    fn set_field_value(&mut self, var: Variable, path: &str, start: Option<NodeRef>, checked: bool) -> Result<bool, Error> {
         let pth;
         if start.is_none() && !path.contains('.') { pth = format!("root.{path}"); }
         else { pth = path.to_string(); }
@@ -1341,15 +1627,20 @@ impl Graph {
         if let Some(field_ref) = Field::field_from_path(self, &pth, start.clone()) {
             let mut fvar = None;
             if let Some(field) = self.get_stof_data::<Field>(&field_ref) {
-                if !field.can_set() { return false; }
+                if !field.can_set() { return Ok(false); }
                 fvar = Some(field.value.clone());
             }
             if let Some(mut fvar) = fvar {
                 let context;
                 if start.is_some() { context = start; }
                 else { context = Some(self.ensure_main_root()); }
+                if checked {
+                    self.quota_set(&field_ref, &var.val.read())?;
+                } else {
+                    self.bytes = None; // not measured, so re-measure when needed
+                }
                 let res = fvar.set(&var, self, context);
-                if res.is_err() { return false; } // const field
+                if res.is_err() { return Ok(false); } // const field
 
                 if let Some(field) = self.get_mut_stof_data::<Field>(&field_ref) {
                     field.value = fvar;
@@ -1358,9 +1649,73 @@ impl Graph {
             if let Some(field) = field_ref.data_mut(self) {
                 field.invalidate_value();
             }
-            return true;
+            self.invalidate_dependents(&field_ref);
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+
+    /*****************************************************************************
+     * Computed Fields.
+     *****************************************************************************/
+    
+    #[inline]
+    /// Is a computed field up to date?
+    pub fn computed_valid(&self, field: &DataRef) -> bool {
+        self.computed.contains_key(field)
+    }
+
+    /// Record the fields a computed field read when it was (re)computed.
+    pub fn set_computed(&mut self, field: &DataRef, deps: FxHashSet<DataRef>) {
+        for dep in &deps {
+            if let Some(dependents) = self.dependents.get_mut(dep) {
+                dependents.insert(field.clone());
+            } else {
+                let mut dependents = FxHashSet::default();
+                dependents.insert(field.clone());
+                self.dependents.insert(dep.clone(), dependents);
+            }
+        }
+        self.computed.insert(field.clone(), deps);
+    }
+
+    /// A field has changed, so invalidate every computed field that depends on it (transitively).
+    /// Invalidated computed fields get recomputed lazily on read or eagerly on flush.
+    pub fn invalidate_dependents(&mut self, field: &DataRef) {
+        if let Some(dependents) = self.dependents.remove(field) {
+            for dependent in dependents {
+                if let Some(deps) = self.computed.remove(&dependent) {
+                    for dep in deps {
+                        if let Some(others) = self.dependents.get_mut(&dep) {
+                            others.remove(&dependent);
+                        }
+                    }
+                    if let Some(data) = dependent.data_mut(self) {
+                        data.invalidate_value();
+                    }
+                    self.invalidate_dependents(&dependent);
+                }
+            }
+        }
+    }
+
+    /// Recompute all computed fields that are out of date.
+    /// A field that errors while computing stays out of date.
+    pub fn recompute_fields(&mut self) {
+        let mut stale = Vec::new();
+        for (dref, data) in &self.data {
+            if let Some(field) = data.get::<Field>() {
+                if field.is_computed() && !self.computed.contains_key(dref) {
+                    stale.push(dref.clone());
+                }
+            }
+        }
+        for dref in stale {
+            if !self.computed_valid(&dref) {
+                let _ = Runtime::eval(self, Arc::new(ComputedIns::Load(dref)));
+            }
         }
-        false
     }
 
 
@@ -1379,28 +1734,44 @@ impl Graph {
     /// Parse a stof file into this graph, optionally into a specific node.
     /// This serves as an entrypoint for parsing Stof into a graph.
     pub fn parse_stof_file(&mut self, format: &str, path: &str, node: Option<NodeRef>, profile: Profile) -> Result<(), Error> {
-        let mut context = ParseContext::new(self, profile);
-        context.parse_from_file(format, path, node)
+        let res;
+        {
+            let mut context = ParseContext::new(self, profile);
+            res = context.parse_from_file(format, path, node);
+        }
+        self.import_quota(res)
     }
 
-    /// Call a function (by named '.' separated path) in this graph.
-    pub fn call(&mut self, func_path: &str, start: Option<NodeRef>, args: Vec<Val>) -> Result<Val, Error> {
-        let search;
+    /// Function search path for a call (relative to start, or root if not given).
+    fn call_search(&self, func_path: &str, start: Option<NodeRef>) -> Result<String, Error> {
         if let Some(start) = start {
             if let Some(nodepath) = start.node_path(&self, true) {
-                search = format!("{}.{}", nodepath.join("."), func_path);
+                Ok(format!("{}.{}", nodepath.join("."), func_path))
             } else {
-                return Err(Error::Custom("Graph call start node doesn't exist".into()));
+                Err(Error::Custom("Graph call start node doesn't exist".into()))
             }
         } else if func_path.split('.').collect::<Vec<_>>().len() < 2 {
-            search = format!("root.{func_path}");
+            Ok(format!("root.{func_path}"))
         } else {
-            search = func_path.to_string();
+            Ok(func_path.to_string())
         }
+    }
 
+    /// Call a function (by named '.' separated path) in this graph.
+    pub fn call(&mut self, func_path: &str, start: Option<NodeRef>, args: Vec<Val>) -> Result<Val, Error> {
+        let search = self.call_search(func_path, start)?;
         Runtime::call(self, &search, args)
     }
 
+    #[cfg(any(feature = "js", feature = "tokio"))]
+    /// Call a function in this graph asynchronously (see Graph::call).
+    /// With tokio, the processes are driven by the host executor (see Runtime::run_async),
+    /// and dropping the future cancels the call.
+    pub async fn call_async(&mut self, func_path: &str, start: Option<NodeRef>, args: Vec<Val>) -> Result<Val, Error> {
+        let search = self.call_search(func_path, start)?;
+        Runtime::async_call(self, &search, args).await
+    }
+
     #[inline]
     /// Test this graph, calling all #[test] functions, optionally resulting in an Err or always Ok.
     pub fn test(&mut self, context: Option<String>, throw: bool) -> Result<String, String> {
@@ -1473,7 +1844,7 @@ fn deserialize_nodes<'de, D>(deserializer: D) -> Result<FxHashMap<NodeRef, Node>
 
 #[cfg(test)]
 mod tests {
-    use crate::{model::{Data, Graph, ROOT_NODE_NAME, SPath, StofData}, runtime::Variable};
+    use crate::{model::{Data, Field, Graph, ROOT_NODE_NAME, SPath, StofData}, runtime::Variable};
 
     #[test]
     fn new_with_id() {
@@ -1722,4 +2093,87 @@ mod tests {
         let msg = graph.field_value("msg", Some(sub)).unwrap();
         assert_eq!(msg, "reset".into());
     }
+
+    #[test]
+    fn flush_computed_fields() {
+        let mut graph = Graph::default();
+        graph.parse_stof_src(r#"
+        
+        price: 10
+        count: 2
+
+        #[computed('calc_total')]
+        total: 0
+
+        fn calc_total() -> int { self.price * self.count }
+
+        "#, None, Default::default()).unwrap();
+
+        graph.flush();
+        let total = Field::field_from_path(&mut graph, "root.total", None).unwrap();
+        assert!(graph.computed_valid(&total));
+        assert_eq!(graph.field_value("total", None).unwrap(), 20.into());
+
+        graph.set_field(Variable::val(5.into()), "count", None);
+        assert!(!graph.computed_valid(&total));
+        graph.flush();
+        assert!(graph.computed_valid(&total));
+        assert_eq!(graph.field_value("total", None).unwrap(), 50.into());
+
+        graph.set_field(Variable::val(1.into()), "price", None);
+        assert_eq!(graph.field_value("total", None).unwrap(), 5.into()); // lazy on read
+        assert!(!graph.set_field(Variable::val(0.into()), "total", None));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn call_async() {
+        let mut graph = Graph::default();
+        graph.parse_stof_src(r#"
+            fn add(a: int, b: int) -> int { a + b }
+        "#, None, crate::model::Profile::test()).unwrap();
+
+        fn is_send<T: Send>(_: &T) {}
+        let future = graph.call_async("add", None, vec![1.into(), 2.into()]);
+        is_send(&future);
+        assert_eq!(future.await.unwrap(), 3.into());
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test(flavor = "current_thread")]
+    async fn call_async_parks_while_sleeping() {
+        let src = r#"
+            fn nap() -> str { sleep(150); 'rested' }
+        "#;
+        let mut a = Graph::default();
+        a.parse_stof_src(src, None, crate::model::Profile::test()).unwrap();
+        let mut b = Graph::default();
+        b.parse_stof_src(src, None, crate::model::Profile::test()).unwrap();
+
+        // both calls share one thread, so they only finish together if sleeping processes park
+        let start = std::time::Instant::now();
+        let (a, b) = tokio::join!(a.call_async("nap", None, vec![]), b.call_async("nap", None, vec![]));
+        assert_eq!(a.unwrap(), "rested".into());
+        assert_eq!(b.unwrap(), "rested".into());
+        assert!(start.elapsed() < std::time::Duration::from_millis(280));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn call_async_cancel_on_drop() {
+        let mut graph = Graph::default();
+        graph.parse_stof_src(r#"
+            count: 0
+            fn forever() { while (true) { self.count += 1; sleep(5); } }
+            fn get_count() -> int { self.count }
+        "#, None, crate::model::Profile::test()).unwrap();
+
+        let res = tokio::time::timeout(std::time::Duration::from_millis(50), graph.call_async("forever", None, vec![])).await;
+        assert!(res.is_err()); // timed out, so the call was dropped
+
+        let count = graph.call("get_count", None, vec![]).unwrap();
+        assert_ne!(count, 0.into());
+        tokio::time::sleep(std::time::Duration::from_millis(30)).await;
+        assert_eq!(graph.call("get_count", None, vec![]).unwrap(), count); // no longer running
+    }
 }
@@ -21,7 +21,7 @@ use base64::{engine::general_purpose::{STANDARD, URL_SAFE}, Engine as _};
 use bytes::Bytes;
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
-use crate::{model::{blob::ops::{blob_at, blob_base64, blob_from_base64, blob_from_url_base64, blob_from_utf8, blob_len, blob_size, blob_url_base64, blob_utf8}, Graph}, runtime::{instruction::{Instruction, Instructions}, proc::ProcEnv, Error, Num, Units, Val, Variable}};
+use crate::{model::{blob::ops::{blob_at, blob_base64, blob_from_base64, blob_from_hex, blob_from_url_base64, blob_from_utf8, blob_hex, blob_len, blob_size, blob_url_base64, blob_utf8}, Graph}, runtime::{instruction::{Instruction, Instructions}, proc::ProcEnv, Error, Num, Units, Val, Variable}};
 mod ops;
 
 /// Library name.
@@ -36,10 +36,12 @@ pub fn insert_blob_lib(graph: &mut Graph) {
     graph.insert_libfunc(blob_utf8());
     graph.insert_libfunc(blob_base64());
     graph.insert_libfunc(blob_url_base64());
+    graph.insert_libfunc(blob_hex());
 
     graph.insert_libfunc(blob_from_utf8());
     graph.insert_libfunc(blob_from_base64());
     graph.insert_libfunc(blob_from_url_base64());
+    graph.insert_libfunc(blob_from_hex());
 }
 
 
@@ -50,9 +52,11 @@ lazy_static! {
     pub(self) static ref UTF8_BLOB: Arc<dyn Instruction> = Arc::new(BlobIns::Utf8Str);
     pub(self) static ref BASE64_BLOB: Arc<dyn Instruction> = Arc::new(BlobIns::Base64Str);
     pub(self) static ref URL_SAFE_BLOB: Arc<dyn Instruction> = Arc::new(BlobIns::UrlSafeBase64Str);
+    pub(self) static ref HEX_BLOB: Arc<dyn Instruction> = Arc::new(BlobIns::HexStr);
     pub(self) static ref FROM_UTF8_BLOB: Arc<dyn Instruction> = Arc::new(BlobIns::FromUtf8Str);
     pub(self) static ref FROM_BASE64_BLOB: Arc<dyn Instruction> = Arc::new(BlobIns::FromBase64Str);
     pub(self) static ref FROM_URL_SAFE_BLOB: Arc<dyn Instruction> = Arc::new(BlobIns::FromUrlSafeBase64Str);
+    pub(self) static ref FROM_HEX_BLOB: Arc<dyn Instruction> = Arc::new(BlobIns::FromHexStr);
 }
 
 
@@ -66,10 +70,12 @@ pub enum BlobIns {
     Utf8Str,
     Base64Str,
     UrlSafeBase64Str,
+    HexStr,
 
     FromUtf8Str,
     FromBase64Str,
     FromUrlSafeBase64Str,
+    FromHexStr,
 }
 #[typetag::serde(name = "BlobIns")]
 impl Instruction for BlobIns {
@@ -202,6 +208,39 @@ impl Instruction for BlobIns {
                 }
                 Err(Error::BlobFromUrlSafeBase64Str)
             },
+            Self::HexStr => {
+                if let Some(var) = env.stack.pop() {
+                    match var.val.read().deref() {
+                        Val::Blob(blob) => {
+                            let res = blob.iter().map(|byte| format!("{byte:02x}")).collect::<String>();
+                            env.stack.push(Variable::val(Val::Str(res.into())));
+                            return Ok(None);
+                        },
+                        _ => {}
+                    }
+                }
+                Err(Error::BlobHexStr)
+            },
+            Self::FromHexStr => {
+                if let Some(var) = env.stack.pop() {
+                    match var.val.read().deref() {
+                        Val::Str(hex) => {
+                            let hex = hex.trim();
+                            if hex.len() % 2 == 0 && hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+                                let bytes = (0..hex.len()).step_by(2)
+                                    .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+                                    .collect::<Result<Vec<u8>, _>>();
+                                if let Ok(bytes) = bytes {
+                                    env.stack.push(Variable::val(Val::Blob(bytes.into())));
+                                    return Ok(None);
+                                }
+                            }
+                        },
+                        _ => {}
+                    }
+                }
+                Err(Error::BlobFromHexStr)
+            },
         }
     }
 }
@@ -16,7 +16,7 @@
 
 use std::sync::Arc;
 use imbl::vector;
-use crate::{model::{blob::{AT_BLOB, BASE64_BLOB, BLOB_LIB, FROM_BASE64_BLOB, FROM_URL_SAFE_BLOB, FROM_UTF8_BLOB, LEN_BLOB, SIZE_BLOB, URL_SAFE_BLOB, UTF8_BLOB}, LibFunc, Param}, runtime::{instruction::Instructions, NumT, Type}};
+use crate::{model::{blob::{AT_BLOB, BASE64_BLOB, BLOB_LIB, FROM_BASE64_BLOB, FROM_HEX_BLOB, FROM_URL_SAFE_BLOB, FROM_UTF8_BLOB, HEX_BLOB, LEN_BLOB, SIZE_BLOB, URL_SAFE_BLOB, UTF8_BLOB}, LibFunc, Param}, runtime::{instruction::Instructions, NumT, Type}};
 
 
 /// Len.
@@ -254,3 +254,55 @@ assert_eq(bytes as str, "hello");
         })
     }
 }
+
+/// Hex.
+pub fn blob_hex() -> LibFunc {
+    LibFunc {
+        library: BLOB_LIB.clone(),
+        name: "hex".into(),
+        is_async: false,
+        docs: r#"# Blob.hex(bytes: blob) -> str
+Transform this blob into a lowercase hexadecimal string (2 characters per byte).
+```rust
+const bytes: blob = "hello";
+assert_eq(bytes.hex(), "68656c6c6f");
+```"#.into(),
+        params: vector![
+            Param { name: "blob".into(), param_type: Type::Blob, default: None }
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(HEX_BLOB.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// From Hex.
+pub fn blob_from_hex() -> LibFunc {
+    LibFunc {
+        library: BLOB_LIB.clone(),
+        name: "from_hex".into(),
+        is_async: false,
+        docs: r#"# Blob.from_hex(val: str) -> blob
+Transform a hexadecimal string (upper or lowercase, 2 characters per byte) into a blob.
+```rust
+const bytes: blob = Blob.from_hex("68656C6C6F");
+assert_eq(bytes as str, "hello");
+```"#.into(),
+        params: vector![
+            Param { name: "val".into(), param_type: Type::Str, default: None }
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(FROM_HEX_BLOB.clone());
+            Ok(instructions)
+        })
+    }
+}
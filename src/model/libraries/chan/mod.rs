@@ -0,0 +1,382 @@
+//
+// Copyright 2025 Formata, Inc. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use std::{collections::VecDeque, ops::Deref, sync::Arc};
+use arcstr::{literal, ArcStr};
+use imbl::vector;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use crate::{model::{chan::ops::{chan_close, chan_is_closed, chan_len, chan_new, chan_recv, chan_select, chan_send, chan_try_recv}, DataRef, Graph, SId, StofData}, runtime::{instruction::{Instruction, Instructions}, instructions::Base, proc::ProcEnv, wake, Error, Num, Val, ValRef, Variable, WakeRef}};
+
+mod ops;
+
+
+/// Library name.
+pub(self) const CHAN_LIB: ArcStr = literal!("Chan");
+
+
+/// Add the chan library to a graph.
+pub fn insert_chan_lib(graph: &mut Graph) {
+    graph.insert_libfunc(chan_new());
+    graph.insert_libfunc(chan_send());
+    graph.insert_libfunc(chan_recv());
+    graph.insert_libfunc(chan_try_recv());
+    graph.insert_libfunc(chan_close());
+    graph.insert_libfunc(chan_select());
+    graph.insert_libfunc(chan_len());
+    graph.insert_libfunc(chan_is_closed());
+}
+
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+/// Channel (Data<Chan>).
+/// Buffered values sent from one process and received by another.
+pub struct Chan {
+    /// Max number of buffered values (None is unbounded).
+    pub capacity: Option<usize>,
+    pub buffer: VecDeque<Val>,
+    pub closed: bool,
+
+    /// Processes asleep until a value is sent (or the channel closes).
+    #[serde(skip)]
+    receivers: Vec<WakeRef>,
+
+    /// Processes asleep until there is room in the buffer (or the channel closes).
+    #[serde(skip)]
+    senders: Vec<WakeRef>,
+}
+
+#[typetag::serde(name = "Chan")] // also the libname (Data<Chan>)!
+impl StofData for Chan {}
+
+impl Chan {
+    /// Create a new channel.
+    pub fn new(capacity: Option<usize>) -> Self {
+        Self { capacity, ..Default::default() }
+    }
+
+    /// Is the buffer full?
+    pub fn full(&self) -> bool {
+        if let Some(capacity) = self.capacity {
+            return self.buffer.len() >= capacity;
+        }
+        false
+    }
+
+    /// Buffer a value, waking the processes waiting to receive.
+    pub fn push(&mut self, val: Val) {
+        self.buffer.push_back(val);
+        for wref in self.receivers.drain(..) {
+            wake(&wref);
+        }
+    }
+
+    /// Take the next value, waking the processes waiting to send.
+    pub fn pop(&mut self) -> Option<Val> {
+        let val = self.buffer.pop_front();
+        if val.is_some() {
+            for wref in self.senders.drain(..) {
+                wake(&wref);
+            }
+        }
+        val
+    }
+
+    /// Close this channel, waking every waiting process.
+    /// Buffered values can still be received.
+    pub fn close(&mut self) -> bool {
+        if self.closed { return false; }
+        self.closed = true;
+        for wref in self.receivers.drain(..).chain(self.senders.drain(..)) {
+            wake(&wref);
+        }
+        true
+    }
+}
+
+
+lazy_static! {
+    pub(self) static ref NEW: Arc<dyn Instruction> = Arc::new(ChanIns::New);
+    pub(self) static ref SEND: Arc<dyn Instruction> = Arc::new(ChanIns::Send);
+    pub(self) static ref RECV: Arc<dyn Instruction> = Arc::new(ChanIns::Recv);
+    pub(self) static ref TRY_RECV: Arc<dyn Instruction> = Arc::new(ChanIns::TryRecv);
+    pub(self) static ref CLOSE: Arc<dyn Instruction> = Arc::new(ChanIns::Close);
+    pub(self) static ref SELECT: Arc<dyn Instruction> = Arc::new(ChanIns::Select);
+    pub(self) static ref LEN: Arc<dyn Instruction> = Arc::new(ChanIns::Len);
+    pub(self) static ref IS_CLOSED: Arc<dyn Instruction> = Arc::new(ChanIns::IsClosed);
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Chan instructions.
+pub enum ChanIns {
+    New,
+    Send,
+    Recv,
+    TryRecv,
+    Close,
+    Select,
+    Len,
+    IsClosed,
+    Unwatch(WakeRef),
+}
+#[typetag::serde(name = "ChanIns")]
+impl Instruction for ChanIns {
+    fn exec(&self, env: &mut ProcEnv, graph: &mut Graph) -> Result<Option<Instructions>, Error> {
+        match self {
+            Self::New => {
+                // Chan.new(capacity: int = 0, context: obj = self) -> Data<Chan>
+                let mut context = env.self_ptr();
+                if let Some(context_var) = env.stack.pop() {
+                    if let Some(ctx) = context_var.try_obj() {
+                        context = ctx;
+                    }
+                }
+                let mut capacity = None;
+                if let Some(capacity_var) = env.stack.pop() {
+                    match capacity_var.val.read().deref() {
+                        Val::Num(num) => {
+                            let cap = num.int();
+                            if cap > 0 { capacity = Some(cap as usize); }
+                        },
+                        Val::Void |
+                        Val::Null => {},
+                        _ => {
+                            return Err(Error::ChanNew);
+                        }
+                    }
+                }
+
+                let name = SId::default();
+                let id = name.clone();
+                if let Some(dref) = graph.insert_stof_data(&context, name, Box::new(Chan::new(capacity)), Some(id)) {
+                    env.stack.push(Variable::val(Val::Data(dref)));
+                    return Ok(None);
+                }
+                Err(Error::ChanNew)
+            },
+            Self::Send => {
+                // Chan.send(chan: Data<Chan>, value: unknown)
+                if let Some(val_var) = env.stack.pop() {
+                    if let Some(chan_var) = env.stack.pop() {
+                        if let Some(dref) = chan_var.try_data_or_func() {
+                            if let Some(chan) = graph.get_mut_stof_data::<Chan>(&dref) {
+                                if chan.closed {
+                                    return Err(Error::ChanClosed);
+                                }
+                                let val = val_var.val.read().clone();
+                                if !chan.full() {
+                                    chan.push(val);
+                                    return Ok(None);
+                                }
+
+                                // sleep until a value is received, then try again
+                                let wref = WakeRef::default();
+                                chan.senders.push(wref.clone());
+                                let mut instructions = Instructions::default();
+                                instructions.push(Arc::new(Base::CtrlSleepRef(wref)));
+                                instructions.push(Arc::new(Base::Literal(Val::Data(dref))));
+                                instructions.push(Arc::new(Base::Literal(val)));
+                                instructions.push(SEND.clone());
+                                return Ok(Some(instructions));
+                            }
+                        }
+                    }
+                }
+                Err(Error::ChanSend)
+            },
+            Self::Recv => {
+                // Chan.recv(chan: Data<Chan>) -> unknown
+                if let Some(chan_var) = env.stack.pop() {
+                    if let Some(dref) = chan_var.try_data_or_func() {
+                        if let Some(chan) = graph.get_mut_stof_data::<Chan>(&dref) {
+                            if let Some(val) = chan.pop() {
+                                env.stack.push(Variable::val(val));
+                                return Ok(None);
+                            }
+                            if chan.closed {
+                                env.stack.push(Variable::val(Val::Null));
+                                return Ok(None);
+                            }
+
+                            // sleep until a value is sent, then try again
+                            let wref = WakeRef::default();
+                            chan.receivers.push(wref.clone());
+                            let mut instructions = Instructions::default();
+                            instructions.push(Arc::new(Base::CtrlSleepRef(wref)));
+                            instructions.push(Arc::new(Base::Literal(Val::Data(dref))));
+                            instructions.push(RECV.clone());
+                            return Ok(Some(instructions));
+                        }
+                    }
+                }
+                Err(Error::ChanRecv)
+            },
+            Self::TryRecv => {
+                // Chan.try_recv(chan: Data<Chan>) -> unknown
+                if let Some(chan_var) = env.stack.pop() {
+                    if let Some(dref) = chan_var.try_data_or_func() {
+                        if let Some(chan) = graph.get_mut_stof_data::<Chan>(&dref) {
+                            env.stack.push(Variable::val(chan.pop().unwrap_or(Val::Null)));
+                            return Ok(None);
+                        }
+                    }
+                }
+                Err(Error::ChanTryRecv)
+            },
+            Self::Close => {
+                // Chan.close(chan: Data<Chan>) -> bool
+                if let Some(chan_var) = env.stack.pop() {
+                    if let Some(dref) = chan_var.try_data_or_func() {
+                        if let Some(chan) = graph.get_mut_stof_data::<Chan>(&dref) {
+                            env.stack.push(Variable::val(Val::Bool(chan.close())));
+                            return Ok(None);
+                        }
+                    }
+                }
+                Err(Error::ChanClose)
+            },
+            Self::Select => {
+                // Chan.select(chans: list) -> (int, unknown)
+                if let Some(chans_var) = env.stack.pop() {
+                    let mut chans: Vec<DataRef> = Vec::new();
+                    match chans_var.val.read().deref() {
+                        Val::List(list) => {
+                            for val in list {
+                                if let Some(dref) = val.read().try_data_or_func() {
+                                    chans.push(dref);
+                                } else {
+                                    return Err(Error::ChanSelect);
+                                }
+                            }
+                        },
+                        _ => {
+                            return Err(Error::ChanSelect);
+                        }
+                    }
+
+                    // first channel (in order) with a value wins
+                    let mut all_closed = true;
+                    for (index, dref) in chans.iter().enumerate() {
+                        if let Some(chan) = graph.get_mut_stof_data::<Chan>(dref) {
+                            if let Some(val) = chan.pop() {
+                                env.stack.push(Variable::val(Val::Tup(vector![ValRef::new(Val::Num(Num::Int(index as i64))), ValRef::new(val)])));
+                                return Ok(None);
+                            }
+                            all_closed = all_closed && chan.closed;
+                        } else {
+                            return Err(Error::ChanSelect);
+                        }
+                    }
+                    if all_closed {
+                        env.stack.push(Variable::val(Val::Null));
+                        return Ok(None);
+                    }
+
+                    // sleep until any of the channels has a value, then try again
+                    let wref = WakeRef::default();
+                    for dref in &chans {
+                        if let Some(chan) = graph.get_mut_stof_data::<Chan>(dref) {
+                            if !chan.closed {
+                                chan.receivers.push(wref.clone());
+                            }
+                        }
+                    }
+                    let mut instructions = Instructions::default();
+                    instructions.push(Arc::new(Base::CtrlSleepRef(wref.clone())));
+                    instructions.push(Arc::new(Base::Variable(chans_var)));
+                    instructions.push(Arc::new(ChanIns::Unwatch(wref)));
+                    instructions.push(SELECT.clone());
+                    return Ok(Some(instructions));
+                }
+                Err(Error::ChanSelect)
+            },
+            Self::Len => {
+                // Chan.len(chan: Data<Chan>) -> int
+                if let Some(chan_var) = env.stack.pop() {
+                    if let Some(dref) = chan_var.try_data_or_func() {
+                        if let Some(chan) = graph.get_stof_data::<Chan>(&dref) {
+                            env.stack.push(Variable::val(Val::Num(Num::Int(chan.buffer.len() as i64))));
+                            return Ok(None);
+                        }
+                    }
+                }
+                Err(Error::ChanLen)
+            },
+            Self::IsClosed => {
+                // Chan.is_closed(chan: Data<Chan>) -> bool
+                if let Some(chan_var) = env.stack.pop() {
+                    if let Some(dref) = chan_var.try_data_or_func() {
+                        if let Some(chan) = graph.get_stof_data::<Chan>(&dref) {
+                            env.stack.push(Variable::val(Val::Bool(chan.closed)));
+                            return Ok(None);
+                        }
+                    }
+                }
+                Err(Error::ChanIsClosed)
+            },
+            Self::Unwatch(wref) => {
+                // a woken select stops waiting on the other channels (leaves the channel list on the stack)
+                if let Some(chans_var) = env.stack.last() {
+                    if let Val::List(list) = chans_var.val.read().deref() {
+                        for val in list {
+                            if let Some(dref) = val.read().try_data_or_func() {
+                                if let Some(chan) = graph.get_mut_stof_data::<Chan>(&dref) {
+                                    chan.receivers.retain(|receiver| !Arc::ptr_eq(receiver, wref));
+                                }
+                            }
+                        }
+                    }
+                }
+                Ok(None)
+            },
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{chan::Chan, Field, Graph, Profile};
+
+    #[test]
+    fn select_unwatch() {
+        let mut graph = Graph::default();
+        graph.parse_stof_src(r#"
+            busy: Chan.new()
+            idle: Chan.new()
+
+            #[main]
+            fn main() {
+                async {
+                    for (let i = 0; i < 10; i += 1) {
+                        sleep(1ms);
+                        self.busy.send(i);
+                    }
+                };
+                for (let i = 0; i < 10; i += 1) {
+                    Chan.select([self.busy, self.idle]);
+                }
+            }
+        "#, None, Profile::test()).unwrap();
+        graph.run(None, true).unwrap();
+
+        // each select that slept stopped waiting on the idle channel once it returned
+        let field_ref = Field::field_from_path(&mut graph, "root.idle", None).unwrap();
+        let idle = graph.get_stof_data::<Field>(&field_ref).unwrap().value.try_data_or_func().unwrap();
+        assert!(graph.get_stof_data::<Chan>(&idle).unwrap().receivers.is_empty());
+    }
+}
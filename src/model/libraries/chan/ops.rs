@@ -0,0 +1,257 @@
+//
+// Copyright 2025 Formata, Inc. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use std::sync::Arc;
+use imbl::vector;
+use crate::{model::{chan::{CHAN_LIB, CLOSE, IS_CLOSED, LEN, NEW, RECV, SELECT, SEND, TRY_RECV}, LibFunc, Param}, runtime::{instruction::Instructions, instructions::Base, NumT, Type, Val}};
+
+
+/// New.
+pub fn chan_new() -> LibFunc {
+    LibFunc {
+        library: CHAN_LIB.clone(),
+        name: "new".into(),
+        is_async: false,
+        docs: r#"# Chan.new(capacity: int = 0, context: obj = self) -> Data<Chan>
+Create a new channel on the given context object (default is self). A bounded channel (capacity > 0) puts senders to sleep while it is full. The default capacity of 0 creates an unbounded channel.
+```rust
+const ch = Chan.new(8);
+ch.send(42);
+assert_eq(ch.recv(), 42);
+```
+"#.into(),
+        params: vector![
+            Param { name: "capacity".into(), param_type: Type::Num(NumT::Int), default: Some(Arc::new(Base::Literal(Val::Null))) },
+            Param { name: "context".into(), param_type: Type::Void, default: Some(Arc::new(Base::Literal(Val::Null))) },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(NEW.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// Send.
+pub fn chan_send() -> LibFunc {
+    LibFunc {
+        library: CHAN_LIB.clone(),
+        name: "send".into(),
+        is_async: false,
+        docs: r#"# Chan.send(chan: Data<Chan>, value: unknown) -> void
+Send a value on a channel. If the channel is bounded and full, the sending process sleeps until another process receives a value. Throws a "ChanClosed" error if the channel is closed.
+```rust
+const ch = Chan.new();
+const producer = async {
+    for (const i in 5) ch.send(i);
+    ch.close();
+};
+```
+"#.into(),
+        params: vector![
+            Param { name: "chan".into(), param_type: Type::Data(CHAN_LIB.clone()), default: None },
+            Param { name: "value".into(), param_type: Type::Void, default: None },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(SEND.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// Receive.
+pub fn chan_recv() -> LibFunc {
+    LibFunc {
+        library: CHAN_LIB.clone(),
+        name: "recv".into(),
+        is_async: false,
+        docs: r#"# Chan.recv(chan: Data<Chan>) -> unknown
+Receive the next value from a channel. If the channel is empty, the receiving process sleeps until another process sends a value. Returns null once the channel is closed and empty.
+```rust
+let total = 0;
+loop {
+    const value = ch.recv();
+    if (value == null) break;
+    total += value;
+}
+```
+"#.into(),
+        params: vector![
+            Param { name: "chan".into(), param_type: Type::Data(CHAN_LIB.clone()), default: None },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(RECV.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// Try receive.
+pub fn chan_try_recv() -> LibFunc {
+    LibFunc {
+        library: CHAN_LIB.clone(),
+        name: "try_recv".into(),
+        is_async: false,
+        docs: r#"# Chan.try_recv(chan: Data<Chan>) -> unknown
+Receive the next value from a channel without sleeping. Returns null if the channel is empty.
+```rust
+const ch = Chan.new();
+assert_eq(ch.try_recv(), null);
+ch.send('hi');
+assert_eq(ch.try_recv(), 'hi');
+```
+"#.into(),
+        params: vector![
+            Param { name: "chan".into(), param_type: Type::Data(CHAN_LIB.clone()), default: None },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(TRY_RECV.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// Close.
+pub fn chan_close() -> LibFunc {
+    LibFunc {
+        library: CHAN_LIB.clone(),
+        name: "close".into(),
+        is_async: false,
+        docs: r#"# Chan.close(chan: Data<Chan>) -> bool
+Close a channel, waking every process waiting on it. Values already sent can still be received, after which receiving returns null. Returns false if the channel was already closed.
+```rust
+const ch = Chan.new();
+ch.send(1);
+assert(ch.close());
+assert_eq(ch.recv(), 1);
+assert_eq(ch.recv(), null);
+```
+"#.into(),
+        params: vector![
+            Param { name: "chan".into(), param_type: Type::Data(CHAN_LIB.clone()), default: None },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(CLOSE.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// Select.
+pub fn chan_select() -> LibFunc {
+    LibFunc {
+        library: CHAN_LIB.clone(),
+        name: "select".into(),
+        is_async: false,
+        docs: r#"# Chan.select(chans: list) -> (int, unknown)
+Receive a value from whichever channel in the list has one first, returning a tuple with the index of the channel and the value. Channels earlier in the list win ties. The process sleeps while every channel is empty, and null is returned once every channel is closed and empty.
+```rust
+const numbers = Chan.new();
+const words = Chan.new();
+words.send('hi');
+const res = Chan.select([numbers, words]);
+assert_eq(res[0], 1);
+assert_eq(res[1], 'hi');
+```
+"#.into(),
+        params: vector![
+            Param { name: "chans".into(), param_type: Type::List, default: None },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(SELECT.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// Length.
+pub fn chan_len() -> LibFunc {
+    LibFunc {
+        library: CHAN_LIB.clone(),
+        name: "len".into(),
+        is_async: false,
+        docs: r#"# Chan.len(chan: Data<Chan>) -> int
+Number of values sent on a channel that have not yet been received.
+```rust
+const ch = Chan.new();
+ch.send(1);
+assert_eq(ch.len(), 1);
+```
+"#.into(),
+        params: vector![
+            Param { name: "chan".into(), param_type: Type::Data(CHAN_LIB.clone()), default: None },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(LEN.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// Is closed?
+pub fn chan_is_closed() -> LibFunc {
+    LibFunc {
+        library: CHAN_LIB.clone(),
+        name: "is_closed".into(),
+        is_async: false,
+        docs: r#"# Chan.is_closed(chan: Data<Chan>) -> bool
+Has this channel been closed?
+```rust
+const ch = Chan.new();
+ch.close();
+assert(ch.is_closed());
+```
+"#.into(),
+        params: vector![
+            Param { name: "chan".into(), param_type: Type::Data(CHAN_LIB.clone()), default: None },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(IS_CLOSED.clone());
+            Ok(instructions)
+        })
+    }
+}
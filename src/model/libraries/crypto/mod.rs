@@ -0,0 +1,342 @@
+//
+// Copyright 2025 Formata, Inc. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use std::{ops::Deref, sync::Arc};
+use arcstr::{literal, ArcStr};
+use bytes::Bytes;
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use hmac::{Hmac, Mac};
+use imbl::OrdMap;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha512};
+use subtle::ConstantTimeEq;
+use crate::{model::{crypto::ops::{crypto_blake3, crypto_eq, crypto_hmac, crypto_keygen, crypto_public_key, crypto_random_bytes, crypto_sha256, crypto_sha512, crypto_sign, crypto_uuid, crypto_verify}, Graph}, runtime::{instruction::{Instruction, Instructions}, proc::ProcEnv, Error, Val, ValRef, Variable}};
+
+mod ops;
+
+
+/// Library name.
+pub(self) const CRYPTO_LIB: ArcStr = literal!("Crypto");
+
+/// Most random bytes a single Crypto.random_bytes call can create (1 MiB).
+pub const MAX_RANDOM_BYTES: usize = 1 << 20;
+
+
+/// Add the crypto library to a graph.
+pub fn insert_crypto_lib(graph: &mut Graph) {
+    graph.insert_libfunc(crypto_sha256());
+    graph.insert_libfunc(crypto_sha512());
+    graph.insert_libfunc(crypto_blake3());
+    graph.insert_libfunc(crypto_hmac());
+    graph.insert_libfunc(crypto_eq());
+    graph.insert_libfunc(crypto_keygen());
+    graph.insert_libfunc(crypto_public_key());
+    graph.insert_libfunc(crypto_sign());
+    graph.insert_libfunc(crypto_verify());
+    graph.insert_libfunc(crypto_random_bytes());
+    graph.insert_libfunc(crypto_uuid());
+}
+
+
+lazy_static! {
+    pub(self) static ref SHA256: Arc<dyn Instruction> = Arc::new(CryptoIns::Sha256);
+    pub(self) static ref SHA512: Arc<dyn Instruction> = Arc::new(CryptoIns::Sha512);
+    pub(self) static ref BLAKE3: Arc<dyn Instruction> = Arc::new(CryptoIns::Blake3);
+    pub(self) static ref HMAC: Arc<dyn Instruction> = Arc::new(CryptoIns::Hmac);
+    pub(self) static ref EQ: Arc<dyn Instruction> = Arc::new(CryptoIns::Eq);
+    pub(self) static ref KEYGEN: Arc<dyn Instruction> = Arc::new(CryptoIns::Keygen);
+    pub(self) static ref PUBLIC_KEY: Arc<dyn Instruction> = Arc::new(CryptoIns::PublicKey);
+    pub(self) static ref SIGN: Arc<dyn Instruction> = Arc::new(CryptoIns::Sign);
+    pub(self) static ref VERIFY: Arc<dyn Instruction> = Arc::new(CryptoIns::Verify);
+    pub(self) static ref RANDOM_BYTES: Arc<dyn Instruction> = Arc::new(CryptoIns::RandomBytes);
+    pub(self) static ref UUID: Arc<dyn Instruction> = Arc::new(CryptoIns::Uuid);
+}
+
+
+/// Secure random bytes from the OS (or the browser with the "js" feature).
+pub fn random_bytes(count: usize) -> Result<Vec<u8>, Error> {
+    let mut bytes = vec![0u8; count];
+    getrandom::getrandom(&mut bytes).map_err(|error| Error::CryptoRandom(error.to_string()))?;
+    Ok(bytes)
+}
+
+/// Random (version 4) UUID string.
+pub fn random_uuid() -> Result<String, Error> {
+    let mut bytes = random_bytes(16)?;
+    bytes[6] = (bytes[6] & 0x0f) | 0x40; // version 4
+    bytes[8] = (bytes[8] & 0x3f) | 0x80; // RFC 4122 variant
+    let hex = bytes.iter().map(|byte| format!("{byte:02x}")).collect::<String>();
+    Ok(format!("{}-{}-{}-{}-{}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32]))
+}
+
+/// HMAC of data with a key, using "sha256" or "sha512".
+pub fn hmac(algorithm: &str, key: &[u8], data: &[u8]) -> Result<Vec<u8>, Error> {
+    match algorithm.to_lowercase().as_str() {
+        "sha256" => {
+            let mut mac = Hmac::<Sha256>::new_from_slice(key).map_err(|error| Error::CryptoHmac(error.to_string()))?;
+            mac.update(data);
+            Ok(mac.finalize().into_bytes().to_vec())
+        },
+        "sha512" => {
+            let mut mac = Hmac::<Sha512>::new_from_slice(key).map_err(|error| Error::CryptoHmac(error.to_string()))?;
+            mac.update(data);
+            Ok(mac.finalize().into_bytes().to_vec())
+        },
+        other => Err(Error::CryptoHmac(format!("unsupported algorithm '{other}' (expected 'sha256' or 'sha512')"))),
+    }
+}
+
+/// Ed25519 signing key from 32 secret bytes.
+pub fn signing_key(secret: &[u8]) -> Result<SigningKey, Error> {
+    match <[u8; 32]>::try_from(secret) {
+        Ok(secret) => Ok(SigningKey::from_bytes(&secret)),
+        Err(_) => Err(Error::CryptoKey(format!("expected a 32 byte Ed25519 secret key, found {} bytes", secret.len()))),
+    }
+}
+
+/// Ed25519 verifying key from 32 public bytes.
+pub fn verifying_key(public: &[u8]) -> Result<VerifyingKey, Error> {
+    match <[u8; 32]>::try_from(public) {
+        Ok(public) => VerifyingKey::from_bytes(&public).map_err(|error| Error::CryptoKey(error.to_string())),
+        Err(_) => Err(Error::CryptoKey(format!("expected a 32 byte Ed25519 public key, found {} bytes", public.len()))),
+    }
+}
+
+/// Generate a new Ed25519 keypair (secret, public).
+pub fn ed25519_keygen() -> Result<(Vec<u8>, Vec<u8>), Error> {
+    let secret = random_bytes(32)?;
+    let key = signing_key(&secret)?;
+    Ok((secret, key.verifying_key().to_bytes().to_vec()))
+}
+
+/// Ed25519 signature (64 bytes) of data with a secret key.
+pub fn ed25519_sign(secret: &[u8], data: &[u8]) -> Result<Vec<u8>, Error> {
+    Ok(signing_key(secret)?.sign(data).to_bytes().to_vec())
+}
+
+/// Verify an Ed25519 signature of data with a public key.
+/// Malformed signatures do not verify (they are not an error).
+pub fn ed25519_verify(public: &[u8], data: &[u8], signature: &[u8]) -> Result<bool, Error> {
+    let key = verifying_key(public)?;
+    match Signature::from_slice(signature) {
+        Ok(signature) => Ok(key.verify_strict(data, &signature).is_ok()),
+        Err(_) => Ok(false),
+    }
+}
+
+
+/// Bytes of a str (UTF-8) or blob value.
+fn value_bytes(var: &Variable) -> Option<Vec<u8>> {
+    match var.val.read().deref() {
+        Val::Blob(blob) => Some(blob.to_vec()),
+        Val::Str(val) => Some(val.as_bytes().to_vec()),
+        _ => None,
+    }
+}
+
+/// Blob value.
+fn blob(bytes: impl AsRef<[u8]>) -> Variable {
+    Variable::val(Val::Blob(Bytes::copy_from_slice(bytes.as_ref())))
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Crypto instructions.
+pub enum CryptoIns {
+    Sha256,
+    Sha512,
+    Blake3,
+    Hmac,
+    Eq,
+
+    Keygen,
+    PublicKey,
+    Sign,
+    Verify,
+
+    RandomBytes,
+    Uuid,
+}
+#[typetag::serde(name = "CryptoIns")]
+impl Instruction for CryptoIns {
+    fn exec(&self, env: &mut ProcEnv, graph: &mut Graph) -> Result<Option<Instructions>, Error> {
+        match self {
+            Self::Sha256 => {
+                if let Some(var) = env.stack.pop() {
+                    if let Some(data) = value_bytes(&var) {
+                        env.stack.push(blob(Sha256::digest(&data)));
+                        return Ok(None);
+                    }
+                }
+                Err(Error::CryptoHash)
+            },
+            Self::Sha512 => {
+                if let Some(var) = env.stack.pop() {
+                    if let Some(data) = value_bytes(&var) {
+                        env.stack.push(blob(Sha512::digest(&data)));
+                        return Ok(None);
+                    }
+                }
+                Err(Error::CryptoHash)
+            },
+            Self::Blake3 => {
+                if let Some(var) = env.stack.pop() {
+                    if let Some(data) = value_bytes(&var) {
+                        env.stack.push(blob(blake3::hash(&data).as_bytes()));
+                        return Ok(None);
+                    }
+                }
+                Err(Error::CryptoHash)
+            },
+            Self::Hmac => {
+                if let Some(algo_var) = env.stack.pop() {
+                    if let Some(data_var) = env.stack.pop() {
+                        if let Some(key_var) = env.stack.pop() {
+                            let algorithm = match algo_var.val.read().deref() {
+                                Val::Str(algo) => algo.to_string(),
+                                _ => "sha256".to_string(),
+                            };
+                            if let Some(key) = value_bytes(&key_var) {
+                                if let Some(data) = value_bytes(&data_var) {
+                                    env.stack.push(blob(hmac(&algorithm, &key, &data)?));
+                                    return Ok(None);
+                                }
+                            }
+                        }
+                    }
+                }
+                Err(Error::CryptoHmac("expected a str or blob key and data".into()))
+            },
+            Self::Eq => {
+                if let Some(b_var) = env.stack.pop() {
+                    if let Some(a_var) = env.stack.pop() {
+                        if let Some(a) = value_bytes(&a_var) {
+                            if let Some(b) = value_bytes(&b_var) {
+                                let equal: bool = a.ct_eq(&b).into();
+                                env.stack.push(Variable::val(Val::Bool(equal)));
+                                return Ok(None);
+                            }
+                        }
+                    }
+                }
+                Err(Error::CryptoEq)
+            },
+            Self::Keygen => {
+                let (secret, public) = ed25519_keygen()?;
+                let mut map = OrdMap::default();
+                map.insert(ValRef::new(Val::Str("secret".into())), ValRef::new(Val::Blob(secret.into())));
+                map.insert(ValRef::new(Val::Str("public".into())), ValRef::new(Val::Blob(public.into())));
+                env.stack.push(Variable::val(Val::Map(map)));
+                Ok(None)
+            },
+            Self::PublicKey => {
+                if let Some(secret_var) = env.stack.pop() {
+                    if let Some(secret) = value_bytes(&secret_var) {
+                        let key = signing_key(&secret)?;
+                        env.stack.push(blob(key.verifying_key().to_bytes()));
+                        return Ok(None);
+                    }
+                }
+                Err(Error::CryptoKey("expected a blob secret key".into()))
+            },
+            Self::Sign => {
+                if let Some(data_var) = env.stack.pop() {
+                    if let Some(secret_var) = env.stack.pop() {
+                        if let Some(secret) = value_bytes(&secret_var) {
+                            if let Some(data) = value_bytes(&data_var) {
+                                env.stack.push(blob(ed25519_sign(&secret, &data)?));
+                                return Ok(None);
+                            }
+                        }
+                    }
+                }
+                Err(Error::CryptoSign)
+            },
+            Self::Verify => {
+                if let Some(sig_var) = env.stack.pop() {
+                    if let Some(data_var) = env.stack.pop() {
+                        if let Some(public_var) = env.stack.pop() {
+                            if let Some(public) = value_bytes(&public_var) {
+                                if let Some(data) = value_bytes(&data_var) {
+                                    if let Some(signature) = value_bytes(&sig_var) {
+                                        let valid = ed25519_verify(&public, &data, &signature)?;
+                                        env.stack.push(Variable::val(Val::Bool(valid)));
+                                        return Ok(None);
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                Err(Error::CryptoVerify)
+            },
+            Self::RandomBytes => {
+                if let Some(count_var) = env.stack.pop() {
+                    if let Val::Num(num) = count_var.val.read().deref() {
+                        let count = num.int().max(0) as usize;
+                        if count > MAX_RANDOM_BYTES {
+                            return Err(Error::CryptoRandom(format!("{count} bytes requested, but at most {MAX_RANDOM_BYTES} can be created at once")));
+                        }
+                        graph.quota_grow(count, 0)?;
+                        env.stack.push(blob(random_bytes(count)?));
+                        return Ok(None);
+                    }
+                }
+                Err(Error::CryptoRandom("expected a byte count".into()))
+            },
+            Self::Uuid => {
+                env.stack.push(Variable::val(Val::Str(random_uuid()?.into())));
+                Ok(None)
+            },
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{ed25519_keygen, ed25519_sign, ed25519_verify, hmac, random_uuid};
+
+    #[test]
+    fn hmac_sha256_vector() {
+        // RFC 4231, test case 2
+        let mac = hmac("sha256", b"Jefe", b"what do ya want for nothing?").unwrap();
+        let hex = mac.iter().map(|byte| format!("{byte:02x}")).collect::<String>();
+        assert_eq!(hex, "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+        assert!(hmac("md5", b"key", b"data").is_err());
+    }
+
+    #[test]
+    fn sign_and_verify() {
+        let (secret, public) = ed25519_keygen().unwrap();
+        let signature = ed25519_sign(&secret, b"payload").unwrap();
+        assert_eq!(signature.len(), 64);
+        assert!(ed25519_verify(&public, b"payload", &signature).unwrap());
+        assert!(!ed25519_verify(&public, b"tampered", &signature).unwrap());
+        assert!(!ed25519_verify(&public, b"payload", b"short").unwrap());
+        assert!(ed25519_sign(b"short", b"payload").is_err());
+    }
+
+    #[test]
+    fn uuid_v4() {
+        let uuid = random_uuid().unwrap();
+        assert_eq!(uuid.len(), 36);
+        assert_eq!(&uuid[14..15], "4");
+        assert!(matches!(&uuid[19..20], "8" | "9" | "a" | "b"));
+        assert_ne!(uuid, random_uuid().unwrap());
+    }
+}
@@ -0,0 +1,323 @@
+//
+// Copyright 2025 Formata, Inc. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use std::sync::Arc;
+use imbl::vector;
+use crate::{model::{crypto::{CRYPTO_LIB, BLAKE3, EQ, HMAC, KEYGEN, PUBLIC_KEY, RANDOM_BYTES, SHA256, SHA512, SIGN, UUID, VERIFY}, LibFunc, Param}, runtime::{instruction::Instructions, instructions::Base, NumT, Type, Val}};
+
+
+/// SHA-256.
+pub fn crypto_sha256() -> LibFunc {
+    LibFunc {
+        library: CRYPTO_LIB.clone(),
+        name: "sha256".into(),
+        is_async: false,
+        docs: r#"# Crypto.sha256(data: str | blob) -> blob
+SHA-256 digest of a string (UTF-8) or blob.
+```rust
+const digest = Crypto.sha256("abc");
+assert_eq(digest.len(), 32);
+assert_eq(digest.hex(), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+```
+"#.into(),
+        params: vector![
+            Param { name: "data".into(), param_type: Type::Union(vector![Type::Str, Type::Blob]), default: None },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(SHA256.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// SHA-512.
+pub fn crypto_sha512() -> LibFunc {
+    LibFunc {
+        library: CRYPTO_LIB.clone(),
+        name: "sha512".into(),
+        is_async: false,
+        docs: r#"# Crypto.sha512(data: str | blob) -> blob
+SHA-512 digest of a string (UTF-8) or blob.
+```rust
+assert_eq(Crypto.sha512("abc").len(), 64);
+```
+"#.into(),
+        params: vector![
+            Param { name: "data".into(), param_type: Type::Union(vector![Type::Str, Type::Blob]), default: None },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(SHA512.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// BLAKE3.
+pub fn crypto_blake3() -> LibFunc {
+    LibFunc {
+        library: CRYPTO_LIB.clone(),
+        name: "blake3".into(),
+        is_async: false,
+        docs: r#"# Crypto.blake3(data: str | blob) -> blob
+BLAKE3 digest (32 bytes) of a string (UTF-8) or blob. Fast, and a good fit for content-addressed caching.
+```rust
+const key = Crypto.blake3(stringify('json', self)).hex();
+```
+"#.into(),
+        params: vector![
+            Param { name: "data".into(), param_type: Type::Union(vector![Type::Str, Type::Blob]), default: None },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(BLAKE3.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// HMAC.
+pub fn crypto_hmac() -> LibFunc {
+    LibFunc {
+        library: CRYPTO_LIB.clone(),
+        name: "hmac".into(),
+        is_async: false,
+        docs: r#"# Crypto.hmac(key: str | blob, data: str | blob, algorithm: str = 'sha256') -> blob
+HMAC of data with a secret key. The algorithm is either 'sha256' (default) or 'sha512'.
+```rust
+// webhook signature verification
+const expected = Crypto.hmac(secret, body).hex();
+assert(Crypto.eq(expected, signature_header));
+```
+"#.into(),
+        params: vector![
+            Param { name: "key".into(), param_type: Type::Union(vector![Type::Str, Type::Blob]), default: None },
+            Param { name: "data".into(), param_type: Type::Union(vector![Type::Str, Type::Blob]), default: None },
+            Param { name: "algorithm".into(), param_type: Type::Str, default: Some(Arc::new(Base::Literal(Val::Str("sha256".into())))) },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(HMAC.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// Constant-time equality.
+pub fn crypto_eq() -> LibFunc {
+    LibFunc {
+        library: CRYPTO_LIB.clone(),
+        name: "eq".into(),
+        is_async: false,
+        docs: r#"# Crypto.eq(a: str | blob, b: str | blob) -> bool
+Constant-time comparison of two strings or blobs. Use this instead of "==" when comparing secrets (Ex. signatures), so that timing does not leak how many bytes matched.
+```rust
+assert(Crypto.eq("secret", "secret"));
+assert_not(Crypto.eq("secret", "Secret"));
+```
+"#.into(),
+        params: vector![
+            Param { name: "a".into(), param_type: Type::Union(vector![Type::Str, Type::Blob]), default: None },
+            Param { name: "b".into(), param_type: Type::Union(vector![Type::Str, Type::Blob]), default: None },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(EQ.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// Keygen.
+pub fn crypto_keygen() -> LibFunc {
+    LibFunc {
+        library: CRYPTO_LIB.clone(),
+        name: "keygen".into(),
+        is_async: false,
+        docs: r#"# Crypto.keygen() -> map
+Generate a new Ed25519 keypair. Returns a map with a 32 byte 'secret' blob and a 32 byte 'public' blob.
+```rust
+const keys = Crypto.keygen();
+const signature = Crypto.sign(keys.get('secret'), "hello");
+assert(Crypto.verify(keys.get('public'), "hello", signature));
+```
+"#.into(),
+        params: vector![],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(KEYGEN.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// Public key.
+pub fn crypto_public_key() -> LibFunc {
+    LibFunc {
+        library: CRYPTO_LIB.clone(),
+        name: "public_key".into(),
+        is_async: false,
+        docs: r#"# Crypto.public_key(secret: blob) -> blob
+Ed25519 public key for a 32 byte secret key.
+```rust
+const keys = Crypto.keygen();
+assert_eq(Crypto.public_key(keys.get('secret')), keys.get('public'));
+```
+"#.into(),
+        params: vector![
+            Param { name: "secret".into(), param_type: Type::Blob, default: None },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(PUBLIC_KEY.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// Sign.
+pub fn crypto_sign() -> LibFunc {
+    LibFunc {
+        library: CRYPTO_LIB.clone(),
+        name: "sign".into(),
+        is_async: false,
+        docs: r#"# Crypto.sign(secret: blob, data: str | blob) -> blob
+Ed25519 signature (64 bytes) of a string (UTF-8) or blob with a 32 byte secret key.
+```rust
+const keys = Crypto.keygen();
+const signature = Crypto.sign(keys.get('secret'), "hello");
+assert_eq(signature.len(), 64);
+```
+"#.into(),
+        params: vector![
+            Param { name: "secret".into(), param_type: Type::Blob, default: None },
+            Param { name: "data".into(), param_type: Type::Union(vector![Type::Str, Type::Blob]), default: None },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(SIGN.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// Verify.
+pub fn crypto_verify() -> LibFunc {
+    LibFunc {
+        library: CRYPTO_LIB.clone(),
+        name: "verify".into(),
+        is_async: false,
+        docs: r#"# Crypto.verify(public: blob, data: str | blob, signature: blob) -> bool
+Verify an Ed25519 signature of a string (UTF-8) or blob with a 32 byte public key. Returns false for invalid or malformed signatures.
+```rust
+const keys = Crypto.keygen();
+const signature = Crypto.sign(keys.get('secret'), "hello");
+assert(Crypto.verify(keys.get('public'), "hello", signature));
+assert_not(Crypto.verify(keys.get('public'), "goodbye", signature));
+```
+"#.into(),
+        params: vector![
+            Param { name: "public".into(), param_type: Type::Blob, default: None },
+            Param { name: "data".into(), param_type: Type::Union(vector![Type::Str, Type::Blob]), default: None },
+            Param { name: "signature".into(), param_type: Type::Blob, default: None },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(VERIFY.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// Random bytes.
+pub fn crypto_random_bytes() -> LibFunc {
+    LibFunc {
+        library: CRYPTO_LIB.clone(),
+        name: "random_bytes".into(),
+        is_async: false,
+        docs: r#"# Crypto.random_bytes(count: int) -> blob
+Cryptographically secure random bytes (from the OS, or the browser when running in JS). Throws a "CryptoRandom" error for more than 1 MiB (1048576 bytes) at once.
+```rust
+const nonce = Crypto.random_bytes(16);
+assert_eq(nonce.len(), 16);
+```
+"#.into(),
+        params: vector![
+            Param { name: "count".into(), param_type: Type::Num(NumT::Int), default: None },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(RANDOM_BYTES.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// UUID.
+pub fn crypto_uuid() -> LibFunc {
+    LibFunc {
+        library: CRYPTO_LIB.clone(),
+        name: "uuid".into(),
+        is_async: false,
+        docs: r#"# Crypto.uuid() -> str
+Random (version 4) UUID string, using a cryptographically secure random source.
+```rust
+const id = Crypto.uuid();
+assert_eq(id.len(), 36);
+```
+"#.into(),
+        params: vector![],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(UUID.clone());
+            Ok(instructions)
+        })
+    }
+}
@@ -0,0 +1,154 @@
+//
+// Copyright 2025 Formata, Inc. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use std::{fs, path::{Path, PathBuf}};
+
+
+/// Paths matching a glob pattern, sorted.
+/// Supports "*" and "?" within a path component, and "**" for any number of directories.
+/// Wildcards do not match names starting with "." unless the pattern component does.
+pub fn glob(pattern: &str) -> Vec<PathBuf> {
+    let pattern = pattern.replace('\\', "/");
+    let mut base = PathBuf::new();
+    if pattern.starts_with('/') {
+        base.push("/");
+    }
+
+    // literal components lead up to the directory we start walking from
+    let components = pattern.split('/').filter(|comp| !comp.is_empty() && *comp != ".").collect::<Vec<_>>();
+    let mut start = 0;
+    while start < components.len() && !is_wild(components[start]) {
+        base.push(components[start]);
+        start += 1;
+    }
+
+    let mut matches = Vec::new();
+    if start == components.len() {
+        if base.exists() { matches.push(base); }
+        return matches;
+    }
+    walk(&base, &components[start..], &mut matches);
+    matches.sort();
+    matches.dedup();
+    matches
+}
+
+
+/// Does a component have wildcards?
+fn is_wild(component: &str) -> bool {
+    component.contains(['*', '?'])
+}
+
+
+/// Match components against the entries of a directory.
+fn walk(dir: &Path, components: &[&str], matches: &mut Vec<PathBuf>) {
+    if components.is_empty() {
+        matches.push(dir.to_path_buf());
+        return;
+    }
+    let component = components[0];
+    if !is_wild(component) {
+        let path = dir.join(component);
+        if path.exists() {
+            walk(&path, &components[1..], matches);
+        }
+        return;
+    }
+
+    let read_dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+    let mut entries = match fs::read_dir(read_dir) {
+        Ok(entries) => entries.flatten().collect::<Vec<_>>(),
+        Err(_) => return,
+    };
+    entries.sort_by_key(|entry| entry.file_name());
+
+    if component == "**" {
+        walk(dir, &components[1..], matches); // zero directories
+        for entry in entries {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !name.starts_with('.') && entry.file_type().is_ok_and(|ty| ty.is_dir()) {
+                walk(&dir.join(name), components, matches);
+            }
+        }
+        return;
+    }
+
+    for entry in entries {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with('.') && !component.starts_with('.') {
+            continue;
+        }
+        if wildcard_match(component.as_bytes(), name.as_bytes()) {
+            let path = dir.join(name);
+            if components.len() == 1 || path.is_dir() {
+                walk(&path, &components[1..], matches);
+            }
+        }
+    }
+}
+
+
+/// Match a name against a pattern with "*" and "?" wildcards.
+fn wildcard_match(pattern: &[u8], name: &[u8]) -> bool {
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == b'?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == b'*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            p = star_p + 1;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+    while p < pattern.len() && pattern[p] == b'*' {
+        p += 1;
+    }
+    p == pattern.len()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::wildcard_match;
+
+    #[test]
+    fn wildcards() {
+        assert!(wildcard_match(b"*.rs", b"mod.rs"));
+        assert!(wildcard_match(b"m?d.*", b"mod.rs"));
+        assert!(wildcard_match(b"*", b"anything"));
+        assert!(wildcard_match(b"a*b*c", b"aXXbYYc"));
+        assert!(!wildcard_match(b"*.rs", b"mod.rs.bak"));
+        assert!(!wildcard_match(b"?", b""));
+    }
+
+    #[test]
+    fn glob_src() {
+        let matches = super::glob("src/model/libraries/filesys/*.rs");
+        assert!(matches.iter().any(|path| path.ends_with("glob.rs")));
+        assert!(matches.iter().all(|path| path.extension().is_some_and(|ext| ext == "rs")));
+
+        let recursive = super::glob("src/**/glob.rs");
+        assert_eq!(recursive.len(), 1);
+        assert!(super::glob("src/*.nope").is_empty());
+    }
+}
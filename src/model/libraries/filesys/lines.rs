@@ -0,0 +1,261 @@
+//
+// Copyright 2025 Formata, Inc. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use std::{fs::File, io::{BufRead, BufReader, Seek, SeekFrom}, ops::Deref, sync::Arc};
+use arcstr::{literal, ArcStr};
+use imbl::vector;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use crate::{model::{Graph, LibFunc, Param, StofData}, runtime::{instruction::{Instruction, Instructions}, proc::ProcEnv, Error, Num, NumT, Type, Val, Variable}};
+
+
+/// Library name.
+pub(super) const LINES_LIB: ArcStr = literal!("Lines");
+
+
+/// Add the lines library (fs.read_lines iterator) to a graph.
+pub(super) fn insert_lines_lib(graph: &mut Graph) {
+    graph.insert_libfunc(LibFunc {
+        library: LINES_LIB.clone(),
+        name: "next".into(),
+        is_async: false,
+        docs: r#"# Lines.next(lines: Data<Lines>) -> str
+Read the next line (without the line ending), or null at the end of the file.
+```rust
+const lines = fs.read_lines('big.csv');
+let line = lines.next();
+while (line != null) {
+    pln(line);
+    line = lines.next();
+}
+```
+"#.into(),
+        params: vector![
+            Param { name: "lines".into(), param_type: Type::Data(LINES_LIB.clone()), default: None },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(Arc::new(LinesIns::Next));
+            Ok(instructions)
+        })
+    });
+
+    graph.insert_libfunc(LibFunc {
+        library: LINES_LIB.clone(),
+        name: "len".into(),
+        is_async: false,
+        docs: r#"# Lines.len(lines: Data<Lines>) -> int
+Number of lines in the file (counted once, without keeping the file in memory).
+"#.into(),
+        params: vector![
+            Param { name: "lines".into(), param_type: Type::Data(LINES_LIB.clone()), default: None },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(Arc::new(LinesIns::Len));
+            Ok(instructions)
+        })
+    });
+
+    graph.insert_libfunc(LibFunc {
+        library: LINES_LIB.clone(),
+        name: "at".into(),
+        is_async: false,
+        docs: r#"# Lines.at(lines: Data<Lines>, index: int) -> str
+Line at an index, or null if out of bounds. Reading forwards (like in a for loop) only reads each line once.
+```rust
+for (const line in fs.read_lines('big.csv')) {
+    if (first) continue; // header
+    pln(line);
+}
+```
+"#.into(),
+        params: vector![
+            Param { name: "lines".into(), param_type: Type::Data(LINES_LIB.clone()), default: None },
+            Param { name: "index".into(), param_type: Type::Num(NumT::Int), default: None },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(Arc::new(LinesIns::At));
+            Ok(instructions)
+        })
+    });
+}
+
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+/// Lazy line reader over a file (Data<Lines>).
+/// Keeps its position (byte offset and line index), so only the current line is in memory.
+pub struct Lines {
+    pub path: String,
+    pub offset: u64,
+    pub line: usize,
+    pub count: Option<usize>,
+
+    #[serde(skip)]
+    reader: Option<Arc<Mutex<BufReader<File>>>>,
+}
+
+#[typetag::serde(name = "Lines")] // also the libname (Data<Lines>)!
+impl StofData for Lines {}
+
+impl Lines {
+    /// Create a new line reader for a file.
+    pub fn open(path: &str) -> Result<Self, Error> {
+        let file = File::open(path).map_err(|error| Error::FsReadLinesError(error.to_string()))?;
+        Ok(Self {
+            path: path.to_string(),
+            reader: Some(Arc::new(Mutex::new(BufReader::new(file)))),
+            ..Default::default()
+        })
+    }
+
+    /// Read the next line.
+    pub fn next_line(&mut self) -> Result<Option<String>, Error> {
+        if self.reader.is_none() {
+            // reopened where we left off (Ex. after being deserialized)
+            let mut file = File::open(&self.path).map_err(|error| Error::FsReadLinesError(error.to_string()))?;
+            file.seek(SeekFrom::Start(self.offset)).map_err(|error| Error::FsReadLinesError(error.to_string()))?;
+            self.reader = Some(Arc::new(Mutex::new(BufReader::new(file))));
+        }
+        if let Some(reader) = &self.reader {
+            let mut line = String::new();
+            let read = reader.lock().read_line(&mut line).map_err(|error| Error::FsReadLinesError(error.to_string()))?;
+            if read == 0 {
+                return Ok(None);
+            }
+            self.offset += read as u64;
+            self.line += 1;
+            if line.ends_with('\n') {
+                line.pop();
+                if line.ends_with('\r') { line.pop(); }
+            }
+            return Ok(Some(line));
+        }
+        Ok(None)
+    }
+
+    /// Line at an index (rewinds if the index has already been read).
+    pub fn at(&mut self, index: usize) -> Result<Option<String>, Error> {
+        if index < self.line {
+            self.offset = 0;
+            self.line = 0;
+            self.reader = None;
+        }
+        while self.line < index {
+            if self.next_line()?.is_none() {
+                return Ok(None);
+            }
+        }
+        self.next_line()
+    }
+
+    /// Number of lines in the file.
+    pub fn count_lines(&mut self) -> Result<usize, Error> {
+        if let Some(count) = self.count {
+            return Ok(count);
+        }
+        let file = File::open(&self.path).map_err(|error| Error::FsReadLinesError(error.to_string()))?;
+        let mut reader = BufReader::new(file);
+        let mut count = 0;
+        let mut buf = Vec::new();
+        loop {
+            buf.clear();
+            match reader.read_until(b'\n', &mut buf) {
+                Ok(0) => break,
+                Ok(_) => count += 1,
+                Err(error) => return Err(Error::FsReadLinesError(error.to_string())),
+            }
+        }
+        self.count = Some(count);
+        Ok(count)
+    }
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Lines instructions.
+pub enum LinesIns {
+    Next,
+    Len,
+    At,
+}
+#[typetag::serde(name = "LinesIns")]
+impl Instruction for LinesIns {
+    fn exec(&self, env: &mut ProcEnv, graph: &mut Graph) -> Result<Option<Instructions>, Error> {
+        match self {
+            Self::Next => {
+                if let Some(lines_var) = env.stack.pop() {
+                    if let Some(dref) = lines_var.try_data_or_func() {
+                        if let Some(lines) = graph.get_mut_stof_data::<Lines>(&dref) {
+                            match lines.next_line()? {
+                                Some(line) => env.stack.push(Variable::val(Val::Str(line.into()))),
+                                None => env.stack.push(Variable::val(Val::Null)),
+                            }
+                            return Ok(None);
+                        }
+                    }
+                }
+                Err(Error::LinesNext)
+            },
+            Self::Len => {
+                if let Some(lines_var) = env.stack.pop() {
+                    if let Some(dref) = lines_var.try_data_or_func() {
+                        if let Some(lines) = graph.get_mut_stof_data::<Lines>(&dref) {
+                            let count = lines.count_lines()?;
+                            env.stack.push(Variable::val(Val::Num(Num::Int(count as i64))));
+                            return Ok(None);
+                        }
+                    }
+                }
+                Err(Error::LinesLen)
+            },
+            Self::At => {
+                if let Some(index_var) = env.stack.pop() {
+                    if let Some(lines_var) = env.stack.pop() {
+                        if let Some(dref) = lines_var.try_data_or_func() {
+                            if let Some(lines) = graph.get_mut_stof_data::<Lines>(&dref) {
+                                let index = match index_var.val.read().deref() {
+                                    Val::Num(num) => num.int(),
+                                    _ => return Err(Error::LinesAt),
+                                };
+                                if index < 0 {
+                                    env.stack.push(Variable::val(Val::Null));
+                                    return Ok(None);
+                                }
+                                match lines.at(index as usize)? {
+                                    Some(line) => env.stack.push(Variable::val(Val::Str(line.into()))),
+                                    None => env.stack.push(Variable::val(Val::Null)),
+                                }
+                                return Ok(None);
+                            }
+                        }
+                    }
+                }
+                Err(Error::LinesAt)
+            },
+        }
+    }
+}
@@ -15,10 +15,13 @@
 //
 
 
-use std::{fs, ops::Deref, sync::Arc};
-use imbl::vector;
+use std::{fs::{self, OpenOptions}, io::Write, ops::Deref, path::Path, sync::Arc, time::SystemTime};
+use imbl::{vector, OrdMap, Vector};
 use serde::{Deserialize, Serialize};
-use crate::{model::{Graph, LibFunc, Param, FS_LIB}, runtime::{instruction::{Instruction, Instructions}, proc::ProcEnv, Error, Type, Val, Variable}};
+use crate::{model::{filesys::lines::{insert_lines_lib, Lines, LINES_LIB}, Graph, LibFunc, Param, SId, FS_LIB}, runtime::{instruction::{Instruction, Instructions}, instructions::Base, proc::ProcEnv, Error, Num, Type, Units, Val, ValRef, Variable}};
+
+mod glob;
+pub mod lines;
 
 
 /// Add fs library to a graph.
@@ -26,6 +29,17 @@ pub fn fs_library(graph: &mut Graph) {
     graph.insert_libfunc(read());
     graph.insert_libfunc(write());
     graph.insert_libfunc(read_string());
+    graph.insert_libfunc(exists());
+    graph.insert_libfunc(list_dir());
+    graph.insert_libfunc(glob());
+    graph.insert_libfunc(mkdir());
+    graph.insert_libfunc(remove());
+    graph.insert_libfunc(rename());
+    graph.insert_libfunc(copy());
+    graph.insert_libfunc(metadata());
+    graph.insert_libfunc(append());
+    graph.insert_libfunc(read_lines());
+    insert_lines_lib(graph);
 }
 
 
@@ -108,19 +122,300 @@ fs.write("src/text.txt", "testing");
 }
 
 
+/// Does a path exist?
+pub(self) fn exists() -> LibFunc {
+    LibFunc {
+        library: FS_LIB.clone(),
+        name: "exists".into(),
+        is_async: false,
+        docs: r#"# fs.exists(path: str) -> bool
+Does a file or directory exist at the given path?
+```rust
+if (!fs.exists("out")) fs.mkdir("out");
+```"#.into(),
+        params: vector![
+            Param { name: "path".into(), param_type: Type::Str, default: None }
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(Arc::new(FsIns::Exists));
+            Ok(instructions)
+        })
+    }
+}
+
+
+/// List a directory.
+pub(self) fn list_dir() -> LibFunc {
+    LibFunc {
+        library: FS_LIB.clone(),
+        name: "list_dir".into(),
+        is_async: false,
+        docs: r#"# fs.list_dir(path: str = ".") -> list
+Paths of the entries (files and directories) in a directory, sorted.
+```rust
+for (const path in fs.list_dir("src")) pln(path); // "src/lib.rs", ...
+```"#.into(),
+        params: vector![
+            Param { name: "path".into(), param_type: Type::Str, default: Some(Arc::new(Base::Literal(Val::Str(".".into())))) }
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(Arc::new(FsIns::ListDir));
+            Ok(instructions)
+        })
+    }
+}
+
+
+/// Glob paths.
+pub(self) fn glob() -> LibFunc {
+    LibFunc {
+        library: FS_LIB.clone(),
+        name: "glob".into(),
+        is_async: false,
+        docs: r#"# fs.glob(pattern: str) -> list
+Paths matching a glob pattern, sorted. Supports "*" and "?" within a name and "**" for any number of directories. Wildcards skip hidden names (starting with ".") unless the pattern names them explicitly. Paths outside of a sandbox's read allow-list are not included.
+```rust
+const sources = fs.glob("src/**/*.rs");
+```"#.into(),
+        params: vector![
+            Param { name: "pattern".into(), param_type: Type::Str, default: None }
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(Arc::new(FsIns::Glob));
+            Ok(instructions)
+        })
+    }
+}
+
+
+/// Make a directory.
+pub(self) fn mkdir() -> LibFunc {
+    LibFunc {
+        library: FS_LIB.clone(),
+        name: "mkdir".into(),
+        is_async: false,
+        docs: r#"# fs.mkdir(path: str, all: bool = true) -> void
+Create a directory. By default, missing parent directories are created as well and an existing directory is not an error.
+```rust
+fs.mkdir("out/docs");
+```"#.into(),
+        params: vector![
+            Param { name: "path".into(), param_type: Type::Str, default: None },
+            Param { name: "all".into(), param_type: Type::Bool, default: Some(Arc::new(Base::Literal(Val::Bool(true)))) }
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(Arc::new(FsIns::Mkdir));
+            Ok(instructions)
+        })
+    }
+}
+
+
+/// Remove a file or directory.
+pub(self) fn remove() -> LibFunc {
+    LibFunc {
+        library: FS_LIB.clone(),
+        name: "remove".into(),
+        is_async: false,
+        docs: r#"# fs.remove(path: str, all: bool = false) -> void
+Remove a file or an empty directory. Set "all" to remove a directory along with everything in it.
+```rust
+fs.remove("out", true);
+```"#.into(),
+        params: vector![
+            Param { name: "path".into(), param_type: Type::Str, default: None },
+            Param { name: "all".into(), param_type: Type::Bool, default: Some(Arc::new(Base::Literal(Val::Bool(false)))) }
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(Arc::new(FsIns::Remove));
+            Ok(instructions)
+        })
+    }
+}
+
+
+/// Rename (move) a file or directory.
+pub(self) fn rename() -> LibFunc {
+    LibFunc {
+        library: FS_LIB.clone(),
+        name: "rename".into(),
+        is_async: false,
+        docs: r#"# fs.rename(from: str, to: str) -> void
+Rename (move) a file or directory, replacing the destination file if it exists.
+```rust
+fs.rename("out/tmp.json", "out/data.json");
+```"#.into(),
+        params: vector![
+            Param { name: "from".into(), param_type: Type::Str, default: None },
+            Param { name: "to".into(), param_type: Type::Str, default: None }
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(Arc::new(FsIns::Rename));
+            Ok(instructions)
+        })
+    }
+}
+
+
+/// Copy a file.
+pub(self) fn copy() -> LibFunc {
+    LibFunc {
+        library: FS_LIB.clone(),
+        name: "copy".into(),
+        is_async: false,
+        docs: r#"# fs.copy(from: str, to: str) -> void
+Copy a file, overwriting the destination if it exists.
+```rust
+fs.copy("Cargo.toml", "out/Cargo.toml");
+```"#.into(),
+        params: vector![
+            Param { name: "from".into(), param_type: Type::Str, default: None },
+            Param { name: "to".into(), param_type: Type::Str, default: None }
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(Arc::new(FsIns::Copy));
+            Ok(instructions)
+        })
+    }
+}
+
+
+/// File or directory metadata.
+pub(self) fn metadata() -> LibFunc {
+    LibFunc {
+        library: FS_LIB.clone(),
+        name: "metadata".into(),
+        is_async: false,
+        docs: r#"# fs.metadata(path: str) -> map
+Metadata for a file or directory: "size" (bytes), "modified", "created", and "accessed" (ms since the Unix Epoch, like Time.now(), or null if the platform doesn't support it), "is_dir", "is_file", "is_symlink", and "readonly".
+```rust
+const meta = fs.metadata("Cargo.toml");
+pln(meta.get("size") as KB);
+pln(Time.diff(meta.get("modified")) as days);
+```"#.into(),
+        params: vector![
+            Param { name: "path".into(), param_type: Type::Str, default: None }
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(Arc::new(FsIns::Metadata));
+            Ok(instructions)
+        })
+    }
+}
+
+
+/// Append to a file.
+pub(self) fn append() -> LibFunc {
+    LibFunc {
+        library: FS_LIB.clone(),
+        name: "append".into(),
+        is_async: false,
+        docs: r#"# fs.append(path: str, content: str | blob) -> void
+Append content to the end of a file, creating the file if it doesn't exist.
+```rust
+fs.append("out/log.txt", "done\n");
+```"#.into(),
+        params: vector![
+            Param { name: "path".into(), param_type: Type::Str, default: None },
+            Param { name: "content".into(), param_type: Type::Union(vector![Type::Str, Type::Blob]), default: None }
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(Arc::new(FsIns::Append));
+            Ok(instructions)
+        })
+    }
+}
+
+
+/// Read lines lazily.
+pub(self) fn read_lines() -> LibFunc {
+    LibFunc {
+        library: FS_LIB.clone(),
+        name: "read_lines".into(),
+        is_async: false,
+        docs: r#"# fs.read_lines(path: str) -> Data<Lines>
+Lazily read a file line by line, keeping only the current line in memory (for large files). The result can be iterated with a for loop, or read one line at a time with "next" (null at the end of the file).
+```rust
+let count = 0;
+for (const line in fs.read_lines("big.csv")) {
+    if (line.contains("error")) count += 1;
+}
+```"#.into(),
+        params: vector![
+            Param { name: "path".into(), param_type: Type::Str, default: None }
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(Arc::new(FsIns::ReadLines));
+            Ok(instructions)
+        })
+    }
+}
+
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum FsIns {
     Read,
     ReadString,
     Write,
+    Exists,
+    ListDir,
+    Glob,
+    Mkdir,
+    Remove,
+    Rename,
+    Copy,
+    Metadata,
+    Append,
+    ReadLines,
 }
 #[typetag::serde(name = "FsIns")]
 impl Instruction for FsIns {
-    fn exec(&self, env: &mut ProcEnv, _graph: &mut Graph) -> Result<Option<Instructions> , Error> {
+    fn exec(&self, env: &mut ProcEnv, graph: &mut Graph) -> Result<Option<Instructions> , Error> {
         match self {
             Self::Read => {
                 if let Some(var) = env.stack.pop() {
                     let path = var.val.read().to_string();
+                    graph.sandbox_read(&path)?;
                     match fs::read(path) {
                         Ok(contents) => {
                             env.stack.push(Variable::val(Val::Blob(contents.into())));
@@ -136,6 +431,7 @@ impl Instruction for FsIns {
             Self::ReadString => {
                 if let Some(var) = env.stack.pop() {
                     let path = var.val.read().to_string();
+                    graph.sandbox_read(&path)?;
                     match fs::read_to_string(path) {
                         Ok(contents) => {
                             env.stack.push(Variable::val(Val::Str(contents.into())));
@@ -152,6 +448,7 @@ impl Instruction for FsIns {
                 if let Some(content) = env.stack.pop() {
                     if let Some(path) = env.stack.pop() {
                         let path = path.val.read().to_string();
+                        graph.sandbox_write(&path)?;
                         match content.val.read().deref() {
                             Val::Blob(content) => {
                                 match fs::write(path, content) {
@@ -179,7 +476,196 @@ impl Instruction for FsIns {
                 }
                 return Err(Error::FsWriteStackError);
             },
+            Self::Exists => {
+                if let Some(var) = env.stack.pop() {
+                    let path = var.val.read().to_string();
+                    graph.sandbox_read(&path)?;
+                    env.stack.push(Variable::val(Val::Bool(Path::new(&path).exists())));
+                    return Ok(None);
+                }
+                return Err(Error::FsStackError);
+            },
+            Self::ListDir => {
+                if let Some(var) = env.stack.pop() {
+                    let path = var.val.read().to_string();
+                    graph.sandbox_read(&path)?;
+                    match fs::read_dir(&path) {
+                        Ok(entries) => {
+                            let mut paths = Vec::new();
+                            for entry in entries {
+                                match entry {
+                                    Ok(entry) => paths.push(entry.path().to_string_lossy().to_string()),
+                                    Err(error) => return Err(Error::FsListDirError(error.to_string())),
+                                }
+                            }
+                            paths.sort();
+                            let list = paths.into_iter().map(|path| ValRef::new(Val::Str(path.into()))).collect::<Vector<_>>();
+                            env.stack.push(Variable::val(Val::List(list)));
+                            return Ok(None);
+                        },
+                        Err(error) => {
+                            return Err(Error::FsListDirError(error.to_string()));
+                        }
+                    }
+                }
+                return Err(Error::FsStackError);
+            },
+            Self::Glob => {
+                if let Some(var) = env.stack.pop() {
+                    let pattern = var.val.read().to_string();
+                    let mut list = Vector::default();
+                    for path in glob::glob(&pattern) {
+                        let path = path.to_string_lossy().to_string();
+                        if graph.sandbox_read(&path).is_ok() {
+                            list.push_back(ValRef::new(Val::Str(path.into())));
+                        }
+                    }
+                    env.stack.push(Variable::val(Val::List(list)));
+                    return Ok(None);
+                }
+                return Err(Error::FsStackError);
+            },
+            Self::Mkdir => {
+                if let Some(all_var) = env.stack.pop() {
+                    if let Some(path_var) = env.stack.pop() {
+                        let path = path_var.val.read().to_string();
+                        graph.sandbox_write(&path)?;
+                        let res = if all_var.val.read().truthy() {
+                            fs::create_dir_all(&path)
+                        } else {
+                            fs::create_dir(&path)
+                        };
+                        if let Err(error) = res {
+                            return Err(Error::FsMkdirError(error.to_string()));
+                        }
+                        return Ok(None);
+                    }
+                }
+                return Err(Error::FsStackError);
+            },
+            Self::Remove => {
+                if let Some(all_var) = env.stack.pop() {
+                    if let Some(path_var) = env.stack.pop() {
+                        let path = path_var.val.read().to_string();
+                        graph.sandbox_write(&path)?;
+                        let res = if Path::new(&path).is_dir() {
+                            if all_var.val.read().truthy() {
+                                fs::remove_dir_all(&path)
+                            } else {
+                                fs::remove_dir(&path)
+                            }
+                        } else {
+                            fs::remove_file(&path)
+                        };
+                        if let Err(error) = res {
+                            return Err(Error::FsRemoveError(error.to_string()));
+                        }
+                        return Ok(None);
+                    }
+                }
+                return Err(Error::FsStackError);
+            },
+            Self::Rename => {
+                if let Some(to_var) = env.stack.pop() {
+                    if let Some(from_var) = env.stack.pop() {
+                        let from = from_var.val.read().to_string();
+                        let to = to_var.val.read().to_string();
+                        graph.sandbox_write(&from)?;
+                        graph.sandbox_write(&to)?;
+                        if let Err(error) = fs::rename(&from, &to) {
+                            return Err(Error::FsRenameError(error.to_string()));
+                        }
+                        return Ok(None);
+                    }
+                }
+                return Err(Error::FsStackError);
+            },
+            Self::Copy => {
+                if let Some(to_var) = env.stack.pop() {
+                    if let Some(from_var) = env.stack.pop() {
+                        let from = from_var.val.read().to_string();
+                        let to = to_var.val.read().to_string();
+                        graph.sandbox_read(&from)?;
+                        graph.sandbox_write(&to)?;
+                        if let Err(error) = fs::copy(&from, &to) {
+                            return Err(Error::FsCopyError(error.to_string()));
+                        }
+                        return Ok(None);
+                    }
+                }
+                return Err(Error::FsStackError);
+            },
+            Self::Metadata => {
+                if let Some(var) = env.stack.pop() {
+                    let path = var.val.read().to_string();
+                    graph.sandbox_read(&path)?;
+                    match fs::metadata(&path) {
+                        Ok(meta) => {
+                            let is_symlink = fs::symlink_metadata(&path).is_ok_and(|meta| meta.file_type().is_symlink());
+                            let mut map = OrdMap::default();
+                            map.insert(ValRef::new(Val::Str("size".into())), ValRef::new(Val::Num(Num::Units(meta.len() as f64, Units::Bytes))));
+                            map.insert(ValRef::new(Val::Str("modified".into())), ValRef::new(unix_ms(meta.modified().ok())));
+                            map.insert(ValRef::new(Val::Str("created".into())), ValRef::new(unix_ms(meta.created().ok())));
+                            map.insert(ValRef::new(Val::Str("accessed".into())), ValRef::new(unix_ms(meta.accessed().ok())));
+                            map.insert(ValRef::new(Val::Str("is_dir".into())), ValRef::new(Val::Bool(meta.is_dir())));
+                            map.insert(ValRef::new(Val::Str("is_file".into())), ValRef::new(Val::Bool(meta.is_file())));
+                            map.insert(ValRef::new(Val::Str("is_symlink".into())), ValRef::new(Val::Bool(is_symlink)));
+                            map.insert(ValRef::new(Val::Str("readonly".into())), ValRef::new(Val::Bool(meta.permissions().readonly())));
+                            env.stack.push(Variable::val(Val::Map(map)));
+                            return Ok(None);
+                        },
+                        Err(error) => {
+                            return Err(Error::FsMetadataError(error.to_string()));
+                        }
+                    }
+                }
+                return Err(Error::FsStackError);
+            },
+            Self::Append => { // path, then content
+                if let Some(content) = env.stack.pop() {
+                    if let Some(path) = env.stack.pop() {
+                        let path = path.val.read().to_string();
+                        graph.sandbox_write(&path)?;
+                        let bytes = match content.val.read().deref() {
+                            Val::Blob(content) => content.to_vec(),
+                            val => val.to_string().into_bytes(),
+                        };
+                        let res = OpenOptions::new().create(true).append(true).open(&path).and_then(|mut file| file.write_all(&bytes));
+                        if let Err(error) = res {
+                            return Err(Error::FsAppendError(error.to_string()));
+                        }
+                        return Ok(None);
+                    }
+                }
+                return Err(Error::FsStackError);
+            },
+            Self::ReadLines => {
+                if let Some(var) = env.stack.pop() {
+                    let path = var.val.read().to_string();
+                    graph.sandbox_read(&path)?;
+                    let lines = Lines::open(&path)?;
+                    let name = SId::default();
+                    let id = name.clone();
+                    if let Some(dref) = graph.insert_stof_data(&env.self_ptr(), name, Box::new(lines), Some(id)) {
+                        env.stack.push(Variable::val(Val::Data(dref)));
+                        return Ok(None);
+                    }
+                    return Err(Error::FsReadLinesError(format!("could not create Data<{LINES_LIB}>")));
+                }
+                return Err(Error::FsStackError);
+            },
         }
         Ok(None)   
     }
 }
+
+
+/// Time since the Unix Epoch in milliseconds (null if unknown).
+fn unix_ms(time: Option<SystemTime>) -> Val {
+    if let Some(time) = time {
+        if let Ok(dur) = time.duration_since(SystemTime::UNIX_EPOCH) {
+            return Val::Num(Num::Units(dur.as_millis() as f64, Units::Milliseconds));
+        }
+    }
+    Val::Null
+}
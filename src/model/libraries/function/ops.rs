@@ -293,6 +293,7 @@ pub fn fn_is_async() -> LibFunc {
         is_async: false,
         docs: r#"# Fn.is_async(func: fn) -> bool
 Is this function async? This is just shorthand for checking if an "async" attribute exists (what makes a func async).
+Async functions run at "normal" priority unless a "priority" attribute (or the "async" attribute value) says otherwise (Ex. `#[priority('high')] async fn work() {}` or `#[async('low')] fn work() {}`).
 ```rust
 const func: fn = self.hi; // async fn hi() {}
 assert(func.is_async());
@@ -655,6 +655,7 @@ impl Instruction for HttpIns {
                 if let Some(url_var) = env.stack.pop() {
                     match url_var.val.read().deref() {
                         Val::Str(url_val) => {
+                            graph.sandbox_url(url_val)?;
                             url = url_val.to_string();
                         },
                         _ => {
@@ -703,7 +703,7 @@ fn list_sort() -> LibFunc {
         name: "sort".into(),
         is_async: false,
         docs: r#"# List.sort(array: list) -> void
-Sort the values in this array according to their already defined ordering.
+Sort the values in this array according to their already defined ordering. Prototype objects that define a #[cmp] function (returning an int) are ordered with it.
 ```rust
 const array = [2, 1, 4, 3];
 array.sort();
@@ -32,6 +32,9 @@ pub mod http;
 #[cfg(feature = "age_encrypt")]
 pub mod age;
 
+#[cfg(feature = "crypto")]
+pub mod crypto;
+
 pub mod stof_std;
 pub mod num;
 pub mod string;
@@ -46,6 +49,10 @@ pub mod data;
 pub mod obj;
 pub mod prompt;
 pub mod prof;
+pub mod promise;
+pub mod chan;
+pub mod rand;
+pub mod regexp;
 
 
 #[derive(Clone)]
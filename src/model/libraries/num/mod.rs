@@ -17,8 +17,9 @@
 use std::sync::Arc;
 use arcstr::{literal, ArcStr};
 use lazy_static::lazy_static;
+use rust_decimal::RoundingStrategy;
 use serde::{Deserialize, Serialize};
-use crate::{model::{Graph, num::{iter::{num_at, num_len}, maxmin::{num_max, num_min}, ops::{num_abs, num_acos, num_acosh, num_asin, num_asinh, num_atan, num_atan2, num_atanh, num_bin, num_cbrt, num_ceil, num_cos, num_cosh, num_exp, num_exp2, num_floor, num_fract, num_has_units, num_hex, num_inf, num_is_angle, num_is_length, num_is_mass, num_is_memory, num_is_temp, num_is_time, num_ln, num_log, num_nan, num_oct, num_pow, num_remove_units, num_round, num_signum, num_sin, num_sinh, num_sqrt, num_string, num_tan, num_tanh, num_to_units, num_trunc}}}, parser::types::parse_type_complete, runtime::{Error, NumT, Type, Val, Variable, instruction::{Instruction, Instructions}, proc::ProcEnv}};
+use crate::{model::{Graph, num::{iter::{num_at, num_len}, maxmin::{num_max, num_min}, ops::{num_abs, num_acos, num_acosh, num_asin, num_asinh, num_atan, num_atan2, num_atanh, num_bin, num_cbrt, num_ceil, num_cos, num_cosh, num_exp, num_exp2, num_floor, num_fract, num_has_units, num_hex, num_inf, num_is_angle, num_is_length, num_is_mass, num_is_memory, num_is_temp, num_is_time, num_ln, num_log, num_nan, num_oct, num_pow, num_remove_units, num_round, num_signum, num_sin, num_sinh, num_sqrt, num_string, num_tan, num_tanh, num_to_units, num_trunc}}}, parser::types::parse_type_complete, runtime::{Error, Num, NumT, Type, Val, Variable, instruction::{Instruction, Instructions}, proc::ProcEnv}};
 
 mod ops;
 mod maxmin;
@@ -87,6 +88,21 @@ pub fn insert_number_lib(graph: &mut Graph) {
 pub(self) const NUM_LIB: ArcStr = literal!("Num");
 
 
+/// Rounding mode by name (for Num.round).
+pub(self) fn rounding_mode(mode: &str) -> Result<RoundingStrategy, Error> {
+    match mode {
+        "half_up" => Ok(RoundingStrategy::MidpointAwayFromZero),
+        "half_down" => Ok(RoundingStrategy::MidpointTowardZero),
+        "half_even" => Ok(RoundingStrategy::MidpointNearestEven),
+        "up" => Ok(RoundingStrategy::AwayFromZero),
+        "down" => Ok(RoundingStrategy::ToZero),
+        "ceiling" => Ok(RoundingStrategy::ToPositiveInfinity),
+        "floor" => Ok(RoundingStrategy::ToNegativeInfinity),
+        _ => Err(Error::NumRoundMode(mode.to_string())),
+    }
+}
+
+
 // Static instructions.
 lazy_static! {
     pub(self) static ref ABS: Arc<dyn Instruction> = Arc::new(NumIns::Abs);
@@ -770,18 +786,25 @@ impl Instruction for NumIns {
                 return Err(Error::NumRound);
             },
             Self::Round2 => {
-                if let Some(places_var) = env.stack.pop() {
-                    if let Some(val_var) = env.stack.pop() {
-                        let mut push = false;
-                        if let Some(digits) = places_var.val.write().try_num() {
-                            if let Some(val) = val_var.val.write().try_num() {
-                                val.round2(&digits)?;
-                                push = true;
+                if let Some(mode_var) = env.stack.pop() {
+                    if let Some(places_var) = env.stack.pop() {
+                        if let Some(val_var) = env.stack.pop() {
+                            let mode = match mode_var.val.read().clone() {
+                                Val::Null | Val::Void => None,
+                                Val::Str(mode) => Some(rounding_mode(&mode)?),
+                                mode => return Err(Error::NumRoundMode(mode.to_string())),
+                            };
+                            let mut push = false;
+                            if let Some(digits) = places_var.val.write().try_num() {
+                                if let Some(val) = val_var.val.write().try_num() {
+                                    val.round2(&digits, mode)?;
+                                    push = true;
+                                }
+                            }
+                            if push {
+                                env.stack.push(val_var);
+                                return Ok(None);
                             }
-                        }
-                        if push {
-                            env.stack.push(val_var);
-                            return Ok(None);
                         }
                     }
                 }
@@ -794,6 +817,9 @@ impl Instruction for NumIns {
                         if let Some(to) = to_var.val.write().try_num() {
                             if let Some(val) = val_var.val.write().try_num() {
                                 val.pow(&to)?;
+                                if let Num::BigInt(big) = val {
+                                    graph.quota_grow(big.bits().div_ceil(8) as usize, 0)?;
+                                }
                                 push = true;
                             }
                         }
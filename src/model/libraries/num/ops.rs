@@ -985,17 +985,24 @@ pub fn num_round() -> LibFunc {
         library: NUM_LIB.clone(),
         name: "round".into(),
         is_async: false,
-        docs: r#"# Num.round(val: int | float, places: int = 0) -> int | float
+        docs: r#"# Num.round(val: int | float | decimal, places: int = 0, mode: str = null) -> int | float | decimal
 Round the given number to the given number of places. If value is an integer, do nothing.
+
+An optional rounding mode can be given: "half_up" (default, halfway values away from zero), "half_down", "half_even" (banker's rounding), "up" (away from zero), "down" (toward zero), "ceiling", or "floor". Floats rounded with a mode are rounded as written (2.675 is not 2.67499...).
 ```rust
 const val = 10.348;
 assert_eq(val.round(2), 10.35);
 assert_eq(val.round(), 10);
+
+const price = 2.675d;
+assert_eq(price.round(2, 'half_even'), 2.68d);
+assert_eq(price.round(2, 'down'), 2.67d);
 ```
 "#.into(),
         params: vector![
             Param { name: "val".into(), param_type: Type::Void, default: None },
-            Param { name: "places".into(), param_type: Type::Void, default: Some(Arc::new(Base::Literal(Val::Num(Num::Int(0))))) }
+            Param { name: "places".into(), param_type: Type::Void, default: Some(Arc::new(Base::Literal(Val::Num(Num::Int(0))))) },
+            Param { name: "mode".into(), param_type: Type::Void, default: Some(Arc::new(Base::Literal(Val::Null))) }
         ],
         return_type: None,
         unbounded_args: false,
@@ -1015,11 +1022,12 @@ pub fn num_pow() -> LibFunc {
         name: "pow".into(),
         is_async: false,
         docs: r#"# Num.pow(val: int | float, to: int | float = 2) -> float
-Returns the given value raised to the given power.
+Returns the given value raised to the given power. Bigints raised to whole powers stay exact; results larger than 1 Mib (MAX_BIGINT_BITS) throw a "NumOverflow" error.
 ```rust
 const val = 10;
 assert_eq(val.pow(to = 2), 100);
 assert_eq(val.pow(), 100);
+assert_eq(((2 as bigint).pow(100)) as str, '1267650600228229401496703205376');
 ```
 "#.into(),
         params: vector![
@@ -1434,7 +1434,7 @@ impl Instruction for ObjIns {
                 if let Some(var) = env.stack.pop() {
                     match var.val.read().deref() {
                         Val::Map(map) => {
-                            let obj = graph.insert_node(&nanoid!(10), Some(env.self_ptr()), false);
+                            let obj = graph.try_insert_node(&nanoid!(10), Some(env.self_ptr()), false)?;
                             for (k, v) in map {
                                 match k.read().deref() {
                                     Val::Str(name) => {
@@ -0,0 +1,81 @@
+//
+// Copyright 2025 Formata, Inc. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use std::{ops::Deref, sync::Arc};
+use arcstr::{literal, ArcStr};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use web_time::Duration;
+use crate::{model::{promise::ops::{promise_all_settled, promise_any, promise_cancel, promise_race, promise_timeout}, Graph}, runtime::{instruction::{Instruction, Instructions}, instructions::Base, proc::{ProcEnv, SettleMode}, Error, Units, Val}};
+
+mod ops;
+
+
+/// Library name.
+pub(self) const PROMISE_LIB: ArcStr = literal!("Promise");
+
+
+/// Add the promise library to a graph.
+pub fn insert_promise_lib(graph: &mut Graph) {
+    graph.insert_libfunc(promise_cancel());
+    graph.insert_libfunc(promise_timeout());
+    graph.insert_libfunc(promise_race());
+    graph.insert_libfunc(promise_any());
+    graph.insert_libfunc(promise_all_settled());
+}
+
+
+lazy_static! {
+    pub(self) static ref CANCEL: Arc<dyn Instruction> = Arc::new(Base::CtrlCancel);
+    pub(self) static ref TIMEOUT: Arc<dyn Instruction> = Arc::new(PromiseIns::Timeout);
+    pub(self) static ref RACE: Arc<dyn Instruction> = Arc::new(Base::CtrlSettle(SettleMode::Race));
+    pub(self) static ref ANY: Arc<dyn Instruction> = Arc::new(Base::CtrlSettle(SettleMode::Any));
+    pub(self) static ref ALL_SETTLED: Arc<dyn Instruction> = Arc::new(Base::CtrlSettle(SettleMode::All));
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Promise instructions.
+pub enum PromiseIns {
+    Timeout,
+}
+#[typetag::serde(name = "PromiseIns")]
+impl Instruction for PromiseIns {
+    fn exec(&self, env: &mut ProcEnv, _graph: &mut Graph) -> Result<Option<Instructions>, Error> {
+        match self {
+            Self::Timeout => {
+                let duration;
+                if let Some(var) = env.stack.pop() {
+                    match var.val.read().deref() {
+                        Val::Num(num) => {
+                            duration = num.float(Some(Units::Milliseconds));
+                        },
+                        _ => {
+                            return Err(Error::PromiseTimeout);
+                        }
+                    }
+                } else {
+                    return Err(Error::PromiseTimeout);
+                }
+
+                // the promise is next on the stack
+                let mut instructions = Instructions::default();
+                instructions.push(Arc::new(Base::CtrlAwaitTimeout(Duration::from_millis(duration.abs() as u64))));
+                Ok(Some(instructions))
+            },
+        }
+    }
+}
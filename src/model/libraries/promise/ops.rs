@@ -0,0 +1,158 @@
+//
+// Copyright 2025 Formata, Inc. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use std::sync::Arc;
+use imbl::vector;
+use crate::{model::{promise::{ALL_SETTLED, ANY, CANCEL, PROMISE_LIB, RACE, TIMEOUT}, LibFunc, Param}, runtime::{instruction::Instructions, NumT, Type}};
+
+
+/// Cancel.
+pub fn promise_cancel() -> LibFunc {
+    LibFunc {
+        library: PROMISE_LIB.clone(),
+        name: "cancel".into(),
+        is_async: false,
+        docs: r#"# Promise.cancel(promise: Promise) -> bool
+Cancel the process behind a promise, along with any processes it spawned that are still running. Each cancelled process first calls the #[cancelled] functions on the object it is running on (like #[dropped] functions when an object is dropped), then unwinds with a "Cancelled" error, so its finally blocks still run, and awaiting it afterwards throws. Returns false if the process already completed.
+```rust
+const promise = async { sleep(10s); };
+assert(promise.cancel());
+try { await promise; }
+catch { pln('cancelled'); }
+```
+"#.into(),
+        params: vector![
+            Param { name: "promise".into(), param_type: Type::Void, default: None }
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(CANCEL.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// Timeout.
+pub fn promise_timeout() -> LibFunc {
+    LibFunc {
+        library: PROMISE_LIB.clone(),
+        name: "timeout".into(),
+        is_async: true,
+        docs: r#"# async Promise.timeout(promise: Promise, time: float) -> Promise
+Wait on a promise for at most an amount of time (default units are milliseconds). If the time runs out first, the promise is cancelled (see Promise.cancel) and the returned promise throws a "TimedOut" error. Values that are not promises pass through.
+```rust
+const resp = await Http.fetch("https://restcountries.com/v3.1/region/europe").timeout(5s);
+```
+"#.into(),
+        params: vector![
+            Param { name: "promise".into(), param_type: Type::Void, default: None },
+            Param { name: "time".into(), param_type: Type::Num(NumT::Float), default: None },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(TIMEOUT.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// Race.
+pub fn promise_race() -> LibFunc {
+    LibFunc {
+        library: PROMISE_LIB.clone(),
+        name: "race".into(),
+        is_async: true,
+        docs: r#"# async Promise.race(promises: list) -> Promise
+Result of the first promise to complete or throw (throwing if it threw). Values that are not promises are already complete. The other promises keep running (see Promise.cancel).
+```rust
+const first = await Promise.race([async { sleep(50ms); 'slow' }, async 'fast']);
+assert_eq(first, 'fast');
+```
+"#.into(),
+        params: vector![
+            Param { name: "promises".into(), param_type: Type::List, default: None },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(RACE.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// Any.
+pub fn promise_any() -> LibFunc {
+    LibFunc {
+        library: PROMISE_LIB.clone(),
+        name: "any".into(),
+        is_async: true,
+        docs: r#"# async Promise.any(promises: list) -> Promise
+Result of the first promise to complete without throwing. If every promise throws, this throws an "AllRejected" error with all of the errors. The other promises keep running (see Promise.cancel).
+```rust
+const res = await Promise.any([async { throw('nope'); }, async 42]);
+assert_eq(res, 42);
+```
+"#.into(),
+        params: vector![
+            Param { name: "promises".into(), param_type: Type::List, default: None },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(ANY.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// All settled.
+pub fn promise_all_settled() -> LibFunc {
+    LibFunc {
+        library: PROMISE_LIB.clone(),
+        name: "all_settled".into(),
+        is_async: true,
+        docs: r#"# async Promise.all_settled(promises: list) -> Promise<list>
+Wait for every promise, without throwing. Results are maps in the same order as the promises, either {'status': 'fulfilled', 'value': ..} or {'status': 'rejected', 'error': ..} (the error is what a catch block would get).
+```rust
+const results = await Promise.all_settled([async 42, async { throw('nope'); }]);
+assert_eq(results[0].get('value'), 42);
+assert_eq(results[1].get('status'), 'rejected');
+```
+"#.into(),
+        params: vector![
+            Param { name: "promises".into(), param_type: Type::List, default: None },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(ALL_SETTLED.clone());
+            Ok(instructions)
+        })
+    }
+}
@@ -0,0 +1,366 @@
+//
+// Copyright 2025 Formata, Inc. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use std::{ops::{Deref, DerefMut}, sync::Arc};
+use arcstr::{literal, ArcStr};
+use imbl::Vector;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use crate::{model::{rand::ops::{rand_bool, rand_choice, rand_float, rand_int, rand_normal, rand_sample, rand_seed, rand_shuffle, rand_uniform}, Graph}, runtime::{instruction::{Instruction, Instructions}, proc::ProcEnv, Error, Num, Units, Val, Variable}};
+
+mod ops;
+
+
+/// Library name.
+pub(self) const RAND_LIB: ArcStr = literal!("Rand");
+
+
+/// Add the rand library to a graph.
+pub fn insert_rand_lib(graph: &mut Graph) {
+    graph.insert_libfunc(rand_seed());
+    graph.insert_libfunc(rand_int());
+    graph.insert_libfunc(rand_float());
+    graph.insert_libfunc(rand_uniform());
+    graph.insert_libfunc(rand_normal());
+    graph.insert_libfunc(rand_bool());
+    graph.insert_libfunc(rand_choice());
+    graph.insert_libfunc(rand_shuffle());
+    graph.insert_libfunc(rand_sample());
+}
+
+
+lazy_static! {
+    pub(self) static ref SEED: Arc<dyn Instruction> = Arc::new(RandIns::Seed);
+    pub(self) static ref INT: Arc<dyn Instruction> = Arc::new(RandIns::Int);
+    pub(self) static ref FLOAT: Arc<dyn Instruction> = Arc::new(RandIns::Float);
+    pub(self) static ref NORMAL: Arc<dyn Instruction> = Arc::new(RandIns::Normal);
+    pub(self) static ref BOOL: Arc<dyn Instruction> = Arc::new(RandIns::Bool);
+    pub(self) static ref CHOICE: Arc<dyn Instruction> = Arc::new(RandIns::Choice);
+    pub(self) static ref SHUFFLE: Arc<dyn Instruction> = Arc::new(RandIns::Shuffle);
+    pub(self) static ref SAMPLE: Arc<dyn Instruction> = Arc::new(RandIns::Sample);
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Pseudo-random number generator (xoshiro256**).
+/// Not cryptographically secure (see the Crypto library), but fast and reproducible when seeded.
+pub struct Rng {
+    state: [u64; 4],
+}
+impl Rng {
+    /// Generator with a fixed seed (same seed, same sequence, on every platform).
+    pub fn seeded(seed: u64) -> Self {
+        // expand the seed with splitmix64, so that similar seeds give unrelated sequences
+        let mut split = seed;
+        let mut state = [0u64; 4];
+        for word in state.iter_mut() {
+            split = split.wrapping_add(0x9e3779b97f4a7c15);
+            let mut z = split;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+            *word = z ^ (z >> 31);
+        }
+        Self { state }
+    }
+
+    /// Generator seeded from the OS (or the browser with the "js" feature).
+    pub fn from_entropy() -> Self {
+        let mut bytes = [0u8; 8];
+        if getrandom::getrandom(&mut bytes).is_err() {
+            // no entropy source, so fall back to the clock
+            let nanos = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|dur| dur.as_nanos()).unwrap_or_default();
+            bytes = (nanos as u64).to_le_bytes();
+        }
+        Self::seeded(u64::from_le_bytes(bytes))
+    }
+
+    /// Next random u64.
+    pub fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        result
+    }
+
+    /// Random float in [0, 1).
+    pub fn float(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+
+    /// Random index in [0, len), without modulo bias.
+    pub fn index(&mut self, len: usize) -> usize {
+        self.below(len as u64) as usize
+    }
+
+    /// Random u64 in [0, bound), where a bound of 0 means the full range.
+    fn below(&mut self, bound: u64) -> u64 {
+        if bound == 0 {
+            return self.next_u64();
+        }
+        let zone = u64::MAX - (u64::MAX - bound + 1) % bound;
+        loop {
+            let val = self.next_u64();
+            if val <= zone {
+                return val % bound;
+            }
+        }
+    }
+
+    /// Random integer in [min, max] (inclusive).
+    pub fn int(&mut self, min: i64, max: i64) -> i64 {
+        let span = max.wrapping_sub(min) as u64;
+        min.wrapping_add(self.below(span.wrapping_add(1)) as i64)
+    }
+
+    /// Random float in [min, max).
+    pub fn uniform(&mut self, min: f64, max: f64) -> f64 {
+        min + (max - min) * self.float()
+    }
+
+    /// Normally distributed float (Box-Muller).
+    pub fn normal(&mut self, mean: f64, std_dev: f64) -> f64 {
+        let u1 = 1. - self.float(); // (0, 1], so ln is finite
+        let u2 = self.float();
+        mean + std_dev * (-2. * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos()
+    }
+
+    /// Shuffle values in place (Fisher-Yates).
+    pub fn shuffle<T: Clone>(&mut self, values: &mut Vector<T>) {
+        for i in (1..values.len()).rev() {
+            let j = self.index(i + 1);
+            values.swap(i, j);
+        }
+    }
+}
+
+
+impl Graph {
+    /// Random number generator for this graph (Rand library), seeded from entropy unless seeded with "seed_rng".
+    pub fn rng(&mut self) -> &mut Rng {
+        self.rng.get_or_insert_with(Rng::from_entropy)
+    }
+
+    /// Seed this graph's random number generator, for reproducible simulations and tests.
+    pub fn seed_rng(&mut self, seed: u64) {
+        self.rng = Some(Rng::seeded(seed));
+    }
+}
+
+
+/// Float bounds (converted to common units) for a random number.
+fn bounds(min: &Val, max: &Val) -> Option<(f64, f64, Option<Units>)> {
+    match (min, max) {
+        (Val::Num(min), Val::Num(max)) => {
+            let units = min.units().or(max.units());
+            Some((min.float(units), max.float(units), units))
+        },
+        _ => None,
+    }
+}
+
+/// Number value with optional units.
+fn num(val: f64, units: Option<Units>) -> Val {
+    match units {
+        Some(units) => Val::Num(Num::Units(val, units)),
+        None => Val::Num(Num::Float(val)),
+    }
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Rand instructions.
+pub enum RandIns {
+    Seed,
+    Int,
+    Float,
+    Normal,
+    Bool,
+
+    Choice,
+    Shuffle,
+    Sample,
+}
+#[typetag::serde(name = "RandIns")]
+impl Instruction for RandIns {
+    fn exec(&self, env: &mut ProcEnv, graph: &mut Graph) -> Result<Option<Instructions>, Error> {
+        match self {
+            Self::Seed => {
+                if let Some(seed_var) = env.stack.pop() {
+                    if let Val::Num(seed) = seed_var.val.read().deref() {
+                        graph.seed_rng(seed.int() as u64);
+                        return Ok(None);
+                    }
+                }
+                Err(Error::RandSeed)
+            },
+            Self::Int => {
+                if let Some(max_var) = env.stack.pop() {
+                    if let Some(min_var) = env.stack.pop() {
+                        if let Val::Num(min) = min_var.val.read().deref() {
+                            if let Val::Num(max) = max_var.val.read().deref() {
+                                let (min, max) = (min.int(), max.int());
+                                if min <= max {
+                                    let val = graph.rng().int(min, max);
+                                    env.stack.push(Variable::val(Val::Num(Num::Int(val))));
+                                    return Ok(None);
+                                }
+                            }
+                        }
+                    }
+                }
+                Err(Error::RandInt)
+            },
+            Self::Float => {
+                if let Some(max_var) = env.stack.pop() {
+                    if let Some(min_var) = env.stack.pop() {
+                        if let Some((min, max, units)) = bounds(min_var.val.read().deref(), max_var.val.read().deref()) {
+                            if min <= max {
+                                let val = graph.rng().uniform(min, max);
+                                env.stack.push(Variable::val(num(val, units)));
+                                return Ok(None);
+                            }
+                        }
+                    }
+                }
+                Err(Error::RandFloat)
+            },
+            Self::Normal => {
+                if let Some(std_var) = env.stack.pop() {
+                    if let Some(mean_var) = env.stack.pop() {
+                        if let Some((mean, std_dev, units)) = bounds(mean_var.val.read().deref(), std_var.val.read().deref()) {
+                            let val = graph.rng().normal(mean, std_dev);
+                            env.stack.push(Variable::val(num(val, units)));
+                            return Ok(None);
+                        }
+                    }
+                }
+                Err(Error::RandNormal)
+            },
+            Self::Bool => {
+                if let Some(p_var) = env.stack.pop() {
+                    if let Val::Num(p) = p_var.val.read().deref() {
+                        let val = graph.rng().float() < p.float(None);
+                        env.stack.push(Variable::val(Val::Bool(val)));
+                        return Ok(None);
+                    }
+                }
+                Err(Error::RandBool)
+            },
+            Self::Choice => {
+                if let Some(list_var) = env.stack.pop() {
+                    if let Val::List(list) = list_var.val.read().deref() {
+                        if list.is_empty() {
+                            env.stack.push(Variable::val(Val::Null));
+                        } else {
+                            let index = graph.rng().index(list.len());
+                            env.stack.push(Variable::refval(list[index].duplicate(false)));
+                        }
+                        return Ok(None);
+                    }
+                }
+                Err(Error::RandChoice)
+            },
+            Self::Shuffle => {
+                if let Some(list_var) = env.stack.pop() {
+                    if let Val::List(list) = list_var.val.write().deref_mut() {
+                        graph.rng().shuffle(list);
+                        return Ok(None);
+                    }
+                }
+                Err(Error::RandShuffle)
+            },
+            Self::Sample => {
+                if let Some(count_var) = env.stack.pop() {
+                    if let Some(list_var) = env.stack.pop() {
+                        if let Val::Num(count) = count_var.val.read().deref() {
+                            if let Val::List(list) = list_var.val.read().deref() {
+                                // partial Fisher-Yates: the first "count" values are a sample without replacement
+                                let count = (count.int().max(0) as usize).min(list.len());
+                                let mut values = list.clone();
+                                let rng = graph.rng();
+                                for i in 0..count {
+                                    let j = i + rng.index(values.len() - i);
+                                    values.swap(i, j);
+                                }
+                                let sample = values.into_iter().take(count).map(|val| val.duplicate(false)).collect::<Vector<_>>();
+                                env.stack.push(Variable::val(Val::List(sample)));
+                                return Ok(None);
+                            }
+                        }
+                    }
+                }
+                Err(Error::RandSample)
+            },
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use imbl::{vector, Vector};
+    use super::Rng;
+
+    #[test]
+    fn seeded_is_reproducible() {
+        let mut a = Rng::seeded(42);
+        let mut b = Rng::seeded(42);
+        for _ in 0..100 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+        assert_ne!(Rng::seeded(1).next_u64(), Rng::seeded(2).next_u64());
+    }
+
+    #[test]
+    fn ranges() {
+        let mut rng = Rng::seeded(7);
+        let mut seen = [false; 6];
+        for _ in 0..1000 {
+            let roll = rng.int(1, 6);
+            assert!((1..=6).contains(&roll));
+            seen[roll as usize - 1] = true;
+
+            let val = rng.uniform(-2., 3.);
+            assert!((-2. ..3.).contains(&val));
+        }
+        assert!(seen.iter().all(|seen| *seen));
+        assert_eq!(rng.int(5, 5), 5);
+        rng.int(i64::MIN, i64::MAX); // full range doesn't overflow
+    }
+
+    #[test]
+    fn normal_mean() {
+        let mut rng = Rng::seeded(3);
+        let mean = (0..10000).map(|_| rng.normal(10., 2.)).sum::<f64>() / 10000.;
+        assert!((mean - 10.).abs() < 0.1);
+    }
+
+    #[test]
+    fn shuffle_is_permutation() {
+        let mut rng = Rng::seeded(11);
+        let mut values: Vector<i32> = vector![1, 2, 3, 4, 5, 6, 7, 8];
+        rng.shuffle(&mut values);
+        let mut sorted = values.clone();
+        sorted.sort();
+        assert_eq!(sorted, vector![1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+}
@@ -0,0 +1,274 @@
+//
+// Copyright 2025 Formata, Inc. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use std::sync::Arc;
+use imbl::vector;
+use crate::{model::{rand::{RAND_LIB, BOOL, CHOICE, FLOAT, INT, NORMAL, SAMPLE, SEED, SHUFFLE}, LibFunc, Param}, runtime::{instruction::Instructions, instructions::Base, Num, NumT, Type, Val}};
+
+
+/// Seed.
+pub fn rand_seed() -> LibFunc {
+    LibFunc {
+        library: RAND_LIB.clone(),
+        name: "seed".into(),
+        is_async: false,
+        docs: r#"# Rand.seed(seed: int) -> void
+Seed this graph's random number generator, making every following Rand call reproducible (simulations, tests, etc.). Unseeded graphs use a seed from the OS (or the browser when running in JS).
+```rust
+Rand.seed(42);
+const a = Rand.int(0, 100);
+Rand.seed(42);
+assert_eq(Rand.int(0, 100), a);
+```
+"#.into(),
+        params: vector![
+            Param { name: "seed".into(), param_type: Type::Num(NumT::Int), default: None },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(SEED.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// Int.
+pub fn rand_int() -> LibFunc {
+    LibFunc {
+        library: RAND_LIB.clone(),
+        name: "int".into(),
+        is_async: false,
+        docs: r#"# Rand.int(min: int, max: int) -> int
+Random integer between min and max (both inclusive). Throws an error if min is greater than max.
+```rust
+const roll = Rand.int(1, 6);
+assert(roll >= 1 && roll <= 6);
+```
+"#.into(),
+        params: vector![
+            Param { name: "min".into(), param_type: Type::Num(NumT::Int), default: None },
+            Param { name: "max".into(), param_type: Type::Num(NumT::Int), default: None },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(INT.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// Float.
+pub fn rand_float() -> LibFunc {
+    LibFunc {
+        library: RAND_LIB.clone(),
+        name: "float".into(),
+        is_async: false,
+        docs: r#"# Rand.float(min: float = 0, max: float = 1) -> float
+Random float in the range [min, max), uniformly distributed. Bounds with units give a value in those units.
+```rust
+const val = Rand.float();
+assert(val >= 0 && val < 1);
+
+const length = Rand.float(1m, 5m);
+assert(length >= 1m && length < 5m);
+```
+"#.into(),
+        params: vector![
+            Param { name: "min".into(), param_type: Type::Void, default: Some(Arc::new(Base::Literal(Val::Num(Num::Int(0))))) },
+            Param { name: "max".into(), param_type: Type::Void, default: Some(Arc::new(Base::Literal(Val::Num(Num::Int(1))))) },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(FLOAT.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// Uniform.
+pub fn rand_uniform() -> LibFunc {
+    LibFunc {
+        library: RAND_LIB.clone(),
+        name: "uniform".into(),
+        is_async: false,
+        docs: r#"# Rand.uniform(min: float, max: float) -> float
+Sample a uniform distribution over the range [min, max). Same as Rand.float, but with required bounds. Bounds with units give a value in those units.
+```rust
+const delay = Rand.uniform(100ms, 2s);
+assert(delay >= 100ms && delay < 2s);
+```
+"#.into(),
+        params: vector![
+            Param { name: "min".into(), param_type: Type::Void, default: None },
+            Param { name: "max".into(), param_type: Type::Void, default: None },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(FLOAT.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// Normal.
+pub fn rand_normal() -> LibFunc {
+    LibFunc {
+        library: RAND_LIB.clone(),
+        name: "normal".into(),
+        is_async: false,
+        docs: r#"# Rand.normal(mean: float = 0, std_dev: float = 1) -> float
+Sample a normal (Gaussian) distribution with a mean and standard deviation. A mean or standard deviation with units gives a value in those units.
+```rust
+const height = Rand.normal(170cm, 10cm);
+```
+"#.into(),
+        params: vector![
+            Param { name: "mean".into(), param_type: Type::Void, default: Some(Arc::new(Base::Literal(Val::Num(Num::Int(0))))) },
+            Param { name: "std_dev".into(), param_type: Type::Void, default: Some(Arc::new(Base::Literal(Val::Num(Num::Int(1))))) },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(NORMAL.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// Bool.
+pub fn rand_bool() -> LibFunc {
+    LibFunc {
+        library: RAND_LIB.clone(),
+        name: "bool".into(),
+        is_async: false,
+        docs: r#"# Rand.bool(p: float = 0.5) -> bool
+Random boolean that is true with probability p.
+```rust
+const heads = Rand.bool();
+assert(Rand.bool(1));
+assert_not(Rand.bool(0));
+```
+"#.into(),
+        params: vector![
+            Param { name: "p".into(), param_type: Type::Void, default: Some(Arc::new(Base::Literal(Val::Num(Num::Float(0.5))))) },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(BOOL.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// Choice.
+pub fn rand_choice() -> LibFunc {
+    LibFunc {
+        library: RAND_LIB.clone(),
+        name: "choice".into(),
+        is_async: false,
+        docs: r#"# Rand.choice(list: list) -> unknown
+Random value from a list, or null if the list is empty.
+```rust
+const color = Rand.choice(['red', 'green', 'blue']);
+assert(['red', 'green', 'blue'].contains(color));
+```
+"#.into(),
+        params: vector![
+            Param { name: "list".into(), param_type: Type::List, default: None },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(CHOICE.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// Shuffle.
+pub fn rand_shuffle() -> LibFunc {
+    LibFunc {
+        library: RAND_LIB.clone(),
+        name: "shuffle".into(),
+        is_async: false,
+        docs: r#"# Rand.shuffle(list: list) -> void
+Shuffle a list in-place.
+```rust
+const deck = [1, 2, 3, 4, 5];
+Rand.shuffle(deck);
+assert_eq(deck.len(), 5);
+```
+"#.into(),
+        params: vector![
+            Param { name: "list".into(), param_type: Type::List, default: None },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(SHUFFLE.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// Sample.
+pub fn rand_sample() -> LibFunc {
+    LibFunc {
+        library: RAND_LIB.clone(),
+        name: "sample".into(),
+        is_async: false,
+        docs: r#"# Rand.sample(list: list, count: int) -> list
+Random sample of count values from a list (without replacement), in random order. If count is larger than the list, every value is returned (shuffled).
+```rust
+const winners = Rand.sample(['a', 'b', 'c', 'd'], 2);
+assert_eq(winners.len(), 2);
+```
+"#.into(),
+        params: vector![
+            Param { name: "list".into(), param_type: Type::List, default: None },
+            Param { name: "count".into(), param_type: Type::Num(NumT::Int), default: None },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(SAMPLE.clone());
+            Ok(instructions)
+        })
+    }
+}
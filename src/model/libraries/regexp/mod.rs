@@ -0,0 +1,424 @@
+//
+// Copyright 2025 Formata, Inc. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use std::{ops::Deref, sync::Arc};
+use arcstr::{literal, ArcStr};
+use imbl::{vector, OrdMap, Vector};
+use lazy_static::lazy_static;
+use parking_lot::RwLock;
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use crate::{model::{regexp::ops::{regex_captures, regex_captures_iter, regex_escape, regex_find, regex_find_all, regex_is_match, regex_new, regex_pattern, regex_replace, regex_replace_all, regex_split}, Graph, SId, StofData}, runtime::{instruction::{Instruction, Instructions}, instructions::{call::FuncCall, Base}, proc::ProcEnv, Error, Num, Val, ValRef, Variable}};
+
+mod ops;
+
+
+/// Library name.
+pub(self) const REGEX_LIB: ArcStr = literal!("Regex");
+
+/// Max number of compiled patterns kept in the cache.
+const REGEX_CACHE_SIZE: usize = 256;
+
+
+/// Add the regex library to a graph.
+pub fn insert_regex_lib(graph: &mut Graph) {
+    graph.insert_libfunc(regex_new());
+    graph.insert_libfunc(regex_pattern());
+    graph.insert_libfunc(regex_is_match());
+    graph.insert_libfunc(regex_find());
+    graph.insert_libfunc(regex_find_all());
+    graph.insert_libfunc(regex_captures());
+    graph.insert_libfunc(regex_captures_iter());
+    graph.insert_libfunc(regex_replace());
+    graph.insert_libfunc(regex_replace_all());
+    graph.insert_libfunc(regex_split());
+    graph.insert_libfunc(regex_escape());
+}
+
+
+lazy_static! {
+    pub(self) static ref NEW: Arc<dyn Instruction> = Arc::new(RegexIns::New);
+    pub(self) static ref PATTERN: Arc<dyn Instruction> = Arc::new(RegexIns::Pattern);
+    pub(self) static ref IS_MATCH: Arc<dyn Instruction> = Arc::new(RegexIns::IsMatch);
+    pub(self) static ref FIND: Arc<dyn Instruction> = Arc::new(RegexIns::Find);
+    pub(self) static ref FIND_ALL: Arc<dyn Instruction> = Arc::new(RegexIns::FindAll);
+    pub(self) static ref CAPTURES: Arc<dyn Instruction> = Arc::new(RegexIns::Captures);
+    pub(self) static ref CAPTURES_ITER: Arc<dyn Instruction> = Arc::new(RegexIns::CapturesIter);
+    pub(self) static ref REPLACE: Arc<dyn Instruction> = Arc::new(RegexIns::Replace(false));
+    pub(self) static ref REPLACE_ALL: Arc<dyn Instruction> = Arc::new(RegexIns::Replace(true));
+    pub(self) static ref SPLIT: Arc<dyn Instruction> = Arc::new(RegexIns::Split);
+    pub(self) static ref ESCAPE: Arc<dyn Instruction> = Arc::new(RegexIns::Escape);
+
+    /// Compiled patterns, shared by every graph (compiling is far more expensive than matching).
+    static ref REGEX_CACHE: RwLock<FxHashMap<String, regex::Regex>> = RwLock::new(FxHashMap::default());
+}
+
+
+/// Compile a pattern, using the cache of previously compiled patterns.
+pub fn compile(pattern: &str) -> Result<regex::Regex, Error> {
+    if let Some(regex) = REGEX_CACHE.read().get(pattern) {
+        return Ok(regex.clone()); // cheap (shared internally)
+    }
+    match regex::Regex::new(pattern) {
+        Ok(regex) => {
+            let mut cache = REGEX_CACHE.write();
+            if cache.len() >= REGEX_CACHE_SIZE {
+                cache.clear();
+            }
+            cache.insert(pattern.to_string(), regex.clone());
+            Ok(regex)
+        },
+        Err(error) => Err(Error::RegexNew(error.to_string())),
+    }
+}
+
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+/// Compiled regular expression (Data<Regex>).
+/// Only the pattern is serialized, and it is compiled again (from the cache) when first used.
+pub struct Regex {
+    pub pattern: String,
+
+    #[serde(skip)]
+    compiled: Option<regex::Regex>,
+}
+
+#[typetag::serde(name = "Regex")] // also the libname (Data<Regex>)!
+impl StofData for Regex {}
+
+impl Regex {
+    /// Create (compile) a new regex.
+    pub fn new(pattern: &str) -> Result<Self, Error> {
+        Ok(Self {
+            pattern: pattern.to_string(),
+            compiled: Some(compile(pattern)?),
+        })
+    }
+
+    /// Compiled regex.
+    pub fn compiled(&mut self) -> Result<regex::Regex, Error> {
+        if let Some(regex) = &self.compiled {
+            return Ok(regex.clone());
+        }
+        let regex = compile(&self.pattern)?;
+        self.compiled = Some(regex.clone());
+        Ok(regex)
+    }
+}
+
+
+/// Regex from a pattern (str) or Data<Regex> value.
+fn regex_of(var: &Variable, graph: &mut Graph) -> Option<Result<regex::Regex, Error>> {
+    if let Val::Str(pattern) = var.val.read().deref() {
+        return Some(compile(pattern));
+    }
+    if let Some(dref) = var.try_data_or_func() {
+        if let Some(regex) = graph.get_mut_stof_data::<Regex>(&dref) {
+            return Some(regex.compiled());
+        }
+    }
+    None
+}
+
+/// String value.
+fn str_of(var: &Variable) -> Option<String> {
+    match var.val.read().deref() {
+        Val::Str(val) => Some(val.to_string()),
+        _ => None,
+    }
+}
+
+/// Match tuple (content, start, end), like Str.find_matches.
+fn match_tup(re_match: regex::Match) -> Val {
+    Val::Tup(vector![
+        ValRef::new(Val::Str(re_match.as_str().into())),
+        ValRef::new(Val::Num(Num::Int(re_match.start() as i64))),
+        ValRef::new(Val::Num(Num::Int(re_match.end() as i64))),
+    ])
+}
+
+/// Capture groups as a map (index and name keys, null for groups that didn't participate).
+fn captures_map(regex: &regex::Regex, captures: &regex::Captures) -> Val {
+    let mut map = OrdMap::default();
+    for (index, name) in regex.capture_names().enumerate() {
+        let val = match captures.get(index) {
+            Some(group) => Val::Str(group.as_str().into()),
+            None => Val::Null,
+        };
+        if let Some(name) = name {
+            map.insert(ValRef::new(Val::Str(name.into())), ValRef::new(val.clone()));
+        }
+        map.insert(ValRef::new(Val::Num(Num::Int(index as i64))), ValRef::new(val));
+    }
+    Val::Map(map)
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Regex instructions.
+pub enum RegexIns {
+    New,
+    Pattern,
+    IsMatch,
+    Find,
+    FindAll,
+    Captures,
+    CapturesIter,
+
+    /// Replace the first match (false), or all matches (true).
+    Replace(bool),
+    /// Join the text between matches with replacements returned by a callback (on the stack).
+    JoinReplaced(Vec<String>),
+
+    Split,
+    Escape,
+}
+#[typetag::serde(name = "RegexIns")]
+impl Instruction for RegexIns {
+    fn exec(&self, env: &mut ProcEnv, graph: &mut Graph) -> Result<Option<Instructions>, Error> {
+        match self {
+            Self::New => {
+                // Regex.new(pattern: str, context: obj = self) -> Data<Regex>
+                let mut context = env.self_ptr();
+                if let Some(context_var) = env.stack.pop() {
+                    if let Some(ctx) = context_var.try_obj() {
+                        context = ctx;
+                    }
+                }
+                if let Some(pattern_var) = env.stack.pop() {
+                    if let Some(pattern) = str_of(&pattern_var) {
+                        let regex = Regex::new(&pattern)?;
+                        let name = SId::default();
+                        let id = name.clone();
+                        if let Some(dref) = graph.insert_stof_data(&context, name, Box::new(regex), Some(id)) {
+                            env.stack.push(Variable::val(Val::Data(dref)));
+                            return Ok(None);
+                        }
+                    }
+                }
+                Err(Error::RegexNew("expected a str pattern".into()))
+            },
+            Self::Pattern => {
+                if let Some(regex_var) = env.stack.pop() {
+                    if let Some(regex) = regex_of(&regex_var, graph) {
+                        env.stack.push(Variable::val(Val::Str(regex?.as_str().into())));
+                        return Ok(None);
+                    }
+                }
+                Err(Error::RegexPattern)
+            },
+            Self::IsMatch => {
+                if let Some(text_var) = env.stack.pop() {
+                    if let Some(regex_var) = env.stack.pop() {
+                        if let Some(regex) = regex_of(&regex_var, graph) {
+                            if let Some(text) = str_of(&text_var) {
+                                env.stack.push(Variable::val(Val::Bool(regex?.is_match(&text))));
+                                return Ok(None);
+                            }
+                        }
+                    }
+                }
+                Err(Error::RegexIsMatch)
+            },
+            Self::Find => {
+                if let Some(text_var) = env.stack.pop() {
+                    if let Some(regex_var) = env.stack.pop() {
+                        if let Some(regex) = regex_of(&regex_var, graph) {
+                            if let Some(text) = str_of(&text_var) {
+                                match regex?.find(&text) {
+                                    Some(re_match) => env.stack.push(Variable::val(match_tup(re_match))),
+                                    None => env.stack.push(Variable::val(Val::Null)),
+                                }
+                                return Ok(None);
+                            }
+                        }
+                    }
+                }
+                Err(Error::RegexFind)
+            },
+            Self::FindAll => {
+                if let Some(text_var) = env.stack.pop() {
+                    if let Some(regex_var) = env.stack.pop() {
+                        if let Some(regex) = regex_of(&regex_var, graph) {
+                            if let Some(text) = str_of(&text_var) {
+                                let list = regex?.find_iter(&text).map(|re_match| ValRef::new(match_tup(re_match))).collect::<Vector<_>>();
+                                env.stack.push(Variable::val(Val::List(list)));
+                                return Ok(None);
+                            }
+                        }
+                    }
+                }
+                Err(Error::RegexFind)
+            },
+            Self::Captures => {
+                if let Some(text_var) = env.stack.pop() {
+                    if let Some(regex_var) = env.stack.pop() {
+                        if let Some(regex) = regex_of(&regex_var, graph) {
+                            if let Some(text) = str_of(&text_var) {
+                                let regex = regex?;
+                                match regex.captures(&text) {
+                                    Some(captures) => env.stack.push(Variable::val(captures_map(&regex, &captures))),
+                                    None => env.stack.push(Variable::val(Val::Null)),
+                                }
+                                return Ok(None);
+                            }
+                        }
+                    }
+                }
+                Err(Error::RegexCaptures)
+            },
+            Self::CapturesIter => {
+                if let Some(text_var) = env.stack.pop() {
+                    if let Some(regex_var) = env.stack.pop() {
+                        if let Some(regex) = regex_of(&regex_var, graph) {
+                            if let Some(text) = str_of(&text_var) {
+                                let regex = regex?;
+                                let list = regex.captures_iter(&text).map(|captures| ValRef::new(captures_map(&regex, &captures))).collect::<Vector<_>>();
+                                env.stack.push(Variable::val(Val::List(list)));
+                                return Ok(None);
+                            }
+                        }
+                    }
+                }
+                Err(Error::RegexCaptures)
+            },
+            Self::Replace(all) => {
+                if let Some(replacement_var) = env.stack.pop() {
+                    if let Some(text_var) = env.stack.pop() {
+                        if let Some(regex_var) = env.stack.pop() {
+                            if let Some(regex) = regex_of(&regex_var, graph) {
+                                if let Some(text) = str_of(&text_var) {
+                                    let regex = regex?;
+                                    let limit = if *all { 0 } else { 1 };
+
+                                    // template replacement (Ex. "$1" or "${name}")
+                                    if let Some(template) = str_of(&replacement_var) {
+                                        let res = regex.replacen(&text, limit, template.as_str());
+                                        env.stack.push(Variable::val(Val::Str(res.as_ref().into())));
+                                        return Ok(None);
+                                    }
+
+                                    // callback replacement, called with the captures map of each match
+                                    if let Some(func) = replacement_var.try_func() {
+                                        let mut instructions = Instructions::default();
+                                        let mut segments = Vec::new();
+                                        let mut last = 0;
+                                        for captures in regex.captures_iter(&text).take(if *all { usize::MAX } else { 1 }) {
+                                            if let Some(whole) = captures.get(0) {
+                                                segments.push(text[last..whole.start()].to_string());
+                                                last = whole.end();
+                                            }
+                                            instructions.push(Arc::new(FuncCall {
+                                                func: Some(func.clone()),
+                                                search: None,
+                                                stack: false,
+                                                as_ref: false,
+                                                cnull: false,
+                                                args: vector![Arc::new(Base::Literal(captures_map(&regex, &captures))) as Arc<dyn Instruction>],
+                                                oself: None,
+                                            }));
+                                        }
+                                        segments.push(text[last..].to_string());
+                                        instructions.push(Arc::new(Self::JoinReplaced(segments)));
+                                        return Ok(Some(instructions));
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                Err(Error::RegexReplace)
+            },
+            Self::JoinReplaced(segments) => {
+                let count = segments.len().saturating_sub(1);
+                let mut replacements = Vec::with_capacity(count);
+                for _ in 0..count {
+                    if let Some(var) = env.stack.pop() {
+                        replacements.push(var.val.read().print(graph));
+                    } else {
+                        return Err(Error::RegexReplace);
+                    }
+                }
+                let mut res = String::new();
+                for (index, segment) in segments.iter().enumerate() {
+                    res.push_str(segment);
+                    if index < count {
+                        if let Some(replacement) = replacements.pop() { res.push_str(&replacement); }
+                    }
+                }
+                env.stack.push(Variable::val(Val::Str(res.into())));
+                Ok(None)
+            },
+            Self::Split => {
+                if let Some(limit_var) = env.stack.pop() {
+                    if let Some(text_var) = env.stack.pop() {
+                        if let Some(regex_var) = env.stack.pop() {
+                            if let Some(regex) = regex_of(&regex_var, graph) {
+                                if let Some(text) = str_of(&text_var) {
+                                    let regex = regex?;
+                                    let list = match limit_var.val.read().deref() {
+                                        Val::Num(limit) if limit.int() > 0 => {
+                                            regex.splitn(&text, limit.int() as usize).map(|part| ValRef::new(Val::Str(part.into()))).collect::<Vector<_>>()
+                                        },
+                                        _ => {
+                                            regex.split(&text).map(|part| ValRef::new(Val::Str(part.into()))).collect::<Vector<_>>()
+                                        },
+                                    };
+                                    env.stack.push(Variable::val(Val::List(list)));
+                                    return Ok(None);
+                                }
+                            }
+                        }
+                    }
+                }
+                Err(Error::RegexSplit)
+            },
+            Self::Escape => {
+                if let Some(text_var) = env.stack.pop() {
+                    if let Some(text) = str_of(&text_var) {
+                        env.stack.push(Variable::val(Val::Str(regex::escape(&text).into())));
+                        return Ok(None);
+                    }
+                }
+                Err(Error::RegexEscape)
+            },
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{compile, Regex, REGEX_CACHE};
+
+    #[test]
+    fn cached() {
+        let pattern = r"(?<year>\d{4})-cache-test";
+        let regex = compile(pattern).unwrap();
+        assert!(REGEX_CACHE.read().contains_key(pattern));
+        assert!(compile(pattern).unwrap().is_match("2025-cache-test"));
+        assert_eq!(regex.as_str(), pattern);
+        assert!(compile("(unclosed").is_err());
+    }
+
+    #[test]
+    fn recompiled_after_deserialize() {
+        let regex = Regex::new(r"\d+").unwrap();
+        let bytes = bincode::serialize(&regex).unwrap();
+        let mut regex = bincode::deserialize::<Regex>(&bytes).unwrap();
+        assert!(regex.compiled().unwrap().is_match("42"));
+    }
+}
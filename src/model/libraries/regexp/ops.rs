@@ -0,0 +1,336 @@
+//
+// Copyright 2025 Formata, Inc. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use std::sync::Arc;
+use imbl::vector;
+use crate::{model::{regexp::{REGEX_LIB, CAPTURES, CAPTURES_ITER, ESCAPE, FIND, FIND_ALL, IS_MATCH, NEW, PATTERN, REPLACE, REPLACE_ALL, SPLIT}, LibFunc, Param}, runtime::{instruction::Instructions, instructions::Base, Type, Val}};
+
+
+/// New.
+pub fn regex_new() -> LibFunc {
+    LibFunc {
+        library: REGEX_LIB.clone(),
+        name: "new".into(),
+        is_async: false,
+        docs: r#"# Regex.new(pattern: str, context: obj = self) -> Data<Regex>
+Compile a regular expression (Rust regex syntax) into data on the given context object (default is self). Throws an error if the pattern is invalid. Every Regex function also accepts a pattern string in place of a compiled regex (compiled patterns are cached either way).
+```rust
+const re = Regex.new("(?<year>\\d{4})-(?<month>\\d{2})");
+assert(re.is_match("2025-06"));
+```
+"#.into(),
+        params: vector![
+            Param { name: "pattern".into(), param_type: Type::Str, default: None },
+            Param { name: "context".into(), param_type: Type::Void, default: Some(Arc::new(Base::Literal(Val::Null))) },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(NEW.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// Pattern.
+pub fn regex_pattern() -> LibFunc {
+    LibFunc {
+        library: REGEX_LIB.clone(),
+        name: "pattern".into(),
+        is_async: false,
+        docs: r#"# Regex.pattern(regex: str | Data<Regex>) -> str
+The pattern this regex was compiled from.
+```rust
+const re = Regex.new("\\d+");
+assert_eq(re.pattern(), "\\d+");
+```
+"#.into(),
+        params: vector![
+            Param { name: "regex".into(), param_type: Type::Union(vector![Type::Str, Type::Data(REGEX_LIB.clone())]), default: None },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(PATTERN.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// Is match.
+pub fn regex_is_match() -> LibFunc {
+    LibFunc {
+        library: REGEX_LIB.clone(),
+        name: "is_match".into(),
+        is_async: false,
+        docs: r#"# Regex.is_match(regex: str | Data<Regex>, text: str) -> bool
+Does this regex match anywhere in the text?
+```rust
+const re = Regex.new("^\\d+$");
+assert(re.is_match("42"));
+assert_not(re.is_match("forty-two"));
+```
+"#.into(),
+        params: vector![
+            Param { name: "regex".into(), param_type: Type::Union(vector![Type::Str, Type::Data(REGEX_LIB.clone())]), default: None },
+            Param { name: "text".into(), param_type: Type::Str, default: None },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(IS_MATCH.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// Find.
+pub fn regex_find() -> LibFunc {
+    LibFunc {
+        library: REGEX_LIB.clone(),
+        name: "find".into(),
+        is_async: false,
+        docs: r#"# Regex.find(regex: str | Data<Regex>, text: str) -> (str, int, int)
+First match in the text as a tuple (content, start, end), or null if there isn't a match. Start and end are byte offsets.
+```rust
+assert_eq(Regex.find("\\d+", "abc 123 def"), ("123", 4, 7));
+```
+"#.into(),
+        params: vector![
+            Param { name: "regex".into(), param_type: Type::Union(vector![Type::Str, Type::Data(REGEX_LIB.clone())]), default: None },
+            Param { name: "text".into(), param_type: Type::Str, default: None },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(FIND.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// Find all.
+pub fn regex_find_all() -> LibFunc {
+    LibFunc {
+        library: REGEX_LIB.clone(),
+        name: "find_all".into(),
+        is_async: false,
+        docs: r#"# Regex.find_all(regex: str | Data<Regex>, text: str) -> list
+Every match in the text as a list of tuples (content, start, end), like Str.find_matches.
+```rust
+const matches = Regex.find_all("\\d+", "1 22 333");
+assert_eq(matches.len(), 3);
+assert_eq(matches[2], ("333", 5, 8));
+```
+"#.into(),
+        params: vector![
+            Param { name: "regex".into(), param_type: Type::Union(vector![Type::Str, Type::Data(REGEX_LIB.clone())]), default: None },
+            Param { name: "text".into(), param_type: Type::Str, default: None },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(FIND_ALL.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// Captures.
+pub fn regex_captures() -> LibFunc {
+    LibFunc {
+        library: REGEX_LIB.clone(),
+        name: "captures".into(),
+        is_async: false,
+        docs: r#"# Regex.captures(regex: str | Data<Regex>, text: str) -> map
+Capture groups of the first match, or null if there isn't a match. The map has a key for each group index (0 is the whole match) and each group name. Groups that did not participate in the match are null.
+```rust
+const re = Regex.new("(?<year>\\d{4})-(?<month>\\d{2})");
+const caps = re.captures("due 2025-06");
+assert_eq(caps.get(0), "2025-06");
+assert_eq(caps.get(1), "2025");
+assert_eq(caps.get("month"), "06");
+```
+"#.into(),
+        params: vector![
+            Param { name: "regex".into(), param_type: Type::Union(vector![Type::Str, Type::Data(REGEX_LIB.clone())]), default: None },
+            Param { name: "text".into(), param_type: Type::Str, default: None },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(CAPTURES.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// Captures iter.
+pub fn regex_captures_iter() -> LibFunc {
+    LibFunc {
+        library: REGEX_LIB.clone(),
+        name: "captures_iter".into(),
+        is_async: false,
+        docs: r#"# Regex.captures_iter(regex: str | Data<Regex>, text: str) -> list
+Capture groups (maps, see Regex.captures) for every match in the text.
+```rust
+const re = Regex.new("(?<key>\\w+)=(?<val>\\w+)");
+for (const caps in re.captures_iter("a=1 b=2")) {
+    pln(caps.get("key"), caps.get("val"));
+}
+```
+"#.into(),
+        params: vector![
+            Param { name: "regex".into(), param_type: Type::Union(vector![Type::Str, Type::Data(REGEX_LIB.clone())]), default: None },
+            Param { name: "text".into(), param_type: Type::Str, default: None },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(CAPTURES_ITER.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// Replace.
+pub fn regex_replace() -> LibFunc {
+    LibFunc {
+        library: REGEX_LIB.clone(),
+        name: "replace".into(),
+        is_async: false,
+        docs: r##"# Regex.replace(regex: str | Data<Regex>, text: str, replacement: str | fn) -> str
+Replace the first match in the text. See Regex.replace_all for the replacement options.
+```rust
+assert_eq(Regex.replace("\\d+", "1 2 3", "#"), "# 2 3");
+```
+"##.into(),
+        params: vector![
+            Param { name: "regex".into(), param_type: Type::Union(vector![Type::Str, Type::Data(REGEX_LIB.clone())]), default: None },
+            Param { name: "text".into(), param_type: Type::Str, default: None },
+            Param { name: "replacement".into(), param_type: Type::Union(vector![Type::Str, Type::Fn]), default: None },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(REPLACE.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// Replace all.
+pub fn regex_replace_all() -> LibFunc {
+    LibFunc {
+        library: REGEX_LIB.clone(),
+        name: "replace_all".into(),
+        is_async: false,
+        docs: r#"# Regex.replace_all(regex: str | Data<Regex>, text: str, replacement: str | fn) -> str
+Replace every match in the text. A string replacement is a template, where "$1" or "${name}" is replaced with a capture group ("$$" for a literal "$"). A function replacement is called with the captures map (see Regex.captures) of each match, and what it returns is inserted as a string.
+```rust
+const re = Regex.new("(?<first>\\w+) (?<last>\\w+)");
+assert_eq(re.replace_all("Jane Doe", "${last}, $first"), "Doe, Jane");
+
+const doubled = Regex.replace_all("\\d+", "1 2 3", (caps: map): str => ((caps.get(0) as int) * 2) as str);
+assert_eq(doubled, "2 4 6");
+```
+"#.into(),
+        params: vector![
+            Param { name: "regex".into(), param_type: Type::Union(vector![Type::Str, Type::Data(REGEX_LIB.clone())]), default: None },
+            Param { name: "text".into(), param_type: Type::Str, default: None },
+            Param { name: "replacement".into(), param_type: Type::Union(vector![Type::Str, Type::Fn]), default: None },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(REPLACE_ALL.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// Split.
+pub fn regex_split() -> LibFunc {
+    LibFunc {
+        library: REGEX_LIB.clone(),
+        name: "split".into(),
+        is_async: false,
+        docs: r#"# Regex.split(regex: str | Data<Regex>, text: str, limit: int = null) -> list
+Split text by the matches of a regex, optionally into at most "limit" parts.
+```rust
+assert_eq(Regex.split("\\s*,\\s*", "a , b,c"), ["a", "b", "c"]);
+assert_eq(Regex.split(",", "a,b,c", 2), ["a", "b,c"]);
+```
+"#.into(),
+        params: vector![
+            Param { name: "regex".into(), param_type: Type::Union(vector![Type::Str, Type::Data(REGEX_LIB.clone())]), default: None },
+            Param { name: "text".into(), param_type: Type::Str, default: None },
+            Param { name: "limit".into(), param_type: Type::Void, default: Some(Arc::new(Base::Literal(Val::Null))) },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(SPLIT.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// Escape.
+pub fn regex_escape() -> LibFunc {
+    LibFunc {
+        library: REGEX_LIB.clone(),
+        name: "escape".into(),
+        is_async: false,
+        docs: r#"# Regex.escape(text: str) -> str
+Escape all regex meta characters in the text, so that it matches literally.
+```rust
+assert(Regex.is_match(Regex.escape("1+1=2"), "is 1+1=2?"));
+```
+"#.into(),
+        params: vector![
+            Param { name: "text".into(), param_type: Type::Str, default: None },
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(ESCAPE.clone());
+            Ok(instructions)
+        })
+    }
+}
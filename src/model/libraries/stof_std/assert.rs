@@ -31,6 +31,16 @@ Throw an error with an optional value. Optionally catch this value within a try-
 ```rust
 throw("error message");
 ```
+
+Thrown objects can be caught by type (prototype). Runtime errors are caught as their message, or as objects with "kind", "message", "trace", and "location" fields when caught as an "obj", "Error", or by kind (error objects are dropped when the try statement is left).
+```rust
+try throw(new NotFound { message: "missing" });
+catch (error: NotFound) { pln(error.message); }
+finally { pln("always runs"); }
+
+try Std.DNE();
+catch (error: FuncDne) { pln(error.kind, error.trace); }
+```
 "#.into(),
         params: vector![
             Param { name: "value".into(), param_type: Type::Void, default: Some(Arc::new(Base::Literal(Val::Str(literal!("Error"))))) }
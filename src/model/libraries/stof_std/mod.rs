@@ -1106,6 +1106,7 @@ impl Instruction for StdIns {
                 if let Some(var) = env.stack.pop() {
                     match var.val.read().deref() {
                         Val::Str(var) => {
+                            graph.sandbox_env(var)?;
                             if let Ok(env_var) = std::env::var(var.as_str()) {
                                 env.stack.push(Variable::val(Val::Str(env_var.into())));
                             } else {
@@ -1124,6 +1125,7 @@ impl Instruction for StdIns {
                     if let Some(key_var) = env.stack.pop() {
                         match key_var.val.read().deref() {
                             Val::Str(key) => {
+                                graph.sandbox_env(key)?;
                                 match value_var.val.read().deref() {
                                     Val::Str(value) => {
                                         unsafe {
@@ -1145,6 +1147,7 @@ impl Instruction for StdIns {
                 if let Some(var) = env.stack.pop() {
                     match var.val.read().deref() {
                         Val::Str(var) => {
+                            graph.sandbox_env(var)?;
                             unsafe {
                                 std::env::remove_var(var.as_str());
                             }
@@ -1160,6 +1163,7 @@ impl Instruction for StdIns {
                 use imbl::OrdMap;
                 let mut map = OrdMap::default();
                 for (k, v) in std::env::vars() {
+                    if graph.sandbox_env(&k).is_err() { continue; } // only the vars allowed by the sandbox
                     map.insert(ValRef::new(Val::Str(k.into())), ValRef::new(Val::Str(v.into())));
                 }
                 env.stack.push(Variable::val(Val::Map(map)));
@@ -18,9 +18,8 @@ use std::{ops::{Deref, DerefMut}, sync::Arc};
 use arcstr::{literal, ArcStr};
 use imbl::{Vector, vector};
 use lazy_static::lazy_static;
-use regex::Regex;
 use serde::{Deserialize, Serialize};
-use crate::{model::{Graph, string::ops::{str_at, str_contains, str_ends_with, str_find_matches, str_first, str_index_of, str_last, str_len, str_lower, str_matches, str_push, str_replace, str_split, str_starts_with, str_substr, str_trim, str_trim_end, str_trim_start, str_upper}}, runtime::{Error, Num, Val, ValRef, Variable, instruction::{Instruction, Instructions}, proc::ProcEnv}};
+use crate::{model::{Graph, regexp::compile, string::ops::{str_at, str_contains, str_ends_with, str_find_matches, str_first, str_format, str_index_of, str_last, str_len, str_lower, str_matches, str_push, str_replace, str_split, str_starts_with, str_substr, str_trim, str_trim_end, str_trim_start, str_upper}}, runtime::{Error, FormatSpec, Num, Val, ValRef, Variable, instruction::{Instruction, Instructions}, proc::ProcEnv}};
 
 mod ops;
 
@@ -49,6 +48,7 @@ pub fn insert_string_lib(graph: &mut Graph) {
     graph.insert_libfunc(str_substr());
     graph.insert_libfunc(str_matches());
     graph.insert_libfunc(str_find_matches());
+    graph.insert_libfunc(str_format());
 }
 
 
@@ -99,10 +99,12 @@ pub enum StrIns {
     // REGEX
     IsMatch,
     FindAll,
+
+    Format(usize),
 }
 #[typetag::serde(name = "StrIns")]
 impl Instruction for StrIns {
-    fn exec(&self, env: &mut ProcEnv, _graph: &mut Graph) -> Result<Option<Instructions>, Error> {
+    fn exec(&self, env: &mut ProcEnv, graph: &mut Graph) -> Result<Option<Instructions>, Error> {
         match self {
             Self::Len => {
                 if let Some(var) = env.stack.pop() {
@@ -440,7 +442,7 @@ impl Instruction for StrIns {
                             Val::Str(val) => {
                                 match matches_var.val.read().deref() {
                                     Val::Str(matches) => {
-                                        if let Ok(regex) = Regex::new(&matches) {
+                                        if let Ok(regex) = compile(&matches) {
                                             env.stack.push(Variable::val(Val::Bool(regex.is_match(&val))));
                                             return Ok(None);
                                         } else {
@@ -465,7 +467,7 @@ impl Instruction for StrIns {
                             Val::Str(val) => {
                                 match regex.val.read().deref() {
                                     Val::Str(regex) => {
-                                        if let Ok(regex) = Regex::new(&regex) {
+                                        if let Ok(regex) = compile(&regex) {
                                             let mut list = vector![];
                                             for re_match in regex.find_iter(&val) {
                                                 let tup = vector![
@@ -489,7 +491,92 @@ impl Instruction for StrIns {
                     }
                 }
                 Err(Error::StrFindAll)
-            }
+            },
+            Self::Format(stack_count) => {
+                let mut args = Vec::new();
+                if *stack_count > 1 {
+                    for _ in 0..(*stack_count - 1) {
+                        if let Some(var) = env.stack.pop() {
+                            args.push(var.val.read().clone());
+                        }
+                    }
+                    args.reverse();
+                }
+                if let Some(var) = env.stack.pop() {
+                    match var.val.read().deref() {
+                        Val::Str(fmt) => {
+                            let formatted = format_str(fmt, &args, graph)?;
+                            env.stack.push(Variable::val(Val::Str(formatted.into())));
+                            return Ok(None);
+                        },
+                        _ => {}
+                    }
+                }
+                Err(Error::StrFormat("expected a format string".into()))
+            },
+        }
+    }
+}
+
+
+/// Format a string, replacing "{}", "{index}", "{:spec}", and "{index:spec}" placeholders with args.
+/// Use "{{" and "}}" for literal braces.
+pub fn format_str(fmt: &str, args: &[Val], graph: &Graph) -> Result<String, Error> {
+    let mut res = String::default();
+    let mut next = 0;
+    let mut chars = fmt.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => {
+                if chars.peek() == Some(&'{') {
+                    chars.next();
+                    res.push('{');
+                    continue;
+                }
+                let mut placeholder = String::default();
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        closed = true;
+                        break;
+                    }
+                    placeholder.push(c);
+                }
+                if !closed {
+                    return Err(Error::StrFormat(format!("unclosed placeholder in '{fmt}'")));
+                }
+
+                let (index, spec) = match placeholder.split_once(':') {
+                    Some((index, spec)) => (index.trim(), Some(spec)),
+                    None => (placeholder.trim(), None),
+                };
+                let index = if index.is_empty() {
+                    next += 1;
+                    next - 1
+                } else if let Ok(index) = index.parse::<usize>() {
+                    index
+                } else {
+                    return Err(Error::StrFormat(format!("invalid placeholder '{{{placeholder}}}'")));
+                };
+                let Some(arg) = args.get(index) else {
+                    return Err(Error::StrFormat(format!("missing argument {index} for '{fmt}'")));
+                };
+
+                if let Some(spec) = spec {
+                    let spec = FormatSpec::parse(spec).map_err(Error::StrFormat)?;
+                    res.push_str(&spec.format(arg, graph)?);
+                } else {
+                    res.push_str(&arg.print(graph));
+                }
+            },
+            '}' => {
+                if chars.peek() == Some(&'}') {
+                    chars.next();
+                }
+                res.push('}');
+            },
+            _ => res.push(c),
         }
     }
+    Ok(res)
 }
@@ -17,7 +17,7 @@
 use std::sync::Arc;
 use arcstr::literal;
 use imbl::vector;
-use crate::{model::{LibFunc, Param, string::{AT, CONTAINS, ENDS_WITH, FIND_ALL, FIRST, INDEX_OF, IS_MATCH, LAST, LEN, LOWER, PUSH, REPLACE, SPLIT, STARTS_WITH, STR_LIB, SUBSTRING, TRIM, TRIM_END, TRIM_START, UPPER}}, runtime::{Num, NumT, Type, Val, instruction::Instructions, instructions::Base}};
+use crate::{model::{LibFunc, Param, string::{StrIns, AT, CONTAINS, ENDS_WITH, FIND_ALL, FIRST, INDEX_OF, IS_MATCH, LAST, LEN, LOWER, PUSH, REPLACE, SPLIT, STARTS_WITH, STR_LIB, SUBSTRING, TRIM, TRIM_END, TRIM_START, UPPER}}, runtime::{Num, NumT, Type, Val, instruction::Instructions, instructions::Base}};
 
 
 /// Len.
@@ -549,3 +549,37 @@ assert_eq(val.find_matches(regex), [("categorically", 2, 15)]);
         })
     }
 }
+
+/// Format.
+pub fn str_format() -> LibFunc {
+    LibFunc {
+        library: STR_LIB.clone(),
+        name: "format".into(),
+        is_async: false,
+        docs: r#"# Str.format(fmt: str, ..) -> str
+Format a string with any number of arguments. Placeholders are "{}" (next argument), "{index}" (a specific argument), and either with a format spec after a colon, like "{:.2}" or "{1:>8}". Use "{{" and "}}" for literal braces.
+
+Format specs are the same as in template strings: `[[fill]align][+][0][width][,][.precision][type][units]`, where align is "<", ">", or "^", type is one of "e", "E", "x", "X", "b", or "o", and units converts the number before display. A spec starting with "%" formats a timestamp (strftime).
+```rust
+assert_eq(Str.format("{} x {:.2}", 3, 1.23456), "3 x 1.23");
+assert_eq("{1}, {0}".format("world", "hello"), "hello, world");
+assert_eq(Str.format("[{:>6}]", 42), "[    42]");
+assert_eq(Str.format("{:,.2}", 1234567.891), "1,234,567.89");
+assert_eq(Str.format("{:.1MiB}", 1572864bytes), "1.5MiB");
+assert_eq(Str.format("{:%Y-%m-%d}", 0ms), "1970-01-01");
+```
+"#.into(),
+        params: vector![
+            Param { name: "fmt".into(), param_type: Type::Str, default: None },
+            // Unbounded arguments after the format string
+        ],
+        return_type: None,
+        unbounded_args: true,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(Arc::new(StrIns::Format(arg_count)));
+            Ok(instructions)
+        })
+    }
+}
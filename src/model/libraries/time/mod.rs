@@ -17,10 +17,11 @@
 use std::{ops::Deref, sync::Arc};
 use web_time::{Duration, SystemTime, UNIX_EPOCH};
 use arcstr::{literal, ArcStr};
-use chrono::{DateTime, Datelike, Days, NaiveDate, Timelike, Utc, Weekday};
+use chrono::{format::{Item, StrftimeItems}, DateTime, Datelike, Days, LocalResult, NaiveDate, NaiveDateTime, Offset, SecondsFormat, TimeDelta, TimeZone, Timelike, Utc, Weekday};
+use chrono_tz::Tz;
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
-use crate::{model::{time::ops::{time_add_days, time_add_months, time_day_of_month, time_day_of_week, time_days_in_month, time_diff, time_diff_ns, time_from_rfc2822, time_from_rfc3339, time_hour, time_minute, time_month, time_now, time_now_ns, time_now_rfc2822, time_now_rfc3339, time_second, time_sleep, time_start_of_day, time_start_of_month, time_start_of_period, time_start_of_week, time_start_of_year, time_to_rfc2822, time_to_rfc3339, time_year}, Graph}, runtime::{instruction::{Instruction, Instructions}, instructions::Base, proc::ProcEnv, Error, Num, Units, Val, Variable}};
+use crate::{model::{time::ops::{time_add_days, time_add_months, time_day_of_month, time_day_of_week, time_days_in_month, time_diff, time_format, time_diff_ns, time_duration, time_from_rfc2822, time_from_rfc3339, time_hour, time_instant, time_iso_duration, time_minute, time_month, time_now, time_now_ns, time_now_rfc2822, time_now_rfc3339, time_parse, time_second, time_sleep, time_start_of_day, time_start_of_month, time_start_of_period, time_start_of_week, time_start_of_year, time_to_rfc2822, time_to_rfc3339, time_year, time_zone}, Graph}, parser::datetime::parse_iso_duration_alone, runtime::{instruction::{Instruction, Instructions}, instructions::Base, proc::ProcEnv, Error, Num, Units, Val, Variable}};
 mod ops;
 
 
@@ -62,6 +63,16 @@ pub fn insert_time_lib(graph: &mut Graph) {
     // Start of week
     graph.insert_libfunc(time_start_of_week());
     graph.insert_libfunc(time_start_of_year());
+
+    // Formatting & parsing
+    graph.insert_libfunc(time_format());
+    graph.insert_libfunc(time_parse());
+
+    // Time values & durations
+    graph.insert_libfunc(time_instant());
+    graph.insert_libfunc(time_zone());
+    graph.insert_libfunc(time_duration());
+    graph.insert_libfunc(time_iso_duration());
 }
 
 
@@ -94,26 +105,191 @@ lazy_static! {
     pub(self) static ref HOUR: Arc<dyn Instruction> = Arc::new(TimeIns::Hour);
     pub(self) static ref MINUTE: Arc<dyn Instruction> = Arc::new(TimeIns::Minute);
     pub(self) static ref SECOND: Arc<dyn Instruction> = Arc::new(TimeIns::Second);
+    pub(self) static ref FORMAT: Arc<dyn Instruction> = Arc::new(TimeIns::Format);
+    pub(self) static ref PARSE: Arc<dyn Instruction> = Arc::new(TimeIns::Parse);
+    pub(self) static ref INSTANT: Arc<dyn Instruction> = Arc::new(TimeIns::Instant);
+    pub(self) static ref ZONE: Arc<dyn Instruction> = Arc::new(TimeIns::Zone);
+    pub(self) static ref DURATION: Arc<dyn Instruction> = Arc::new(TimeIns::Duration);
+    pub(self) static ref ISO_DURATION: Arc<dyn Instruction> = Arc::new(TimeIns::IsoDuration);
 }
 
 
-/// Parse a ms timestamp from the stack into a chrono UTC DateTime.
-fn pop_ms_as_datetime(env: &mut ProcEnv) -> Option<DateTime<Utc>> {
+/// Timestamp argument: a ms number, or a time value with an optional zone.
+struct Stamp {
+    utc: DateTime<Utc>,
+    zone: Option<ArcStr>,
+    time: bool,
+}
+impl Stamp {
+    /// Pop a timestamp from the stack.
+    fn pop(env: &mut ProcEnv) -> Option<Self> {
+        if let Some(var) = env.stack.pop() {
+            match var.val.read().deref() {
+                Val::Num(num) => {
+                    let ms = num.float(Some(Units::Milliseconds)).abs() as i64;
+                    return Some(Self { utc: DateTime::from_timestamp_millis(ms)?, zone: None, time: false });
+                },
+                Val::Time(ms, zone) => {
+                    return Some(Self { utc: DateTime::from_timestamp_millis(*ms)?, zone: zone.clone(), time: true });
+                },
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Time zone to use: the given zone, else the zone of a time value, else UTC.
+    fn tz(&self, tz: Option<Tz>) -> Tz {
+        tz.or_else(|| self.zone.as_ref().and_then(|zone| parse_tz(zone))).unwrap_or(Tz::UTC)
+    }
+
+    /// This timestamp as a local DateTime (and the zone used).
+    fn local(&self, tz: Option<Tz>) -> (DateTime<Tz>, Tz) {
+        let tz = self.tz(tz);
+        (self.utc.with_timezone(&tz), tz)
+    }
+
+    /// Push a resulting instant in the same form as this timestamp (time value or ms).
+    fn push<T: TimeZone>(&self, env: &mut ProcEnv, dt: DateTime<T>) {
+        if self.time {
+            env.stack.push(Variable::val(Val::Time(dt.timestamp_millis(), self.zone.clone())));
+        } else {
+            push_datetime_as_ms(env, dt);
+        }
+    }
+}
+
+/// Push a DateTime back onto the stack as a ms unit value.
+fn push_datetime_as_ms<T: TimeZone>(env: &mut ProcEnv, dt: DateTime<T>) {
+    env.stack.push(Variable::val(Val::Num(Num::Units(dt.timestamp_millis() as f64, Units::Milliseconds))));
+}
+
+/// Parse a time zone name: "UTC" or an IANA name, like "America/Denver".
+pub fn parse_tz(name: &str) -> Option<Tz> {
+    if name.is_empty() {
+        return Some(Tz::UTC);
+    }
+    name.parse::<Tz>().ok()
+}
+
+/// Zone name stored on a time value (UTC is stored as no zone).
+pub fn zone_name(tz: &Tz) -> Option<ArcStr> {
+    if *tz == Tz::UTC {
+        return None;
+    }
+    Some(tz.name().into())
+}
+
+/// RFC 3339 string for a time value, with the offset of its zone (if any).
+pub fn instant_rfc3339(ms: i64, zone: Option<&ArcStr>) -> Option<String> {
+    let utc = DateTime::from_timestamp_millis(ms)?;
+    if let Some(tz) = zone.and_then(|zone| parse_tz(zone)) {
+        return Some(utc.with_timezone(&tz).to_rfc3339_opts(SecondsFormat::AutoSi, true));
+    }
+    Some(utc.to_rfc3339_opts(SecondsFormat::AutoSi, true))
+}
+
+/// String for a time value: RFC 3339, with an RFC 9557 "[Zone]" suffix when it has a zone.
+pub fn instant_to_string(ms: i64, zone: Option<&ArcStr>) -> String {
+    if let Some(rfc3339) = instant_rfc3339(ms, zone) {
+        if let Some(zone) = zone {
+            return format!("{rfc3339}[{zone}]");
+        }
+        return rfc3339;
+    }
+    ms.to_string()
+}
+
+/// ISO 8601 duration string for a number of milliseconds ("P1DT2H30M").
+/// Days are the largest unit, since months and years have no fixed length.
+pub fn iso_duration_string(ms: f64) -> String {
+    let mut res = String::new();
+    if ms < 0. { res.push('-'); }
+    res.push('P');
+
+    let total = ms.abs().round() as u64;
+    let days = total / 86_400_000;
+    let hours = (total / 3_600_000) % 24;
+    let minutes = (total / 60_000) % 60;
+    let millis = total % 60_000;
+    if days > 0 {
+        res.push_str(&format!("{days}D"));
+    }
+    if hours > 0 || minutes > 0 || millis > 0 || days == 0 {
+        res.push('T');
+        if hours > 0 { res.push_str(&format!("{hours}H")); }
+        if minutes > 0 { res.push_str(&format!("{minutes}M")); }
+        if millis > 0 || (hours == 0 && minutes == 0) {
+            let secs = millis / 1000;
+            let frac = millis % 1000;
+            if frac > 0 {
+                res.push_str(&format!("{secs}.{}S", format!("{frac:03}").trim_end_matches('0')));
+            } else {
+                res.push_str(&format!("{secs}S"));
+            }
+        }
+    }
+    res
+}
+
+/// Pop an optional time zone name from the stack (null if not given).
+fn pop_tz(env: &mut ProcEnv) -> Result<Option<Tz>, Error> {
     if let Some(var) = env.stack.pop() {
         match var.val.read().deref() {
-            Val::Num(num) => {
-                let ms = num.float(Some(Units::Milliseconds)).abs() as i64;
-                return DateTime::from_timestamp_millis(ms);
+            Val::Null |
+            Val::Void => return Ok(None),
+            Val::Str(name) => {
+                if let Some(tz) = parse_tz(name) {
+                    return Ok(Some(tz));
+                }
+                return Err(Error::TimeZone(format!("unknown time zone '{name}'")));
             },
             _ => {}
         }
     }
+    Err(Error::TimeZone("expected a time zone name".into()))
+}
+
+/// Resolve a local wall-clock time in a time zone to a UTC DateTime.
+/// Ambiguous times (DST fall back) resolve to the earlier instant, and times skipped by
+/// a DST gap (spring forward) are shifted forward by the length of the gap.
+pub fn local_to_utc(tz: &Tz, local: NaiveDateTime) -> Option<DateTime<Utc>> {
+    match tz.from_local_datetime(&local) {
+        LocalResult::Single(dt) => Some(dt.with_timezone(&Utc)),
+        LocalResult::Ambiguous(earliest, _) => Some(earliest.with_timezone(&Utc)),
+        LocalResult::None => {
+            // Use the offset in effect before the gap
+            let offset = tz.offset_from_utc_datetime(&(local - TimeDelta::days(1))).fix();
+            let utc = local - TimeDelta::seconds(offset.local_minus_utc() as i64);
+            Some(utc.and_utc())
+        },
+    }
+}
+
+/// Is this a valid strftime format string?
+pub fn valid_time_format(fmt: &str) -> bool {
+    !StrftimeItems::new(fmt).any(|item| item == Item::Error)
+}
+
+/// Parse a date/time string with a strftime format.
+/// If the string has no offset, it is interpreted as local time in the given time zone,
+/// and if it has no time, midnight is used.
+pub fn parse_time(val: &str, fmt: &str, tz: &Tz) -> Option<DateTime<Utc>> {
+    if let Ok(dt) = DateTime::parse_from_str(val, fmt) {
+        return Some(dt.with_timezone(&Utc));
+    }
+    if let Ok(local) = NaiveDateTime::parse_from_str(val, fmt) {
+        return local_to_utc(tz, local);
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(val, fmt) {
+        return local_to_utc(tz, date.and_hms_opt(0, 0, 0)?);
+    }
     None
 }
 
-/// Push a DateTime<Utc> back onto the stack as a ms unit value.
-fn push_datetime_as_ms(env: &mut ProcEnv, dt: DateTime<Utc>) {
-    env.stack.push(Variable::val(Val::Num(Num::Units(dt.timestamp_millis() as f64, Units::Milliseconds))));
+/// Local midnight at the start of a date.
+fn midnight(date: NaiveDate) -> Option<NaiveDateTime> {
+    date.and_hms_opt(0, 0, 0)
 }
 
 /// Parse a schedule string and return the start of the current period for a given timestamp.
@@ -125,6 +301,18 @@ fn push_datetime_as_ms(env: &mut ProcEnv, dt: DateTime<Utc>) {
 ///   "yearly:M-D"         — Month M, day D of every year (e.g. "yearly:1-1" for Jan 1)
 ///   "quarterly:D"        — Day D of the first month of each quarter (Jan/Apr/Jul/Oct)
 pub fn start_of_period_for(ts: DateTime<Utc>, schedule: &str) -> Option<DateTime<Utc>> {
+    start_of_period_in(ts, schedule, &Tz::UTC)
+}
+
+/// Start of the current period for a given timestamp, evaluated in a time zone.
+/// Periods start at local midnight in that time zone (see start_of_period_for).
+pub fn start_of_period_in(ts: DateTime<Utc>, schedule: &str, tz: &Tz) -> Option<DateTime<Utc>> {
+    let local = ts.with_timezone(tz).naive_local();
+    local_to_utc(tz, local_start_of_period(local, schedule)?)
+}
+
+/// Start of the current period for a local (wall-clock) timestamp.
+fn local_start_of_period(ts: NaiveDateTime, schedule: &str) -> Option<NaiveDateTime> {
     let parts: Vec<&str> = schedule.splitn(3, ':').collect();
     if parts.is_empty() { return None; }
 
@@ -137,18 +325,16 @@ pub fn start_of_period_for(ts: DateTime<Utc>, schedule: &str) -> Option<DateTime
             } else {
                 parts[1].parse::<u32>().ok()?.min(dim).max(1)
             };
-            // Period start: target_day of this month at midnight UTC
+            // Period start: target_day of this month at local midnight
             let candidate = NaiveDate::from_ymd_opt(ts.year(), ts.month(), target_day)?
-                .and_hms_opt(0, 0, 0)?
-                .and_utc();
+                .and_hms_opt(0, 0, 0)?;
             // If we haven't reached the target day yet this month, period started last month
             if ts < candidate {
                 let (prev_year, prev_month) = prev_month(ts.year(), ts.month());
                 let prev_dim = days_in_month(prev_year, prev_month);
                 let prev_day = target_day.min(prev_dim);
                 Some(NaiveDate::from_ymd_opt(prev_year, prev_month, prev_day)?
-                    .and_hms_opt(0, 0, 0)?
-                    .and_utc())
+                    .and_hms_opt(0, 0, 0)?)
             } else {
                 Some(candidate)
             }
@@ -157,14 +343,14 @@ pub fn start_of_period_for(ts: DateTime<Utc>, schedule: &str) -> Option<DateTime
             if parts.len() < 2 { return None; }
             let target_wd = parse_weekday(parts[1])?;
             // Walk back from ts to find the most recent occurrence of target_wd
-            let mut candidate = ts.date_naive();
+            let mut candidate = ts.date();
             for _ in 0..7 {
                 if candidate.weekday() == target_wd {
                     break;
                 }
                 candidate = candidate.pred_opt()?;
             }
-            Some(candidate.and_hms_opt(0, 0, 0)?.and_utc())
+            Some(candidate.and_hms_opt(0, 0, 0)?)
         },
         "nth_weekday" => {
             if parts.len() < 3 { return None; }
@@ -187,17 +373,15 @@ pub fn start_of_period_for(ts: DateTime<Utc>, schedule: &str) -> Option<DateTime
             if date_parts.len() < 2 { return None; }
             let target_month: u32 = date_parts[0].parse::<u32>().ok()?.max(1).min(12);
             let target_day: u32 = date_parts[1].parse::<u32>().ok()?.max(1).min(31);
-            // Period start: target_month/target_day of this year at midnight UTC, clamped to month length
+            // Period start: target_month/target_day of this year at local midnight, clamped to month length
             let dim = days_in_month(ts.year(), target_month);
             let candidate = NaiveDate::from_ymd_opt(ts.year(), target_month, target_day.min(dim))?
-                .and_hms_opt(0, 0, 0)?
-                .and_utc();
+                .and_hms_opt(0, 0, 0)?;
             if ts < candidate {
                 // Anniversary hasn't arrived yet this year — period started last year
                 let prev_dim = days_in_month(ts.year() - 1, target_month);
                 Some(NaiveDate::from_ymd_opt(ts.year() - 1, target_month, target_day.min(prev_dim))?
-                    .and_hms_opt(0, 0, 0)?
-                    .and_utc())
+                    .and_hms_opt(0, 0, 0)?)
             } else {
                 Some(candidate)
             }
@@ -211,8 +395,7 @@ pub fn start_of_period_for(ts: DateTime<Utc>, schedule: &str) -> Option<DateTime
             let dim = days_in_month(ts.year(), current_quarter_month);
             let day = target_day.min(dim);
             let candidate = NaiveDate::from_ymd_opt(ts.year(), current_quarter_month, day)?
-                .and_hms_opt(0, 0, 0)?
-                .and_utc();
+                .and_hms_opt(0, 0, 0)?;
             if ts < candidate {
                 // Haven't reached the start day in this quarter yet — use previous quarter
                 let (prev_year, prev_quarter_month) = if current_quarter_month == 1 {
@@ -222,8 +405,7 @@ pub fn start_of_period_for(ts: DateTime<Utc>, schedule: &str) -> Option<DateTime
                 };
                 let prev_dim = days_in_month(prev_year, prev_quarter_month);
                 Some(NaiveDate::from_ymd_opt(prev_year, prev_quarter_month, target_day.min(prev_dim))?
-                    .and_hms_opt(0, 0, 0)?
-                    .and_utc())
+                    .and_hms_opt(0, 0, 0)?)
             } else {
                 Some(candidate)
             }
@@ -262,8 +444,8 @@ fn parse_weekday(s: &str) -> Option<Weekday> {
     }
 }
 
-/// Find the Nth occurrence of a weekday in a given month, at midnight UTC.
-fn nth_weekday_of_month(year: i32, month: u32, n: u32, wd: Weekday) -> Option<DateTime<Utc>> {
+/// Find the Nth occurrence of a weekday in a given month, at local midnight.
+fn nth_weekday_of_month(year: i32, month: u32, n: u32, wd: Weekday) -> Option<NaiveDateTime> {
     let mut count = 0u32;
     let dim = days_in_month(year, month);
     for day in 1..=dim {
@@ -271,7 +453,7 @@ fn nth_weekday_of_month(year: i32, month: u32, n: u32, wd: Weekday) -> Option<Da
         if d.weekday() == wd {
             count += 1;
             if count == n {
-                return Some(d.and_hms_opt(0, 0, 0)?.and_utc());
+                return d.and_hms_opt(0, 0, 0);
             }
         }
     }
@@ -319,6 +501,16 @@ pub enum TimeIns {
 
     // Start of year
     StartOfYear,
+
+    // Formatting & parsing
+    Format,
+    Parse,
+
+    // Time values & durations
+    Instant,
+    Zone,
+    Duration,
+    IsoDuration,
 }
 #[typetag::serde(name = "TimeIns")]
 impl Instruction for TimeIns {
@@ -346,6 +538,12 @@ impl Instruction for TimeIns {
                             env.stack.push(Variable::val(Val::Num(Num::Units((dur.as_millis() as f64) - millis, Units::Milliseconds))));
                             return Ok(None);
                         },
+                        Val::Time(ms, _) => {
+                            let now = SystemTime::now();
+                            let dur = now.duration_since(UNIX_EPOCH).unwrap();
+                            env.stack.push(Variable::val(Val::Num(Num::Units((dur.as_millis() as f64) - (*ms as f64), Units::Milliseconds))));
+                            return Ok(None);
+                        },
                         _ => {}
                     }
                 }
@@ -393,32 +591,18 @@ impl Instruction for TimeIns {
                 Ok(None)
             },
             Self::ToRFC3339 => {
-                if let Some(var) = env.stack.pop() {
-                    match var.val.read().deref() {
-                        Val::Num(num) => {
-                            let ms = num.float(Some(Units::Milliseconds)).abs() as i64;
-                            if let Some(time) = DateTime::from_timestamp_millis(ms) {
-                                env.stack.push(Variable::val(Val::Str(time.to_rfc3339().into())));
-                                return Ok(None);
-                            }
-                        },
-                        _ => {}
-                    }
+                if let Some(stamp) = Stamp::pop(env) {
+                    let (time, _) = stamp.local(None);
+                    env.stack.push(Variable::val(Val::Str(time.to_rfc3339().into())));
+                    return Ok(None);
                 }
                 Err(Error::TimeToRFC3339)
             },
             Self::ToRFC2822 => {
-                if let Some(var) = env.stack.pop() {
-                    match var.val.read().deref() {
-                        Val::Num(num) => {
-                            let ms = num.float(Some(Units::Milliseconds)).abs() as i64;
-                            if let Some(time) = DateTime::from_timestamp_millis(ms) {
-                                env.stack.push(Variable::val(Val::Str(time.to_rfc2822().into())));
-                                return Ok(None);
-                            }
-                        },
-                        _ => {}
-                    }
+                if let Some(stamp) = Stamp::pop(env) {
+                    let (time, _) = stamp.local(None);
+                    env.stack.push(Variable::val(Val::Str(time.to_rfc2822().into())));
+                    return Ok(None);
                 }
                 Err(Error::TimeToRFC2822)
             },
@@ -456,27 +640,34 @@ impl Instruction for TimeIns {
             // ── Calendar instructions ──────────────────────────────────────
 
             Self::StartOfDay => {
-                if let Some(dt) = pop_ms_as_datetime(env) {
-                    let sod = dt.date_naive().and_hms_opt(0, 0, 0).unwrap().and_utc();
-                    push_datetime_as_ms(env, sod);
-                    return Ok(None);
+                let tz = pop_tz(env)?;
+                if let Some(stamp) = Stamp::pop(env) {
+                    let (dt, tz) = stamp.local(tz);
+                    if let Some(sod) = midnight(dt.date_naive()).and_then(|local| local_to_utc(&tz, local)) {
+                        stamp.push(env, sod);
+                        return Ok(None);
+                    }
                 }
                 Err(Error::Custom("Time.start_of_day: invalid timestamp".into()))
             },
             Self::StartOfMonth => {
-                if let Some(dt) = pop_ms_as_datetime(env) {
+                let tz = pop_tz(env)?;
+                if let Some(stamp) = Stamp::pop(env) {
+                    let (dt, tz) = stamp.local(tz);
                     if let Some(som) = NaiveDate::from_ymd_opt(dt.year(), dt.month(), 1)
-                        .and_then(|d| d.and_hms_opt(0, 0, 0))
-                        .map(|ndt| ndt.and_utc())
+                        .and_then(midnight)
+                        .and_then(|local| local_to_utc(&tz, local))
                     {
-                        push_datetime_as_ms(env, som);
+                        stamp.push(env, som);
                         return Ok(None);
                     }
                 }
                 Err(Error::Custom("Time.start_of_month: invalid timestamp".into()))
             },
             Self::DayOfMonth => {
-                if let Some(dt) = pop_ms_as_datetime(env) {
+                let tz = pop_tz(env)?;
+                if let Some(stamp) = Stamp::pop(env) {
+                    let (dt, _) = stamp.local(tz);
                     env.stack.push(Variable::val(Val::Num(Num::Int(dt.day() as i64))));
                     return Ok(None);
                 }
@@ -484,7 +675,9 @@ impl Instruction for TimeIns {
             },
             Self::DayOfWeek => {
                 // Returns 0=Mon..6=Sun (ISO weekday - 1)
-                if let Some(dt) = pop_ms_as_datetime(env) {
+                let tz = pop_tz(env)?;
+                if let Some(stamp) = Stamp::pop(env) {
+                    let (dt, _) = stamp.local(tz);
                     let dow = dt.weekday().num_days_from_monday() as i64;
                     env.stack.push(Variable::val(Val::Num(Num::Int(dow))));
                     return Ok(None);
@@ -492,7 +685,9 @@ impl Instruction for TimeIns {
                 Err(Error::Custom("Time.day_of_week: invalid timestamp".into()))
             },
             Self::DaysInMonth => {
-                if let Some(dt) = pop_ms_as_datetime(env) {
+                let tz = pop_tz(env)?;
+                if let Some(stamp) = Stamp::pop(env) {
+                    let (dt, _) = stamp.local(tz);
                     let dim = days_in_month(dt.year(), dt.month());
                     env.stack.push(Variable::val(Val::Num(Num::Int(dim as i64))));
                     return Ok(None);
@@ -500,7 +695,8 @@ impl Instruction for TimeIns {
                 Err(Error::Custom("Time.days_in_month: invalid timestamp".into()))
             },
             Self::AddMonths => {
-                // Stack: ts (bottom), n (top)
+                // Stack: ts (bottom), n, tz (top)
+                let tz = pop_tz(env)?;
                 let n = if let Some(var) = env.stack.pop() {
                     match var.val.read().deref() {
                         Val::Num(num) => num.float(None) as i32,
@@ -509,7 +705,8 @@ impl Instruction for TimeIns {
                 } else {
                     return Err(Error::Custom("Time.add_months: missing n".into()));
                 };
-                if let Some(dt) = pop_ms_as_datetime(env) {
+                if let Some(stamp) = Stamp::pop(env) {
+                    let (dt, tz) = stamp.local(tz);
                     let mut year = dt.year();
                     let mut month = dt.month() as i32 + n;
                     while month > 12 { month -= 12; year += 1; }
@@ -518,16 +715,17 @@ impl Instruction for TimeIns {
                     let day = dt.day().min(dim);
                     if let Some(result) = NaiveDate::from_ymd_opt(year, month as u32, day)
                         .and_then(|d| d.and_hms_opt(dt.hour(), dt.minute(), dt.second()))
-                        .map(|ndt| ndt.and_utc())
+                        .and_then(|local| local_to_utc(&tz, local))
                     {
-                        push_datetime_as_ms(env, result);
+                        stamp.push(env, result);
                         return Ok(None);
                     }
                 }
                 Err(Error::Custom("Time.add_months: invalid arguments".into()))
             },
             Self::AddDays => {
-                // Stack: ts (bottom), n (top)
+                // Stack: ts (bottom), n, tz (top)
+                let tz = pop_tz(env)?;
                 let n = if let Some(var) = env.stack.pop() {
                     match var.val.read().deref() {
                         Val::Num(num) => num.float(None) as i64,
@@ -536,21 +734,25 @@ impl Instruction for TimeIns {
                 } else {
                     return Err(Error::Custom("Time.add_days: missing n".into()));
                 };
-                if let Some(dt) = pop_ms_as_datetime(env) {
+                if let Some(stamp) = Stamp::pop(env) {
+                    let (dt, tz) = stamp.local(tz);
+                    // Calendar days keep the local wall-clock time, so a day can be 23 or 25 hours across DST
+                    let local = dt.naive_local();
                     let result = if n >= 0 {
-                        dt.checked_add_days(Days::new(n as u64))
+                        local.checked_add_days(Days::new(n as u64))
                     } else {
-                        dt.checked_sub_days(Days::new((-n) as u64))
+                        local.checked_sub_days(Days::new((-n) as u64))
                     };
-                    if let Some(r) = result {
-                        push_datetime_as_ms(env, r);
+                    if let Some(r) = result.and_then(|local| local_to_utc(&tz, local)) {
+                        stamp.push(env, r);
                         return Ok(None);
                     }
                 }
                 Err(Error::Custom("Time.add_days: invalid arguments".into()))
             },
             Self::StartOfPeriod => {
-                // Stack: ts (bottom), schedule str (top)
+                // Stack: ts (bottom), schedule str, tz (top)
+                let tz = pop_tz(env)?;
                 let schedule = if let Some(var) = env.stack.pop() {
                     match var.val.read().deref() {
                         Val::Str(s) => s.to_string(),
@@ -559,9 +761,9 @@ impl Instruction for TimeIns {
                 } else {
                     return Err(Error::Custom("Time.start_of_period: missing schedule".into()));
                 };
-                if let Some(dt) = pop_ms_as_datetime(env) {
-                    if let Some(period_start) = start_of_period_for(dt, &schedule) {
-                        push_datetime_as_ms(env, period_start);
+                if let Some(stamp) = Stamp::pop(env) {
+                    if let Some(period_start) = start_of_period_in(stamp.utc, &schedule, &stamp.tz(tz)) {
+                        stamp.push(env, period_start);
                         return Ok(None);
                     }
                     return Err(Error::Custom(format!("Time.start_of_period: could not compute period for schedule '{}'", schedule).into()));
@@ -572,7 +774,9 @@ impl Instruction for TimeIns {
             // ── Component extraction ───────────────────────────────────────
 
             Self::Year => {
-                if let Some(dt) = pop_ms_as_datetime(env) {
+                let tz = pop_tz(env)?;
+                if let Some(stamp) = Stamp::pop(env) {
+                    let (dt, _) = stamp.local(tz);
                     env.stack.push(Variable::val(Val::Num(Num::Int(dt.year() as i64))));
                     return Ok(None);
                 }
@@ -580,28 +784,36 @@ impl Instruction for TimeIns {
             },
             Self::Month => {
                 // Returns 1–12
-                if let Some(dt) = pop_ms_as_datetime(env) {
+                let tz = pop_tz(env)?;
+                if let Some(stamp) = Stamp::pop(env) {
+                    let (dt, _) = stamp.local(tz);
                     env.stack.push(Variable::val(Val::Num(Num::Int(dt.month() as i64))));
                     return Ok(None);
                 }
                 Err(Error::Custom("Time.month: invalid timestamp".into()))
             },
             Self::Hour => {
-                if let Some(dt) = pop_ms_as_datetime(env) {
+                let tz = pop_tz(env)?;
+                if let Some(stamp) = Stamp::pop(env) {
+                    let (dt, _) = stamp.local(tz);
                     env.stack.push(Variable::val(Val::Num(Num::Int(dt.hour() as i64))));
                     return Ok(None);
                 }
                 Err(Error::Custom("Time.hour: invalid timestamp".into()))
             },
             Self::Minute => {
-                if let Some(dt) = pop_ms_as_datetime(env) {
+                let tz = pop_tz(env)?;
+                if let Some(stamp) = Stamp::pop(env) {
+                    let (dt, _) = stamp.local(tz);
                     env.stack.push(Variable::val(Val::Num(Num::Int(dt.minute() as i64))));
                     return Ok(None);
                 }
                 Err(Error::Custom("Time.minute: invalid timestamp".into()))
             },
             Self::Second => {
-                if let Some(dt) = pop_ms_as_datetime(env) {
+                let tz = pop_tz(env)?;
+                if let Some(stamp) = Stamp::pop(env) {
+                    let (dt, _) = stamp.local(tz);
                     env.stack.push(Variable::val(Val::Num(Num::Int(dt.second() as i64))));
                     return Ok(None);
                 }
@@ -611,7 +823,8 @@ impl Instruction for TimeIns {
             // ── Start of week ──────────────────────────────────────────────
 
             Self::StartOfWeek => {
-                // Stack: ts (bottom), start_day int (top) — 0=Mon (default), 6=Sun
+                // Stack: ts (bottom), start_day int — 0=Mon (default), 6=Sun, tz (top)
+                let tz = pop_tz(env)?;
                 let start_day = if let Some(var) = env.stack.pop() {
                     match var.val.read().deref() {
                         Val::Num(num) => (num.float(None) as u32).min(6),
@@ -620,17 +833,18 @@ impl Instruction for TimeIns {
                 } else {
                     return Err(Error::Custom("Time.start_of_week: missing start_day".into()));
                 };
-                if let Some(dt) = pop_ms_as_datetime(env) {
+                if let Some(stamp) = Stamp::pop(env) {
+                    let (dt, tz) = stamp.local(tz);
                     // ISO: Mon=0..Sun=6 via num_days_from_monday
                     let current_dow = dt.weekday().num_days_from_monday();
                     // How many days back to reach start_day?
                     let days_back = (current_dow + 7 - start_day) % 7;
                     let sow = dt.date_naive()
                         .checked_sub_days(Days::new(days_back as u64))
-                        .and_then(|d| d.and_hms_opt(0, 0, 0))
-                        .map(|ndt| ndt.and_utc());
+                        .and_then(midnight)
+                        .and_then(|local| local_to_utc(&tz, local));
                     if let Some(result) = sow {
-                        push_datetime_as_ms(env, result);
+                        stamp.push(env, result);
                         return Ok(None);
                     }
                 }
@@ -640,17 +854,126 @@ impl Instruction for TimeIns {
             // ── Start of year ──────────────────────────────────────────────
 
             Self::StartOfYear => {
-                if let Some(dt) = pop_ms_as_datetime(env) {
+                let tz = pop_tz(env)?;
+                if let Some(stamp) = Stamp::pop(env) {
+                    let (dt, tz) = stamp.local(tz);
                     if let Some(soy) = NaiveDate::from_ymd_opt(dt.year(), 1, 1)
-                        .and_then(|d| d.and_hms_opt(0, 0, 0))
-                        .map(|ndt| ndt.and_utc())
+                        .and_then(midnight)
+                        .and_then(|local| local_to_utc(&tz, local))
                     {
-                        push_datetime_as_ms(env, soy);
+                        stamp.push(env, soy);
                         return Ok(None);
                     }
                 }
                 Err(Error::Custom("Time.start_of_year: invalid timestamp".into()))
             },
+
+            // ── Formatting & parsing ───────────────────────────────────────
+
+            Self::Format => {
+                // Stack: ts (bottom), fmt str, tz (top)
+                let tz = pop_tz(env)?;
+                let fmt = if let Some(var) = env.stack.pop() {
+                    match var.val.read().deref() {
+                        Val::Str(fmt) => fmt.to_string(),
+                        _ => return Err(Error::TimeFormat("format must be a string".into())),
+                    }
+                } else {
+                    return Err(Error::TimeFormat("missing format".into()));
+                };
+                if !valid_time_format(&fmt) {
+                    return Err(Error::TimeFormat(format!("invalid format '{fmt}'")));
+                }
+                if let Some(stamp) = Stamp::pop(env) {
+                    let (dt, _) = stamp.local(tz);
+                    env.stack.push(Variable::val(Val::Str(dt.format(&fmt).to_string().into())));
+                    return Ok(None);
+                }
+                Err(Error::TimeFormat("invalid timestamp".into()))
+            },
+            Self::Parse => {
+                // Stack: val str (bottom), fmt str, tz (top)
+                let tz = pop_tz(env)?.unwrap_or(Tz::UTC);
+                if let Some(fmt_var) = env.stack.pop() {
+                    if let Some(var) = env.stack.pop() {
+                        match (var.val.read().deref(), fmt_var.val.read().deref()) {
+                            (Val::Str(val), Val::Str(fmt)) => {
+                                if let Some(dt) = parse_time(val, fmt, &tz) {
+                                    push_datetime_as_ms(env, dt);
+                                    return Ok(None);
+                                }
+                                return Err(Error::TimeParse(format!("could not parse '{val}' with format '{fmt}'")));
+                            },
+                            _ => {}
+                        }
+                    }
+                }
+                Err(Error::TimeParse("expected a string and a format string".into()))
+            },
+
+            // ── Time values & durations ────────────────────────────────────
+
+            Self::Instant => {
+                // Stack: ts (bottom), tz (top)
+                let tz = pop_tz(env)?;
+                if let Some(var) = env.stack.pop() {
+                    let (ms, zone) = match var.val.read().deref() {
+                        Val::Null |
+                        Val::Void => (SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as i64, None),
+                        Val::Num(num) => (num.float(Some(Units::Milliseconds)) as i64, None),
+                        Val::Time(ms, zone) => (*ms, zone.clone()),
+                        _ => return Err(Error::Custom("Time.instant: invalid timestamp".into())),
+                    };
+                    let zone = match tz {
+                        Some(tz) => zone_name(&tz),
+                        None => zone,
+                    };
+                    env.stack.push(Variable::val(Val::Time(ms, zone)));
+                    return Ok(None);
+                }
+                Err(Error::Custom("Time.instant: missing timestamp".into()))
+            },
+            Self::Zone => {
+                if let Some(var) = env.stack.pop() {
+                    match var.val.read().deref() {
+                        Val::Time(_, zone) => {
+                            let zone = zone.clone().unwrap_or(literal!("UTC"));
+                            env.stack.push(Variable::val(Val::Str(zone)));
+                            return Ok(None);
+                        },
+                        _ => {}
+                    }
+                }
+                Err(Error::TimeZone("expected a time value".into()))
+            },
+            Self::Duration => {
+                if let Some(var) = env.stack.pop() {
+                    match var.val.read().deref() {
+                        Val::Str(iso) => {
+                            if let Some(duration) = parse_iso_duration_alone(iso) {
+                                env.stack.push(Variable::val(duration));
+                                return Ok(None);
+                            }
+                            return Err(Error::TimeDuration(format!("invalid ISO 8601 duration '{iso}'")));
+                        },
+                        _ => {}
+                    }
+                }
+                Err(Error::TimeDuration("expected an ISO 8601 duration string".into()))
+            },
+            Self::IsoDuration => {
+                if let Some(var) = env.stack.pop() {
+                    match var.val.read().deref() {
+                        Val::Num(num) => {
+                            let iso = iso_duration_string(num.float(Some(Units::Milliseconds)));
+                            env.stack.push(Variable::val(Val::Str(iso.into())));
+                            return Ok(None);
+                        },
+                        _ => {}
+                    }
+                }
+                Err(Error::TimeDuration("expected a duration".into()))
+            },
         }
     }
 }
@@ -15,8 +15,9 @@
 //
 
 use std::sync::Arc;
+use arcstr::literal;
 use imbl::vector;
-use crate::{model::{time::{ADD_DAYS, ADD_MONTHS, DAY_OF_MONTH, DAY_OF_WEEK, DAYS_IN_MONTH, DIFF, DIFF_NANO, FROM_RFC2822, FROM_RFC3339, HOUR, MINUTE, MONTH, NOW, NOW_NANO, NOW_RFC2822, NOW_RFC3339, SECOND, SLEEP, START_OF_DAY, START_OF_MONTH, START_OF_PERIOD, START_OF_WEEK, START_OF_YEAR, TIME_LIB, TO_RFC2822, TO_RFC3339, YEAR}, LibFunc, Param}, runtime::{instruction::Instructions, instructions::Base, Num, NumT, Type, Val}};
+use crate::{model::{time::{ADD_DAYS, ADD_MONTHS, DAY_OF_MONTH, DAY_OF_WEEK, DAYS_IN_MONTH, DIFF, DIFF_NANO, DURATION, FORMAT, FROM_RFC2822, FROM_RFC3339, HOUR, INSTANT, ISO_DURATION, MINUTE, MONTH, NOW, NOW_NANO, NOW_RFC2822, NOW_RFC3339, PARSE, SECOND, SLEEP, START_OF_DAY, START_OF_MONTH, START_OF_PERIOD, START_OF_WEEK, START_OF_YEAR, TIME_LIB, TO_RFC2822, TO_RFC3339, YEAR, ZONE}, LibFunc, Param}, runtime::{instruction::Instructions, instructions::Base, Num, NumT, Type, Val}};
 
 
 /// Now.
@@ -75,8 +76,8 @@ pub fn time_diff() -> LibFunc {
         library: TIME_LIB.clone(),
         name: "diff".into(),
         is_async: false,
-        docs: r#"# Time.diff(prev: float) -> ms
-Convenience function for getting the difference in milliseconds between a previous timestamp (takes any units, default ms) and the current time. Shorthand for (Time.now() - prev).
+        docs: r#"# Time.diff(prev: float | time) -> ms
+Convenience function for getting the difference in milliseconds between a previous timestamp (takes any units, default ms, or a time value) and the current time. Shorthand for (Time.now() - prev).
 ```rust
 const ts = Time.now();
 sleep(50ms);
@@ -85,7 +86,7 @@ assert(diff >= 50ms);
 ```
 "#.into(),
         params: vector![
-            Param { name: "prev".into(), param_type: Type::Num(NumT::Float), default: None }
+            Param { name: "prev".into(), param_type: Type::Union(vector![Type::Num(NumT::Float), Type::Time]), default: None }
         ],
         return_type: None,
         unbounded_args: false,
@@ -212,15 +213,15 @@ pub fn time_to_rfc3339() -> LibFunc {
         library: TIME_LIB.clone(),
         name: "to_rfc3339".into(),
         is_async: false,
-        docs: r#"# Time.to_rfc3339(time: float) -> str
-Returns a string representing the given timestamp according to the RFC-3339 specefication.
+        docs: r#"# Time.to_rfc3339(time: float | time) -> str
+Returns a string representing the given timestamp according to the RFC-3339 specefication. Time values use the offset of their time zone.
 ```rust
 const now = Time.to_rfc3339(Time.now());
 pln(now); // "2025-08-13T16:22:43.028375200+00:00" when these docs were written
 ```
 "#.into(),
         params: vector![
-            Param { name: "time".into(), param_type: Type::Num(NumT::Float), default: None }
+            Param { name: "time".into(), param_type: Type::Union(vector![Type::Num(NumT::Float), Type::Time]), default: None }
         ],
         return_type: None,
         unbounded_args: false,
@@ -239,15 +240,15 @@ pub fn time_to_rfc2822() -> LibFunc {
         library: TIME_LIB.clone(),
         name: "to_rfc2822".into(),
         is_async: false,
-        docs: r#"# Time.to_rfc2822(time: float) -> str
-Returns a string representing the given timestamp according to the RFC-2822 specefication.
+        docs: r#"# Time.to_rfc2822(time: float | time) -> str
+Returns a string representing the given timestamp according to the RFC-2822 specefication. Time values use the offset of their time zone.
 ```rust
 const now = Time.to_rfc2822(Time.now());
 pln(now); // "Wed, 13 Aug 2025 16:24:12 +0000" when these docs were written
 ```
 "#.into(),
         params: vector![
-            Param { name: "time".into(), param_type: Type::Num(NumT::Float), default: None }
+            Param { name: "time".into(), param_type: Type::Union(vector![Type::Num(NumT::Float), Type::Time]), default: None }
         ],
         return_type: None,
         unbounded_args: false,
@@ -319,15 +320,17 @@ pub fn time_start_of_day() -> LibFunc {
         library: TIME_LIB.clone(),
         name: "start_of_day".into(),
         is_async: false,
-        docs: r#"# Time.start_of_day(ts: ms) -> ms
-Returns the UTC midnight timestamp for the day containing the given timestamp.
+        docs: r#"# Time.start_of_day(ts: ms | time, tz: str = null) -> ms | time
+Returns the midnight timestamp for the day containing the given timestamp.
+Midnight is local to the optional tz (an IANA time zone name, Ex. 'America/Denver'), defaulting to UTC.
 ```rust
 const today = Time.start_of_day(Time.now());
 assert(today <= Time.now());
 ```
 "#.into(),
         params: vector![
-            Param { name: "ts".into(), param_type: Type::Num(NumT::Float), default: None }
+            Param { name: "ts".into(), param_type: Type::Union(vector![Type::Num(NumT::Float), Type::Time]), default: None },
+            Param { name: "tz".into(), param_type: Type::Str, default: Some(Arc::new(Base::Literal(Val::Null))) }
         ],
         return_type: None,
         unbounded_args: false,
@@ -346,8 +349,9 @@ pub fn time_start_of_month() -> LibFunc {
         library: TIME_LIB.clone(),
         name: "start_of_month".into(),
         is_async: false,
-        docs: r#"# Time.start_of_month(ts: ms) -> ms
-Returns the UTC midnight timestamp for the first day of the month containing the given timestamp.
+        docs: r#"# Time.start_of_month(ts: ms | time, tz: str = null) -> ms | time
+Returns the midnight timestamp for the first day of the month containing the given timestamp.
+Midnight is local to the optional tz (an IANA time zone name, Ex. 'America/Denver'), defaulting to UTC.
 ```rust
 const som = Time.start_of_month(Time.now());
 assert(som <= Time.now());
@@ -355,7 +359,8 @@ assert(Time.day_of_month(som) == 1);
 ```
 "#.into(),
         params: vector![
-            Param { name: "ts".into(), param_type: Type::Num(NumT::Float), default: None }
+            Param { name: "ts".into(), param_type: Type::Union(vector![Type::Num(NumT::Float), Type::Time]), default: None },
+            Param { name: "tz".into(), param_type: Type::Str, default: Some(Arc::new(Base::Literal(Val::Null))) }
         ],
         return_type: None,
         unbounded_args: false,
@@ -374,15 +379,17 @@ pub fn time_day_of_month() -> LibFunc {
         library: TIME_LIB.clone(),
         name: "day_of_month".into(),
         is_async: false,
-        docs: r#"# Time.day_of_month(ts: ms) -> int
-Returns the day of the month (1–31) for the given UTC timestamp.
+        docs: r#"# Time.day_of_month(ts: ms | time, tz: str = null) -> int
+Returns the day of the month (1–31) for the given timestamp.
+The optional tz is an IANA time zone name (Ex. 'America/Denver') that the calendar is read in, defaulting to UTC.
 ```rust
 const dom = Time.day_of_month(Time.now());
 assert(dom >= 1 && dom <= 31);
 ```
 "#.into(),
         params: vector![
-            Param { name: "ts".into(), param_type: Type::Num(NumT::Float), default: None }
+            Param { name: "ts".into(), param_type: Type::Union(vector![Type::Num(NumT::Float), Type::Time]), default: None },
+            Param { name: "tz".into(), param_type: Type::Str, default: Some(Arc::new(Base::Literal(Val::Null))) }
         ],
         return_type: None,
         unbounded_args: false,
@@ -401,16 +408,18 @@ pub fn time_day_of_week() -> LibFunc {
         library: TIME_LIB.clone(),
         name: "day_of_week".into(),
         is_async: false,
-        docs: r#"# Time.day_of_week(ts: ms) -> int
-Returns the ISO day of the week for the given UTC timestamp.
+        docs: r#"# Time.day_of_week(ts: ms | time, tz: str = null) -> int
+Returns the ISO day of the week for the given timestamp.
 0 = Monday, 1 = Tuesday, ..., 6 = Sunday.
+The optional tz is an IANA time zone name (Ex. 'America/Denver') that the calendar is read in, defaulting to UTC.
 ```rust
 const dow = Time.day_of_week(Time.now());
 assert(dow >= 0 && dow <= 6);
 ```
 "#.into(),
         params: vector![
-            Param { name: "ts".into(), param_type: Type::Num(NumT::Float), default: None }
+            Param { name: "ts".into(), param_type: Type::Union(vector![Type::Num(NumT::Float), Type::Time]), default: None },
+            Param { name: "tz".into(), param_type: Type::Str, default: Some(Arc::new(Base::Literal(Val::Null))) }
         ],
         return_type: None,
         unbounded_args: false,
@@ -429,16 +438,18 @@ pub fn time_days_in_month() -> LibFunc {
         library: TIME_LIB.clone(),
         name: "days_in_month".into(),
         is_async: false,
-        docs: r#"# Time.days_in_month(ts: ms) -> int
-Returns the number of days in the month containing the given UTC timestamp.
+        docs: r#"# Time.days_in_month(ts: ms | time, tz: str = null) -> int
+Returns the number of days in the month containing the given timestamp.
 Correctly handles leap years for February.
+The optional tz is an IANA time zone name (Ex. 'America/Denver') that the calendar is read in, defaulting to UTC.
 ```rust
 const dim = Time.days_in_month(Time.now());
 assert(dim >= 28 && dim <= 31);
 ```
 "#.into(),
         params: vector![
-            Param { name: "ts".into(), param_type: Type::Num(NumT::Float), default: None }
+            Param { name: "ts".into(), param_type: Type::Union(vector![Type::Num(NumT::Float), Type::Time]), default: None },
+            Param { name: "tz".into(), param_type: Type::Str, default: Some(Arc::new(Base::Literal(Val::Null))) }
         ],
         return_type: None,
         unbounded_args: false,
@@ -457,18 +468,20 @@ pub fn time_add_months() -> LibFunc {
         library: TIME_LIB.clone(),
         name: "add_months".into(),
         is_async: false,
-        docs: r#"# Time.add_months(ts: ms, n: int) -> ms
-Adds n calendar months to the given UTC timestamp.
+        docs: r#"# Time.add_months(ts: ms | time, n: int, tz: str = null) -> ms | time
+Adds n calendar months to the given timestamp.
 The day of month is clamped to the last day of the target month if necessary
 (e.g. Jan 31 + 1 month = Feb 28/29).
+The local wall-clock time is kept in the optional tz (an IANA time zone name), defaulting to UTC.
 ```rust
 const next = Time.add_months(Time.now(), 1);
 assert(next > Time.now());
 ```
 "#.into(),
         params: vector![
-            Param { name: "ts".into(), param_type: Type::Num(NumT::Float), default: None },
-            Param { name: "n".into(), param_type: Type::Num(NumT::Float), default: None }
+            Param { name: "ts".into(), param_type: Type::Union(vector![Type::Num(NumT::Float), Type::Time]), default: None },
+            Param { name: "n".into(), param_type: Type::Num(NumT::Float), default: None },
+            Param { name: "tz".into(), param_type: Type::Str, default: Some(Arc::new(Base::Literal(Val::Null))) }
         ],
         return_type: None,
         unbounded_args: false,
@@ -487,8 +500,9 @@ pub fn time_add_days() -> LibFunc {
         library: TIME_LIB.clone(),
         name: "add_days".into(),
         is_async: false,
-        docs: r#"# Time.add_days(ts: ms, n: int) -> ms
-Adds n calendar days to the given UTC timestamp. n may be negative.
+        docs: r#"# Time.add_days(ts: ms | time, n: int, tz: str = null) -> ms | time
+Adds n calendar days to the given timestamp. n may be negative.
+Days are calendar days in the optional tz (an IANA time zone name), so the local wall-clock time is kept across DST changes (a day may be 23 or 25 hours).
 ```rust
 const tomorrow = Time.add_days(Time.now(), 1);
 assert(tomorrow > Time.now());
@@ -497,8 +511,9 @@ assert(yesterday < Time.now());
 ```
 "#.into(),
         params: vector![
-            Param { name: "ts".into(), param_type: Type::Num(NumT::Float), default: None },
-            Param { name: "n".into(), param_type: Type::Num(NumT::Float), default: None }
+            Param { name: "ts".into(), param_type: Type::Union(vector![Type::Num(NumT::Float), Type::Time]), default: None },
+            Param { name: "n".into(), param_type: Type::Num(NumT::Float), default: None },
+            Param { name: "tz".into(), param_type: Type::Str, default: Some(Arc::new(Base::Literal(Val::Null))) }
         ],
         return_type: None,
         unbounded_args: false,
@@ -517,8 +532,8 @@ pub fn time_start_of_period() -> LibFunc {
         library: TIME_LIB.clone(),
         name: "start_of_period".into(),
         is_async: false,
-        docs: r#"# Time.start_of_period(ts: ms, schedule: str) -> ms
-Returns the start of the current billing/reset period for the given UTC timestamp
+        docs: r#"# Time.start_of_period(ts: ms | time, schedule: str, tz: str = null) -> ms | time
+Returns the start of the current billing/reset period for the given timestamp
 and schedule expression. Returns null if the schedule is invalid.
 
 Schedule formats:
@@ -529,7 +544,7 @@ Schedule formats:
   "yearly:M-D"            — Month M, day D of every year (e.g. "yearly:1-1" for Jan 1)
   "quarterly:D"           — Day D of the first month of each quarter (Jan/Apr/Jul/Oct)
 
-All times are UTC. The returned timestamp is always midnight UTC on the period start day.
+Periods are evaluated in the optional tz (an IANA time zone name, Ex. 'America/Denver'), defaulting to UTC. The returned timestamp is always local midnight on the period start day, so customer-local billing periods line up with the customer's calendar.
 
 ```rust
 // Resets on the 1st of every month
@@ -554,8 +569,9 @@ assert(quarterly <= Time.now());
 ```
 "#.into(),
         params: vector![
-            Param { name: "ts".into(), param_type: Type::Num(NumT::Float), default: None },
-            Param { name: "schedule".into(), param_type: Type::Str, default: None }
+            Param { name: "ts".into(), param_type: Type::Union(vector![Type::Num(NumT::Float), Type::Time]), default: None },
+            Param { name: "schedule".into(), param_type: Type::Str, default: None },
+            Param { name: "tz".into(), param_type: Type::Str, default: Some(Arc::new(Base::Literal(Val::Null))) }
         ],
         return_type: None,
         unbounded_args: false,
@@ -574,15 +590,17 @@ pub fn time_year() -> LibFunc {
         library: TIME_LIB.clone(),
         name: "year".into(),
         is_async: false,
-        docs: r#"# Time.year(ts: ms) -> int
-Returns the UTC year for the given timestamp.
+        docs: r#"# Time.year(ts: ms | time, tz: str = null) -> int
+Returns the year for the given timestamp.
+The optional tz is an IANA time zone name (Ex. 'America/Denver') that the calendar is read in, defaulting to UTC.
 ```rust
 const y = Time.year(Time.now());
 assert(y >= 2025);
 ```
 "#.into(),
         params: vector![
-            Param { name: "ts".into(), param_type: Type::Num(NumT::Float), default: None }
+            Param { name: "ts".into(), param_type: Type::Union(vector![Type::Num(NumT::Float), Type::Time]), default: None },
+            Param { name: "tz".into(), param_type: Type::Str, default: Some(Arc::new(Base::Literal(Val::Null))) }
         ],
         return_type: None,
         unbounded_args: false,
@@ -601,15 +619,17 @@ pub fn time_month() -> LibFunc {
         library: TIME_LIB.clone(),
         name: "month".into(),
         is_async: false,
-        docs: r#"# Time.month(ts: ms) -> int
-Returns the UTC month (1–12) for the given timestamp.
+        docs: r#"# Time.month(ts: ms | time, tz: str = null) -> int
+Returns the month (1–12) for the given timestamp.
+The optional tz is an IANA time zone name (Ex. 'America/Denver') that the calendar is read in, defaulting to UTC.
 ```rust
 const m = Time.month(Time.now());
 assert(m >= 1 && m <= 12);
 ```
 "#.into(),
         params: vector![
-            Param { name: "ts".into(), param_type: Type::Num(NumT::Float), default: None }
+            Param { name: "ts".into(), param_type: Type::Union(vector![Type::Num(NumT::Float), Type::Time]), default: None },
+            Param { name: "tz".into(), param_type: Type::Str, default: Some(Arc::new(Base::Literal(Val::Null))) }
         ],
         return_type: None,
         unbounded_args: false,
@@ -628,15 +648,17 @@ pub fn time_hour() -> LibFunc {
         library: TIME_LIB.clone(),
         name: "hour".into(),
         is_async: false,
-        docs: r#"# Time.hour(ts: ms) -> int
-Returns the UTC hour (0–23) for the given timestamp.
+        docs: r#"# Time.hour(ts: ms | time, tz: str = null) -> int
+Returns the hour (0–23) for the given timestamp.
+The optional tz is an IANA time zone name (Ex. 'America/Denver') that the calendar is read in, defaulting to UTC.
 ```rust
 const h = Time.hour(Time.now());
 assert(h >= 0 && h <= 23);
 ```
 "#.into(),
         params: vector![
-            Param { name: "ts".into(), param_type: Type::Num(NumT::Float), default: None }
+            Param { name: "ts".into(), param_type: Type::Union(vector![Type::Num(NumT::Float), Type::Time]), default: None },
+            Param { name: "tz".into(), param_type: Type::Str, default: Some(Arc::new(Base::Literal(Val::Null))) }
         ],
         return_type: None,
         unbounded_args: false,
@@ -655,15 +677,17 @@ pub fn time_minute() -> LibFunc {
         library: TIME_LIB.clone(),
         name: "minute".into(),
         is_async: false,
-        docs: r#"# Time.minute(ts: ms) -> int
-Returns the UTC minute (0–59) for the given timestamp.
+        docs: r#"# Time.minute(ts: ms | time, tz: str = null) -> int
+Returns the minute (0–59) for the given timestamp.
+The optional tz is an IANA time zone name (Ex. 'America/Denver') that the calendar is read in, defaulting to UTC.
 ```rust
 const m = Time.minute(Time.now());
 assert(m >= 0 && m <= 59);
 ```
 "#.into(),
         params: vector![
-            Param { name: "ts".into(), param_type: Type::Num(NumT::Float), default: None }
+            Param { name: "ts".into(), param_type: Type::Union(vector![Type::Num(NumT::Float), Type::Time]), default: None },
+            Param { name: "tz".into(), param_type: Type::Str, default: Some(Arc::new(Base::Literal(Val::Null))) }
         ],
         return_type: None,
         unbounded_args: false,
@@ -682,15 +706,17 @@ pub fn time_second() -> LibFunc {
         library: TIME_LIB.clone(),
         name: "second".into(),
         is_async: false,
-        docs: r#"# Time.second(ts: ms) -> int
-Returns the UTC second (0–59) for the given timestamp.
+        docs: r#"# Time.second(ts: ms | time, tz: str = null) -> int
+Returns the second (0–59) for the given timestamp.
+The optional tz is an IANA time zone name (Ex. 'America/Denver') that the calendar is read in, defaulting to UTC.
 ```rust
 const s = Time.second(Time.now());
 assert(s >= 0 && s <= 59);
 ```
 "#.into(),
         params: vector![
-            Param { name: "ts".into(), param_type: Type::Num(NumT::Float), default: None }
+            Param { name: "ts".into(), param_type: Type::Union(vector![Type::Num(NumT::Float), Type::Time]), default: None },
+            Param { name: "tz".into(), param_type: Type::Str, default: Some(Arc::new(Base::Literal(Val::Null))) }
         ],
         return_type: None,
         unbounded_args: false,
@@ -709,9 +735,10 @@ pub fn time_start_of_week() -> LibFunc {
         library: TIME_LIB.clone(),
         name: "start_of_week".into(),
         is_async: false,
-        docs: r#"# Time.start_of_week(ts: ms, start_day: int = 0) -> ms
-Returns the UTC midnight timestamp for the start of the week containing the given timestamp.
+        docs: r#"# Time.start_of_week(ts: ms | time, start_day: int = 0, tz: str = null) -> ms | time
+Returns the midnight timestamp for the start of the week containing the given timestamp.
 start_day follows ISO convention: 0 = Monday (default), 6 = Sunday.
+Midnight is local to the optional tz (an IANA time zone name, Ex. 'America/Denver'), defaulting to UTC.
 ```rust
 const sow = Time.start_of_week(Time.now());       // week starting Monday
 assert(sow <= Time.now());
@@ -721,8 +748,9 @@ assert(sun <= Time.now());
 ```
 "#.into(),
         params: vector![
-            Param { name: "ts".into(), param_type: Type::Num(NumT::Float), default: None },
-            Param { name: "start_day".into(), param_type: Type::Num(NumT::Float), default: Some(Arc::new(Base::Literal(Val::Num(Num::Int(0))))) }
+            Param { name: "ts".into(), param_type: Type::Union(vector![Type::Num(NumT::Float), Type::Time]), default: None },
+            Param { name: "start_day".into(), param_type: Type::Num(NumT::Float), default: Some(Arc::new(Base::Literal(Val::Num(Num::Int(0))))) },
+            Param { name: "tz".into(), param_type: Type::Str, default: Some(Arc::new(Base::Literal(Val::Null))) }
         ],
         return_type: None,
         unbounded_args: false,
@@ -742,8 +770,9 @@ pub fn time_start_of_year() -> LibFunc {
         library: TIME_LIB.clone(),
         name: "start_of_year".into(),
         is_async: false,
-        docs: r#"# Time.start_of_year(ts: ms) -> ms
-Returns the UTC midnight timestamp for January 1st of the year containing the given timestamp.
+        docs: r#"# Time.start_of_year(ts: ms | time, tz: str = null) -> ms | time
+Returns the midnight timestamp for January 1st of the year containing the given timestamp.
+Midnight is local to the optional tz (an IANA time zone name, Ex. 'America/Denver'), defaulting to UTC.
 ```rust
 const soy = Time.start_of_year(Time.now());
 assert(soy <= Time.now());
@@ -752,7 +781,8 @@ assert(Time.day_of_month(soy) == 1);
 ```
 "#.into(),
         params: vector![
-            Param { name: "ts".into(), param_type: Type::Num(NumT::Float), default: None }
+            Param { name: "ts".into(), param_type: Type::Union(vector![Type::Num(NumT::Float), Type::Time]), default: None },
+            Param { name: "tz".into(), param_type: Type::Str, default: Some(Arc::new(Base::Literal(Val::Null))) }
         ],
         return_type: None,
         unbounded_args: false,
@@ -764,3 +794,175 @@ assert(Time.day_of_month(soy) == 1);
         })
     }
 }
+
+/// Format a timestamp.
+pub fn time_format() -> LibFunc {
+    LibFunc {
+        library: TIME_LIB.clone(),
+        name: "format".into(),
+        is_async: false,
+        docs: r#"# Time.format(ts: ms | time, fmt: str = '%Y-%m-%dT%H:%M:%S%:z', tz: str = null) -> str
+Format a timestamp with a strftime-style format string (Ex. '%Y-%m-%d %H:%M'), in the optional tz (an IANA time zone name, Ex. 'America/Denver').
+```rust
+const ts = Time.from_rfc3339('2026-03-15T14:30:00Z');
+assert_eq(Time.format(ts, '%Y-%m-%d %H:%M'), '2026-03-15 14:30');
+assert_eq(Time.format(ts, '%Y-%m-%d %H:%M %Z', 'America/Denver'), '2026-03-15 08:30 MDT');
+```
+"#.into(),
+        params: vector![
+            Param { name: "ts".into(), param_type: Type::Union(vector![Type::Num(NumT::Float), Type::Time]), default: None },
+            Param { name: "fmt".into(), param_type: Type::Str, default: Some(Arc::new(Base::Literal(Val::Str(literal!("%Y-%m-%dT%H:%M:%S%:z"))))) },
+            Param { name: "tz".into(), param_type: Type::Str, default: Some(Arc::new(Base::Literal(Val::Null))) }
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(FORMAT.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// Parse a timestamp.
+pub fn time_parse() -> LibFunc {
+    LibFunc {
+        library: TIME_LIB.clone(),
+        name: "parse".into(),
+        is_async: false,
+        docs: r#"# Time.parse(val: str, fmt: str, tz: str = null) -> ms
+Parse a date/time string with a strftime-style format string, returning a unix timestamp (milliseconds since Epoch). If the format has no offset (%z), the value is local time in the optional tz (an IANA time zone name), and if it has no time, midnight is used.
+```rust
+const ts = Time.parse('2026-03-15 08:30', '%Y-%m-%d %H:%M', 'America/Denver');
+assert_eq(ts, Time.from_rfc3339('2026-03-15T14:30:00Z'));
+assert_eq(Time.parse('2026-03-15', '%Y-%m-%d'), Time.from_rfc3339('2026-03-15T00:00:00Z'));
+```
+"#.into(),
+        params: vector![
+            Param { name: "val".into(), param_type: Type::Str, default: None },
+            Param { name: "fmt".into(), param_type: Type::Str, default: None },
+            Param { name: "tz".into(), param_type: Type::Str, default: Some(Arc::new(Base::Literal(Val::Null))) }
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(PARSE.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// Instant.
+pub fn time_instant() -> LibFunc {
+    LibFunc {
+        library: TIME_LIB.clone(),
+        name: "instant".into(),
+        is_async: false,
+        docs: r#"# Time.instant(ts: ms | time = null, tz: str = null) -> time
+Create a time value (an instant in time, with an optional IANA time zone) from a timestamp, or from the current time if no timestamp is given. If tz is given, the time value is in that zone (the instant does not change). Time values print as RFC-3339 strings, with a "[Zone]" suffix when they have a zone.
+```rust
+const t = Time.instant(1773585000000ms, 'America/Denver');
+assert_eq(t as str, '2026-03-15T08:30:00-06:00[America/Denver]');
+assert_eq(t, '2026-03-15T14:30:00Z' as time); // same instant
+assert_eq(typeof Time.instant(), 'time');
+```
+"#.into(),
+        params: vector![
+            Param { name: "ts".into(), param_type: Type::Union(vector![Type::Num(NumT::Float), Type::Time]), default: Some(Arc::new(Base::Literal(Val::Null))) },
+            Param { name: "tz".into(), param_type: Type::Str, default: Some(Arc::new(Base::Literal(Val::Null))) }
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(INSTANT.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// Zone.
+pub fn time_zone() -> LibFunc {
+    LibFunc {
+        library: TIME_LIB.clone(),
+        name: "zone".into(),
+        is_async: false,
+        docs: r#"# Time.zone(time: time) -> str
+Time zone name of a time value ('UTC' if it does not have one).
+```rust
+const t = '2026-03-15T08:30:00[America/Denver]' as time;
+assert_eq(t.zone(), 'America/Denver');
+assert_eq(Time.zone(Time.instant()), 'UTC');
+```
+"#.into(),
+        params: vector![
+            Param { name: "time".into(), param_type: Type::Time, default: None }
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(ZONE.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// Duration.
+pub fn time_duration() -> LibFunc {
+    LibFunc {
+        library: TIME_LIB.clone(),
+        name: "duration".into(),
+        is_async: false,
+        docs: r#"# Time.duration(iso: str) -> ms
+Parse an ISO-8601 duration ("P1DT2H", "PT1.5S", "-P2W") into milliseconds. Years and months have no fixed length, so they are taken as 365 and 30 days (use Time.add_months for calendar months).
+```rust
+assert_eq(Time.duration('P1DT2H'), 26hr);
+assert_eq(Time.duration('PT1.5S'), 1500ms);
+```
+"#.into(),
+        params: vector![
+            Param { name: "iso".into(), param_type: Type::Str, default: None }
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(DURATION.clone());
+            Ok(instructions)
+        })
+    }
+}
+
+/// ISO duration.
+pub fn time_iso_duration() -> LibFunc {
+    LibFunc {
+        library: TIME_LIB.clone(),
+        name: "iso_duration".into(),
+        is_async: false,
+        docs: r#"# Time.iso_duration(duration: float) -> str
+Format a duration (takes any time units, default ms) as an ISO-8601 duration string. Days are the largest unit used.
+```rust
+assert_eq(Time.iso_duration(26hr), 'P1DT2H');
+assert_eq(Time.iso_duration(90s), 'PT1M30S');
+```
+"#.into(),
+        params: vector![
+            Param { name: "duration".into(), param_type: Type::Num(NumT::Float), default: None }
+        ],
+        return_type: None,
+        unbounded_args: false,
+        args_to_symbol_table: false,
+        func: Arc::new(|_as_ref, _arg_count, _env, _graph| {
+            let mut instructions = Instructions::default();
+            instructions.push(ISO_DURATION.clone());
+            Ok(instructions)
+        })
+    }
+}
@@ -36,6 +36,12 @@ pub use data::*;
 pub mod components;
 pub use components::*;
 
+pub mod quota;
+pub use quota::*;
+
+pub mod sandbox;
+pub use sandbox::*;
+
 pub mod formats;
 pub use formats::*;
 
@@ -73,6 +79,11 @@ pub trait StofData: AsDynAny + std::fmt::Debug + DataClone + Send + Sync {
         false
     }
 
+    /// Total string/blob bytes and the largest collection length held by this data (quotas).
+    fn value_size(&self) -> (usize, usize) {
+        (0, 0)
+    }
+
     /// Deep copy this data.
     #[allow(unused)]
     fn deep_copy(&self, graph: &mut Graph, context: Option<NodeRef>) -> Box::<dyn StofData> {
@@ -118,6 +129,10 @@ impl StofData for String {
     fn core_data(&self) -> bool {
         return true;
     }
+
+    fn value_size(&self) -> (usize, usize) {
+        (self.len(), 0)
+    }
 }
 
 
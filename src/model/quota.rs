@@ -0,0 +1,316 @@
+//
+// Copyright 2025 Formata, Inc. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use serde::{Deserialize, Serialize};
+use crate::runtime::{Error, Val};
+
+
+/// Resource limits for running documents (Ex. customer-supplied documents).
+/// Set on a graph, these apply to every process run on that graph.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Limits {
+    /// Instruction budget ("fuel") for each process.
+    /// Unlike the max execution time, this is deterministic.
+    pub fuel: Option<u64>,
+
+    /// Quotas for the whole graph.
+    pub graph: Quota,
+
+    /// Quotas for each process (what a single process can insert into the graph).
+    pub process: Quota,
+}
+
+
+/// Quotas, enforced when inserting nodes and data into a graph.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Quota {
+    /// Max number of nodes.
+    pub max_nodes: Option<usize>,
+
+    /// Max number of data components (fields, functions, etc.).
+    pub max_data: Option<usize>,
+
+    /// Max total string and blob bytes inserted.
+    pub max_bytes: Option<usize>,
+
+    /// Max length of a list (or any other collection) in an inserted value.
+    pub max_list_len: Option<usize>,
+}
+impl Quota {
+    /// No quotas set?
+    pub fn is_empty(&self) -> bool {
+        self.max_nodes.is_none() && self.max_data.is_none() && self.max_bytes.is_none() && self.max_list_len.is_none()
+    }
+
+    /// Does this quota need the size of inserted values?
+    pub fn sized(&self) -> bool {
+        self.max_bytes.is_some() || self.max_list_len.is_some()
+    }
+
+    /// Check an insert against this quota, given the current usage.
+    pub fn check(&self, usage: &Usage, insert: &Usage, list_len: usize) -> Result<(), Error> {
+        if let Some(max) = self.max_nodes {
+            if insert.nodes > 0 && usage.nodes + insert.nodes > max {
+                return Err(Error::NodeQuota(max));
+            }
+        }
+        if let Some(max) = self.max_data {
+            if insert.data > 0 && usage.data + insert.data > max {
+                return Err(Error::DataQuota(max));
+            }
+        }
+        if let Some(max) = self.max_bytes {
+            if insert.bytes > 0 && usage.bytes + insert.bytes > max {
+                return Err(Error::ByteQuota(max));
+            }
+        }
+        if let Some(max) = self.max_list_len {
+            if list_len > max {
+                return Err(Error::ListQuota(max));
+            }
+        }
+        Ok(())
+    }
+}
+
+
+/// Resource usage (nodes, data, and string/blob bytes).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Usage {
+    pub nodes: usize,
+    pub data: usize,
+    pub bytes: usize,
+}
+impl Usage {
+    /// Add another usage to this one.
+    pub fn add(&mut self, other: &Self) {
+        self.nodes += other.nodes;
+        self.data += other.data;
+        self.bytes += other.bytes;
+    }
+}
+
+
+/// Total string/blob bytes and the largest collection length in a value.
+pub fn val_size(val: &Val) -> (usize, usize) {
+    match val {
+        Val::Str(val) => (val.len(), 0),
+        Val::Blob(blob) => (blob.len(), 0),
+        Val::List(vals) |
+        Val::Tup(vals) => {
+            let mut bytes = 0;
+            let mut len = vals.len();
+            for val in vals {
+                let (val_bytes, val_len) = val_size(&val.read());
+                bytes += val_bytes;
+                len = len.max(val_len);
+            }
+            (bytes, len)
+        },
+        Val::Set(set) => {
+            let mut bytes = 0;
+            let mut len = set.len();
+            for val in set {
+                let (val_bytes, val_len) = val_size(&val.read());
+                bytes += val_bytes;
+                len = len.max(val_len);
+            }
+            (bytes, len)
+        },
+        Val::Map(map) => {
+            let mut bytes = 0;
+            let mut len = map.len();
+            for (key, val) in map {
+                let (key_bytes, key_len) = val_size(&key.read());
+                let (val_bytes, val_len) = val_size(&val.read());
+                bytes += key_bytes + val_bytes;
+                len = len.max(key_len).max(val_len);
+            }
+            (bytes, len)
+        },
+        _ => (0, 0),
+    }
+}
+
+
+/// String/blob bytes added & the resulting collection length when adding a value onto another (Ex. string or list growth).
+pub fn add_growth(val: &Val, other: &Val) -> (usize, usize) {
+    let (bytes, len) = val_size(other);
+    let added = match other {
+        Val::List(vals) |
+        Val::Tup(vals) => vals.len(),
+        Val::Set(set) => set.len(),
+        Val::Map(map) => map.len(),
+        _ => 1,
+    };
+    match val {
+        Val::Str(_) |
+        Val::Blob(_) => (bytes, 0),
+        Val::List(vals) => (bytes, len.max(vals.len() + added)),
+        Val::Set(set) => (bytes, len.max(set.len() + added)),
+        Val::Map(map) => (bytes, len.max(map.len() + added)),
+        _ => (0, 0),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{model::{Graph, Profile, Quota}, runtime::{Error, Val, Variable}};
+
+    fn graph(stof: &str) -> Graph {
+        let mut graph = Graph::default();
+        graph.parse_stof_src(stof, None, Profile::test()).expect("failed to parse");
+        graph
+    }
+
+    #[test]
+    fn fuel() {
+        let mut graph = graph(r#"
+            fn count(n: int) -> int {
+                let total = 0;
+                for (const i in n) total += 1;
+                return total;
+            }
+        "#);
+        graph.limits.fuel = Some(2_000);
+        assert_eq!(graph.call("root.count", None, vec![Val::from(10)]).unwrap(), Val::from(10));
+        assert_eq!(graph.call("root.count", None, vec![Val::from(100_000)]), Err(Error::OutOfFuel));
+    }
+
+    #[test]
+    fn process_node_quota() {
+        let mut graph = graph(r#"
+            fn objects(n: int) {
+                for (const i in n) {
+                    const obj = new {};
+                }
+            }
+        "#);
+        graph.limits.process = Quota { max_nodes: Some(10), ..Default::default() };
+        assert!(graph.call("root.objects", None, vec![Val::from(10)]).is_ok());
+        assert_eq!(graph.call("root.objects", None, vec![Val::from(11)]), Err(Error::NodeQuota(10)));
+    }
+
+    #[test]
+    fn graph_data_quota() {
+        let mut graph = graph(r#"
+            fn fields(prefix: str, n: int) {
+                for (const i in n) self.insert(prefix + i, i);
+            }
+        "#);
+        let data = graph.data.len();
+        graph.limits.graph = Quota { max_data: Some(data + 5), ..Default::default() };
+        assert!(graph.call("root.fields", None, vec![Val::from("a"), Val::from(5)]).is_ok());
+        assert_eq!(graph.call("root.fields", None, vec![Val::from("b"), Val::from(1)]), Err(Error::DataQuota(data + 5)));
+        assert_eq!(graph.data.len(), data + 5);
+    }
+
+    #[test]
+    fn byte_and_list_quotas() {
+        let mut graph = Graph::default();
+        graph.limits.graph = Quota { max_bytes: Some(10), max_list_len: Some(3), ..Default::default() };
+        assert!(graph.parse_stof_src("a: 'hello'", None, Profile::test()).is_ok());
+        assert_eq!(graph.parse_stof_src("b: 'world!'", None, Profile::test()), Err(Error::ByteQuota(10)));
+        assert_eq!(graph.parse_stof_src("c: [1, 2, 3, 4]", None, Profile::test()), Err(Error::ListQuota(3)));
+    }
+
+    #[test]
+    fn growth_quotas() {
+        let mut graph = graph(r#"
+            fn push(n: int) {
+                const list = [];
+                for (const i in n) list.push_back(i);
+            }
+            fn concat(n: int) -> int {
+                let text = '';
+                for (const i in n) text += 'ab';
+                return text.len();
+            }
+        "#);
+        graph.limits.process = Quota { max_bytes: Some(10), max_list_len: Some(3), ..Default::default() };
+        assert!(graph.call("root.push", None, vec![Val::from(3)]).is_ok());
+        assert_eq!(graph.call("root.push", None, vec![Val::from(4)]), Err(Error::ListQuota(3)));
+        assert_eq!(graph.call("root.concat", None, vec![Val::from(5)]).unwrap(), Val::from(10));
+        assert_eq!(graph.call("root.concat", None, vec![Val::from(6)]), Err(Error::ByteQuota(10)));
+    }
+
+    #[test]
+    fn bigint_pow_quota() {
+        let mut graph = graph(r#"
+            fn pow(exp: int) -> str {
+                return ((2 as bigint).pow(exp)) as str;
+            }
+        "#);
+        graph.limits.process = Quota { max_bytes: Some(64), ..Default::default() };
+        assert!(graph.call("root.pow", None, vec![Val::from(100)]).is_ok());
+        assert_eq!(graph.call("root.pow", None, vec![Val::from(1000)]), Err(Error::ByteQuota(64)));
+    }
+
+    #[test]
+    fn bigint_shl_quota() {
+        let mut graph = graph(r#"
+            fn shl(shift: int) -> str {
+                return ((1 as bigint) << shift) as str;
+            }
+        "#);
+        graph.limits.process = Quota { max_bytes: Some(64), ..Default::default() };
+        assert!(graph.call("root.shl", None, vec![Val::from(100)]).is_ok());
+        assert_eq!(graph.call("root.shl", None, vec![Val::from(1000)]), Err(Error::ByteQuota(64)));
+    }
+
+    #[test]
+    fn set_quotas() {
+        let mut graph = graph(r#"
+            text: 'hello'
+            fn set(text: str) { self.text = text; }
+            fn clear() { drop('self.text'); }
+        "#);
+        graph.limits.graph = Quota { max_bytes: Some(10), ..Default::default() };
+        for _ in 0..5 {
+            assert!(graph.call("root.set", None, vec![Val::from("world")]).is_ok()); // replaced bytes are freed
+        }
+        assert_eq!(graph.call("root.set", None, vec![Val::from("hello world")]), Err(Error::ByteQuota(10)));
+        assert_eq!(graph.try_set_field(Variable::val(Val::from("hello world")), "text", None), Err(Error::ByteQuota(10)));
+        assert_eq!(graph.quota_error, None);
+
+        assert!(graph.call("root.clear", None, vec![]).is_ok());
+        assert_eq!(graph.graph_bytes(), 0);
+        assert!(graph.parse_stof_src("other: 'hello'", None, Profile::test()).is_ok());
+    }
+
+    #[test]
+    fn refused_node() {
+        let mut graph = Graph::default();
+        let root = graph.ensure_main_root();
+        graph.limits.graph = Quota { max_nodes: Some(graph.nodes.len()), ..Default::default() };
+        assert_eq!(graph.try_insert_child("child", &root, false), Err(Error::NodeQuota(graph.nodes.len())));
+        assert_eq!(graph.quota_error, None);
+
+        // host inserts aren't checked
+        let child = graph.insert_child("child", &root, false);
+        assert!(child.node_exists(&graph));
+    }
+
+    #[test]
+    fn main_root_exempt() {
+        let mut graph = Graph::default();
+        graph.limits.graph = Quota { max_nodes: Some(0), ..Default::default() };
+        let root = graph.ensure_main_root();
+        assert!(root.node_exists(&graph));
+        assert_eq!(graph.try_insert_child("child", &root, false), Err(Error::NodeQuota(0)));
+    }
+}
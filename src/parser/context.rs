@@ -20,7 +20,7 @@ use imbl::vector;
 use lazy_static::lazy_static;
 use nanoid::nanoid;
 use rustc_hash::{FxHashMap, FxHashSet};
-use crate::{model::{DataRef, Graph, NodeRef, PROTOTYPE_EXTENDS_ATTR, PROTOTYPE_TYPE_ATTR, Profile, SId, libraries::prof::insert_profile_lib}, runtime::{Error, Runtime, Type, Val, Variable, instruction::Instruction, instructions::call::FuncCall, proc::Process}};
+use crate::{model::{DataRef, Graph, NodeRef, PROTOTYPE_EXTENDS_ATTR, PROTOTYPE_IMPLEMENTS_ATTR, PROTOTYPE_INTERFACE_ATTR, PROTOTYPE_TYPE_ATTR, Profile, Prototype, SId, libraries::prof::insert_profile_lib}, runtime::{Error, Runtime, Type, Val, Variable, instruction::Instruction, instructions::call::FuncCall, proc::Process}};
 
 
 lazy_static! {
@@ -67,6 +67,10 @@ impl<'ctx> ParseContext<'ctx> {
         let mut path = path.to_string();
 
         path = self.create_import_path(format, &path).expect("could not create Stof import path");
+        if let Err(error) = self.graph.sandbox_read(&path) {
+            self.pop_relative_import_stack();
+            return Err(error);
+        }
         if !self.fresh_import_for_node(&node, &path, format) {
             self.pop_relative_import_stack();
             return Ok(()); // already parsed this path
@@ -181,7 +185,8 @@ impl<'ctx> ParseContext<'ctx> {
     }
 
     /// Push a new root node to the self stack.
-    pub fn push_root(&mut self, name: Option<String>, cid: Option<SId>) {
+    /// Returns false if the node is refused by a quota (the import raises the error).
+    pub fn push_root(&mut self, name: Option<String>, cid: Option<SId>) -> bool {
         let mut obj_name = nanoid!(12);
         if let Some(name) = name {
             obj_name = name;
@@ -189,15 +194,24 @@ impl<'ctx> ParseContext<'ctx> {
         let nref;
         if let Some(id) = cid {
             if id.node_exists(&self.graph) {
-                nref = self.graph.insert_root(&obj_name); // no collisions
+                nref = self.graph.try_insert_root(&obj_name); // no collisions
             } else {
-                nref = self.graph.insert_node_id(&obj_name, id, None, false);
+                nref = self.graph.try_insert_node_id(&obj_name, id, None, false);
             }
         } else {
-            nref = self.graph.insert_root(&obj_name);
+            nref = self.graph.try_insert_root(&obj_name);
+        }
+        match nref {
+            Ok(nref) => {
+                let proc = self.parse_proc();
+                proc.env.self_stack.push(nref);
+                true
+            },
+            Err(error) => {
+                self.graph.refuse(error); // raised by the import
+                false
+            },
         }
-        let proc = self.parse_proc();
-        proc.env.self_stack.push(nref);
     }
 
     /// Post push object (cast to an extends type here).
@@ -214,28 +228,90 @@ impl<'ctx> ParseContext<'ctx> {
                         let cast_type = Type::Obj(proto.clone());
                         Val::Obj(obj).cast(&cast_type, &mut self.graph, Some(context))?;
                     },
+                    Val::List(types) => {
+                        // First type is the primary prototype, the rest are mixins
+                        let mut first = true;
+                        for ty in types {
+                            let cast_type = match ty.read().clone() {
+                                Val::Str(typename) => Type::Obj(typename.as_str().into()),
+                                Val::Obj(proto) => Type::Obj(proto),
+                                _ => continue,
+                            };
+                            if first {
+                                Val::Obj(obj.clone()).cast(&cast_type, &mut self.graph, Some(context.clone()))?;
+                                first = false;
+                            } else {
+                                Val::Obj(obj.clone()).mixin(&cast_type, &mut self.graph, Some(context.clone()))?;
+                            }
+                        }
+                    },
                     _ => {}
                 }
             }
         }
+        if let Some(implements_attr) = attributes.get(PROTOTYPE_IMPLEMENTS_ATTR.as_str()) {
+            if let Some(obj) = value.try_obj() {
+                let context = self.self_ptr();
+                let mut interfaces = Vec::new();
+                match implements_attr {
+                    Val::Str(typename) => interfaces.push(Type::Obj(typename.as_str().into())),
+                    Val::Obj(proto) => interfaces.push(Type::Obj(proto.clone())),
+                    Val::List(types) => {
+                        for ty in types {
+                            match ty.read().clone() {
+                                Val::Str(typename) => interfaces.push(Type::Obj(typename.as_str().into())),
+                                Val::Obj(proto) => interfaces.push(Type::Obj(proto)),
+                                _ => {}
+                            }
+                        }
+                    },
+                    _ => {}
+                }
+                for mut interface in interfaces {
+                    interface.obj_to_proto(&self.graph, Some(context.clone()));
+                    match &interface {
+                        Type::Obj(iface) if iface.node_exists(&self.graph) => {
+                            Prototype::check_interface(&self.graph, &obj, iface)?;
+                            Val::Obj(obj.clone()).mixin(&interface, &mut self.graph, Some(context.clone()))?;
+                        },
+                        _ => {
+                            return Err(Error::InterfaceNotImplemented(format!("interface not found: {interface:?}")));
+                        }
+                    }
+                }
+            }
+        }
         Ok(())
     }
 
     /// Push self stack as a variable.
-    pub fn push_self(&mut self, name: &str, attributes: &mut FxHashMap<String, Val>, id: Option<SId>) -> Variable {
+    /// Returns None if the node is refused by a quota (the import raises the error).
+    pub fn push_self(&mut self, name: &str, attributes: &mut FxHashMap<String, Val>, id: Option<SId>) -> Option<Variable> {
         let parent = self.self_ptr();
 
+        // Interfaces are types also, so make sure they end up in the typemap.
+        if attributes.contains_key(PROTOTYPE_INTERFACE_ATTR.as_str()) && !attributes.contains_key(PROTOTYPE_TYPE_ATTR.as_str()) {
+            attributes.insert(PROTOTYPE_TYPE_ATTR.to_string(), Val::Null);
+        }
+
         // Insert the new node, not as a field (we're overridding attributes anyways)
         let nref;
         if let Some(cid) = id {
             if cid.node_exists(&self.graph) {
-                nref = self.graph.insert_node(name, Some(parent), false); // no collisions
+                nref = self.graph.try_insert_node(name, Some(parent), false); // no collisions
             } else {
-                nref = self.graph.insert_node_id(name, cid, Some(parent), false);
+                nref = self.graph.try_insert_node_id(name, cid, Some(parent), false);
             }
         } else {
-            nref = self.graph.insert_node(name, Some(parent), false);
+            nref = self.graph.try_insert_node(name, Some(parent), false);
         }
+        let nref = match nref {
+            Ok(nref) => nref,
+            Err(error) => {
+                self.graph.refuse(error); // raised by the import
+                return None;
+            },
+        };
         if let Some(node) = nref.node_mut(&mut self.graph) {
             node.attributes = attributes.clone(); // set node attributes as the same as field attrs
         }
@@ -256,7 +332,7 @@ impl<'ctx> ParseContext<'ctx> {
 
         let proc = self.parse_proc();
         proc.env.self_stack.push(nref.clone());
-        Variable::val(Val::Obj(nref))
+        Some(Variable::val(Val::Obj(nref)))
     }
 
     /// Push self node.
@@ -0,0 +1,161 @@
+//
+// Copyright 2025 Formata, Inc. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use chrono_tz::Tz;
+use nom::{character::complete::{char, digit1, space0}, combinator::{all_consuming, map, opt, recognize}, sequence::{delimited, preceded, terminated}, IResult, Parser};
+use crate::{model::time::{local_to_utc, parse_tz, zone_name}, parser::doc::StofParseError, runtime::{Num, Units, Val}};
+
+
+/// Parse a time value alone.
+/// Accepts RFC 3339 ("2026-03-15T14:30:00Z"), a local date/time ("2026-03-15T08:30:00"),
+/// or a date ("2026-03-15"), each with an optional RFC 9557 zone suffix ("[America/Denver]").
+/// Local dates and times are in the zone (UTC without one), and dates are at midnight.
+pub fn parse_time_alone(input: &str) -> Option<Val> {
+    let (stamp, tz) = split_zone(input)?;
+    let utc;
+    if let Ok(dt) = DateTime::parse_from_rfc3339(stamp) {
+        utc = dt.to_utc();
+    } else if let Ok(local) = NaiveDateTime::parse_from_str(stamp, "%Y-%m-%dT%H:%M:%S%.f") {
+        utc = local_to_utc(&tz, local)?;
+    } else if let Ok(date) = NaiveDate::parse_from_str(stamp, "%Y-%m-%d") {
+        utc = local_to_utc(&tz, date.and_hms_opt(0, 0, 0)?)?;
+    } else {
+        return None;
+    }
+    Some(Val::Time(utc.timestamp_millis(), zone_name(&tz)))
+}
+
+/// Parse an RFC 3339 time value alone, with an optional zone suffix (no local dates or times).
+pub fn parse_rfc3339_time_alone(input: &str) -> Option<Val> {
+    let (stamp, tz) = split_zone(input)?;
+    let dt = DateTime::parse_from_rfc3339(stamp).ok()?;
+    Some(Val::Time(dt.timestamp_millis(), zone_name(&tz)))
+}
+
+/// Split an optional "[Zone]" suffix from a time string (UTC without one).
+fn split_zone(input: &str) -> Option<(&str, Tz)> {
+    let input = input.trim();
+    if let Some(rest) = input.strip_suffix(']') {
+        let (stamp, zone) = rest.rsplit_once('[')?;
+        return Some((stamp, parse_tz(zone)?));
+    }
+    Some((input, Tz::UTC))
+}
+
+
+/// Parse an ISO 8601 duration alone ("P1DT2H", "PT1.5S", "-P2W") into a ms value.
+/// Years and months have no fixed length, so they are taken as 365 and 30 days.
+pub fn parse_iso_duration_alone(input: &str) -> Option<Val> {
+    match all_consuming(delimited(space0, parse_iso_duration, space0)).parse(input) {
+        Ok((_, ms)) => Some(Val::Num(Num::Units(ms, Units::Milliseconds))),
+        Err(_) => None,
+    }
+}
+
+/// Parse an ISO 8601 duration into milliseconds.
+pub fn parse_iso_duration(input: &str) -> IResult<&str, f64, StofParseError> {
+    let (input, neg) = opt(char('-')).parse(input)?;
+    let (input, _) = char('P').parse(input)?;
+    let (input, (years, months, weeks, days)) = (
+        duration_part('Y'),
+        duration_part('M'),
+        duration_part('W'),
+        duration_part('D'),
+    ).parse(input)?;
+    let (input, time) = opt(preceded(char('T'), (
+        duration_part('H'),
+        duration_part('M'),
+        duration_part('S'),
+    ))).parse(input)?;
+    if let Some((None, None, None)) = time {
+        return Err(nom::Err::Error(StofParseError::from("ISO 8601 duration has no time components after 'T'")));
+    }
+    let (hours, minutes, seconds) = time.unwrap_or_default();
+
+    let parts = [
+        (years, 365. * 86_400_000.),
+        (months, 30. * 86_400_000.),
+        (weeks, 7. * 86_400_000.),
+        (days, 86_400_000.),
+        (hours, 3_600_000.),
+        (minutes, 60_000.),
+        (seconds, 1000.),
+    ];
+    if parts.iter().all(|(part, _)| part.is_none()) {
+        return Err(nom::Err::Error(StofParseError::from("ISO 8601 duration has no components")));
+    }
+
+    let mut ms = 0.;
+    for (part, scale) in parts {
+        if let Some(part) = part {
+            ms += part * scale;
+        }
+    }
+    if neg.is_some() { ms = -ms; }
+    Ok((input, ms))
+}
+fn duration_part<'a>(designator: char) -> impl Parser<&'a str, Output = Option<f64>, Error = StofParseError> {
+    opt(terminated(
+        map(recognize((digit1, opt((char('.'), digit1)))), |num: &str| num.parse::<f64>().unwrap_or_default()),
+        char(designator)
+    ))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{parser::datetime::{parse_iso_duration_alone, parse_rfc3339_time_alone, parse_time_alone}, runtime::{Num, Units, Val}};
+
+    fn ms(input: &str) -> Option<f64> {
+        match parse_iso_duration_alone(input)? {
+            Val::Num(Num::Units(ms, Units::Milliseconds)) => Some(ms),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn iso_durations() {
+        assert_eq!(ms("P1DT2H"), Some(93_600_000.));
+        assert_eq!(ms("PT1.5S"), Some(1500.));
+        assert_eq!(ms("PT30M"), Some(1_800_000.));
+        assert_eq!(ms("P2W"), Some(1_209_600_000.));
+        assert_eq!(ms(" -P1D "), Some(-86_400_000.));
+        assert_eq!(ms("P1M"), Some(2_592_000_000.));
+
+        assert!(ms("P").is_none());
+        assert!(ms("PT").is_none());
+        assert!(ms("P1H").is_none());
+        assert!(ms("1D").is_none());
+        assert!(ms("P1DT").is_none());
+    }
+
+    #[test]
+    fn times() {
+        assert_eq!(parse_time_alone("2026-03-15T14:30:00Z"), Some(Val::Time(1773585000000, None)));
+        assert_eq!(parse_time_alone("2026-03-15T08:30:00-06:00"), Some(Val::Time(1773585000000, None)));
+        assert_eq!(parse_time_alone("2026-03-15T08:30:00-06:00[America/Denver]"), Some(Val::Time(1773585000000, Some("America/Denver".into()))));
+        assert_eq!(parse_time_alone("2026-03-15T08:30:00[America/Denver]"), Some(Val::Time(1773585000000, Some("America/Denver".into()))));
+        assert_eq!(parse_time_alone("2026-03-15"), Some(Val::Time(1773532800000, None)));
+
+        assert!(parse_time_alone("2026-03-15T08:30:00Z[Mars/Olympus]").is_none());
+        assert!(parse_time_alone("yesterday").is_none());
+
+        assert_eq!(parse_rfc3339_time_alone("2026-03-15T14:30:00Z"), Some(Val::Time(1773585000000, None)));
+        assert!(parse_rfc3339_time_alone("2026-03-15").is_none());
+        assert!(parse_rfc3339_time_alone("2026-03-15T08:30:00[America/Denver]").is_none());
+    }
+}
@@ -260,7 +260,9 @@ fn root_statements<'a>(input: &'a str, context: &mut ParseContext) -> IResult<&'
     let mut cid = None;
     if let Some(id) = custom_id { cid = Some(SId::from(id)); }
 
-    context.push_root(name, cid);
+    if !context.push_root(name, cid) {
+        return Err(nom::Err::Failure(StofParseError::from("root refused by a quota"))); // the import raises the quota error
+    }
     loop {
         let res = document_statement(input, context);
         match res {
@@ -15,8 +15,8 @@
 //
 
 use std::sync::Arc;
-use nom::{branch::alt, bytes::complete::{escaped_transform, tag, take_until}, character::complete::{char, none_of}, combinator::{map, opt, value}, multi::fold_many0, sequence::delimited, IResult, Parser};
-use crate::{parser::{doc::StofParseError, expr::expr, whitespace::whitespace}, runtime::{instruction::Instruction, instructions::{block::Block, Base, ADD, NOOP}, Val}};
+use nom::{branch::alt, bytes::complete::{escaped_transform, tag, take_until}, character::complete::{char, none_of}, combinator::{map, opt, value}, multi::fold_many0, sequence::{delimited, preceded, terminated}, IResult, Parser};
+use crate::{parser::{doc::StofParseError, expr::expr, whitespace::whitespace}, runtime::{instruction::Instruction, instructions::{block::Block, fmt::FmtIns, Base, ADD, NOOP}, FormatSpec, Val}};
 
 
 /// Formatted string expression.
@@ -82,8 +82,21 @@ fn parse_inner(input: &str) -> IResult<&str, Arc<dyn Instruction>, StofParseErro
 }
 
 
-/// Parse inner expr.
+/// Parse inner expr, with an optional format spec.
+/// Ex: `${price:.2}`, `${n:>8}`, `${bytes:.1MiB}`, `${date:%Y-%m-%d}`
 fn parse_inner_expr(input: &str) -> IResult<&str, Arc<dyn Instruction>, StofParseError> {
-    let (input, inner) = delimited(tag("${"), expr, tag("}")).parse(input)?;
+    let (input, inner) = preceded(tag("${"), expr).parse(input)?;
+    let (input, _) = whitespace(input)?;
+    let (input, spec) = terminated(opt(preceded(char(':'), take_until("}"))), tag("}")).parse(input)?;
+    if let Some(spec) = spec {
+        match FormatSpec::parse(spec) {
+            Ok(spec) => {
+                return Ok((input, Arc::new(FmtIns { ins: inner, spec })));
+            },
+            Err(message) => {
+                return Err(nom::Err::Failure(StofParseError::from(message)));
+            }
+        }
+    }
     Ok((input, inner))
 }
@@ -103,7 +103,10 @@ pub fn parse_field<'a>(input: &'a str, context: &mut ParseContext) -> IResult<&'
     // Instert the new field in the current parse context
     let field = Field::new(value, Some(attributes));
     let self_ptr = context.self_ptr();
-    let field_ref = context.graph.insert_stof_data(&self_ptr, &name, Box::new(field), None).expect("failed to insert a parsed field into this context");
+    let field_ref = match context.graph.insert_stof_data(&self_ptr, &name, Box::new(field), None) {
+        Some(dref) => dref,
+        None => return Ok((input, ())), // refused by a quota (the import raises the error)
+    };
 
     // Insert the field doc comments also if requested
     if context.profile.docs && comments.len() > 0 {
@@ -207,7 +210,10 @@ fn object_value<'a>(input: &'a str, name: &str, context: &mut ParseContext, attr
     let mut cid = None;
     if let Some(id) = custom_id { cid = Some(SId::from(id)); }
 
-    let value = context.push_self(name, attributes, cid);
+    let value = match context.push_self(name, attributes, cid) {
+        Some(value) => value,
+        None => return Err(nom::Err::Failure(StofParseError::from("object refused by a quota"))), // the import raises the quota error
+    };
     if !input.starts_with('}') { // account for an empty object case "{}"
         loop {
             let res = document_statement(input, context);
@@ -225,7 +231,9 @@ fn object_value<'a>(input: &'a str, name: &str, context: &mut ParseContext, attr
         }
     }
     context.pop_self();
-    context.post_init_obj(&value, attributes).expect("error initializing new object field value");
+    if let Err(error) = context.post_init_obj(&value, attributes) {
+        return Err(nom::Err::Failure(StofParseError::from(format!("error initializing new object field value: {error}"))));
+    }
     let (input, _) = char('}')(input)?;
 
     // Peek at the next value, if its async, then don't do the as below...
@@ -274,4 +282,35 @@ mod tests {
 
         graph.dump(true);
     }
+
+    #[test]
+    fn non_conforming_interface() {
+        let mut graph = Graph::default();
+        let res = graph.parse_stof_src(r#"
+            #[interface]
+            Priced: {
+                fn price() -> float;
+            }
+
+            #[type]
+            #[implements('Priced')]
+            Item: {
+                fn price() -> str { 'free' }
+            }
+        "#, None, Profile::default());
+        assert!(res.unwrap_err().to_string().contains("does not match the signature of Priced.price"));
+
+        let mut graph = Graph::default();
+        let res = graph.parse_stof_src(r#"
+            #[interface]
+            Priced: {
+                fn price() -> float;
+            }
+
+            #[type]
+            #[implements('Priced')]
+            Item: {}
+        "#, None, Profile::default());
+        assert!(res.unwrap_err().to_string().contains("does not implement Priced.price"));
+    }
 }
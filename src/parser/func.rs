@@ -15,8 +15,8 @@
 //
 
 use std::sync::Arc;
-use nom::{bytes::complete::tag, branch::alt, character::complete::{char, multispace0}, combinator::opt, multi::separated_list0, sequence::{delimited, preceded, terminated}, IResult, Parser};
-use crate::{model::{Func, FuncDoc, Param, SId, ASYNC_FUNC_ATTR}, parser::{context::ParseContext, doc::{err_fail, StofParseError}, expr::expr, ident::ident, parse_attributes, statement::block, types::parse_type, whitespace::{doc_comment, whitespace}}, runtime::{instruction::Instruction, instructions::Base, Val}};
+use nom::{bytes::complete::tag, branch::alt, character::complete::{char, multispace0, multispace1}, combinator::{opt, peek}, multi::separated_list0, sequence::{delimited, preceded, terminated}, IResult, Parser};
+use crate::{model::{Func, FuncDoc, Param, SId, ABSTRACT_FUNC_ATTR, ASYNC_FUNC_ATTR, GET_FUNC_ATTR, SET_FUNC_ATTR}, parser::{context::ParseContext, doc::{err_fail, StofParseError}, expr::expr, ident::ident, parse_attributes, statement::block, types::parse_type, whitespace::{doc_comment, whitespace}}, runtime::{instruction::Instruction, instructions::Base, optimizer::Optimizer, Val}};
 
 
 /// Parse a function into a parse context.
@@ -44,11 +44,39 @@ pub fn parse_function<'a>(input: &'a str, context: &mut ParseContext) -> IResult
         func.attributes.insert(ASYNC_FUNC_ATTR.to_string(), Val::Null);
     }
 
-    let (input, _) = tag("fn").parse(input)?;
+    // Accessor functions Ex. get total() -> float { .. } or set email(v: str) { .. }
+    let (input, accessor) = opt(terminated(alt((tag("get"), tag("set"))), peek((multispace1, ident, multispace0, char('('))))).parse(input)?;
+    let mut input = input;
+    if accessor.is_none() {
+        (input, _) = tag("fn").parse(input)?;
+    }
+
     let (input, name) = preceded(multispace0, ident).parse(input).map_err(err_fail)?;
     let (input, params) = delimited(char('('), separated_list0(char(','), alt((parameter, opt_parameter))), char(')')).parse(input).map_err(err_fail)?;
+
+    let mut name = name.to_string();
+    if let Some(accessor) = accessor {
+        if accessor == GET_FUNC_ATTR.as_str() && !params.is_empty() {
+            return Err(nom::Err::Failure(StofParseError::from(format!("getter '{name}' cannot have parameters"))));
+        }
+        if accessor == SET_FUNC_ATTR.as_str() && params.len() != 1 {
+            return Err(nom::Err::Failure(StofParseError::from(format!("setter '{name}' must have exactly one parameter"))));
+        }
+        func.attributes.insert(accessor.to_string(), Val::Str(name.as_str().into()));
+        name = format!("{accessor}_{name}");
+    }
     let (input, return_type) = opt(preceded(delimited(multispace0, tag("->"), multispace0), parse_type)).parse(input).map_err(err_fail)?;
-    let (input, instructions) = block(input).map_err(err_fail)?;
+
+    // Function signatures without a body are abstract (Ex. interface functions)
+    let (input, abstract_fn) = opt(preceded(multispace0, char(';'))).parse(input)?;
+    let (input, instructions) = if abstract_fn.is_some() {
+        (input, Default::default())
+    } else {
+        block(input).map_err(err_fail)?
+    };
+    if abstract_fn.is_some() && !func.attributes.contains_key(ABSTRACT_FUNC_ATTR.as_str()) {
+        func.attributes.insert(ABSTRACT_FUNC_ATTR.to_string(), Val::Null);
+    }
 
     // Check do_create_func now after parse
     if !do_create_func {
@@ -59,6 +87,11 @@ pub fn parse_function<'a>(input: &'a str, context: &mut ParseContext) -> IResult
     func.return_type = return_type.unwrap_or_default(); // default is void
     func.instructions = instructions;
 
+    // Optimize the function if the profile asks for it (prod)
+    if context.profile.optimize {
+        Optimizer::optimize_func(context.graph, &name, &mut func);
+    }
+
     // Is this function an init function (has an #[init] attribute)?
     // These functions will get called automatically when the context is dropped (after parse complete).
     let mut init_func = false;
@@ -67,7 +100,10 @@ pub fn parse_function<'a>(input: &'a str, context: &mut ParseContext) -> IResult
     // Instert the new function in the current parse context
     //println!("({name}){{{func:?}}}");
     let self_ptr = context.self_ptr();
-    let func_ref = context.graph.insert_stof_data(&self_ptr, name, Box::new(func), None).expect("failed to insert a parsed function into this context");
+    let func_ref = match context.graph.insert_stof_data(&self_ptr, &name, Box::new(func), None) {
+        Some(dref) => dref,
+        None => return Ok((input, ())), // refused by a quota (the import raises the error)
+    };
 
     // Insert init if necessary
     if init_func {
@@ -21,6 +21,7 @@ use crate::{parser::{context::ParseContext, doc::{err_fail, StofParseError}, exp
 
 
 pub mod semver;
+pub mod datetime;
 pub mod whitespace;
 pub mod number;
 pub mod types;
@@ -14,7 +14,9 @@
 // limitations under the License.
 //
 
-use nom::{branch::alt, bytes::complete::{tag, take_while1}, character::complete::one_of, combinator::{opt, recognize, value}, multi::{many0, many1}, IResult, Parser};
+use nom::{branch::alt, bytes::complete::{tag, take_while1}, character::complete::{char, one_of, satisfy}, combinator::{not, opt, recognize, value}, multi::{many0, many1}, sequence::terminated, IResult, Parser};
+use num_bigint::BigInt;
+use rust_decimal::Decimal;
 use crate::{parser::{doc::StofParseError, whitespace::whitespace}, runtime::{Num, Units, Val}};
 
 
@@ -27,26 +29,26 @@ pub fn number(input: &str) -> IResult<&str, Val, StofParseError> {
     if input.starts_with("0b") || input.starts_with("-0b") {
         let (input, binary) = take_while1(|c| c == '1' || c == '0' || c == 'b' || c == '_' || c == '-').parse(input)?;
         let bin_input = binary.replace("0b", "").replace('_', "");
-        let val = i64::from_str_radix(&bin_input, 2).expect("failed to parse binary number");
+        let val = radix_int(&bin_input, 2).expect("failed to parse binary number");
 
         let (input, units) = units(input)?;
         if let Some(units) = units {
-            return Ok((input, Val::Num(Num::Units(val as f64, units))));
+            return Ok((input, Val::Num(Num::Units(val.float(None), units))));
         }
-        return Ok((input, Val::Num(Num::Int(val))));
+        return Ok((input, Val::Num(val)));
     }
     // oct
     if input.starts_with("0o") || input.starts_with("-0o") {
         let (input, oct) = take_while1(|c|
             c == '0' || c == '1' || c == '2' || c == '3' || c == '4' || c == '5' || c == '6' || c == '7' || c == 'o' || c == '_' || c == '-').parse(input)?;
         let oct_input = oct.replace("0o", "").replace('_', "");
-        let val = i64::from_str_radix(&oct_input, 8).expect("failed to parse oct number");
+        let val = radix_int(&oct_input, 8).expect("failed to parse oct number");
 
         let (input, units) = units(input)?;
         if let Some(units) = units {
-            return Ok((input, Val::Num(Num::Units(val as f64, units))));
+            return Ok((input, Val::Num(Num::Units(val.float(None), units))));
         }
-        return Ok((input, Val::Num(Num::Int(val))));
+        return Ok((input, Val::Num(val)));
     }
     // hex
     if input.starts_with("0x") || input.starts_with("-0x") {
@@ -54,13 +56,13 @@ pub fn number(input: &str) -> IResult<&str, Val, StofParseError> {
             c == '0' || c == '1' || c == '2' || c == '3' || c == '4' || c == '5' || c == '6' || c == '7' || c == '8' || c == '9' ||
             c == 'a' || c == 'A' || c == 'b' || c == 'B' || c == 'c' || c == 'C' || c == 'd' || c == 'D' || c == 'e' || c == 'E' || c == 'f' || c == 'F' || c == 'x' || c == '_' || c == '-').parse(input)?;
         let hex_input = hex.replace("0x", "").replace('_', "");
-        let val = i64::from_str_radix(&hex_input, 16).expect("failed to parse hex number");
+        let val = radix_int(&hex_input, 16).expect("failed to parse hex number");
         
         let (input, units) = units(input)?;
         if let Some(units) = units {
-            return Ok((input, Val::Num(Num::Units(val as f64, units))));
+            return Ok((input, Val::Num(Num::Units(val.float(None), units))));
         }
-        return Ok((input, Val::Num(Num::Int(val))));
+        return Ok((input, Val::Num(val)));
     }
 
     let (input, recognized_float_str) = recognize(
@@ -88,13 +90,38 @@ pub fn number(input: &str) -> IResult<&str, Val, StofParseError> {
     if let Some(units) = units {
         return Ok((input, Val::Num(Num::Units(float_value, units))));
     }
-    if !recognized_float_str.contains('.') && float_value.fract().abs() < 1e-10 {
+    let (input, dec) = opt(terminated(char('d'), not(satisfy(|c| c.is_alphanumeric() || c == '_')))).parse(input)?;
+    if dec.is_some() {
+        let dec_str = cleaned_string.trim_end_matches('.');
+        let parsed = if dec_str.contains(['e', 'E']) { Decimal::from_scientific(dec_str) } else { Decimal::from_str_exact(dec_str) };
+        return match parsed {
+            Ok(dec) => Ok((input, Val::Num(Num::Decimal(dec)))),
+            Err(error) => Err(nom::Err::Failure(StofParseError::from(format!("invalid decimal literal '{recognized_float_str}d': {error}")))),
+        };
+    }
+    if !recognized_float_str.contains(['.', 'e', 'E']) {
+        if let Ok(int) = cleaned_string.parse::<i64>() {
+            return Ok((input, Val::Num(Num::Int(int))));
+        }
+        if let Ok(big) = cleaned_string.parse::<BigInt>() {
+            return Ok((input, Val::Num(Num::from(big))));
+        }
+    }
+    if !recognized_float_str.contains('.') && float_value.fract().abs() < 1e-10 && float_value.abs() < i64::MAX as f64 {
         Ok((input, Val::Num(Num::Int(float_value.trunc() as i64))))
     } else {
         Ok((input, Val::Num(Num::Float(float_value))))
     }
 }
 
+/// Integer from a radix string, promoted to a bigint if it does not fit in an int.
+fn radix_int(digits: &str, radix: u32) -> Option<Num> {
+    if let Ok(int) = i64::from_str_radix(digits, radix) {
+        return Some(Num::Int(int));
+    }
+    BigInt::parse_bytes(digits.as_bytes(), radix).map(Num::from)
+}
+
 // Parse optional units.
 fn units(input: &str) -> IResult<&str, Option<Units>, StofParseError> {
     opt(
@@ -171,7 +198,8 @@ fn units(input: &str) -> IResult<&str, Option<Units>, StofParseError> {
 
 #[cfg(test)]
 mod tests {
-    use crate::{parser::number::number, runtime::{Units, Val}};
+    use rust_decimal::Decimal;
+    use crate::{parser::number::number, runtime::{Num, Units, Val}};
 
     #[test]
     fn integer() {
@@ -191,6 +219,32 @@ mod tests {
         assert_eq!(val.1, Val::from((4.005, Units::Grams)));
     }
 
+    #[test]
+    fn decimal() {
+        let val = number("19.99d").unwrap();
+        assert_eq!(val.1, Val::Num(Num::Decimal(Decimal::new(1999, 2))));
+        assert_eq!(val.1.to_string(), "19.99");
+
+        assert_eq!(number("-1_000d").unwrap().1, Val::Num(Num::Decimal(Decimal::new(-1000, 0))));
+        assert_eq!(number("2.5e2d").unwrap().1, Val::Num(Num::Decimal(Decimal::new(250, 0))));
+        assert_eq!(number("5days").unwrap().1.to_string(), "5days");
+        assert_eq!(number("5dx").unwrap().0, "dx");
+        assert!(number("1.00000000000000000000000000001d").is_err());
+    }
+
+    #[test]
+    fn bigint() {
+        assert_eq!(number("9223372036854775807").unwrap().1, Val::Num(Num::Int(i64::MAX)));
+        assert_eq!(number("-9223372036854775808").unwrap().1, Val::Num(Num::Int(i64::MIN)));
+
+        let val = number("18_446_744_073_709_551_615").unwrap().1;
+        assert_eq!(val, Val::Num(Num::from(u64::MAX)));
+        assert_eq!(val.to_string(), "18446744073709551615");
+        assert_eq!(number("-123456789012345678901234567890").unwrap().1.to_string(), "-123456789012345678901234567890");
+        assert_eq!(number("0xFFFFFFFFFFFFFFFFFF").unwrap().1.to_string(), "4722366482869645213695");
+        assert_eq!(number("1e3").unwrap().1, Val::from(1000));
+    }
+
     #[test]
     fn binary() {
         let val = number("0b0011").unwrap();
@@ -135,6 +135,7 @@ pub fn for_in_loop(input: &str) -> IResult<&str, Vector<Arc<dyn Instruction>>, S
         ins: inner_instructions.instructions,
         declare: Some(Arc::new(Block { ins: declare_instructions.instructions })),
         inc: Some(Arc::new(Block { ins: inc_instructions.instructions })),
+        program: Default::default(),
     });
     Ok((input, vector![while_ins]))
 }
@@ -56,6 +56,7 @@ pub fn for_loop(input: &str) -> IResult<&str, Vector<Arc<dyn Instruction>>, Stof
         ins,
         declare,
         inc,
+        program: Default::default(),
     };
     Ok((input, vector![Arc::new(while_ins) as Arc<dyn Instruction>]))
 }
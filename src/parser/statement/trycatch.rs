@@ -18,10 +18,11 @@ use std::sync::Arc;
 use arcstr::ArcStr;
 use imbl::{vector, Vector};
 use nom::{branch::alt, bytes::complete::tag, character::complete::{char, multispace0}, combinator::opt, sequence::{delimited, preceded}, IResult, Parser};
-use crate::{model::{Param, SId}, parser::{doc::StofParseError, ident::ident, statement::{block, statement}, types::parse_type, whitespace::whitespace}, runtime::{instruction::Instruction, instructions::{trycatch::TryCatchIns, Base, POP_STACK}, Type}};
+use crate::{model::{Param, SId}, parser::{doc::StofParseError, ident::ident, statement::{block, statement}, types::parse_type, whitespace::whitespace}, runtime::{instruction::Instruction, instructions::{trycatch::{catches_error_obj, TryCatchIns, TryIns}, Base, POP_STACK}, Type}};
 
 
 /// Try catch statement.
+/// Catch and finally blocks are each optional, but at least one is required.
 pub fn try_catch_statement(input: &str) -> IResult<&str, Vector<Arc<dyn Instruction>>, StofParseError> {
     let (input, _) = whitespace(input)?;
 
@@ -32,28 +33,44 @@ pub fn try_catch_statement(input: &str) -> IResult<&str, Vector<Arc<dyn Instruct
         statement
     )).parse(input)?;
 
-    // Any whitespace between try and catch
-    let (input, _) = whitespace(input)?;
-
     // Catch instructions
-    let (input, _) = delimited(multispace0, tag("catch"), multispace0).parse(input)?;
-    let (input, error_param) = opt(delimited(char('('), error_parameter, char(')'))).parse(input)?;
-    let (input, catch_ins) = alt((
-        block,
-        statement
+    let (input, catch) = opt(preceded(
+        whitespace,
+        preceded(delimited(multispace0, tag("catch"), multispace0), (
+            opt(delimited(char('('), error_parameter, char(')'))),
+            alt((block, statement))
+        ))
+    )).parse(input)?;
+
+    // Finally instructions (must be a block)
+    let (input, finally_ins) = opt(preceded(
+        whitespace,
+        preceded(tag("finally"), preceded(multispace0, block))
     )).parse(input)?;
 
     let mut err_ins = vector![];
-    if let Some(error_param) = error_param {
-        // declare const variable that is the error (type irrelivant)
-        err_ins.push_back(Arc::new(Base::Cast(error_param.param_type.clone())) as Arc<dyn Instruction>);
-        err_ins.push_back(Arc::new(Base::DeclareConstVar(ArcStr::from(error_param.name.as_ref()), error_param.param_type)));
+    let mut catch_ins = vector![];
+    let mut error_obj = false;
+    if let Some((error_param, catch_block)) = catch {
+        catch_ins = catch_block;
+        if let Some(error_param) = error_param {
+            error_obj = catches_error_obj(&error_param.param_type);
+            // match the error to the catch type (rethrown if it doesn't match) & declare a const variable for it
+            err_ins.push_back(Arc::new(TryIns::Catch(error_param.param_type.clone())) as Arc<dyn Instruction>);
+            err_ins.push_back(Arc::new(Base::DeclareConstVar(ArcStr::from(error_param.name.as_ref()), error_param.param_type)));
+        } else {
+            // catch everything & pop error from stack
+            err_ins.push_back(Arc::new(TryIns::Catch(Type::Unknown)) as Arc<dyn Instruction>);
+            err_ins.push_back(POP_STACK.clone());
+        }
+    } else if finally_ins.is_some() {
+        // no catch block, so the error gets rethrown after the finally block
+        err_ins.push_back(Arc::new(TryIns::Rethrow) as Arc<dyn Instruction>);
     } else {
-        // pop error from stack
-        err_ins.push_back(POP_STACK.clone() as Arc<dyn Instruction>);
+        return Err(nom::Err::Error(StofParseError::from("try statement requires a catch or finally block")));
     }
 
-    Ok((input, vector![Arc::new(TryCatchIns { try_ins, err_ins, catch_ins }) as Arc<dyn Instruction>]))
+    Ok((input, vector![Arc::new(TryCatchIns { try_ins, err_ins, catch_ins, finally_ins, error_obj, program: Default::default() }) as Arc<dyn Instruction>]))
 }
 
 
@@ -43,6 +43,7 @@ pub fn while_statement(input: &str) -> IResult<&str, Vector<Arc<dyn Instruction>
         ins,
         declare: None,
         inc: None,
+        program: Default::default(),
     };
     Ok((input, vector![Arc::new(while_ins) as Arc<dyn Instruction>]))
 }
@@ -70,6 +71,7 @@ pub fn loop_statement(input: &str) -> IResult<&str, Vector<Arc<dyn Instruction>>
         ins,
         declare: None,
         inc: None,
+        program: Default::default(),
     };
     Ok((input, vector![Arc::new(while_ins) as Arc<dyn Instruction>]))
 }
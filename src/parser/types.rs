@@ -15,7 +15,7 @@
 //
 
 use arcstr::literal;
-use nom::{branch::alt, bytes::complete::tag, character::complete::{char, multispace0}, combinator::{map, opt, recognize, value}, multi::separated_list1, sequence::{delimited, preceded, terminated}, IResult, Parser};
+use nom::{branch::alt, bytes::complete::tag, character::complete::{char, multispace0, satisfy}, combinator::{map, not, opt, recognize, value}, multi::separated_list1, sequence::{delimited, preceded, terminated}, IResult, Parser};
 use crate::{model::SId, parser::{doc::StofParseError, ident::ident_type}, runtime::{NumT, Type, Units}};
 
 
@@ -33,11 +33,11 @@ pub fn parse_type(input: &str) -> IResult<&str, Type, StofParseError> {
             parse_union,
             value(Type::Null, tag("null")),
             value(Type::Void, tag("void")),
-            value(Type::Num(NumT::Int), tag("int")),
-            value(Type::Num(NumT::Float), tag("float")),
+            alt((value(Type::Num(NumT::Int), tag("int")), value(Type::Num(NumT::BigInt), tag("bigint")))),
+            alt((value(Type::Num(NumT::Float), tag("float")), value(Type::Num(NumT::Decimal), tag("decimal")))),
             value(Type::Str, tag("str")),
             value(Type::Prompt, tag("prompt")),
-            value(Type::Ver, tag("ver")),
+            alt((value(Type::Ver, tag("ver")), parse_time)),
             value(Type::Blob, tag("blob")),
             value(Type::Bool, tag("bool")),
             value(Type::List, tag("list")),
@@ -69,11 +69,11 @@ fn parse_inner_union(input: &str) -> IResult<&str, Type, StofParseError> {
         alt((
             value(Type::Null, tag("null")),
             value(Type::Void, tag("void")),
-            value(Type::Num(NumT::Int), tag("int")),
-            value(Type::Num(NumT::Float), tag("float")),
+            alt((value(Type::Num(NumT::Int), tag("int")), value(Type::Num(NumT::BigInt), tag("bigint")))),
+            alt((value(Type::Num(NumT::Float), tag("float")), value(Type::Num(NumT::Decimal), tag("decimal")))),
             value(Type::Str, tag("str")),
             value(Type::Prompt, tag("prompt")),
-            value(Type::Ver, tag("ver")),
+            alt((value(Type::Ver, tag("ver")), parse_time)),
             value(Type::Blob, tag("blob")),
             value(Type::Bool, tag("bool")),
             value(Type::List, tag("list")),
@@ -91,6 +91,11 @@ fn parse_inner_union(input: &str) -> IResult<&str, Type, StofParseError> {
     ), |(_, ty)| ty).parse(input)
 }
 
+/// Parse the time type ("time", but not an identifier that starts with it, like "timestamp").
+fn parse_time(input: &str) -> IResult<&str, Type, StofParseError> {
+    value(Type::Time, terminated(tag("time"), not(satisfy(|c: char| c.is_alphanumeric() || c == '_')))).parse(input)
+}
+
 /// Parse object or units type.
 fn parse_obj_or_units(input: &str) -> IResult<&str, Type, StofParseError> {
     let (input, parsed) = recognize(separated_list1(char('.'), ident_type)).parse(input)?;
@@ -155,11 +160,11 @@ fn parse_inner_promise(input: &str) -> IResult<&str, Type, StofParseError> {
         parse_union,
         value(Type::Null, tag("null")),
         value(Type::Void, tag("void")),
-        value(Type::Num(NumT::Int), tag("int")),
-        value(Type::Num(NumT::Float), tag("float")),
+        alt((value(Type::Num(NumT::Int), tag("int")), value(Type::Num(NumT::BigInt), tag("bigint")))),
+        alt((value(Type::Num(NumT::Float), tag("float")), value(Type::Num(NumT::Decimal), tag("decimal")))),
         value(Type::Str, tag("str")),
         value(Type::Prompt, tag("prompt")),
-        value(Type::Ver, tag("ver")),
+        alt((value(Type::Ver, tag("ver")), parse_time)),
         value(Type::Blob, tag("blob")),
         value(Type::Bool, tag("bool")),
         value(Type::List, tag("list")),
@@ -227,10 +232,14 @@ mod tests {
 
         assert_eq!(parse_type_complete("int").unwrap(), Type::Num(NumT::Int));
         assert_eq!(parse_type_complete("float").unwrap(), Type::Num(NumT::Float));
+        assert_eq!(parse_type_complete("decimal").unwrap(), Type::Num(NumT::Decimal));
+        assert_eq!(parse_type_complete("bigint").unwrap(), Type::Num(NumT::BigInt));
         assert_eq!(parse_type_complete("ms").unwrap(), Type::Num(NumT::Units(Units::Milliseconds)));
         
         assert_eq!(parse_type_complete("str").unwrap(), Type::Str);
         assert_eq!(parse_type_complete("ver").unwrap(), Type::Ver);
+        assert_eq!(parse_type_complete("time").unwrap(), Type::Time);
+        assert_eq!(parse_type_complete("timestamp").unwrap(), Type::Obj("timestamp".into()));
         assert_eq!(parse_type_complete("obj").unwrap(), Type::Obj("obj".into()));
         assert_eq!(parse_type_complete("fn").unwrap(), Type::Fn);
         assert_eq!(parse_type_complete("data").unwrap(), Type::Data("data".into()));
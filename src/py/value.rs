@@ -16,7 +16,8 @@
 
 use bytes::Bytes;
 use imbl::{OrdMap, OrdSet, Vector};
-use pyo3::{Bound, PyAny, Python, types::{PyAnyMethods, PyBool, PyBoolMethods, PyByteArray, PyByteArrayMethods, PyBytes, PyBytesMethods, PyComplex, PyComplexMethods, PyDict, PyDictMethods, PyFloat, PyFloatMethods, PyFrozenSet, PyInt, PyList, PyNone, PyRange, PySet, PyString, PyStringMethods, PyTuple}};
+use rust_decimal::prelude::ToPrimitive;
+use pyo3::{Bound, IntoPyObject, PyAny, Python, types::{PyAnyMethods, PyBool, PyBoolMethods, PyByteArray, PyByteArrayMethods, PyBytes, PyBytesMethods, PyComplex, PyComplexMethods, PyDict, PyDictMethods, PyFloat, PyFloatMethods, PyFrozenSet, PyInt, PyList, PyNone, PyRange, PySet, PyString, PyStringMethods, PyTuple}};
 use crate::{model::{Func, Graph, SId}, runtime::{Num, Type, Val, ValRef}};
 
 
@@ -74,6 +75,9 @@ pub fn py_any_to_val(py: &Bound<'_, PyAny>, graph: &Graph) -> Val {
         if let Ok(v) = res.extract::<i64>() {
             return Val::Num(Num::Int(v));
         }
+        if let Ok(v) = res.extract::<num_bigint::BigInt>() {
+            return Val::Num(Num::from(v));
+        }
     }
 
     // Sequence
@@ -195,6 +199,9 @@ pub fn py_any_to_raw_val(py: &Bound<'_, PyAny>) -> Val {
         if let Ok(v) = res.extract::<i64>() {
             return Val::Num(Num::Int(v));
         }
+        if let Ok(v) = res.extract::<num_bigint::BigInt>() {
+            return Val::Num(Num::from(v));
+        }
     }
 
     // Sequence
@@ -281,6 +288,8 @@ pub fn val_to_py(py: Python<'_>, val: Val) -> Bound<'_, PyAny> {
                 Num::Float(v) => PyFloat::new(py, v).into_any(),
                 Num::Int(v) => PyInt::new(py, v).into_any(),
                 Num::Units(v, _) => PyFloat::new(py, v).into_any(),
+                Num::Decimal(v) => PyFloat::new(py, v.to_f64().unwrap_or_default()).into_any(),
+                Num::BigInt(v) => v.as_ref().into_pyobject(py).map(|v| v.into_any()).unwrap_or_else(|_| PyNone::get(py).to_owned().into_any()),
             }
         },
         Val::Blob(bytes) => PyByteArray::new(py, &bytes).into_any(),
@@ -289,6 +298,7 @@ pub fn val_to_py(py: Python<'_>, val: Val) -> Bound<'_, PyAny> {
         Val::Str(v) => PyString::new(py, &v).into_any(),
         Val::Prompt(v) => PyString::new(py, &v.to_string()).into_any(),
         Val::Ver(..) => PyString::new(py, &val.to_string()).into_any(),
+        Val::Time(..) => PyString::new(py, &val.to_string()).into_any(),
         Val::Data(dref) => PyString::new(py, dref.as_ref()).into_any(),
         Val::Fn(dref) => PyString::new(py, dref.as_ref()).into_any(),
         Val::Obj(nref) => PyString::new(py, nref.as_ref()).into_any(),
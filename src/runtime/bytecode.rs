@@ -0,0 +1,622 @@
+//
+// Copyright 2025 Formata, Inc. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use std::sync::{Arc, OnceLock};
+use arcstr::ArcStr;
+use imbl::Vector;
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use crate::{model::Graph, runtime::{instruction::{Instruction, Instructions}, instructions::{ops::Op, tag_scope, trycatch::TryIns, Base, ConsumeStack, ADD, DIVIDE, EQUAL, GREATER_THAN, GREATER_THAN_OR_EQ, LESS_THAN, LESS_THAN_OR_EQ, MODULUS, MULTIPLY, NOT_EQUAL, SUBTRACT}, proc::ProcEnv, Error, Val}};
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Bytecode operation.
+/// Control flow between tags in the same program is resolved to offsets at compile time.
+/// Constants, locals, fields, arithmetic, and comparisons run directly in the execution loop,
+/// while everything else is dispatched to its instruction.
+pub enum OpCode {
+    /// Push a value from the constant pool onto the stack.
+    Const(usize),
+
+    /// Jump to an offset.
+    Jump(usize),
+
+    /// Jump to an offset if the value on the stack is truthy.
+    JumpIfTruthy(usize, ConsumeStack),
+
+    /// Jump to an offset if the value on the stack is not truthy.
+    JumpIfNotTruthy(usize, ConsumeStack),
+
+    /// Jump back to the top of a loop (yields to other processes).
+    LoopBack(usize),
+
+    /// Load a local variable by slot (relative to the frame), optionally by reference.
+    /// Looked up by name if the slot holds a different local (Ex. a conditional declaration).
+    LoadLocal(usize, ArcStr, bool),
+
+    /// Set a local variable by slot (relative to the frame) to the value on the stack.
+    /// Looked up by name if the slot holds a different local.
+    StoreLocal(usize, ArcStr),
+
+    /// Load a field (path) of the object on the stack, optionally by reference.
+    GetField(ArcStr, bool),
+
+    /// Load a field (path) of self, optionally by reference.
+    GetSelfField(ArcStr, bool),
+
+    /// Arithmetic & comparisons on the two values on the stack (lhs on top).
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Greater,
+    Less,
+    GreaterOrEq,
+    LessOrEq,
+    Eq,
+    Neq,
+
+    /// Dispatch an instruction.
+    Ins(Arc<dyn Instruction>),
+}
+impl OpCode {
+    /// Operator for an arithmetic or comparison opcode.
+    pub fn binary_op(&self) -> Option<Op> {
+        match self {
+            Self::Add => Some(Op::Add),
+            Self::Sub => Some(Op::Sub),
+            Self::Mul => Some(Op::Mul),
+            Self::Div => Some(Op::Div),
+            Self::Mod => Some(Op::Mod),
+            Self::Greater => Some(Op::Greater),
+            Self::Less => Some(Op::Less),
+            Self::GreaterOrEq => Some(Op::GreaterOrEq),
+            Self::LessOrEq => Some(Op::LessOrEq),
+            Self::Eq => Some(Op::Eq),
+            Self::Neq => Some(Op::Neq),
+            _ => None,
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+/// Program.
+/// Linear array of operations with a constant pool, compiled from instructions.
+pub struct Program {
+    pub code: Vec<OpCode>,
+    pub consts: Vec<Val>,
+
+    /// Tag offsets (index of the operation right after the tag).
+    pub tags: FxHashMap<ArcStr, Vec<usize>>,
+
+    /// Locals declared before the program is entered (function parameters or the locals of
+    /// the enclosing program), in slots 0..params.
+    #[serde(default)]
+    pub params: usize,
+}
+impl Program {
+    /// Compile instructions into a program.
+    /// Tags created while compiling (static expansions) are scoped to this program.
+    pub fn compile(instructions: &Vector<Arc<dyn Instruction>>) -> Self {
+        tag_scope(|| Self::compile_scoped(instructions, Locals::new(&[])))
+    }
+
+    /// Compile a function body, with its parameters declared in the first local slots.
+    pub fn compile_func(instructions: &Vector<Arc<dyn Instruction>>, params: &[ArcStr]) -> Self {
+        tag_scope(|| Self::compile_scoped(instructions, Locals::new(params)))
+    }
+
+    /// Compile the instructions made by a builder, with the tags it creates scoped to the program.
+    pub fn build(builder: impl FnOnce() -> Instructions) -> Self {
+        tag_scope(|| Self::compile_scoped(&builder().instructions, Locals::new(&[])))
+    }
+
+    /// Compile the instructions made by a builder, entered from within another program.
+    /// The locals of the enclosing program keep their slots in this one.
+    pub fn build_within(locals: &Locals, builder: impl FnOnce() -> Instructions) -> Self {
+        tag_scope(|| Self::compile_scoped(&builder().instructions, locals.enter()))
+    }
+
+    /// Compile instructions within a tag scope.
+    fn compile_scoped(instructions: &Vector<Arc<dyn Instruction>>, mut locals: Locals) -> Self {
+        let mut program = Self { params: locals.count, ..Default::default() };
+        let mut flat = Vec::with_capacity(instructions.len());
+        Self::inline(instructions, &mut flat);
+        let instructions = flat;
+
+        // Tags do not become operations, they are offsets
+        let mut offset = 0;
+        for ins in &instructions {
+            if let Some(Base::Tag(tag)) = ins.as_dyn_any().downcast_ref::<Base>() {
+                if let Some(offsets) = program.tags.get_mut(tag) {
+                    offsets.push(offset);
+                } else {
+                    program.tags.insert(tag.clone(), vec![offset]);
+                }
+            } else {
+                offset += 1;
+            }
+        }
+
+        program.code.reserve(offset);
+        for ins in &instructions {
+            if let Some(base) = ins.as_dyn_any().downcast_ref::<Base>() {
+                let pc = program.code.len() + 1;
+                if let Some(slot) = locals.resolve(base) {
+                    match base {
+                        Base::SetVariable(name) => program.code.push(OpCode::StoreLocal(slot, name.clone())),
+                        Base::LoadVariable(name, _, by_ref) => program.code.push(OpCode::LoadLocal(slot, name.clone(), *by_ref)),
+                        _ => program.code.push(OpCode::Ins(ins.clone())),
+                    }
+                    continue;
+                }
+                let code = match base {
+                    Base::Add => Some(OpCode::Add),
+                    Base::Sub => Some(OpCode::Sub),
+                    Base::Mul => Some(OpCode::Mul),
+                    Base::Div => Some(OpCode::Div),
+                    Base::Mod => Some(OpCode::Mod),
+                    Base::GreaterThan => Some(OpCode::Greater),
+                    Base::LessThan => Some(OpCode::Less),
+                    Base::GreaterOrEq => Some(OpCode::GreaterOrEq),
+                    Base::LessOrEq => Some(OpCode::LessOrEq),
+                    Base::Eq => Some(OpCode::Eq),
+                    Base::Neq => Some(OpCode::Neq),
+                    Base::LoadVariable(name, true, by_ref) => Some(OpCode::GetField(name.clone(), *by_ref)),
+                    Base::LoadVariable(name, false, by_ref) => {
+                        name.strip_prefix("self.").map(|path| OpCode::GetSelfField(path.into(), *by_ref))
+                    },
+                    _ => None,
+                };
+                if let Some(code) = code {
+                    program.code.push(code);
+                    continue;
+                }
+                match base {
+                    Base::Tag(_) => {
+                        continue;
+                    },
+                    Base::Literal(val) => {
+                        program.code.push(OpCode::Const(program.consts.len()));
+                        program.consts.push(val.clone());
+                        continue;
+                    },
+                    Base::CtrlForwardTo(tag) => {
+                        if let Some(to) = program.forward(tag, pc) {
+                            program.code.push(OpCode::Jump(to));
+                            continue;
+                        }
+                    },
+                    Base::CtrlForwardToIfTruthy(tag, consume) => {
+                        if let Some(to) = program.forward(tag, pc) {
+                            program.code.push(OpCode::JumpIfTruthy(to, consume.clone()));
+                            continue;
+                        }
+                    },
+                    Base::CtrlForwardToIfNotTruthy(tag, consume) => {
+                        if let Some(to) = program.forward(tag, pc) {
+                            program.code.push(OpCode::JumpIfNotTruthy(to, consume.clone()));
+                            continue;
+                        }
+                    },
+                    Base::CtrlLoopBackTo { top_tag, .. } |
+                    Base::CtrlLoopBack(top_tag) => {
+                        if let Some(to) = program.backward(top_tag, pc) {
+                            program.code.push(OpCode::LoopBack(to));
+                            continue;
+                        }
+                    },
+                    _ => {}
+                }
+            }
+            ins.precompile(&locals);
+            program.code.push(OpCode::Ins(ins.clone()));
+        }
+        program
+    }
+
+    /// Inline the static expansions of instructions.
+    fn inline(instructions: &Vector<Arc<dyn Instruction>>, flat: &mut Vec<Arc<dyn Instruction>>) {
+        for ins in instructions {
+            if let Some(expanded) = ins.compile() {
+                Self::inline(&expanded.instructions, flat);
+            } else {
+                flat.push(ins.clone());
+            }
+        }
+    }
+
+    #[inline]
+    /// Offset of a tag at or after the program counter.
+    pub fn forward(&self, tag: &ArcStr, pc: usize) -> Option<usize> {
+        if let Some(offsets) = self.tags.get(tag) {
+            return offsets.iter().find(|offset| **offset >= pc).cloned();
+        }
+        None
+    }
+
+    #[inline]
+    /// Offset of a tag before the program counter.
+    pub fn backward(&self, tag: &ArcStr, pc: usize) -> Option<usize> {
+        if let Some(offsets) = self.tags.get(tag) {
+            return offsets.iter().rev().find(|offset| **offset < pc).cloned();
+        }
+        None
+    }
+
+    #[inline(always)]
+    /// Number of operations in this program.
+    pub fn len(&self) -> usize {
+        self.code.len()
+    }
+
+    #[inline(always)]
+    /// Is this program empty?
+    pub fn is_empty(&self) -> bool {
+        self.code.is_empty()
+    }
+
+    /// Instruction for an operation (used for traces).
+    pub fn instruction(&self, index: usize) -> Option<Arc<dyn Instruction>> {
+        match self.code.get(index) {
+            Some(OpCode::Ins(ins)) => Some(ins.clone()),
+            Some(OpCode::Const(i)) => Some(Arc::new(Base::Literal(self.consts[*i].clone()))),
+            Some(OpCode::LoadLocal(_, name, by_ref)) => Some(Arc::new(Base::LoadVariable(name.clone(), false, *by_ref))),
+            Some(OpCode::StoreLocal(_, name)) => Some(Arc::new(Base::SetVariable(name.clone()))),
+            Some(OpCode::GetField(path, by_ref)) => Some(Arc::new(Base::LoadVariable(path.clone(), true, *by_ref))),
+            Some(OpCode::GetSelfField(path, by_ref)) => Some(Arc::new(Base::LoadVariable(format!("self.{path}").into(), false, *by_ref))),
+            Some(OpCode::Add) => Some(ADD.clone()),
+            Some(OpCode::Sub) => Some(SUBTRACT.clone()),
+            Some(OpCode::Mul) => Some(MULTIPLY.clone()),
+            Some(OpCode::Div) => Some(DIVIDE.clone()),
+            Some(OpCode::Mod) => Some(MODULUS.clone()),
+            Some(OpCode::Greater) => Some(GREATER_THAN.clone()),
+            Some(OpCode::Less) => Some(LESS_THAN.clone()),
+            Some(OpCode::GreaterOrEq) => Some(GREATER_THAN_OR_EQ.clone()),
+            Some(OpCode::LessOrEq) => Some(LESS_THAN_OR_EQ.clone()),
+            Some(OpCode::Eq) => Some(EQUAL.clone()),
+            Some(OpCode::Neq) => Some(NOT_EQUAL.clone()),
+            Some(_) => Some(Arc::new(Base::CtrlNoOp)),
+            None => None,
+        }
+    }
+
+    /// Trace string for an operation.
+    pub fn trace(&self, index: usize) -> String {
+        match self.code.get(index) {
+            Some(OpCode::Ins(ins)) => format!("{ins:?}"),
+            Some(OpCode::Const(i)) => format!("Const({:?})", self.consts[*i]),
+            Some(op) => format!("{op:?}"),
+            None => String::default(),
+        }
+    }
+}
+
+
+/// Local slots, resolved while compiling.
+/// Follows the declarations & scopes of a program in order, giving each declared local the slot it will
+/// have at runtime (relative to the frame). Slots are checked against the name when used, so a slot that
+/// turns out to be wrong (Ex. a conditional declaration) falls back to a lookup by name.
+pub struct Locals {
+    scopes: Vec<Vec<(ArcStr, usize)>>,
+    entry: usize,
+    loops: Vec<usize>,
+    count: usize,
+    known: bool,
+}
+impl Locals {
+    /// Locals for a program, starting with its parameters (or an empty entry scope).
+    fn new(params: &[ArcStr]) -> Self {
+        let scope = params.iter().cloned().enumerate().map(|(slot, name)| (name, slot)).collect();
+        Self { scopes: vec![scope], entry: 1, loops: vec![], count: params.len(), known: true }
+    }
+
+    /// Locals for a program entered at this point (loops, try statements, etc.).
+    fn enter(&self) -> Self {
+        if !self.known {
+            return Self::new(&[]);
+        }
+        Self { scopes: self.scopes.clone(), entry: self.scopes.len(), loops: vec![], count: self.count, known: true }
+    }
+
+    /// Pop scopes until there are a number of them.
+    fn truncate(&mut self, depth: usize) {
+        while self.scopes.len() > depth {
+            if let Some(scope) = self.scopes.pop() {
+                self.count -= scope.len();
+            }
+        }
+    }
+
+    /// Slot for a plain local name.
+    fn slot(&self, name: &str) -> Option<usize> {
+        if !self.known || name.contains('.') { return None; }
+        for scope in self.scopes.iter().rev() {
+            if let Some((_, slot)) = scope.iter().rev().find(|(local, _)| local.as_str() == name) {
+                return Some(*slot);
+            }
+        }
+        None
+    }
+
+    /// Follow an instruction, returning a slot if it loads or sets a local.
+    fn resolve(&mut self, base: &Base) -> Option<usize> {
+        match base {
+            Base::PushSymbolScope => self.scopes.push(vec![]),
+            Base::PopSymbolScope => {
+                let depth = self.scopes.len().saturating_sub(1);
+                self.truncate(depth);
+            },
+            Base::PushLoop(_) => {
+                self.loops.push(self.scopes.len());
+                self.scopes.push(vec![]);
+            },
+            Base::RestoreDepth(Depth::Loop, depth) => {
+                if let Some(scopes) = self.loops.get(*depth).copied() {
+                    self.loops.truncate(*depth);
+                    self.truncate(scopes);
+                }
+            },
+            Base::RestoreDepth(Depth::Scope, depth) => {
+                self.truncate(self.entry + depth);
+            },
+            Base::PopSymbolScopeUntilDepth(_) |
+            Base::PopLoopUntilDepth(_) => {
+                self.known = false; // absolute depths
+            },
+            Base::DeclareVar(name, _) |
+            Base::DeclareConstVar(name, _) => {
+                if self.known {
+                    if self.scopes.is_empty() { self.scopes.push(vec![]); }
+                    if let Some(scope) = self.scopes.last_mut() {
+                        scope.push((name.clone(), self.count));
+                        self.count += 1;
+                    }
+                }
+            },
+            Base::LoadVariable(name, false, _) |
+            Base::SetVariable(name) => {
+                return self.slot(name);
+            },
+            _ => {}
+        }
+        None
+    }
+}
+
+
+#[derive(Clone, Default)]
+/// Cached program.
+/// Compiled once for an instruction that runs in its own frame (loops, try statements, etc.).
+pub struct CachedProgram(OnceLock<Arc<Program>>);
+impl CachedProgram {
+    #[inline]
+    /// Compiled program, building it the first time.
+    pub fn get(&self, builder: impl FnOnce() -> Instructions) -> Arc<Program> {
+        self.0.get_or_init(|| Arc::new(Program::build(builder))).clone()
+    }
+
+    #[inline]
+    /// Build the program ahead of time, within the program it is compiled into.
+    pub fn precompile(&self, locals: &Locals, builder: impl FnOnce() -> Instructions) {
+        self.0.get_or_init(|| Arc::new(Program::build_within(locals, builder)));
+    }
+}
+impl std::fmt::Debug for CachedProgram {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "CachedProgram({})", self.0.get().is_some())
+    }
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// Process stack a frame can restore when it is left.
+pub enum Depth {
+    Stack,
+    Scope,
+    Loop,
+    Try,
+    Call,
+    Return,
+    SelfStack,
+    RetValid,
+    Error,
+    ErrorObj,
+    Finally,
+    FinallyRunning,
+}
+
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+/// Process stack depths, captured when a frame is entered.
+/// Loops and try statements are compiled once, then restore the process stacks relative to these.
+pub struct Depths {
+    pub stack: usize,
+    pub scope: usize,
+    pub loops: usize,
+    pub tries: usize,
+    pub calls: usize,
+    pub returns: usize,
+    pub selfs: usize,
+    pub ret_valid: usize,
+    pub errors: usize,
+    pub error_objs: usize,
+    pub finally: usize,
+    pub finally_running: usize,
+    pub locals: usize,
+}
+impl Depths {
+    /// Capture the current stack depths of a process.
+    pub fn capture(env: &ProcEnv) -> Self {
+        Self {
+            stack: env.stack.len(),
+            scope: env.table.scopes.len(),
+            loops: env.loop_stack.len(),
+            tries: env.try_stack.len(),
+            calls: env.call_stack.len(),
+            returns: env.return_stack.len(),
+            selfs: env.self_stack.len(),
+            ret_valid: env.ret_valid_stack.len(),
+            errors: env.error_stack.len(),
+            error_objs: env.error_objs.len(),
+            finally: env.finally_stack.len(),
+            finally_running: env.finally_running.len(),
+            locals: env.table.locals.len(),
+        }
+    }
+
+    /// Pop a process stack until it is at the captured depth (plus an offset).
+    pub fn restore(&self, depth: Depth, offset: usize, env: &mut ProcEnv, graph: &mut Graph) -> Result<(), Error> {
+        match depth {
+            Depth::Stack => Base::PopUntilStackCount(self.stack + offset).exec(env, graph)?,
+            Depth::Scope => Base::PopSymbolScopeUntilDepth(self.scope + offset).exec(env, graph)?,
+            Depth::Loop => Base::PopLoopUntilDepth(self.loops + offset).exec(env, graph)?,
+            Depth::Try => Base::PopTryUntilDepth(self.tries + offset).exec(env, graph)?,
+            Depth::Call => Base::PopCallUntilDepth(self.calls + offset).exec(env, graph)?,
+            Depth::Return => Base::PopReturnUntilDepth(self.returns + offset).exec(env, graph)?,
+            Depth::SelfStack => Base::PopSelfUntilDepth(self.selfs + offset).exec(env, graph)?,
+            Depth::RetValid => Base::PopRetValidUntilDepth(self.ret_valid + offset).exec(env, graph)?,
+            Depth::Error => TryIns::PopErrorUntilDepth(self.errors + offset).exec(env, graph)?,
+            Depth::ErrorObj => TryIns::DropErrorObjs(self.error_objs + offset).exec(env, graph)?,
+            Depth::Finally => {
+                env.finally_stack.truncate(self.finally + offset);
+                None
+            },
+            Depth::FinallyRunning => {
+                env.finally_running.truncate(self.finally_running + offset);
+                None
+            },
+        };
+        Ok(())
+    }
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Frame.
+/// A program being executed and the offset of the next operation.
+pub struct Frame {
+    pub program: Arc<Program>,
+    pub pc: usize,
+
+    /// Stack depths when this frame was entered (for programs that restore them).
+    #[serde(default)]
+    pub depths: Option<Depths>,
+}
+impl Frame {
+    #[inline(always)]
+    /// Create a new frame at the start of a program.
+    pub fn new(program: Arc<Program>) -> Self {
+        Self { program, pc: 0, depths: None }
+    }
+
+    #[inline]
+    /// Enter a program in a new frame, capturing the stack depths of the process.
+    pub fn enter(program: Arc<Program>, env: &ProcEnv) -> Self {
+        Self { program, pc: 0, depths: Some(Depths::capture(env)) }
+    }
+
+    #[inline]
+    /// Symbol table slot of this frame's first local (only for entered frames).
+    pub fn base(&self) -> Option<usize> {
+        self.depths.as_ref().and_then(|depths| depths.locals.checked_sub(self.program.params))
+    }
+
+    #[inline(always)]
+    /// Are there more operations in this frame?
+    pub fn more(&self) -> bool {
+        self.pc < self.program.code.len()
+    }
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Program instruction.
+/// Executes an already compiled program (Ex. a function body) without compiling it again.
+pub struct ProgramIns(pub Arc<Program>);
+#[typetag::serde(name = "ProgramIns")]
+impl Instruction for ProgramIns {
+    fn exec(&self, env: &mut ProcEnv, _graph: &mut Graph) -> Result<Option<Instructions>, Error> {
+        let mut instructions = Instructions::default();
+        instructions.frames.push(Frame::enter(self.0.clone(), env));
+        Ok(Some(instructions))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use arcstr::literal;
+    use imbl::vector;
+    use crate::runtime::{bytecode::{OpCode, Program}, instruction::{Instruction, Instructions}, instructions::{unique_tag, Base, ConsumeStack, ADD}, Type, Val};
+
+    #[test]
+    fn resolve_jumps() {
+        let program = Program::compile(&vector![
+            Arc::new(Base::Literal(Val::from(1))) as Arc<dyn Instruction>,
+            Arc::new(Base::CtrlForwardToIfNotTruthy(literal!("end"), ConsumeStack::Consume)),
+            Arc::new(Base::Literal(Val::from(2))),
+            Arc::new(Base::CtrlForwardTo(literal!("outside"))),
+            Arc::new(Base::Tag(literal!("end"))),
+            ADD.clone(),
+        ]);
+        assert_eq!(program.len(), 5);
+        assert_eq!(program.consts.len(), 2);
+        assert!(matches!(program.code[1], OpCode::JumpIfNotTruthy(4, _)));
+        assert!(matches!(program.code[3], OpCode::Ins(_))); // tag not in this program, resolved at runtime
+        assert!(matches!(program.code[4], OpCode::Add));
+        assert_eq!(program.forward(&literal!("end"), 2), Some(4));
+        assert_eq!(program.backward(&literal!("end"), 4), None);
+    }
+
+    #[test]
+    fn resolve_locals() {
+        let program = Program::compile_func(&vector![
+            Arc::new(Base::Literal(Val::from(1))) as Arc<dyn Instruction>,
+            Arc::new(Base::DeclareVar(literal!("x"), Type::Void)),
+            Arc::new(Base::LoadVariable(literal!("a"), false, false)),
+            Arc::new(Base::LoadVariable(literal!("x"), false, false)),
+            Arc::new(Base::SetVariable(literal!("x"))),
+            Arc::new(Base::LoadVariable(literal!("self.x"), false, false)),
+            Arc::new(Base::LoadVariable(literal!("outer"), false, false)),
+        ], &[literal!("a")]);
+        assert_eq!(program.params, 1);
+        assert!(matches!(program.code[2], OpCode::LoadLocal(0, _, false)));
+        assert!(matches!(program.code[3], OpCode::LoadLocal(1, _, false)));
+        assert!(matches!(program.code[4], OpCode::StoreLocal(1, _)));
+        assert!(matches!(&program.code[5], OpCode::GetSelfField(path, false) if path == "x"));
+        assert!(matches!(program.code[6], OpCode::Ins(_))); // not declared in this program
+    }
+
+    #[test]
+    fn program_tags() {
+        let first = Program::build(|| {
+            let mut instructions = Instructions::default();
+            instructions.push(Arc::new(Base::Tag(unique_tag())));
+            instructions
+        });
+        let second = Program::build(|| {
+            let mut instructions = Instructions::default();
+            instructions.push(Arc::new(Base::Tag(unique_tag())));
+            instructions
+        });
+        assert_eq!(first.tags.len(), 1);
+        assert!(first.tags.keys().all(|tag| !second.tags.contains_key(tag)));
+    }
+}
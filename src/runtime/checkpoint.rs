@@ -0,0 +1,201 @@
+//
+// Copyright 2025 Formata, Inc. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use std::time::Duration;
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use crate::runtime::{proc::Process, Error};
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// What a checkpointed process was doing when the checkpoint was taken.
+pub enum CheckpointState {
+    Running,
+
+    /// Waiting on another process (or a group of them).
+    Waiting,
+
+    /// Asleep, with the time to wake (duration since the Unix Epoch) if the sleep was timed.
+    /// Processes asleep on an external event (Ex. an HTTP response) are resumed asleep until the host wakes them (see Runtime::wake_ref).
+    Sleeping(Option<Duration>),
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Checkpoint.
+/// A process (stack, symbol table, and pending instructions) that can be persisted and resumed later.
+///
+/// References in the process (objects, functions, etc.) are IDs into the graph it ran on,
+/// so persist the graph alongside it (Ex. "bstf") and resume on a graph loaded from that.
+pub struct Checkpoint {
+    pub state: CheckpointState,
+    pub proc: Process,
+}
+impl Checkpoint {
+    /// Serialize this checkpoint.
+    pub fn to_bytes(&self) -> Result<Bytes, Error> {
+        match bincode::serialize(self) {
+            Ok(bytes) => Ok(bytes.into()),
+            Err(error) => Err(Error::CheckpointExport(error.to_string())),
+        }
+    }
+
+    /// Deserialize a checkpoint.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        match bincode::deserialize::<Self>(bytes) {
+            Ok(checkpoint) => Ok(checkpoint),
+            Err(error) => Err(Error::CheckpointImport(error.to_string())),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+    use crate::{model::{Graph, Profile}, runtime::{instruction::Instruction, instructions::call::FuncCall, proc::Process, wake, Checkpoint, CheckpointState, Error, Runtime, Val}};
+
+    fn doc(stof: &str) -> Graph {
+        let mut graph = Graph::default();
+        graph.parse_stof_src(stof, None, Profile::test()).expect("failed to parse");
+        graph
+    }
+
+    fn call(search: &str) -> Process {
+        let instruction: Arc<dyn Instruction> = Arc::new(FuncCall {
+            as_ref: false,
+            cnull: false,
+            stack: false,
+            func: None,
+            search: Some(search.into()),
+            args: Default::default(),
+            oself: None,
+        });
+        Process::from(instruction)
+    }
+
+    #[test]
+    fn resume_on_another_graph() {
+        let mut graph = doc(r#"
+            count: 0
+            fn workflow() -> int {
+                let local = 40;
+                self.count = 1;
+                sleep(20ms);
+                self.count += 1;
+                return local + self.count;
+            }
+        "#);
+        let mut runtime = Runtime::default();
+        let pid = runtime.push_running_proc(call("root.workflow"), &mut graph);
+        assert!(runtime.run_until_blocked(&mut graph));
+        let checkpoint = runtime.checkpoint(&pid).unwrap();
+        let bstf = graph.binary_export("bstf", None).unwrap();
+        drop(runtime);
+        drop(graph);
+
+        let mut graph = Graph::default();
+        graph.binary_import("bstf", bstf, None, &Profile::test()).unwrap();
+        assert_eq!(graph.field_value("count", None), Some(Val::from(1)));
+
+        let mut runtime = Runtime::default();
+        assert_eq!(runtime.resume(&checkpoint, &mut graph).unwrap(), pid);
+        assert!(runtime.resume(&checkpoint, &mut graph).is_err()); // already resumed
+        runtime.run_to_complete(&mut graph);
+
+        let proc = runtime.done.get(&pid).expect("resumed process did not complete");
+        assert_eq!(proc.result.as_ref().map(|var| var.get()), Some(Val::from(42)));
+        assert_eq!(graph.field_value("count", None), Some(Val::from(2)));
+    }
+
+    #[test]
+    fn timed_sleep_keeps_wake_time() {
+        let mut graph = doc(r#"fn nap() { sleep(10s); }"#);
+        let mut runtime = Runtime::default();
+        let pid = runtime.push_running_proc(call("root.nap"), &mut graph);
+        runtime.run_until_blocked(&mut graph);
+        let checkpoint = runtime.checkpoint(&pid).unwrap();
+
+        let mut resumed = Runtime::default();
+        resumed.resume(&checkpoint, &mut graph).unwrap();
+        assert!(!resumed.ready());
+        assert!(resumed.next_wake().unwrap().as_secs_f32() > 9.);
+    }
+
+    #[test]
+    fn deadline_wall_clock() {
+        let mut proc = Process::default();
+        proc.deadline = Some(web_time::Instant::now() + Duration::from_secs(10));
+        let bytes = Checkpoint { state: CheckpointState::Waiting, proc }.to_bytes().unwrap();
+
+        let checkpoint = Checkpoint::from_bytes(&bytes).unwrap();
+        let remaining = checkpoint.proc.deadline.unwrap().saturating_duration_since(web_time::Instant::now());
+        assert!(remaining.as_secs_f32() > 9. && remaining.as_secs_f32() <= 10.);
+    }
+
+    #[test]
+    fn awaited_proc_missing() {
+        let mut graph = doc(r#"
+            fn workflow() -> str {
+                const child = async { sleep(10s); 'child' };
+                try {
+                    return await child;
+                } catch (e: AwaitError) {
+                    return e.message;
+                }
+            }
+        "#);
+        let mut runtime = Runtime::default();
+        let pid = runtime.push_running_proc(call("root.workflow"), &mut graph);
+        assert!(runtime.run_until_blocked(&mut graph));
+        let checkpoint = runtime.checkpoint(&pid).unwrap();
+
+        // the child was not resumed, so the await throws instead of waiting forever
+        let mut resumed = Runtime::default();
+        resumed.resume(&checkpoint, &mut graph).unwrap();
+        resumed.run_to_complete(&mut graph);
+        let res = resumed.done.get(&pid).and_then(|proc| proc.result.as_ref()).map(|var| var.get());
+        assert!(matches!(res, Some(Val::Str(message)) if message.contains("CheckpointProcDne")));
+    }
+
+    #[test]
+    fn external_wake() {
+        let mut graph = doc(r#"
+            chan: Chan.new()
+            fn listen() { self.chan.recv(); }
+        "#);
+        let mut runtime = Runtime::default();
+        let pid = runtime.push_running_proc(call("root.listen"), &mut graph);
+        assert!(runtime.run_until_blocked(&mut graph));
+        let checkpoint = runtime.checkpoint(&pid).unwrap();
+
+        let mut resumed = Runtime::default();
+        resumed.resume(&checkpoint, &mut graph).unwrap();
+        assert!(!resumed.ready()); // still asleep on the event
+        let wref = resumed.wake_ref(&pid).unwrap();
+        assert!(wake(&wref));
+        assert!(resumed.ready());
+    }
+
+    #[test]
+    fn checkpoint_missing() {
+        let runtime = Runtime::default();
+        assert!(matches!(runtime.checkpoint(&"missing".into()), Err(Error::CheckpointProcDne(_))));
+        let mut graph = Graph::default();
+        let mut runtime = Runtime::default();
+        assert!(matches!(runtime.resume(b"not a checkpoint", &mut graph), Err(Error::CheckpointImport(_))));
+    }
+}
@@ -17,10 +17,11 @@
 use std::fmt::Display;
 use arcstr::ArcStr;
 use serde::{Deserialize, Serialize};
+use strum::IntoStaticStr;
 use crate::{parser::doc::StofParseError, runtime::{Type, Val}};
 
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, IntoStaticStr)]
 /// Error.
 pub enum Error {
 
@@ -54,6 +55,11 @@ pub enum Error {
     BSTFImport(String),
     BSTFExport(String),
 
+    SignedImport(String),
+    SignedExport(String),
+    SignedUntrustedKey(String),
+    SignedInvalidSignature(String),
+
     PDFImport(String),
     PDFExport(String),
 
@@ -71,6 +77,18 @@ pub enum Error {
     FsReadError(String),
     FsWriteStackError,
     FsWriteError(String),
+    FsStackError,
+    FsListDirError(String),
+    FsMkdirError(String),
+    FsRemoveError(String),
+    FsRenameError(String),
+    FsCopyError(String),
+    FsMetadataError(String),
+    FsAppendError(String),
+    FsReadLinesError(String),
+    LinesNext,
+    LinesLen,
+    LinesAt,
 
     /*****************************************************************************
      * Standard Library Errors.
@@ -108,6 +126,10 @@ pub enum Error {
     TimeToRFC2822,
     TimeFromRFC3339,
     TimeFromRFC2822,
+    TimeZone(String),
+    TimeFormat(String),
+    TimeParse(String),
+    TimeDuration(String),
 
     /*****************************************************************************
      * Func Lib Errors.
@@ -165,6 +187,7 @@ pub enum Error {
     StrRegexFail,
     StrIsMatch,
     StrFindAll,
+    StrFormat(String),
 
     /*****************************************************************************
      * Prompt Lib Errors.
@@ -204,6 +227,9 @@ pub enum Error {
     NumAt,
     NumRound,
     NumRound2,
+    NumRoundMode(String),
+    NumOverflow(String),
+    NumDivideByZero,
     NumPow,
     NumLog,
     NumATan2,
@@ -382,6 +408,8 @@ pub enum Error {
     BlobFromUtf8Str,
     BlobFromBase64Str,
     BlobFromUrlSafeBase64Str,
+    BlobHexStr,
+    BlobFromHexStr,
 
     /*****************************************************************************
      * Object Lib Errors.
@@ -433,11 +461,76 @@ pub enum Error {
      * Cast Errors.
      *****************************************************************************/
     ObjectCastProtoDne,
+    InterfaceNotImplemented(String),
 
     /*****************************************************************************
      * Await Errors.
      *****************************************************************************/
     AwaitError(Box<Self>),
+    Cancelled,
+    TimedOut,
+    AllRejected(Vec<Self>),
+
+    /*****************************************************************************
+     * Checkpoint Errors.
+     *****************************************************************************/
+    CheckpointProcDne(String),
+    CheckpointExport(String),
+    CheckpointImport(String),
+    CheckpointPidExists(String),
+
+    /*****************************************************************************
+     * Promise Library Errors.
+     *****************************************************************************/
+    PromiseTimeout,
+
+    /*****************************************************************************
+     * Chan Library Errors.
+     *****************************************************************************/
+    ChanNew,
+    ChanSend,
+    ChanClosed,
+    ChanRecv,
+    ChanTryRecv,
+    ChanClose,
+    ChanSelect,
+    ChanLen,
+    ChanIsClosed,
+
+    /*****************************************************************************
+     * Crypto Library Errors.
+     *****************************************************************************/
+    CryptoHash,
+    CryptoHmac(String),
+    CryptoEq,
+    CryptoKey(String),
+    CryptoSign,
+    CryptoVerify,
+    CryptoRandom(String),
+
+    /*****************************************************************************
+     * Rand Library Errors.
+     *****************************************************************************/
+    RandSeed,
+    RandInt,
+    RandFloat,
+    RandNormal,
+    RandBool,
+    RandChoice,
+    RandShuffle,
+    RandSample,
+
+    /*****************************************************************************
+     * Regex Library Errors.
+     *****************************************************************************/
+    RegexNew(String),
+    RegexPattern,
+    RegexIsMatch,
+    RegexFind,
+    RegexCaptures,
+    RegexReplace,
+    RegexSplit,
+    RegexEscape,
 
     /*****************************************************************************
      * Parse Errors.
@@ -455,6 +548,7 @@ pub enum Error {
     AssignConst,
     VariableSet,
     FieldReadOnlySet,
+    ComputedCycle(String),
     AssignSelf,
     AssignSuper,
     AssignRootNonObj,
@@ -462,6 +556,20 @@ pub enum Error {
 
     StackError,
     ExecutionTimeout,
+    OutOfFuel,
+    NodeQuota(usize),
+    DataQuota(usize),
+    ByteQuota(usize),
+    ListQuota(usize),
+    SandboxPolicy(String),
+    SandboxLib(String),
+    SandboxRead(String),
+    SandboxWrite(String),
+    SandboxEnv(String),
+    SandboxHost(String),
+    ExecutorDocDne(String),
+    ExecutorJobDropped,
+    ExecutorJobPanic(String),
     StackOverflow,
     SelfStackError,
     NewStackError,
@@ -475,6 +583,7 @@ pub enum Error {
     FuncDefaultArg(Box<Self>),
     FuncArgs,
     FuncInvalidReturn,
+    FuncAbstract(String),
 
     // Value errors
     Truthy,
@@ -496,6 +605,12 @@ pub enum Error {
     SHL,
     SHR,
 }
+impl Error {
+    /// Kind of error (the variant name) Ex. "FuncDne".
+    pub fn kind(&self) -> &'static str {
+        self.into()
+    }
+}
 impl Display for Error { // maps ToString and print to Debug
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -0,0 +1,644 @@
+//
+// Copyright 2025 Formata, Inc. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use std::{any::Any, cell::Cell, collections::VecDeque, panic::{catch_unwind, AssertUnwindSafe}, sync::{atomic::{AtomicBool, AtomicUsize, Ordering}, Arc}, thread::JoinHandle, time::Duration};
+use imbl::Vector;
+use parking_lot::{Condvar, Mutex};
+use rustc_hash::FxHashMap;
+use crate::{model::{Graph, SId}, runtime::{instruction::Instruction, instructions::{call::FuncCall, Base}, on_wake, proc::Process, Error, Runtime, Val, WakeListener}};
+
+
+/// Max time an idle worker waits for new work before checking again.
+const IDLE_WAIT: Duration = Duration::from_millis(50);
+
+/// Max jobs a worker runs on a document before giving other documents a turn.
+const DOC_JOB_BATCH: usize = 64;
+
+
+/// Multi-threaded executor.
+/// Hosts many documents (a graph with its own runtime), running independent documents in parallel
+/// on a pool of worker threads with work stealing.
+///
+/// Each document has a single writer: its jobs run in order, one at a time, on whichever worker has it.
+/// Documents with sleeping processes (Ex. Http.fetch) are parked until woken by their waker (see wake)
+/// or a timer is due, so that workers are free to run other documents in the meantime.
+/// A job that panics errors with ExecutorJobPanic, and its document keeps running other jobs.
+///
+/// Use an ExecutorHandle (Send + Sync + Clone) to add documents and run jobs from any thread.
+pub struct Executor {
+    shared: Arc<Shared>,
+    workers: Vec<JoinHandle<()>>,
+    _wake_listener: WakeListener,
+}
+impl Default for Executor {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+impl Executor {
+    /// Create a new executor with a number of worker threads.
+    /// Zero will use the available parallelism of this machine.
+    pub fn new(workers: usize) -> Self {
+        let mut count = workers;
+        if count < 1 {
+            count = std::thread::available_parallelism().map(|count| count.get()).unwrap_or(1);
+        }
+
+        let shared = Arc::new(Shared {
+            queues: (0..count).map(|_| Mutex::new(VecDeque::new())).collect(),
+            docs: Default::default(),
+            parked: Default::default(),
+            idle: Mutex::new(()),
+            signal: Condvar::new(),
+            next: AtomicUsize::new(0),
+            woken: AtomicBool::new(false),
+            shutdown: AtomicBool::new(false),
+        });
+
+        let weak = Arc::downgrade(&shared);
+        let wake_listener: WakeListener = Arc::new(move || {
+            if let Some(shared) = weak.upgrade() {
+                shared.woken();
+            }
+        });
+        on_wake(&wake_listener);
+
+        let mut handles = Vec::with_capacity(count);
+        for index in 0..count {
+            let shared = shared.clone();
+            let handle = std::thread::Builder::new()
+                .name(format!("stof-worker-{index}"))
+                .spawn(move || shared.work(index))
+                .expect("failed to spawn a Stof executor worker");
+            handles.push(handle);
+        }
+        Self { shared, workers: handles, _wake_listener: wake_listener }
+    }
+
+    /// Number of worker threads.
+    pub fn workers(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Handle to this executor, for adding documents and running jobs from any thread.
+    pub fn handle(&self) -> ExecutorHandle {
+        ExecutorHandle { shared: self.shared.clone() }
+    }
+
+    /// Stop the workers after they finish their current jobs.
+    /// Jobs that haven't run yet error with ExecutorJobDropped.
+    pub fn shutdown(mut self) {
+        self.stop();
+    }
+
+    fn stop(&mut self) {
+        self.shared.shutdown.store(true, Ordering::SeqCst);
+        self.shared.signal.notify_all();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+        self.shared.docs.lock().clear();
+    }
+}
+impl Drop for Executor {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+
+#[derive(Clone)]
+/// Executor handle.
+pub struct ExecutorHandle {
+    shared: Arc<Shared>,
+}
+impl ExecutorHandle {
+    /// Add a document (graph) to the executor, returning its ID.
+    pub fn insert(&self, graph: Graph) -> SId {
+        self.insert_with_runtime(graph, Runtime::default())
+    }
+
+    /// Add a document with a specific runtime (Ex. with a sandbox policy).
+    pub fn insert_with_runtime(&self, graph: Graph, runtime: Runtime) -> SId {
+        let id = SId::default();
+        let slot = DocSlot {
+            doc: Some(Document { graph, runtime, pending: Default::default(), removed: false }),
+            jobs: Default::default(),
+            scheduled: false,
+            parked: false,
+        };
+        self.shared.docs.lock().insert(id.clone(), Arc::new(Mutex::new(slot)));
+        id
+    }
+
+    /// Remove a document from the executor, after its queued jobs have run.
+    pub fn remove(&self, id: &SId) -> JobHandle<Graph> {
+        let shared = self.shared.clone();
+        let doc_id = id.clone();
+        self.job(id, move |doc, completer: Completer<Graph>| {
+            if let Some(slot) = shared.docs.lock().remove(&doc_id) {
+                slot.lock().jobs.clear();
+            }
+            doc.removed = true;
+            completer.complete(Ok(std::mem::take(&mut doc.graph)));
+        })
+    }
+
+    /// Number of documents in this executor.
+    pub fn len(&self) -> usize {
+        self.shared.docs.lock().len()
+    }
+
+    /// No documents in this executor?
+    pub fn is_empty(&self) -> bool {
+        self.shared.docs.lock().is_empty()
+    }
+
+    /// Run a closure with exclusive access to a document's graph and runtime.
+    pub fn with<R: Send + 'static>(&self, id: &SId, func: impl FnOnce(&mut Graph, &mut Runtime) -> R + Send + 'static) -> JobHandle<R> {
+        self.job(id, move |doc, completer: Completer<R>| {
+            let res = func(&mut doc.graph, &mut doc.runtime);
+            completer.complete(Ok(res));
+        })
+    }
+
+    /// Call a function in a document.
+    /// The result is ready once the process completes (including any async work it awaits).
+    pub fn call(&self, id: &SId, search: &str, args: Vec<Val>) -> JobHandle<Val> {
+        let mut arguments: Vector<Arc<dyn Instruction>> = Vector::default();
+        for arg in args { arguments.push_back(Arc::new(Base::Literal(arg))); }
+        self.eval(id, Arc::new(FuncCall {
+            as_ref: false,
+            cnull: false,
+            stack: false,
+            func: None,
+            search: Some(search.into()),
+            args: arguments,
+            oself: None,
+        }))
+    }
+
+    /// Evaluate an instruction in a document.
+    pub fn eval(&self, id: &SId, instruction: Arc<dyn Instruction>) -> JobHandle<Val> {
+        self.job(id, move |doc, completer: Completer<Val>| {
+            let proc = Process::from(instruction);
+            let pid = doc.runtime.push_running_proc(proc, &mut doc.graph);
+            doc.pending.push((pid, completer));
+        })
+    }
+
+    /// Queue a job for a document.
+    fn job<T: Send + 'static>(&self, id: &SId, job: impl FnOnce(&mut Document, Completer<T>) + Send + 'static) -> JobHandle<T> {
+        let (completer, handle) = JobHandle::new();
+        if self.shared.shutdown.load(Ordering::SeqCst) {
+            return handle; // dropped completer
+        }
+        let slot = self.shared.docs.lock().get(id).cloned();
+        if let Some(slot) = slot {
+            let schedule;
+            {
+                let mut slot = slot.lock();
+                let result = completer.result.clone();
+                slot.jobs.push_back(Box::new(move |doc: &mut Document| {
+                    RUNNING_JOB.set(true);
+                    let res = catch_unwind(AssertUnwindSafe(|| job(doc, completer)));
+                    RUNNING_JOB.set(false);
+                    if let Err(panic) = res {
+                        Completer { result }.complete(Err(Error::ExecutorJobPanic(panic_message(panic))));
+                    }
+                }));
+                schedule = !slot.scheduled;
+                slot.scheduled = true;
+                slot.parked = false;
+            }
+            if schedule {
+                self.shared.schedule(id.clone());
+            }
+        } else {
+            completer.complete(Err(Error::ExecutorDocDne(id.to_string())));
+        }
+        handle
+    }
+}
+
+
+/// Handle to the result of a job.
+pub struct JobHandle<T> {
+    result: Arc<JobResult<T>>,
+}
+impl<T> JobHandle<T> {
+    fn new() -> (Completer<T>, Self) {
+        let result = Arc::new(JobResult { value: Mutex::new(None), ready: Condvar::new() });
+        (Completer { result: result.clone() }, Self { result })
+    }
+
+    /// Is the result ready?
+    pub fn is_done(&self) -> bool {
+        self.result.value.lock().is_some()
+    }
+
+    /// Take the result if ready.
+    pub fn try_take(&self) -> Option<Result<T, Error>> {
+        self.result.value.lock().take()
+    }
+
+    /// Block this thread until the result is ready.
+    pub fn wait(self) -> Result<T, Error> {
+        let mut value = self.result.value.lock();
+        loop {
+            if let Some(res) = value.take() {
+                return res;
+            }
+            self.result.ready.wait(&mut value);
+        }
+    }
+
+    /// Block this thread until the result is ready or the timeout passes.
+    pub fn wait_timeout(&self, timeout: Duration) -> Option<Result<T, Error>> {
+        let mut value = self.result.value.lock();
+        if value.is_none() {
+            self.result.ready.wait_for(&mut value, timeout);
+        }
+        value.take()
+    }
+}
+
+struct JobResult<T> {
+    value: Mutex<Option<Result<T, Error>>>,
+    ready: Condvar,
+}
+
+/// Completes a job handle (errors with ExecutorJobDropped if dropped before completing).
+struct Completer<T> {
+    result: Arc<JobResult<T>>,
+}
+impl<T> Completer<T> {
+    fn complete(self, res: Result<T, Error>) {
+        *self.result.value.lock() = Some(res);
+        self.result.ready.notify_all();
+    }
+}
+impl<T> Drop for Completer<T> {
+    fn drop(&mut self) {
+        if std::thread::panicking() && RUNNING_JOB.get() {
+            return; // the worker completes the job with the panic
+        }
+        let mut value = self.result.value.lock();
+        if value.is_none() {
+            *value = Some(Err(Error::ExecutorJobDropped));
+            self.result.ready.notify_all();
+        }
+    }
+}
+
+
+/// Document (graph & runtime) hosted by an executor.
+struct Document {
+    graph: Graph,
+    runtime: Runtime,
+    pending: Vec<(SId, Completer<Val>)>,
+    removed: bool,
+}
+impl Document {
+    /// Complete pending calls whose processes are done.
+    fn complete_pending(&mut self) {
+        let mut index = 0;
+        while index < self.pending.len() {
+            let pid = &self.pending[index].0;
+            let res;
+            if let Some(proc) = self.runtime.done.remove(pid) {
+                if let Some(val) = proc.result {
+                    res = Ok(val.get());
+                } else {
+                    res = Ok(Val::Void);
+                }
+            } else if let Some(proc) = self.runtime.errored.remove(pid) {
+                res = Err(proc.error.unwrap_or(Error::NotImplemented));
+            } else {
+                index += 1;
+                continue;
+            }
+            let (_, completer) = self.pending.swap_remove(index);
+            completer.complete(res);
+        }
+    }
+}
+
+type Job = Box<dyn FnOnce(&mut Document) + Send>;
+
+thread_local! {
+    /// Is this worker running a job (a panic in it completes the job with an error)?
+    static RUNNING_JOB: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Message of a caught panic.
+fn panic_message(panic: Box<dyn Any + Send>) -> String {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "panicked".to_string()
+    }
+}
+
+struct DocSlot {
+    /// Taken by the worker running this document.
+    doc: Option<Document>,
+    jobs: VecDeque<Job>,
+    /// In a run queue or being run.
+    scheduled: bool,
+    /// Waiting on sleeping processes.
+    parked: bool,
+}
+
+
+/// State shared by the workers and handles.
+struct Shared {
+    queues: Vec<Mutex<VecDeque<SId>>>,
+    docs: Mutex<FxHashMap<SId, Arc<Mutex<DocSlot>>>>,
+    parked: Mutex<Vec<SId>>,
+    idle: Mutex<()>,
+    signal: Condvar,
+    next: AtomicUsize,
+    /// A process was woken since parked documents were last checked.
+    woken: AtomicBool,
+    shutdown: AtomicBool,
+}
+impl Shared {
+    /// Put a document in a run queue.
+    fn schedule(&self, id: SId) {
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % self.queues.len();
+        self.queues[index].lock().push_back(id);
+        drop(self.idle.lock()); // a worker going idle either sees this doc or gets notified
+        self.signal.notify_one();
+    }
+
+    /// Next document to run: from this worker's queue, else stolen from another worker.
+    fn next_doc(&self, index: usize) -> Option<SId> {
+        if let Some(id) = self.queues[index].lock().pop_front() {
+            return Some(id);
+        }
+        let count = self.queues.len();
+        for offset in 1..count {
+            if let Some(id) = self.queues[(index + offset) % count].lock().pop_back() {
+                return Some(id);
+            }
+        }
+        None
+    }
+
+    /// A process was woken, so check the parked documents.
+    fn woken(&self) {
+        self.woken.store(true, Ordering::SeqCst);
+        drop(self.idle.lock()); // a worker going idle either sees the flag or gets notified
+        self.signal.notify_all();
+    }
+
+    /// Schedule parked documents that are ready to run again (woken or timed out).
+    /// Returns how long until the next timer of a document that is still parked, if any.
+    fn unpark(&self) -> Option<Duration> {
+        self.woken.store(false, Ordering::SeqCst);
+        let parked = std::mem::take(&mut *self.parked.lock());
+        if parked.is_empty() { return None; }
+
+        let mut still = Vec::new();
+        let mut next: Option<Duration> = None;
+        for id in parked {
+            let slot = self.docs.lock().get(&id).cloned();
+            if let Some(slot) = slot {
+                let mut slot = slot.lock();
+                if !slot.parked { continue; } // scheduled by a new job
+                let ready = match &slot.doc {
+                    Some(doc) => doc.runtime.ready(),
+                    None => false,
+                };
+                if ready {
+                    slot.parked = false;
+                    slot.scheduled = true;
+                    drop(slot);
+                    self.schedule(id);
+                } else {
+                    if let Some(until) = slot.doc.as_ref().and_then(|doc| doc.runtime.next_wake()) {
+                        next = Some(next.map_or(until, |next| next.min(until)));
+                    }
+                    still.push(id);
+                }
+            }
+        }
+        self.parked.lock().append(&mut still);
+        next
+    }
+
+    /// Worker loop.
+    fn work(&self, index: usize) {
+        while !self.shutdown.load(Ordering::SeqCst) {
+            if let Some(id) = self.next_doc(index) {
+                self.run_doc(id);
+                continue;
+            }
+
+            let next_timer = self.unpark();
+            if let Some(id) = self.next_doc(index) {
+                self.run_doc(id);
+                continue;
+            }
+
+            let mut idle = self.idle.lock();
+            if self.shutdown.load(Ordering::SeqCst) { break; }
+            if self.woken.load(Ordering::SeqCst) { continue; }
+            if self.queues.iter().any(|queue| !queue.lock().is_empty()) { continue; }
+            self.signal.wait_for(&mut idle, next_timer.map_or(IDLE_WAIT, |until| until.min(IDLE_WAIT)));
+        }
+    }
+
+    /// Run a document's queued jobs and processes.
+    fn run_doc(&self, id: SId) {
+        let slot = self.docs.lock().get(&id).cloned();
+        if let Some(slot) = slot {
+            self.run_slot(id, slot);
+        }
+    }
+
+    /// Run a document's queued jobs and processes.
+    fn run_slot(&self, id: SId, slot: Arc<Mutex<DocSlot>>) {
+        let mut jobs = VecDeque::new();
+        let mut doc;
+        {
+            let mut slot = slot.lock();
+            doc = match slot.doc.take() {
+                Some(doc) => doc,
+                None => return,
+            };
+            while jobs.len() < DOC_JOB_BATCH {
+                if let Some(job) = slot.jobs.pop_front() {
+                    jobs.push_back(job);
+                } else {
+                    break;
+                }
+            }
+        }
+
+        while let Some(job) = jobs.pop_front() {
+            job(&mut doc);
+            if doc.removed {
+                return; // remaining jobs & pending calls are dropped
+            }
+        }
+        let sleeping = match catch_unwind(AssertUnwindSafe(|| doc.runtime.run_until_blocked(&mut doc.graph))) {
+            Ok(sleeping) => sleeping,
+            Err(panic) => {
+                // processes are in an unknown state, so they're all dropped & their calls error
+                let message = panic_message(panic);
+                doc.runtime.clear();
+                for (_, completer) in doc.pending.drain(..) {
+                    completer.complete(Err(Error::ExecutorJobPanic(message.clone())));
+                }
+                false
+            }
+        };
+        doc.complete_pending();
+
+        let mut slot = slot.lock();
+        slot.doc = Some(doc);
+        if !slot.jobs.is_empty() {
+            drop(slot);
+            self.schedule(id);
+        } else if sleeping {
+            slot.scheduled = false;
+            slot.parked = true;
+            drop(slot);
+            self.parked.lock().push(id);
+        } else {
+            slot.scheduled = false;
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+    use serde::{Deserialize, Serialize};
+    use crate::{model::{Graph, Profile}, runtime::{instruction::{Instruction, Instructions}, proc::ProcEnv, Error, Executor, Val}};
+
+    fn doc(stof: &str) -> Graph {
+        let mut graph = Graph::default();
+        graph.parse_stof_src(stof, None, Profile::test()).expect("failed to parse");
+        graph
+    }
+
+    #[test]
+    fn parallel_documents() {
+        let executor = Executor::new(4);
+        let handle = executor.handle();
+        let docs = (0..16).map(|i| handle.insert(doc(&format!(r#"
+            base: {i}
+            fn sum(n: int) -> int {{
+                let total = self.base;
+                for (const x in n) total += x;
+                return total;
+            }}
+        "#)))).collect::<Vec<_>>();
+
+        let jobs = docs.iter().map(|id| handle.call(id, "root.sum", vec![Val::from(1000)])).collect::<Vec<_>>();
+        for (i, job) in jobs.into_iter().enumerate() {
+            assert_eq!(job.wait().unwrap(), Val::from(i as i64 + 499_500));
+        }
+        assert_eq!(handle.len(), 16);
+    }
+
+    #[test]
+    fn single_writer() {
+        let executor = Executor::new(4);
+        let handle = executor.handle();
+        let id = handle.insert(doc(r#"
+            count: 0
+            fn inc() { self.count += 1; }
+        "#));
+
+        let threads = (0..4).map(|_| {
+            let handle = handle.clone();
+            let id = id.clone();
+            std::thread::spawn(move || {
+                let jobs = (0..250).map(|_| handle.call(&id, "root.inc", vec![])).collect::<Vec<_>>();
+                for job in jobs { job.wait().unwrap(); }
+            })
+        }).collect::<Vec<_>>();
+        for thread in threads { thread.join().unwrap(); }
+
+        let count = handle.with(&id, |graph, _| graph.field_value("count", None)).wait().unwrap();
+        assert_eq!(count, Some(Val::from(1000)));
+    }
+
+    #[test]
+    fn sleeping_documents_park() {
+        let executor = Executor::new(1);
+        let handle = executor.handle();
+        let sleeper = handle.insert(doc(r#"fn run() -> str { sleep(100ms); return 'slept'; }"#));
+        let quick = handle.insert(doc(r#"fn run() -> str { return 'quick'; }"#));
+
+        let slow = handle.call(&sleeper, "root.run", vec![]);
+        std::thread::sleep(Duration::from_millis(10));
+        let fast = handle.call(&quick, "root.run", vec![]);
+        assert_eq!(fast.wait_timeout(Duration::from_millis(80)), Some(Ok(Val::from("quick"))));
+        assert!(!slow.is_done());
+        assert_eq!(slow.wait().unwrap(), Val::from("slept"));
+    }
+
+    #[test]
+    fn errors_and_remove() {
+        let executor = Executor::new(2);
+        let handle = executor.handle();
+        let id = handle.insert(doc(r#"
+            name: 'doc'
+            fn fail() { throw('failed'); }
+        "#));
+        assert!(handle.call(&id, "root.fail", vec![]).wait().is_err());
+
+        let mut graph = handle.remove(&id).wait().unwrap();
+        assert_eq!(graph.field_value("name", None), Some(Val::from("doc")));
+        assert!(handle.is_empty());
+        assert_eq!(handle.call(&id, "root.fail", vec![]).wait(), Err(Error::ExecutorDocDne(id.to_string())));
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct PanicIns;
+    #[typetag::serde(name = "ExecutorTestPanicIns")]
+    impl Instruction for PanicIns {
+        fn exec(&self, _env: &mut ProcEnv, _graph: &mut Graph) -> Result<Option<Instructions>, Error> {
+            panic!("instruction panicked");
+        }
+    }
+
+    #[test]
+    fn panics() {
+        let executor = Executor::new(1);
+        let handle = executor.handle();
+        let id = handle.insert(doc(r#"
+            name: 'doc'
+            fn run() -> str { return self.name; }
+        "#));
+
+        let job = handle.with(&id, |_, _| -> i64 { panic!("job panicked") });
+        assert_eq!(job.wait(), Err(Error::ExecutorJobPanic("job panicked".into())));
+        assert_eq!(handle.call(&id, "root.run", vec![]).wait().unwrap(), Val::from("doc"));
+
+        let eval = handle.eval(&id, Arc::new(PanicIns));
+        assert_eq!(eval.wait(), Err(Error::ExecutorJobPanic("instruction panicked".into())));
+        assert_eq!(handle.call(&id, "root.run", vec![]).wait().unwrap(), Val::from("doc"));
+    }
+}
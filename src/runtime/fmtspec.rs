@@ -0,0 +1,409 @@
+//
+// Copyright 2025 Formata, Inc. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use std::fmt::Write;
+use chrono::{format::{Item, StrftimeItems}, DateTime};
+use chrono_tz::Tz;
+use num_traits::Signed;
+use rust_decimal::RoundingStrategy;
+use serde::{Deserialize, Serialize};
+use crate::{model::{time::parse_tz, Graph}, runtime::{Error, Num, Units, Val}};
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// Format alignment.
+pub enum FmtAlign {
+    Left,
+    Right,
+    Center,
+}
+
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+/// Format type (how numbers are written).
+pub enum FmtKind {
+    #[default]
+    Default,
+    Exp,
+    ExpUpper,
+    Hex,
+    HexUpper,
+    Binary,
+    Octal,
+}
+
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// Format specifier, used by template strings (`${value:spec}`) and Str.format.
+///
+/// Grammar: `[[fill]align][+][0][width][,][.precision][type][units]`, where align is one of
+/// `<`, `>`, or `^`, type is one of `e`, `E`, `x`, `X`, `b`, or `o`, and units converts the
+/// number before display (Ex. `.2MiB`). A spec starting with `%` is a strftime time format,
+/// applied to a timestamp (ms since the Unix epoch, or any time units).
+pub struct FormatSpec {
+    pub fill: char,
+    pub align: Option<FmtAlign>,
+    pub sign: bool,
+    pub zero: bool,
+    pub width: usize,
+    pub grouping: bool,
+    pub precision: Option<usize>,
+    pub kind: FmtKind,
+    pub units: Option<Units>,
+    pub time: Option<String>,
+}
+impl Default for FormatSpec {
+    fn default() -> Self {
+        Self {
+            fill: ' ',
+            align: None,
+            sign: false,
+            zero: false,
+            width: 0,
+            grouping: false,
+            precision: None,
+            kind: FmtKind::Default,
+            units: None,
+            time: None,
+        }
+    }
+}
+impl FormatSpec {
+    /// Parse a format specifier.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut res = Self::default();
+        if spec.starts_with('%') {
+            if StrftimeItems::new(spec).any(|item| item == Item::Error) {
+                return Err(format!("invalid time format '{spec}'"));
+            }
+            res.time = Some(spec.to_string());
+            return Ok(res);
+        }
+
+        let chars: Vec<char> = spec.chars().collect();
+        let mut i = 0;
+        if chars.len() > 1 && Self::align_char(chars[1]).is_some() {
+            res.fill = chars[0];
+            res.align = Self::align_char(chars[1]);
+            i = 2;
+        } else if let Some(first) = chars.first() {
+            if let Some(align) = Self::align_char(*first) {
+                res.align = Some(align);
+                i = 1;
+            }
+        }
+        if chars.get(i) == Some(&'+') {
+            res.sign = true;
+            i += 1;
+        }
+        if chars.get(i) == Some(&'0') {
+            res.zero = true;
+            i += 1;
+        }
+        let (width, next) = Self::digits(&chars, i);
+        if let Some(width) = width {
+            res.width = width;
+        }
+        i = next;
+        if chars.get(i) == Some(&',') {
+            res.grouping = true;
+            i += 1;
+        }
+        if chars.get(i) == Some(&'.') {
+            let (precision, next) = Self::digits(&chars, i + 1);
+            if precision.is_none() {
+                return Err(format!("expected a precision after '.' in format spec '{spec}'"));
+            }
+            res.precision = precision;
+            i = next;
+        }
+
+        let rest: String = chars[i..].iter().collect();
+        if rest.is_empty() {
+            return Ok(res);
+        }
+        let mut units = rest.as_str();
+        if let Some(kind) = Self::kind_char(chars[i]) {
+            if rest.len() == 1 || Self::parse_units(&rest[1..]).is_some() {
+                res.kind = kind;
+                units = &rest[1..];
+            }
+        }
+        if !units.is_empty() {
+            if let Some(units) = Self::parse_units(units) {
+                res.units = Some(units);
+            } else {
+                return Err(format!("unknown format type or units '{units}' in format spec '{spec}'"));
+            }
+        }
+        Ok(res)
+    }
+
+    /// Format a value according to this spec.
+    pub fn format(&self, val: &Val, graph: &Graph) -> Result<String, Error> {
+        if let Some(time) = &self.time {
+            // Numbers are ms timestamps in UTC, and time values are formatted in their zone
+            let stamp = match val {
+                Val::Num(num) => Some((num.float(Some(Units::Milliseconds)) as i64, Tz::UTC)),
+                Val::Time(ms, zone) => Some((*ms, zone.as_ref().and_then(|zone| parse_tz(zone)).unwrap_or(Tz::UTC))),
+                _ => None,
+            };
+            if let Some((ms, tz)) = stamp {
+                if let Some(dt) = DateTime::from_timestamp_millis(ms) {
+                    let mut out = String::new();
+                    if write!(out, "{}", dt.with_timezone(&tz).format(time)).is_ok() {
+                        return Ok(out);
+                    }
+                }
+            }
+            return Err(Error::StrFormat(format!("cannot format '{}' as a time", val.print(graph))));
+        }
+
+        match val {
+            Val::Num(num) => {
+                let (sign, body) = self.number(num)?;
+                Ok(self.pad(sign, body, FmtAlign::Right))
+            },
+            _ => {
+                if self.kind != FmtKind::Default || self.units.is_some() {
+                    return Err(Error::StrFormat(format!("cannot apply a numeric format to '{}'", val.print(graph))));
+                }
+                let mut body = val.print(graph);
+                if let Some(precision) = self.precision {
+                    body = body.chars().take(precision).collect();
+                }
+                Ok(self.pad(String::default(), body, FmtAlign::Left))
+            }
+        }
+    }
+
+    /// Format a number, returning the sign and the body separately (for zero padding).
+    fn number(&self, num: &Num) -> Result<(String, String), Error> {
+        let mut units = num.units();
+        let mut value = num.float(None);
+        if let Some(to) = self.units {
+            if let Some(from) = units {
+                value = Units::convert(value, from, to).map_err(Error::StrFormat)?;
+            }
+            units = Some(to);
+        }
+        let is_int = matches!(num, Num::Int(_)) && units.is_none();
+
+        let negative;
+        let mut body = match self.kind {
+            FmtKind::Hex | FmtKind::HexUpper | FmtKind::Binary | FmtKind::Octal if matches!((num, units), (Num::BigInt(_), None)) => {
+                let big = num.bigint().unwrap_or_default();
+                negative = big.is_negative();
+                let abs = big.magnitude();
+                match self.kind {
+                    FmtKind::Hex => format!("{abs:x}"),
+                    FmtKind::HexUpper => format!("{abs:X}"),
+                    FmtKind::Binary => format!("{abs:b}"),
+                    _ => format!("{abs:o}"),
+                }
+            },
+            FmtKind::Hex | FmtKind::HexUpper | FmtKind::Binary | FmtKind::Octal => {
+                let int = if is_int { num.int() } else { value.round() as i64 };
+                negative = int < 0;
+                let abs = int.unsigned_abs();
+                match self.kind {
+                    FmtKind::Hex => format!("{abs:x}"),
+                    FmtKind::HexUpper => format!("{abs:X}"),
+                    FmtKind::Binary => format!("{abs:b}"),
+                    _ => format!("{abs:o}"),
+                }
+            },
+            FmtKind::Exp | FmtKind::ExpUpper => {
+                negative = value < 0.;
+                let abs = value.abs();
+                let exp = match self.precision {
+                    Some(precision) => format!("{abs:.precision$e}"),
+                    None => format!("{abs:e}"),
+                };
+                if self.kind == FmtKind::ExpUpper { exp.to_uppercase() } else { exp }
+            },
+            FmtKind::Default => {
+                if let (Num::Decimal(dec), None) = (num, units) {
+                    // exact, rounding halfway values away from zero
+                    negative = dec.is_sign_negative() && !dec.is_zero();
+                    let abs = dec.abs();
+                    match self.precision {
+                        Some(precision) => {
+                            let abs = abs.round_dp_with_strategy(precision as u32, RoundingStrategy::MidpointAwayFromZero);
+                            format!("{abs:.precision$}")
+                        },
+                        None => abs.to_string(),
+                    }
+                } else if let (Num::BigInt(big), None) = (num, units) {
+                    negative = big.is_negative();
+                    let abs = big.magnitude().to_string();
+                    match self.precision {
+                        Some(precision) if precision > 0 => format!("{abs}.{}", "0".repeat(precision)),
+                        _ => abs,
+                    }
+                } else if is_int && self.precision.is_none() {
+                    let int = num.int();
+                    negative = int < 0;
+                    int.unsigned_abs().to_string()
+                } else {
+                    negative = value < 0.;
+                    let abs = value.abs();
+                    match self.precision {
+                        Some(precision) => format!("{abs:.precision$}"),
+                        None => format!("{abs}"),
+                    }
+                }
+            },
+        };
+        if self.grouping && self.kind == FmtKind::Default {
+            body = Self::group(&body);
+        }
+        if let Some(units) = units {
+            body.push_str(&units.to_string());
+        }
+
+        let sign = if negative { "-" } else if self.sign { "+" } else { "" };
+        Ok((sign.to_string(), body))
+    }
+
+    /// Pad a formatted sign and body out to the spec width.
+    fn pad(&self, sign: String, body: String, default_align: FmtAlign) -> String {
+        let len = sign.chars().count() + body.chars().count();
+        if len >= self.width {
+            return format!("{sign}{body}");
+        }
+        let count = self.width - len;
+        if self.zero && self.align.is_none() {
+            return format!("{sign}{}{body}", "0".repeat(count));
+        }
+        let fill = self.fill.to_string();
+        match self.align.unwrap_or(default_align) {
+            FmtAlign::Left => format!("{sign}{body}{}", fill.repeat(count)),
+            FmtAlign::Right => format!("{}{sign}{body}", fill.repeat(count)),
+            FmtAlign::Center => {
+                let left = count / 2;
+                format!("{}{sign}{body}{}", fill.repeat(left), fill.repeat(count - left))
+            },
+        }
+    }
+
+    /// Insert thousands separators into the integer portion of a number.
+    fn group(body: &str) -> String {
+        let (int, rest) = match body.find('.') {
+            Some(index) => body.split_at(index),
+            None => (body, ""),
+        };
+        let mut res = String::default();
+        for (index, c) in int.chars().enumerate() {
+            if index > 0 && (int.len() - index) % 3 == 0 {
+                res.push(',');
+            }
+            res.push(c);
+        }
+        res.push_str(rest);
+        res
+    }
+
+    fn align_char(c: char) -> Option<FmtAlign> {
+        match c {
+            '<' => Some(FmtAlign::Left),
+            '>' => Some(FmtAlign::Right),
+            '^' => Some(FmtAlign::Center),
+            _ => None,
+        }
+    }
+
+    fn kind_char(c: char) -> Option<FmtKind> {
+        match c {
+            'e' => Some(FmtKind::Exp),
+            'E' => Some(FmtKind::ExpUpper),
+            'x' => Some(FmtKind::Hex),
+            'X' => Some(FmtKind::HexUpper),
+            'b' => Some(FmtKind::Binary),
+            'o' => Some(FmtKind::Octal),
+            _ => None,
+        }
+    }
+
+    fn parse_units(units: &str) -> Option<Units> {
+        let units = Units::from(units);
+        if units.has_units() && !units.is_undefined() {
+            return Some(units);
+        }
+        None
+    }
+
+    fn digits(chars: &[char], start: usize) -> (Option<usize>, usize) {
+        let mut end = start;
+        while end < chars.len() && chars[end].is_ascii_digit() {
+            end += 1;
+        }
+        if end == start {
+            return (None, start);
+        }
+        let digits: String = chars[start..end].iter().collect();
+        (digits.parse().ok(), end)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+    use crate::{model::Graph, runtime::{fmtspec::FormatSpec, Num, Units, Val}};
+
+    fn fmt(spec: &str, val: Val) -> String {
+        let graph = Graph::default();
+        FormatSpec::parse(spec).unwrap().format(&val, &graph).unwrap()
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(fmt(".2", Val::Num(Num::Float(3.14159))), "3.14");
+        assert_eq!(fmt(">8", Val::Num(Num::Int(42))), "      42");
+        assert_eq!(fmt("08", Val::Num(Num::Int(-42))), "-0000042");
+        assert_eq!(fmt("*^7", Val::Num(Num::Int(42))), "**42***");
+        assert_eq!(fmt("+", Val::Num(Num::Int(7))), "+7");
+        assert_eq!(fmt(",.2", Val::Num(Num::Float(1234567.891))), "1,234,567.89");
+        assert_eq!(fmt("e", Val::Num(Num::Float(1234.5))), "1.2345e3");
+        assert_eq!(fmt("x", Val::Num(Num::Int(255))), "ff");
+        assert_eq!(fmt(".2", Val::Num(Num::Decimal(Decimal::new(2675, 3)))), "2.68");
+        assert_eq!(fmt("08.2", Val::Num(Num::Decimal(Decimal::new(-1999, 2)))), "-0019.99");
+        assert_eq!(fmt("", Val::Num(Num::from(u128::MAX))), "340282366920938463463374607431768211455");
+        assert_eq!(fmt("x", Val::Num(Num::from(u64::MAX as u128 + 1))), "10000000000000000");
+        assert_eq!(fmt(".1", Val::Num(Num::from(-(u64::MAX as i128) - 1))), "-18446744073709551616.0");
+        assert_eq!(fmt("08b", Val::Num(Num::Int(5))), "00000101");
+    }
+
+    #[test]
+    fn units() {
+        assert_eq!(fmt(".2", Val::Num(Num::Units(3.14159, Units::Kilometers))), "3.14km");
+        assert_eq!(fmt("MiB", Val::Num(Num::Units(1048576., Units::Bytes))), "1MiB");
+        assert_eq!(fmt(".1m", Val::Num(Num::Units(1.5, Units::Kilometers))), "1500.0m");
+
+        let graph = Graph::default();
+        assert!(FormatSpec::parse("kg").unwrap().format(&Val::Num(Num::Units(1., Units::Meters)), &graph).is_err());
+        assert!(FormatSpec::parse("bogus").is_err());
+    }
+
+    #[test]
+    fn times_and_strings() {
+        assert_eq!(fmt("%Y-%m-%d", Val::Num(Num::Units(0., Units::Milliseconds))), "1970-01-01");
+        assert_eq!(fmt("%H:%M", Val::Num(Num::Units(90., Units::Minutes))), "01:30");
+        assert_eq!(fmt("<6", Val::Str("hi".into())), "hi    ");
+        assert_eq!(fmt(".3", Val::Str("hello".into())), "hel");
+    }
+}
@@ -19,16 +19,32 @@ use arcstr::ArcStr;
 use colored::Colorize;
 use imbl::{vector, Vector};
 use serde::{Deserialize, Serialize};
-use crate::{model::Graph, runtime::{instructions::{list::{NEW_LIST, PUSH_LIST}, Base, ConsumeStack, AWAIT}, proc::{ProcEnv, ProcRes}, Error, Val, Variable}};
+use crate::{model::Graph, runtime::{bytecode::{CachedProgram, Frame, Locals, OpCode, Program}, optimizer::Optimizer, instructions::{list::{NEW_LIST, PUSH_LIST}, load_path, ops::binary_op, trycatch::{error_value, finally_redirect, Completion}, Base, ConsumeStack, AWAIT}, proc::{ProcEnv, ProcRes, Settle}, Error, Val, Variable}};
+
+
+/// Number of steps between execution time checks.
+const EXEC_TIME_CHECK_STEPS: u32 = 256;
 
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 /// Instructions.
+/// Flat VM that executes compiled programs in frames (the last frame is executing).
+/// Instructions returned by an instruction get compiled and pushed as a new frame.
 pub struct Instructions {
+    /// Instructions to compile & execute after the current frames.
     /// Uses structural sharing, then only copies the Arc when needed lazily.
-    /// Store instructions in a Func, then clone into the proc without any copies.
     pub instructions: Vector<Arc<dyn Instruction>>,
-    pub executed: Vector<Arc<dyn Instruction>>,
+
+    #[serde(default)]
+    pub frames: Vec<Frame>,
+
+    /// First instruction executed that was not replaced (used to find the function a process was started with).
+    #[serde(default)]
+    pub first: Option<Arc<dyn Instruction>>,
+
+    /// Compiled program for these instructions (Ex. the body of an async block, compiled once).
+    #[serde(skip)]
+    compiled: CachedProgram,
 }
 impl From<Arc<dyn Instruction>> for Instructions {
     fn from(value: Arc<dyn Instruction>) -> Self {
@@ -53,33 +69,78 @@ impl Instructions {
         Self { instructions, ..Default::default() }
     }
 
+    #[inline]
+    /// Instructions that run an already compiled program.
+    pub fn program(program: Arc<Program>) -> Self {
+        Self { frames: vec![Frame::new(program)], ..Default::default() }
+    }
+
+    #[inline]
+    /// Compiled program for the pending instructions, compiled the first time it is needed.
+    /// Used for instructions that are executed many times without being changed (async blocks).
+    pub fn compiled(&self) -> Arc<Program> {
+        self.compiled.get(|| Instructions::from(self.instructions.clone()))
+    }
+
     #[inline]
     /// Clear these instructions.
     pub fn clear(&mut self) {
         self.instructions.clear();
-        self.executed.clear();
+        self.frames.clear();
+        self.first = None;
+        self.compiled = Default::default();
     }
 
     #[inline(always)]
     /// Are there more instructions to process?
     pub fn more(&self) -> bool {
-        !self.instructions.is_empty()
+        !self.instructions.is_empty() || self.frames.iter().any(|frame| frame.more())
+    }
+
+    #[inline]
+    /// Compile pending instructions into a frame that runs after the current frames.
+    fn load(&mut self) {
+        if !self.instructions.is_empty() {
+            let program = Program::compile(&self.instructions);
+            self.instructions.clear();
+            self.frames.insert(0, Frame::new(Arc::new(program)));
+        }
+    }
+
+    /// Push instructions to execute next (compiled into a new frame).
+    pub fn push_frames(&mut self, mut other: Instructions) {
+        if !other.instructions.is_empty() {
+            self.frames.push(Frame::new(Arc::new(Program::compile(&other.instructions))));
+        }
+        self.frames.append(&mut other.frames);
+    }
+
+    #[inline]
+    /// Push an instruction to execute next.
+    pub fn push_front(&mut self, instruction: Arc<dyn Instruction>) {
+        self.push_frames(Instructions::from(instruction));
+    }
+
+    #[inline]
+    /// First instruction that was executed.
+    pub fn first_executed(&self) -> Option<Arc<dyn Instruction>> {
+        self.first.clone()
     }
 
     /// Trace out the last N instructions that were executed.
     pub fn trace_n(&self, n: usize) -> String {
-        let mut count = 0;
-        let mut ins = Vector::default();
-        for exec in self.executed.iter().rev() {
-            ins.push_front(exec.clone());
-            count += 1;
-            if count >= n { break; }
+        let mut ins = Vec::new();
+        'frames: for frame in self.frames.iter().rev() {
+            for index in (0..frame.pc.min(frame.program.len())).rev() {
+                if ins.len() >= n { break 'frames; }
+                ins.push(frame.program.trace(index));
+            }
         }
+        ins.reverse();
 
         let mut output = String::default();
         for i in 0..ins.len() {
-            let ins = &ins[i];
-            let inner = format!("{i}: {:?}", ins);
+            let inner = format!("{i}: {}", &ins[i]);
             if i == 0 {
                 output.push_str(&format!("\t\t{}", inner.dimmed()));
             } else {
@@ -91,18 +152,21 @@ impl Instructions {
 
     /// Trace out the next N instructions that are going to be executed.
     pub fn peek_n(&self, n: usize) -> String {
-        let mut count = 0;
-        let mut ins = Vector::default();
+        let mut ins = Vec::new();
+        'frames: for frame in self.frames.iter().rev() {
+            for index in frame.pc..frame.program.len() {
+                if ins.len() >= n { break 'frames; }
+                ins.push(frame.program.trace(index));
+            }
+        }
         for exec in self.instructions.iter() {
-            ins.push_back(exec.clone());
-            count += 1;
-            if count >= n { break; }
+            if ins.len() >= n { break; }
+            ins.push(format!("{:?}", exec));
         }
 
         let mut output = String::default();
         for i in 0..ins.len() {
-            let ins = &ins[i];
-            let inner = format!("{i}: {:?}", ins);
+            let inner = format!("{i}: {}", &ins[i]);
             if i == 0 {
                 output.push_str(&format!("\t\t{}", inner.dimmed()));
             } else {
@@ -114,61 +178,66 @@ impl Instructions {
 
     /// Backup to a specific tag in these instructions.
     pub fn back_to(&mut self, tag: &ArcStr) {
-        'unwind: while let Some(ins) = self.executed.pop_back() {
-            if let Some(base) = ins.as_dyn_any().downcast_ref::<Base>() {
-                match base {
-                    Base::Tag(tagged) => {
-                        if tagged == tag {
-                            self.executed.push_back(ins);
-                            break 'unwind;
-                        }
-                    },
-                    _ => {}
-                }
+        self.load();
+        while let Some(frame) = self.frames.last_mut() {
+            if let Some(offset) = frame.program.backward(tag, frame.pc) {
+                frame.pc = offset;
+                return;
             }
-            self.instructions.push_front(ins);
+            self.frames.pop();
         }
     }
 
-    /// Backup to a specific tag in these instructions, killing all instructions.
-    pub fn kill_back_to(&mut self, tag: &ArcStr) {
-        'unwind: while let Some(ins) = self.executed.pop_back() {
-            if let Some(base) = ins.as_dyn_any().downcast_ref::<Base>() {
-                match base {
-                    Base::Tag(tagged) => {
-                        if tagged == tag {
-                            self.executed.push_back(ins);
-                            break 'unwind;
-                        }
-                    },
-                    _ => {}
-                }
+    /// Forward to a specific tag in these instructions.
+    /// Frames that do not contain the tag are finished.
+    pub fn forward_to(&mut self, tag: &ArcStr) {
+        self.load();
+        while let Some(frame) = self.frames.last_mut() {
+            if let Some(offset) = frame.program.forward(tag, frame.pc) {
+                frame.pc = offset;
+                return;
             }
+            self.frames.pop();
         }
     }
 
-    /// Backup to a specific tag in these instructions.
-    pub fn forward_to(&mut self, tag: &ArcStr) {
-        'fast_forward: while let Some(ins) = self.instructions.pop_front() {
-            self.executed.push_back(ins.clone());
-            if let Some(base) = ins.as_dyn_any().downcast_ref::<Base>() {
-                if let Base::Tag(tagged) = base {
-                    if tagged == tag {
-                        break 'fast_forward;
-                    }
-                }
-            }
+    #[inline(always)]
+    /// Jump the executing frame to an offset.
+    fn jump(&mut self, offset: usize) {
+        if let Some(frame) = self.frames.last_mut() {
+            frame.pc = offset;
         }
     }
 
     #[inline]
-    /// Execute one instruction, in order.
-    /// This will pop the first instruction, leaving the next ready to be consumed later.
-    pub fn exec(&mut self, env: &mut ProcEnv, graph: &mut Graph, mut limit: i32) -> Result<ProcRes, Error> {
+    /// Execute instructions, in order, until the process suspends, finishes, or errors.
+    /// This will advance the current frame, leaving the next ready to be consumed later.
+    pub fn exec(&mut self, env: &mut ProcEnv, graph: &mut Graph, limit: i32) -> Result<ProcRes, Error> {
+        if env.quota.is_empty() && env.sandbox.is_none() {
+            return self.exec_steps(env, graph, limit);
+        }
+
+        // Inserts made while this process runs count towards its quota & its sandbox applies
+        let outer_quota = if env.quota.is_empty() { None } else { graph.process_quota.replace((env.quota, env.usage)) };
+        let outer_sandbox = std::mem::replace(&mut graph.process_sandbox, env.sandbox.clone());
+        let res = self.exec_steps(env, graph, limit);
+        graph.process_sandbox = outer_sandbox;
+        if !env.quota.is_empty() {
+            if let Some((_, usage)) = std::mem::replace(&mut graph.process_quota, outer_quota) {
+                env.usage = usage;
+            }
+        }
+        res
+    }
+
+    /// Execution loop.
+    fn exec_steps(&mut self, env: &mut ProcEnv, graph: &mut Graph, mut limit: i32) -> Result<ProcRes, Error> {
         if env.start_time.is_none() {
             env.start_time = Some(web_time::Instant::now());
         }
+        self.load();
         let keep_count = limit > 0;
+        let mut steps: u32 = 0;
         'exec_loop: loop {
             if keep_count {
                 if limit <= 0 {
@@ -181,14 +250,25 @@ impl Instructions {
                 limit -= 1;
             }
 
-            // enforce max execution time
-            if let Some(max) = &env.max_execution_time {
-                if let Some(start) = &env.start_time {
-                    if &start.elapsed() > max {
-                        return Err(Error::ExecutionTimeout);
+            // enforce max execution time (reading the clock isn't free, so not every step)
+            if steps % EXEC_TIME_CHECK_STEPS == 0 {
+                if let Some(max) = &env.max_execution_time {
+                    if let Some(start) = &env.start_time {
+                        if &start.elapsed() > max {
+                            return Err(Error::ExecutionTimeout);
+                        }
                     }
                 }
             }
+            steps = steps.wrapping_add(1);
+
+            // enforce the instruction budget (deterministic, unlike time)
+            if let Some(fuel) = &mut env.fuel {
+                if *fuel == 0 {
+                    return Err(Error::OutOfFuel);
+                }
+                *fuel -= 1;
+            }
 
             // enforce max stack sizes
             if env.stack.len() > env.max_stack_size {
@@ -198,246 +278,444 @@ impl Instructions {
                 return Err(Error::CallStackOverflow);
             }
 
-            if let Some(ins) = self.instructions.pop_front() {
-                self.executed.push_back(ins.clone());
-
-                if let Some(base) = ins.as_dyn_any().downcast_ref::<Base>() {
-                    match base {
-                        Base::CtrlTrace(n) => {
-                            return Ok(ProcRes::Trace(*n));
-                        },
-                        Base::CtrlPeek(n) => {
-                            return Ok(ProcRes::Peek(*n));
-                        },
-                        Base::CtrlExit => {
-                            if let Some(promise) = env.stack.pop() {
-                                if let Some((pid, _)) = promise.try_promise() {
-                                    return Ok(ProcRes::Exit(Some(pid)));
-                                } else {
-                                    env.stack.push(promise);
-                                }
+            // Fetch the next operation, finishing frames that are done
+            let program;
+            let index;
+            loop {
+                if let Some(frame) = self.frames.last_mut() {
+                    if frame.more() {
+                        program = frame.program.clone();
+                        index = frame.pc;
+                        frame.pc += 1;
+                        break;
+                    }
+                    self.frames.pop();
+                } else {
+                    break 'exec_loop;
+                }
+            }
+            let ins = match &program.code[index] {
+                OpCode::Const(i) => {
+                    env.stack.push(Variable::val(program.consts[*i].clone()));
+                    if self.first.is_none() {
+                        self.first = program.instruction(index);
+                    }
+                    continue 'exec_loop;
+                },
+                OpCode::Jump(offset) => {
+                    self.jump(*offset);
+                    continue 'exec_loop;
+                },
+                OpCode::JumpIfTruthy(offset, consume) => {
+                    if let Some(val) = env.stack.pop() {
+                        if val.truthy() {
+                            match consume {
+                                ConsumeStack::Dont |
+                                ConsumeStack::IfTrue => {
+                                    env.stack.push(val);
+                                },
+                                _ => {}
                             }
-                            return Ok(ProcRes::Exit(None));
-                        },
-                        Base::CtrlAwait => {
-                            if let Some(promise) = env.stack.pop() {
-                                if let Some((pid, cast_type)) = promise.try_promise() {
-                                    if !cast_type.empty() {
-                                        // Special instruction to cast the awaited value when we return to this process
-                                        self.instructions.push_front(Arc::new(Base::CtrlAwaitCast(cast_type)));
-                                    }
-                                    return Ok(ProcRes::Wait(pid.clone()));
-                                } else if promise.val.read().list() || promise.val.read().set() {
-                                    let mut gtg = true;
-                                    let mut awaits = Vec::new();
-
-                                    match promise.val.read().deref() {
-                                        Val::List(vals) => {
-                                            for val in vals {
-                                                if let Some(_) = val.read().try_promise() {
-                                                    awaits.push(Arc::new(Base::Literal(val.read().clone())));
-                                                } else {
-                                                    gtg = false;
-                                                }
+                            self.jump(*offset);
+                        } else {
+                            match consume {
+                                ConsumeStack::Dont |
+                                ConsumeStack::IfFalse => {
+                                    env.stack.push(val);
+                                },
+                                _ => {}
+                            }
+                        }
+                    }
+                    continue 'exec_loop;
+                },
+                OpCode::JumpIfNotTruthy(offset, consume) => {
+                    if let Some(val) = env.stack.pop() {
+                        if !val.truthy() {
+                            match consume {
+                                ConsumeStack::Dont |
+                                ConsumeStack::IfTrue => {
+                                    env.stack.push(val);
+                                },
+                                _ => {}
+                            }
+                            self.jump(*offset);
+                        } else {
+                            match consume {
+                                ConsumeStack::Dont |
+                                ConsumeStack::IfFalse => {
+                                    env.stack.push(val);
+                                },
+                                _ => {}
+                            }
+                        }
+                    }
+                    continue 'exec_loop;
+                },
+                OpCode::LoopBack(offset) => {
+                    // Go back to the top of the loop, giving other processes a chance to run
+                    // with a time slice, the limit decides when other processes run instead
+                    self.jump(*offset);
+                    if env.yield_enabled && !keep_count {
+                        return Ok(ProcRes::More);
+                    }
+                    continue 'exec_loop;
+                },
+                OpCode::LoadLocal(slot, name, by_ref) => {
+                    if let Some(base) = self.frames.last().and_then(|frame| frame.base()) {
+                        if let Some(var) = env.table.slot(base + slot, name) {
+                            env.stack.push(var.stack_var(*by_ref));
+                            continue 'exec_loop;
+                        }
+                    }
+                    // not the closest variable with the name, so look it up
+                    let res = Base::LoadVariable(name.clone(), false, *by_ref).exec(env, graph);
+                    self.op_result(res, &program, index, env, graph)?;
+                    continue 'exec_loop;
+                },
+                OpCode::StoreLocal(slot, name) => {
+                    if let Some(var) = env.stack.pop() {
+                        if let Some(base) = self.frames.last().and_then(|frame| frame.base()) {
+                            let context = env.self_stack.last().cloned();
+                            match env.table.set_slot(base + slot, name, &var, graph, context) {
+                                Ok(true) => continue 'exec_loop,
+                                Ok(false) => {},
+                                Err(error) => {
+                                    self.throw(error, env)?;
+                                    continue 'exec_loop;
+                                },
+                            }
+                        }
+                        env.stack.push(var);
+                    }
+                    // not the closest variable with the name, so look it up
+                    let res = Base::SetVariable(name.clone()).exec(env, graph);
+                    self.op_result(res, &program, index, env, graph)?;
+                    continue 'exec_loop;
+                },
+                OpCode::GetField(path, by_ref) => {
+                    let mut res = Ok(None);
+                    if let Some(obj) = env.stack.pop().and_then(|var| var.try_obj()) {
+                        res = load_path(obj, path, *by_ref, env, graph);
+                    } else {
+                        env.stack.push(Variable::val(Val::Null));
+                    }
+                    self.op_result(res, &program, index, env, graph)?;
+                    continue 'exec_loop;
+                },
+                OpCode::GetSelfField(path, by_ref) => {
+                    let obj = env.self_ptr();
+                    let res = load_path(obj, path, *by_ref, env, graph);
+                    self.op_result(res, &program, index, env, graph)?;
+                    continue 'exec_loop;
+                },
+                OpCode::Add |
+                OpCode::Sub |
+                OpCode::Mul |
+                OpCode::Div |
+                OpCode::Mod |
+                OpCode::Greater |
+                OpCode::Less |
+                OpCode::GreaterOrEq |
+                OpCode::LessOrEq |
+                OpCode::Eq |
+                OpCode::Neq => {
+                    if let Some(op) = program.code[index].binary_op() {
+                        let res = binary_op(&op, env, graph);
+                        self.op_result(res, &program, index, env, graph)?;
+                    }
+                    continue 'exec_loop;
+                },
+                OpCode::Ins(ins) => ins,
+            };
+
+            if let Some(base) = ins.as_dyn_any().downcast_ref::<Base>() {
+                match base {
+                    Base::CtrlTrace(n) => {
+                        return Ok(ProcRes::Trace(*n));
+                    },
+                    Base::CtrlPeek(n) => {
+                        return Ok(ProcRes::Peek(*n));
+                    },
+                    Base::CtrlExit => {
+                        if let Some(promise) = env.stack.pop() {
+                            if let Some((pid, _)) = promise.try_promise() {
+                                return Ok(ProcRes::Exit(Some(pid)));
+                            } else {
+                                env.stack.push(promise);
+                            }
+                        }
+                        return Ok(ProcRes::Exit(None));
+                    },
+                    Base::CtrlAwait => {
+                        if let Some(promise) = env.stack.pop() {
+                            if let Some((pid, cast_type)) = promise.try_promise() {
+                                if !cast_type.empty() {
+                                    // Special instruction to cast the awaited value when we return to this process
+                                    self.push_front(Arc::new(Base::CtrlAwaitCast(cast_type)));
+                                }
+                                return Ok(ProcRes::Wait(pid.clone()));
+                            } else if promise.val.read().list() || promise.val.read().set() {
+                                let mut gtg = true;
+                                let mut awaits = Vec::new();
+
+                                match promise.val.read().deref() {
+                                    Val::List(vals) => {
+                                        for val in vals {
+                                            if let Some(_) = val.read().try_promise() {
+                                                awaits.push(Arc::new(Base::Literal(val.read().clone())));
+                                            } else {
+                                                gtg = false;
                                             }
-                                        },
-                                        Val::Set(set) => {
-                                            for val in set {
-                                                if let Some(_) = val.read().try_promise() {
-                                                    awaits.push(Arc::new(Base::Literal(val.read().clone())));
-                                                } else {
-                                                    gtg = false;
-                                                }
+                                        }
+                                    },
+                                    Val::Set(set) => {
+                                        for val in set {
+                                            if let Some(_) = val.read().try_promise() {
+                                                awaits.push(Arc::new(Base::Literal(val.read().clone())));
+                                            } else {
+                                                gtg = false;
                                             }
-                                        },
-                                        _ => {}
-                                    }
-
-                                    if gtg {
-                                        self.executed.pop_back();
-                                        for promise in awaits.into_iter().rev() {
-                                            self.instructions.push_front(PUSH_LIST.clone());
-                                            self.instructions.push_front(AWAIT.clone());
-                                            self.instructions.push_front(promise);
                                         }
-                                        self.instructions.push_front(NEW_LIST.clone());
-                                    } else {
-                                        env.stack.push(promise); // not a collection of promises
+                                    },
+                                    _ => {}
+                                }
+
+                                if gtg {
+                                    let mut instructions = Instructions::default();
+                                    instructions.push(NEW_LIST.clone());
+                                    for promise in awaits {
+                                        instructions.push(promise);
+                                        instructions.push(AWAIT.clone());
+                                        instructions.push(PUSH_LIST.clone());
                                     }
+                                    self.push_frames(instructions);
                                 } else {
-                                    env.stack.push(promise); // put it back because not a promise
+                                    env.stack.push(promise); // not a collection of promises
                                 }
-                            }
-                            // Awaits on anything else are a passthrough operation...
-                        },
-                        Base::CtrlAwaitCast(cast_type) => {
-                            self.executed.pop_back(); // This one doesn't stick around, which makes it special
-                            if let Some(var) = env.stack.pop() {
-                                var.cast(cast_type, graph, Some(env.self_ptr()))?;
-                                env.stack.push(var);
-                            } else if cast_type.empty() {
-                                // nothing to do in this case
                             } else {
-                                return Err(Error::CastStackError);
+                                env.stack.push(promise); // put it back because not a promise
                             }
-                            continue 'exec_loop;
-                        },
-                        Base::CtrlSuspend => {
-                            // Go to the next processes instructions
-                            // Used to spawn new processes as well
-                            return Ok(ProcRes::More);
-                        },
-                        Base::CtrlYield => {
-                            // Like suspend, but used for process switching
-                            // If multiple processes running or sleeping, go to the next
-                            if env.yield_enabled {
-                                if self.more() {
-                                    return Ok(ProcRes::More);
-                                } else {
-                                    return Ok(ProcRes::Done);
+                        }
+                        // Awaits on anything else are a passthrough operation...
+                    },
+                    Base::CtrlAwaitCast(cast_type) => {
+                        if let Some(var) = env.stack.pop() {
+                            var.cast(cast_type, graph, Some(env.self_ptr()))?;
+                            env.stack.push(var);
+                        } else if cast_type.empty() {
+                            // nothing to do in this case
+                        } else {
+                            return Err(Error::CastStackError);
+                        }
+                        continue 'exec_loop;
+                    },
+                    Base::CtrlCancel => {
+                        if let Some(promise) = env.stack.pop() {
+                            if let Some((pid, _)) = promise.try_promise() {
+                                // the runtime pushes whether the process was cancelled when we return to this process
+                                return Ok(ProcRes::Cancel(pid));
+                            }
+                        }
+                        env.stack.push(Variable::val(Val::Bool(false)));
+                        continue 'exec_loop;
+                    },
+                    Base::CtrlAwaitTimeout(dur) => {
+                        if let Some(promise) = env.stack.pop() {
+                            if let Some((pid, cast_type)) = promise.try_promise() {
+                                if !cast_type.empty() {
+                                    self.push_front(Arc::new(Base::CtrlAwaitCast(cast_type)));
                                 }
+                                return Ok(ProcRes::WaitFor(pid, *dur));
+                            }
+                            env.stack.push(promise); // passthrough, like await
+                        }
+                        continue 'exec_loop;
+                    },
+                    Base::CtrlSettle(mode) => {
+                        if let Some(group) = env.stack.pop() {
+                            let mut values = Vec::new();
+                            match group.val.read().deref() {
+                                Val::List(vals) => {
+                                    for val in vals { values.push(Variable::val(val.read().clone())); }
+                                },
+                                Val::Set(set) => {
+                                    for val in set { values.push(Variable::val(val.read().clone())); }
+                                },
+                                _ => {
+                                    values.push(group.clone());
+                                }
+                            }
+                            return Ok(ProcRes::Settle(Settle::new(*mode, values)));
+                        }
+                        return Err(Error::StackError);
+                    },
+                    Base::CtrlSuspend => {
+                        // Go to the next processes instructions
+                        // Used to spawn new processes as well
+                        return Ok(ProcRes::More);
+                    },
+                    Base::CtrlYield => {
+                        // Like suspend, but used for process switching
+                        // If multiple processes running or sleeping, go to the next
+                        // with a time slice, the limit decides when other processes run instead
+                        if env.yield_enabled && !keep_count {
+                            if self.more() {
+                                return Ok(ProcRes::More);
+                            } else {
+                                return Ok(ProcRes::Done);
                             }
+                        }
+                        continue 'exec_loop;
+                    },
+                    Base::CtrlSleepFor(dur) => {
+                        // Instruct this process to sleep for an amount of time
+                        return Ok(ProcRes::SleepFor(dur.clone()));
+                    },
+                    Base::CtrlSleepRef(wref) => {
+                        // Instruct this process to sleep until the wake reference has been set
+                        return Ok(ProcRes::Sleep(wref.clone()));
+                    },
+                    Base::CtrlBackTo(tag) => {
+                        self.back_to(tag);
+                        continue 'exec_loop;
+                    },
+                    Base::CtrlLoopBack(tag) => {
+                        // Resolved to a LoopBack operation when the tag is in the same program
+                        self.back_to(tag);
+                        if env.yield_enabled && !keep_count {
+                            return Ok(ProcRes::More);
+                        }
+                        continue 'exec_loop;
+                    },
+                    Base::RestoreDepth(depth, offset) => {
+                        if let Some(depths) = self.frames.last().and_then(|frame| frame.depths) {
+                            depths.restore(*depth, *offset, env, graph)?;
+                        }
+                        continue 'exec_loop;
+                    },
+                    Base::CtrlForwardTo(tag) => {
+                        self.forward_to(tag);
+                        continue 'exec_loop;
+                    },
+                    Base::CtrlFnReturn => {
+                        // Run the finally block of a try statement we are returning out of first
+                        if let Some(finally_tag) = finally_redirect(env, Completion::Return) {
+                            self.forward_to(&finally_tag);
+                            continue 'exec_loop;
+                        }
+
+                        // Go forwards to the current callstack function ID return tag
+                        if let Some(last) = env.return_stack.last() {
+                            self.forward_to(last);
                             continue 'exec_loop;
-                        },
-                        Base::CtrlSleepFor(dur) => {
-                            // Instruct this process to sleep for an amount of time
-                            return Ok(ProcRes::SleepFor(dur.clone()));
-                        },
-                        Base::CtrlSleepRef(wref) => {
-                            // Instruct this process to sleep until the wake reference has been set
-                            return Ok(ProcRes::Sleep(wref.clone()));
-                        },
-                        Base::CtrlBackTo(tag) => {
-                            self.back_to(tag);
+                        }
+                    },
+                    Base::CtrlBreak => {
+                        if let Some(finally_tag) = finally_redirect(env, Completion::Break) {
+                            self.forward_to(&finally_tag);
                             continue 'exec_loop;
-                        },
-                        Base::CtrlForwardTo(tag) => {
-                            self.forward_to(tag);
+                        }
+                        if let Some(loop_tag) = env.loop_stack.last() {
+                            let break_tag: ArcStr = format!("{}_brk", &loop_tag).into();
+                            self.forward_to(&break_tag);
+                            continue 'exec_loop;
+                        }
+                    },
+                    Base::CtrlContinue => {
+                        if let Some(finally_tag) = finally_redirect(env, Completion::Continue) {
+                            self.forward_to(&finally_tag);
                             continue 'exec_loop;
-                        },
-                        Base::CtrlFnReturn => {
-                            // Go forwards to the current callstack function ID return tag
-                            if let Some(last) = env.return_stack.last() {
-                                self.forward_to(last);
+                        }
+                        if let Some(loop_tag) = env.loop_stack.last() {
+                            let continue_tag: ArcStr = format!("{}_con", &loop_tag).into();
+                            self.forward_to(&continue_tag);
+                            continue 'exec_loop;
+                        }
+                    },
+                    Base::CtrlJumpTable(table, default, end) => {
+                        // Compares the value on the top of the stack and jumps forwards to the associated tag
+                        if let Some(var) = env.stack.pop() {
+                            if let Some(tag) = table.get(&var.get()) {
+                                self.forward_to(tag);
                                 continue 'exec_loop;
-                            }
-                        },
-                        Base::CtrlBreak => {
-                            if let Some(loop_tag) = env.loop_stack.last() {
-                                let break_tag: ArcStr = format!("{}_brk", &loop_tag).into();
-                                self.forward_to(&break_tag);
+                            } else if let Some(tag) = default {
+                                self.forward_to(tag);
                                 continue 'exec_loop;
-                            }
-                        },
-                        Base::CtrlContinue => {
-                            if let Some(loop_tag) = env.loop_stack.last() {
-                                let continue_tag: ArcStr = format!("{}_con", &loop_tag).into();
-                                self.forward_to(&continue_tag);
+                            } else {
+                                self.forward_to(end);
                                 continue 'exec_loop;
                             }
-                        },
-                        Base::CtrlLoopBackTo { top_tag, .. } => {
-                            self.kill_back_to(top_tag);
-                            // don't continue...
-                        },
-                        Base::CtrlJumpTable(table, default, end) => {
-                            // Compares the value on the top of the stack and jumps forwards to the associated tag
-                            if let Some(var) = env.stack.pop() {
-                                if let Some(tag) = table.get(&var.get()) {
-                                    self.forward_to(tag);
-                                    continue 'exec_loop;
-                                } else if let Some(tag) = default {
-                                    self.forward_to(tag);
-                                    continue 'exec_loop;
-                                } else {
-                                    self.forward_to(end);
-                                    continue 'exec_loop;
+                        } else {
+                            return Err(Error::StackError);
+                        }
+                    },
+                    Base::CtrlForwardToIfTruthy(tag, consume) => {
+                        if let Some(val) = env.stack.pop() {
+                            if val.truthy() {
+                                match consume {
+                                    ConsumeStack::Dont |
+                                    ConsumeStack::IfTrue => {
+                                        env.stack.push(val);
+                                    },
+                                    _ => {}
                                 }
+                                self.forward_to(tag);
+                                continue 'exec_loop;
                             } else {
-                                return Err(Error::StackError);
-                            }
-                        },
-                        Base::CtrlForwardToIfTruthy(tag, consume) => {
-                            if let Some(val) = env.stack.pop() {
-                                if val.truthy() {
-                                    match consume {
-                                        ConsumeStack::Dont |
-                                        ConsumeStack::IfTrue => {
-                                            env.stack.push(val);
-                                        },
-                                        _ => {}
-                                    }
-                                    self.forward_to(tag);
-                                    continue 'exec_loop;
-                                } else {
-                                    match consume {
-                                        ConsumeStack::Dont |
-                                        ConsumeStack::IfFalse => {
-                                            env.stack.push(val);
-                                        },
-                                        _ => {}
-                                    }
-                                }
-                            }
-                        },
-                        Base::CtrlForwardToIfNotTruthy(tag, consume) => {
-                            if let Some(val) = env.stack.pop() {
-                                if !val.truthy() {
-                                    match consume {
-                                        ConsumeStack::Dont |
-                                        ConsumeStack::IfTrue => {
-                                            env.stack.push(val);
-                                        },
-                                        _ => {}
-                                    }
-                                    self.forward_to(tag);
-                                    continue 'exec_loop;
-                                } else {
-                                    match consume {
-                                        ConsumeStack::Dont |
-                                        ConsumeStack::IfFalse => {
-                                            env.stack.push(val);
-                                        },
-                                        _ => {}
-                                    }
-                                }
-                            }
-                        },
-                        _ => {}
-                    }
-                }
-
-                let res = ins.exec(env, graph);
-                match res {
-                    Ok(replacements) => {
-                        if let Some(mut dynamic) = replacements {
-                            if dynamic.more() {
-                                self.executed.pop_back(); // replacing this instruction with these instructions
-                                while dynamic.more() {
-                                    self.instructions.push_front(dynamic.instructions.pop_back().unwrap());
+                                match consume {
+                                    ConsumeStack::Dont |
+                                    ConsumeStack::IfFalse => {
+                                        env.stack.push(val);
+                                    },
+                                    _ => {}
                                 }
                             }
                         }
                     },
-                    Err(error) => {
-                        if let Some(try_tag) = env.try_stack.pop() {
-                            self.forward_to(&try_tag);
-                            match error {
-                                Error::Thrown(val) => {
-                                    env.stack.push(Variable::val(val));
-                                },
-                                _ => {
-                                    env.stack.push(Variable::val(Val::Str(error.to_string().into())));
+                    Base::CtrlForwardToIfNotTruthy(tag, consume) => {
+                        if let Some(val) = env.stack.pop() {
+                            if !val.truthy() {
+                                match consume {
+                                    ConsumeStack::Dont |
+                                    ConsumeStack::IfTrue => {
+                                        env.stack.push(val);
+                                    },
+                                    _ => {}
+                                }
+                                self.forward_to(tag);
+                                continue 'exec_loop;
+                            } else {
+                                match consume {
+                                    ConsumeStack::Dont |
+                                    ConsumeStack::IfFalse => {
+                                        env.stack.push(val);
+                                    },
+                                    _ => {}
                                 }
                             }
-                            continue 'exec_loop;
-                        } else {
-                            return Err(error);
                         }
                     },
+                    _ => {}
                 }
-            } else {
-                break;
+            }
+
+            let mut res = ins.exec(env, graph);
+            if let Some(error) = graph.quota_error.take() {
+                res = Err(error); // an insert was refused
+            }
+            match res {
+                Ok(replacements) => {
+                    if let Some(dynamic) = replacements {
+                        // replacing this instruction with these instructions
+                        self.push_frames(dynamic);
+                    } else if self.first.is_none() {
+                        self.first = Some(ins.clone());
+                    }
+                },
+                Err(error) => {
+                    self.throw(error, env)?;
+                },
             }
         }
         if self.more() {
@@ -447,6 +725,41 @@ impl Instructions {
         }
     }
 
+    /// Handle the result of an operation run directly by the execution loop (not dispatched to an instruction).
+    fn op_result(&mut self, mut res: Result<Option<Instructions>, Error>, program: &Program, index: usize, env: &mut ProcEnv, graph: &mut Graph) -> Result<(), Error> {
+        if let Some(error) = graph.quota_error.take() {
+            res = Err(error); // an insert was refused
+        }
+        match res {
+            Ok(Some(dynamic)) => {
+                self.push_frames(dynamic);
+            },
+            Ok(None) => {
+                if self.first.is_none() {
+                    self.first = program.instruction(index);
+                }
+            },
+            Err(error) => {
+                self.throw(error, env)?;
+            },
+        }
+        Ok(())
+    }
+
+    /// Throw an error, going to the catch block of the try statement we are in.
+    /// Returns the error if there isn't a try statement to catch it.
+    fn throw(&mut self, error: Error, env: &mut ProcEnv) -> Result<(), Error> {
+        if let Some(try_tag) = env.try_stack.pop() {
+            self.forward_to(&try_tag);
+            let val = error_value(&error);
+            env.stack.push(Variable::val(val));
+            env.error_stack.push((error, env.call_stack.clone()));
+            Ok(())
+        } else {
+            Err(error)
+        }
+    }
+
     #[inline(always)]
     /// Append instructions.
     pub fn append(&mut self, instructions: &Vector<Arc<dyn Instruction>>) {
@@ -472,6 +785,23 @@ impl Instructions {
 pub trait Instruction: InsDynAny + std::fmt::Debug + InsClone + Send + Sync {
     /// Execute this instruction given the process it's running on and the graph.
     fn exec(&self, env: &mut ProcEnv, graph: &mut Graph) -> Result<Option<Instructions>, Error>;
+
+    /// Static expansion of this instruction.
+    /// Instructions that always replace themselves with the same instructions (regardless of the process or graph)
+    /// return them here, so they get inlined when compiled into a program instead of expanding on every execution.
+    fn compile(&self) -> Option<Instructions> {
+        None
+    }
+
+    /// Compile anything this instruction runs in its own frame ahead of time, while the program it is in is compiled.
+    /// Lets loops & try statements address the locals of the enclosing program by slot.
+    fn precompile(&self, _locals: &Locals) {}
+
+    /// Optimized replacement for this instruction, if it can be simplified.
+    /// Called by the optimizer on parsed functions (prod profile).
+    fn optimize(&self, _optimizer: &mut Optimizer) -> Option<Arc<dyn Instruction>> {
+        None
+    }
 }
 
 
@@ -17,7 +17,7 @@
 use std::sync::Arc;
 use imbl::Vector;
 use serde::{Deserialize, Serialize};
-use crate::{model::Graph, runtime::{instruction::{Instruction, Instructions}, proc::ProcEnv, Error}};
+use crate::{model::Graph, runtime::{instruction::{Instruction, Instructions}, instructions::Base, optimizer::Optimizer, proc::ProcEnv, Error}};
 
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -28,8 +28,39 @@ pub struct Block {
 #[typetag::serde(name = "Block")]
 impl Instruction for Block {
     fn exec(&self, _env: &mut ProcEnv, _graph: &mut Graph) -> Result<Option<Instructions>, Error> {
+        Ok(self.compile())
+    }
+
+    fn compile(&self) -> Option<Instructions> {
         let mut instructions = Instructions::default();
         instructions.append(&self.ins);
-        Ok(Some(instructions))
+        Some(instructions)
+    }
+
+    fn optimize(&self, optimizer: &mut Optimizer) -> Option<Arc<dyn Instruction>> {
+        // Block expressions push a return tag (returns within stop at the block)
+        let block_expr = self.ins.iter().any(|ins| matches!(ins.as_dyn_any().downcast_ref::<Base>(), Some(Base::PushReturn)));
+        let block = Arc::new(Self {
+            ins: if block_expr { optimizer.nontail_all(&self.ins) } else { optimizer.optimize_all(&self.ins) },
+        });
+
+        // Constant followed by casts and truthy checks (Ex. "10km as m" or "!true")
+        if let Some(first) = block.ins.front() {
+            if Optimizer::constant(first).is_some() {
+                let mut constant = true;
+                for ins in block.ins.iter().skip(1) {
+                    match ins.as_dyn_any().downcast_ref::<Base>() {
+                        Some(Base::Cast(_)) |
+                        Some(Base::Truthy) |
+                        Some(Base::NotTruthy) => {},
+                        _ => { constant = false; break; }
+                    }
+                }
+                if constant {
+                    return Some(optimizer.fold(block));
+                }
+            }
+        }
+        Some(block)
     }
 }
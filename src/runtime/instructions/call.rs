@@ -18,7 +18,7 @@ use std::{ops::Deref, sync::Arc};
 use arcstr::{literal, ArcStr};
 use imbl::Vector;
 use serde::{Deserialize, Serialize};
-use crate::{model::{ASYNC_FUNC_ATTR, DataRef, Field, Func, Graph, LibFunc, NodeRef, PROTOTYPE_TYPE_ATTR, Prototype, SELF_STR_KEYWORD, SId, SUPER_STR_KEYWORD, UNSELF_FUNC_ATTR}, runtime::{Error, Type, Val, ValRef, Variable, instruction::{Instruction, Instructions}, instructions::{Base, DUPLICATE, POP_CALL, POP_RETURN, POP_SELF, PUSH_CALL, PUSH_RETURN, PUSH_SELF, PUSH_SYMBOL_SCOPE, PUSH_VAL_RET, PUSH_VOID_RET, SUSPEND, VALIDATE_FN_RET, YIELD}, proc::ProcEnv}};
+use crate::{model::{ABSTRACT_FUNC_ATTR, ASYNC_FUNC_ATTR, DataRef, Field, Func, Graph, LibFunc, NodeRef, PROTOTYPE_TYPE_ATTR, Prototype, SELF_STR_KEYWORD, SId, SUPER_STR_KEYWORD, UNSELF_FUNC_ATTR}, runtime::{Error, Priority, Type, Val, ValRef, Variable, bytecode::ProgramIns, instruction::{Instruction, Instructions}, optimizer::Optimizer, instructions::{Base, DUPLICATE, POP_CALL, POP_RETURN, POP_SELF, PUSH_CALL, PUSH_RETURN, PUSH_SELF, PUSH_SYMBOL_SCOPE, PUSH_VAL_RET, PUSH_VOID_RET, SUSPEND, VALIDATE_FN_RET, YIELD}, proc::ProcEnv}};
 
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -64,6 +64,37 @@ impl FuncCall {
         Err(Error::FuncDne("No Func or Search".into()))
     }
 
+    /// Push self onto the self stack for a call to func (a prototype self is already on the stack).
+    /// Returns whether self was pushed (and needs to be popped after the call).
+    fn push_self(&self, prototype_self: bool, func: &DataRef, unself: bool, instructions: &mut Instructions, graph: &mut Graph) -> bool {
+        if prototype_self {
+            instructions.push(PUSH_SELF.clone());
+            return true;
+        }
+        if let Some(oself) = &self.oself {
+            instructions.push(oself.clone());
+            instructions.push(PUSH_SELF.clone());
+            return true;
+        }
+        if !unself {
+            instructions.push(Arc::new(Base::Literal(Val::Obj(Self::func_self(func, graph)))));
+            instructions.push(PUSH_SELF.clone());
+            return true;
+        }
+        false
+    }
+
+    /// Self for a call to func (not a prototype or overridden self).
+    /// This is the first object the function is on, or the main root.
+    fn func_self(func: &DataRef, graph: &mut Graph) -> NodeRef {
+        for nref in func.data_nodes(graph) {
+            if nref.node_exists(graph) {
+                return nref;
+            }
+        }
+        graph.ensure_main_root()
+    }
+
     /// Search for a function to call using a path.
     /// If "stack" is set, pop the stack and use the result as a context or library name.
     fn search_func(&self, path: &str, env: &mut ProcEnv, graph: &mut Graph) -> Result<CallContext, Error> {
@@ -478,6 +509,7 @@ impl Instruction for FuncCall {
         if let Some(libname) = func_context.lib {
             let name = func_context.func.as_ref();
             if let Some(func) = graph.libfunc(&libname, name) {
+                graph.sandbox_libfunc(&libname, name)?;
                 return self.call_libfunc(func, func_context.stack_arg, env, graph);
             }
             if self.cnull {
@@ -490,20 +522,30 @@ impl Instruction for FuncCall {
 
         let func = func_context.func;
         let params;
-        let mut func_instructions;
+        let func_program;
         let rtype;
         let is_async;
+        let priority;
         let unself;
+        let is_abstract;
+        let inline;
         if let Some(func) = graph.get_stof_data::<Func>(&func) {
             params = func.params.clone();
-            func_instructions = func.instructions.clone();
+            func_program = func.program();
+            inline = func.inline.clone();
             rtype = func.return_type.clone();
 
             // Only async if we have the attribute and we are not a top level function
             is_async = func.attributes.contains_key(ASYNC_FUNC_ATTR.as_str()) && env.call_stack.len() > 0;
 
+            // Async functions can run at a different priority than the caller
+            priority = Priority::from_attributes(&func.attributes);
+
             // Should this function add itself to the self stack?
             unself = func.attributes.contains_key(UNSELF_FUNC_ATTR.as_str());
+
+            // Abstract functions (signatures only) cannot be called
+            is_abstract = func.attributes.contains_key(ABSTRACT_FUNC_ATTR.as_str());
         } else {
             if self.cnull {
                 let mut instructions = Instructions::default();
@@ -512,8 +554,31 @@ impl Instruction for FuncCall {
             }
             return Err(Error::FuncDne(format!("Data Ptr not Func")));
         }
+        if is_abstract {
+            let name = func.data_name(graph).map(|name| name.to_string()).unwrap_or_default();
+            return Err(Error::FuncAbstract(name));
+        }
+
+        // Trivial getters (marked by the optimizer) are inlined instead of setting up a call
+        if let Some(body) = inline {
+            if self.args.is_empty() && !is_async {
+                let mut instructions = Instructions::default();
+                if let Some(proto_self) = &func_context.prototype_self {
+                    instructions.push(proto_self.clone());
+                }
+                let pushed_self = self.push_self(func_context.prototype_self.is_some(), &func, unself, &mut instructions, graph);
+                instructions.push(body);
+                instructions.push(Arc::new(Base::Cast(rtype)));
+                if pushed_self {
+                    instructions.push(POP_SELF.clone());
+                }
+                return Ok(Some(instructions));
+            }
+        }
 
-        // Add return tag to the end of the func statements
+        // Function body (compiled once) followed by the return tag
+        let mut func_instructions = Vector::default();
+        func_instructions.push_back(Arc::new(ProgramIns(func_program)) as Arc<dyn Instruction>);
         func_instructions.push_back(Arc::new(Base::Tag(func.as_ref().into())));
 
         // Record the current table depth, because we need to pop until we get back here at the end
@@ -597,30 +662,8 @@ impl Instruction for FuncCall {
         }
 
         // Add self to self stack if not a prototype function
-        let mut pushed_self = false;
-        if let Some(_proto_self) = &func_context.prototype_self {
-            //instructions.push(proto_self); // happens before the arg instructions
-            instructions.push(PUSH_SELF.clone());
-            pushed_self = true;
-        } else if let Some(oself) = &self.oself {
-            instructions.push(oself.clone());
-            instructions.push(PUSH_SELF.clone());
-            pushed_self = true;
-        } else if !unself {
-            pushed_self = true;
-            let mut set = false;
-            for nref in func.data_nodes(graph) {
-                if nref.node_exists(graph) {
-                    instructions.push(Arc::new(Base::Literal(Val::Obj(nref))));
-                    instructions.push(PUSH_SELF.clone());
-                    set = true; break;
-                }
-            }
-            if !set {
-                instructions.push(Arc::new(Base::Literal(Val::Obj(graph.ensure_main_root()))));
-                instructions.push(PUSH_SELF.clone());
-            }
-        }
+        //instructions.push(proto_self); // happens before the arg instructions
+        let pushed_self = self.push_self(func_context.prototype_self.is_some(), &func, unself, &mut instructions, graph);
 
         // Push the yield instruction after everything has been set up
         instructions.push(YIELD.clone());
@@ -655,12 +698,21 @@ impl Instruction for FuncCall {
         if is_async {
             let mut async_instructions = Instructions::default();
             async_instructions.push(Arc::new(Base::Spawn((instructions, rtype)))); // adds a Promise<rtype> to the stack when executed!
+            if let Some(priority) = priority {
+                async_instructions.push(Arc::new(Base::SpawnPriority(priority)));
+            }
             async_instructions.push(SUSPEND.clone()); // make sure to spawn the process right after with the runtime... this is not an await
             Ok(Some(async_instructions))
         } else {
             Ok(Some(instructions))
         }
     }
+
+    fn optimize(&self, optimizer: &mut Optimizer) -> Option<Arc<dyn Instruction>> {
+        let mut call = self.clone();
+        call.args = self.args.iter().map(|arg| optimizer.expr(arg)).collect();
+        Some(Arc::new(call))
+    }
 }
 
 
@@ -679,4 +731,105 @@ impl Instruction for NamedArg {
         instructions.push(self.ins.clone());
         Ok(Some(instructions))
     }
+
+    fn optimize(&self, optimizer: &mut Optimizer) -> Option<Arc<dyn Instruction>> {
+        Some(Arc::new(Self {
+            name: self.name.clone(),
+            ins: optimizer.expr(&self.ins),
+        }))
+    }
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Returned call to the function that is running (see the optimizer).
+/// When the call resolves to the current function with the same self, the parameters are
+/// rebound and execution goes back to the top of the function instead of making a new call.
+pub struct TailCallIns {
+    pub call: FuncCall,
+
+    /// Tag at the top of the function body.
+    pub tag: ArcStr,
+}
+impl TailCallIns {
+    /// Instructions that loop back to the top of the current function, if this is a self-recursive call.
+    fn tail_loop(&self, env: &mut ProcEnv, graph: &mut Graph) -> Option<Instructions> {
+        let current = env.call_stack.last()?.clone();
+        let (scope_depth, loop_depth) = *env.call_frames.last()?;
+        let func_context = self.call.get_func_context(env, graph).ok()?;
+        if func_context.lib.is_some() || func_context.func != current {
+            return None;
+        }
+
+        let params;
+        let unself;
+        if let Some(func) = graph.get_stof_data::<Func>(&current) {
+            if func.attributes.contains_key(ASYNC_FUNC_ATTR.as_str()) {
+                return None;
+            }
+            params = func.params.clone();
+            unself = func.attributes.contains_key(UNSELF_FUNC_ATTR.as_str());
+        } else {
+            return None;
+        }
+        if self.call.args.len() > params.len() {
+            return None;
+        }
+        for param in params.iter().skip(self.call.args.len()) {
+            if param.default.is_none() {
+                return None; // let the call report the error
+            }
+        }
+
+        // Self has to stay the same to loop
+        if !unself {
+            let call_self;
+            if let Some(proto_self) = &func_context.prototype_self {
+                if let Some(Base::Literal(Val::Obj(nref))) = proto_self.as_dyn_any().downcast_ref::<Base>() {
+                    call_self = nref.clone();
+                } else {
+                    return None;
+                }
+            } else {
+                call_self = FuncCall::func_self(&current, graph);
+            }
+            if call_self != env.self_ptr() {
+                return None;
+            }
+        }
+
+        // Arguments are evaluated in the current scope, then the call scope is started over
+        let mut instructions = Instructions::default();
+        for arg in &self.call.args {
+            instructions.push(arg.clone());
+        }
+        instructions.push(Arc::new(Base::PopLoopUntilDepth(loop_depth)));
+        instructions.push(Arc::new(Base::PopSymbolScopeUntilDepth(scope_depth)));
+        instructions.push(PUSH_SYMBOL_SCOPE.clone());
+        for (index, param) in params.iter().enumerate().rev() {
+            if index < self.call.args.len() {
+                instructions.push(Arc::new(Base::Cast(param.param_type.clone())));
+                instructions.push(Arc::new(Base::DeclareVar(param.name.to_string().into(), param.param_type.clone())));
+            }
+        }
+        for param in params.iter().skip(self.call.args.len()) {
+            if let Some(default) = &param.default {
+                instructions.push(default.clone());
+                instructions.push(Arc::new(Base::Cast(param.param_type.clone())));
+                instructions.push(Arc::new(Base::DeclareVar(param.name.to_string().into(), param.param_type.clone())));
+            }
+        }
+        instructions.push(YIELD.clone());
+        instructions.push(Arc::new(Base::CtrlBackTo(self.tag.clone())));
+        Some(instructions)
+    }
+}
+#[typetag::serde(name = "TailCallIns")]
+impl Instruction for TailCallIns {
+    fn exec(&self, env: &mut ProcEnv, graph: &mut Graph) -> Result<Option<Instructions>, Error> {
+        if let Some(instructions) = self.tail_loop(env, graph) {
+            return Ok(Some(instructions));
+        }
+        self.call.exec(env, graph)
+    }
 }
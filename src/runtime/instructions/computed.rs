@@ -0,0 +1,126 @@
+//
+// Copyright 2025 Formata, Inc. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use std::sync::Arc;
+use rustc_hash::FxHashSet;
+use serde::{Deserialize, Serialize};
+use crate::{model::{DataRef, Field, Graph, COMPUTED_FIELD_ATTR}, runtime::{instruction::{Instruction, Instructions}, instructions::{call::FuncCall, Base}, proc::ProcEnv, Error, Val, Variable}};
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Computed field instructions.
+/// Fields read while computing are tracked as the computed field's dependencies.
+pub enum ComputedIns {
+    /// Load a computed field onto the stack, recomputing it first if out of date.
+    Load(DataRef),
+
+    /// Start tracking field reads for a computed field.
+    Track(DataRef),
+
+    /// Store the computed result in the field (pops the value & tracked reads, pushes the value).
+    Store(DataRef),
+}
+#[typetag::serde(name = "ComputedIns")]
+impl Instruction for ComputedIns {
+    fn exec(&self, env: &mut ProcEnv, graph: &mut Graph) -> Result<Option<Instructions>, Error> {
+        match self {
+            Self::Load(field_ref) => {
+                if let Some(instructions) = compute_field(field_ref, env, graph)? {
+                    return Ok(Some(instructions));
+                }
+                if let Some(field) = graph.get_stof_data::<Field>(field_ref) {
+                    env.stack.push(field.value.stack_var(false));
+                } else {
+                    env.stack.push(Variable::val(Val::Null));
+                }
+            },
+            Self::Track(field_ref) => {
+                env.computed_reads.push((field_ref.clone(), env.stack.len(), FxHashSet::default()));
+            },
+            Self::Store(field_ref) => {
+                let mut deps = FxHashSet::default();
+                let mut var = Variable::val(Val::Null);
+                if let Some((_, stack_len, reads)) = env.computed_reads.pop() {
+                    deps = reads;
+                    if env.stack.len() > stack_len {
+                        if let Some(res) = env.stack.pop() {
+                            var = res;
+                        }
+                    }
+                    env.stack.truncate(stack_len);
+                }
+
+                let mut fvar = None;
+                if let Some(field) = graph.get_stof_data::<Field>(field_ref) {
+                    fvar = Some(field.value.clone());
+                }
+                if let Some(mut fvar) = fvar {
+                    // computed fields are read-only from the outside, but the result still gets cast to the field type
+                    let mutable = fvar.mutable;
+                    fvar.mutable = true;
+                    graph.quota_set(field_ref, &var.val.read())?;
+                    fvar.set(&var, graph, field_ref.data_nodes(graph).into_iter().next())?;
+                    fvar.mutable = mutable;
+                    env.stack.push(fvar.stack_var(false));
+
+                    if let Some(field) = graph.get_mut_stof_data::<Field>(field_ref) {
+                        field.value = fvar;
+                    }
+                    if let Some(data) = field_ref.data_mut(graph) {
+                        data.invalidate_value();
+                    }
+                    graph.set_computed(field_ref, deps);
+                } else {
+                    env.stack.push(var);
+                }
+            },
+        }
+        Ok(None)
+    }
+}
+
+
+/// Instructions to recompute a computed field, if the field is computed and out of date.
+pub(crate) fn compute_field(field_ref: &DataRef, env: &ProcEnv, graph: &Graph) -> Result<Option<Instructions>, Error> {
+    if graph.computed_valid(field_ref) { return Ok(None); }
+    if let Some(field) = graph.get_stof_data::<Field>(field_ref) {
+        if !field.is_computed() { return Ok(None); }
+
+        let name = field_ref.data_name(graph).map(|name| name.to_string()).unwrap_or_default();
+        if env.computed_reads.iter().any(|(computing, ..)| computing == field_ref) {
+            return Err(Error::ComputedCycle(name));
+        }
+        for node in field_ref.data_nodes(graph) {
+            if let Some(func) = field.attribute_func(graph, &node, COMPUTED_FIELD_ATTR.as_str()) {
+                let mut instructions = Instructions::default();
+                instructions.push(Arc::new(ComputedIns::Track(field_ref.clone())));
+                instructions.push(Arc::new(FuncCall {
+                    func: Some(func),
+                    search: None,
+                    stack: false,
+                    as_ref: false,
+                    cnull: false,
+                    args: Default::default(),
+                    oself: Some(Arc::new(Base::Literal(Val::Obj(node)))),
+                }));
+                instructions.push(Arc::new(ComputedIns::Store(field_ref.clone())));
+                return Ok(Some(instructions));
+            }
+        }
+        return Err(Error::FuncDne(format!("computed function for field '{name}'")));
+    }
+    Ok(None)
+}
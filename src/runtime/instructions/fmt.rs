@@ -0,0 +1,61 @@
+//
+// Copyright 2025 Formata, Inc. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use crate::{model::Graph, runtime::{instruction::{Instruction, Instructions}, optimizer::Optimizer, proc::ProcEnv, Error, FormatSpec, Val, Variable}};
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Format instruction (template string `${expr:spec}`).
+pub struct FmtIns {
+    pub ins: Arc<dyn Instruction>,
+    pub spec: FormatSpec,
+}
+#[typetag::serde(name = "FmtIns")]
+impl Instruction for FmtIns {
+    fn exec(&self, _env: &mut ProcEnv, _graph: &mut Graph) -> Result<Option<Instructions>, Error> {
+        Ok(self.compile())
+    }
+
+    fn compile(&self) -> Option<Instructions> {
+        let mut instructions = Instructions::default();
+        instructions.push(self.ins.clone());
+        instructions.push(Arc::new(FmtValIns(self.spec.clone())));
+        Some(instructions)
+    }
+
+    fn optimize(&self, optimizer: &mut Optimizer) -> Option<Arc<dyn Instruction>> {
+        let ins = optimizer.expr(&self.ins);
+        Some(Arc::new(Self { ins, spec: self.spec.clone() }))
+    }
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Format the value on the top of the stack into a string.
+pub struct FmtValIns(pub FormatSpec);
+#[typetag::serde(name = "FmtValIns")]
+impl Instruction for FmtValIns {
+    fn exec(&self, env: &mut ProcEnv, graph: &mut Graph) -> Result<Option<Instructions>, Error> {
+        if let Some(var) = env.stack.pop() {
+            let formatted = self.0.format(&var.val.read(), graph)?;
+            env.stack.push(Variable::val(Val::Str(formatted.into())));
+            return Ok(None);
+        }
+        Err(Error::StackError)
+    }
+}
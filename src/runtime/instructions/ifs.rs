@@ -17,9 +17,8 @@
 use std::sync::Arc;
 use arcstr::ArcStr;
 use imbl::Vector;
-use nanoid::nanoid;
 use serde::{Deserialize, Serialize};
-use crate::{model::Graph, runtime::{instruction::{Instruction, Instructions}, instructions::{Base, ConsumeStack}, proc::ProcEnv, Error}};
+use crate::{model::Graph, runtime::{instruction::{Instruction, Instructions}, instructions::{block::Block, unique_tag, Base, ConsumeStack}, optimizer::Optimizer, proc::ProcEnv, Error}};
 
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -32,20 +31,44 @@ pub struct IfIns {
 #[typetag::serde(name = "IfIns")]
 impl Instruction for IfIns {
     fn exec(&self, _env: &mut ProcEnv, _graph: &mut Graph) -> Result<Option<Instructions>, Error> {
+        Ok(self.compile())
+    }
+
+    fn compile(&self) -> Option<Instructions> {
         let mut instructions = Instructions::default();
         if let Some(test) = &self.if_test {
             instructions.push(test.clone());
         }
         
-        let if_tag: ArcStr = nanoid!(10).into();
-        let else_tag: ArcStr = nanoid!(10).into();
+        let if_tag: ArcStr = unique_tag();
+        let else_tag: ArcStr = unique_tag();
         instructions.push(Arc::new(Base::CtrlForwardToIfNotTruthy(else_tag.clone(), ConsumeStack::Consume)));
         instructions.append(&self.if_ins);
         instructions.push(Arc::new(Base::CtrlForwardTo(if_tag.clone())));
         instructions.push(Arc::new(Base::Tag(else_tag)));
         instructions.append(&self.el_ins);
         instructions.push(Arc::new(Base::Tag(if_tag)));
-        Ok(Some(instructions))
+        Some(instructions)
+    }
+
+    fn optimize(&self, optimizer: &mut Optimizer) -> Option<Arc<dyn Instruction>> {
+        let if_test = self.if_test.as_ref().map(|test| optimizer.expr(test));
+        if let Some(test) = &if_test {
+            if let Some(val) = Optimizer::constant(test) {
+                // Only the branch that will run is kept
+                let branch = if val.truthy() { &self.if_ins } else { &self.el_ins };
+                let ins = optimizer.optimize_all(branch);
+                if ins.len() == 1 {
+                    return ins.front().cloned();
+                }
+                return Some(Arc::new(Block { ins }));
+            }
+        }
+        Some(Arc::new(Self {
+            if_test,
+            if_ins: optimizer.optimize_all(&self.if_ins),
+            el_ins: optimizer.optimize_all(&self.el_ins),
+        }))
     }
 }
 
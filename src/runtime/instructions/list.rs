@@ -18,7 +18,7 @@ use std::{ops::{Deref, DerefMut}, sync::Arc};
 use imbl::{Vector, vector};
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
-use crate::{model::{Func, Graph}, parser::types::parse_type_complete, runtime::{Error, Num, NumT, Type, Val, Variable, instruction::{Instruction, Instructions}, instructions::{Base, call::FuncCall}, proc::ProcEnv}};
+use crate::{model::{val_size, Func, Graph}, parser::types::parse_type_complete, runtime::{Error, Num, NumT, Type, Val, Variable, instruction::{Instruction, Instructions}, instructions::{Base, call::FuncCall}, ordering::{custom_ordering, sort_values, sync_ordering}, proc::ProcEnv}};
 
 
 lazy_static! {
@@ -145,6 +145,10 @@ impl Instruction for ListIns {
                             Val::List(other) => {
                                 match var.val.write().deref_mut() {
                                     Val::List(list) => {
+                                        if graph.quotas_enabled() {
+                                            let (bytes, len) = val_size(&Val::List(other.clone()));
+                                            graph.quota_grow(bytes, len.max(list.len() + other.len()))?;
+                                        }
                                         list.append(other.clone());
                                         return Ok(None); // does not return anything
                                     },
@@ -167,6 +171,10 @@ impl Instruction for ListIns {
                 if let Some(var) = env.stack.pop() {
                     match var.val.write().deref_mut() {
                         Val::List(list) => {
+                            if graph.quotas_enabled() {
+                                let bytes = to_push.iter().map(|var| val_size(&var.val.read()).0).sum();
+                                graph.quota_grow(bytes, list.len() + to_push.len())?;
+                            }
                             for var in to_push.into_iter().rev() {
                                 list.push_back(var.val);
                             }
@@ -187,6 +195,10 @@ impl Instruction for ListIns {
                 if let Some(var) = env.stack.pop() {
                     match var.val.write().deref_mut() {
                         Val::List(list) => {
+                            if graph.quotas_enabled() {
+                                let bytes = to_push.iter().map(|var| val_size(&var.val.read()).0).sum();
+                                graph.quota_grow(bytes, list.len() + to_push.len())?;
+                            }
                             for var in to_push.into_iter().rev() {
                                 list.push_front(var.val);
                             }
@@ -613,6 +625,9 @@ impl Instruction for ListIns {
                                 Val::Num(index) => {
                                     match var.val.write().deref_mut() {
                                         Val::List(list) => {
+                                            if graph.quotas_enabled() {
+                                                graph.quota_grow(val_size(&insert_var.val.read()).0, list.len() + 1)?;
+                                            }
                                             let int = index.int();
                                             if int < 0 {
                                                 list.push_front(insert_var.val);
@@ -670,12 +685,16 @@ impl Instruction for ListIns {
             },
             Self::Sort => {
                 if let Some(var) = env.stack.pop() {
-                    match var.val.write().deref_mut() {
-                        Val::List(list) => {
-                            list.sort();
-                            return Ok(None);
-                        },
-                        _ => {}
+                    // sort a copy, so the list isn't locked while #[cmp] functions run
+                    let list = match var.val.read().deref() {
+                        Val::List(list) => Some(list.clone()),
+                        _ => None,
+                    };
+                    if let Some(mut list) = list {
+                        // Prototype objects with a #[cmp] function define their own ordering
+                        sort_values(&mut list, env, graph, custom_ordering)?;
+                        *var.val.write() = Val::List(list);
+                        return Ok(None);
                     }
                 }
                 Err(Error::ListSort)
@@ -684,66 +703,35 @@ impl Instruction for ListIns {
                 if let Some(cmp) = env.stack.pop() {
                     if let Some(cmp) = cmp.try_func() {
                         if let Some(var) = env.stack.pop() {
-                            match var.val.write().deref_mut() {
-                                Val::List(list) => {
-                                    // check func signature
-                                    if let Some(func) = graph.get_stof_data::<Func>(&cmp) {
-                                        if func.return_type != Type::Num(NumT::Int) || func.params.len() != 2 {
-                                            return Err(Error::ListSortBy);
-                                        }
+                            let list = match var.val.read().deref() {
+                                Val::List(list) => Some(list.clone()),
+                                _ => None,
+                            };
+                            if let Some(mut list) = list {
+                                // check func signature
+                                if let Some(func) = graph.get_stof_data::<Func>(&cmp) {
+                                    if func.return_type != Type::Num(NumT::Int) || func.params.len() != 2 {
+                                        return Err(Error::ListSortBy);
                                     }
-                                    
-                                    let mut cloned_env = env.clone();
-                                    cloned_env.yield_enabled = false;
-                                    let env_cell = std::cell::RefCell::new(cloned_env);
-                                    let graph_cell = std::cell::RefCell::new(graph);
-                                    list.sort_by(move |a, b| {
-                                        let ins: Arc<dyn Instruction> = Arc::new(FuncCall {
-                                            func: Some(cmp.clone()),
-                                            search: None,
-                                            stack: false,
-                                            as_ref: false,
-                                            cnull: false,
-                                            args: vector![
-                                                Arc::new(Base::Variable(Variable::refval(a.clone()))) as Arc<dyn Instruction>,
-                                                Arc::new(Base::Variable(Variable::refval(b.clone()))) as Arc<dyn Instruction>,
-                                            ],
-                                            oself: None,
-                                        });
-                                        let mut env = env_cell.borrow_mut();
-                                        let mut graph = graph_cell.borrow_mut();
-                                        match ins.exec(&mut env, *graph.deref_mut()) {
-                                            Ok(res) => {
-                                                if let Some(mut func_ins) = res {
-                                                    match func_ins.exec(&mut env, *graph.deref_mut(), -1) {
-                                                        Ok(_res) => {
-                                                            if let Some(ret) = (*env.deref_mut()).stack.pop() {
-                                                                match ret.val.read().deref() {
-                                                                    Val::Num(num) => {
-                                                                        let i = num.int();
-                                                                        if i > 0 { return std::cmp::Ordering::Greater; }
-                                                                        if i < 0 { return std::cmp::Ordering::Less; }
-                                                                        return std::cmp::Ordering::Equal;
-                                                                    },
-                                                                    _ => {}
-                                                                }
-                                                            }
-                                                        },
-                                                        Err(_err) => {
-                                                            // nothing we can do here...?
-                                                        }
-                                                    }
-                                                }
-                                            },
-                                            Err(_err) => {
-                                                // nothing we can do here...?
-                                            },
-                                        }
-                                        std::cmp::Ordering::Equal
+                                }
+
+                                sort_values(&mut list, env, graph, |a, b, env, graph| {
+                                    let ins: Arc<dyn Instruction> = Arc::new(FuncCall {
+                                        func: Some(cmp.clone()),
+                                        search: None,
+                                        stack: false,
+                                        as_ref: false,
+                                        cnull: false,
+                                        args: vector![
+                                            Arc::new(Base::Variable(Variable::refval(a.clone()))) as Arc<dyn Instruction>,
+                                            Arc::new(Base::Variable(Variable::refval(b.clone()))) as Arc<dyn Instruction>,
+                                        ],
+                                        oself: None,
                                     });
-                                    return Ok(None);
-                                },
-                                _ => {}
+                                    sync_ordering(ins, env, graph)
+                                })?;
+                                *var.val.write() = Val::List(list);
+                                return Ok(None);
                             }
                         }
                     }
@@ -809,3 +797,4 @@ impl Instruction for ListIns {
         }
     }
 }
+
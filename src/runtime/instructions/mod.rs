@@ -14,13 +14,14 @@
 // limitations under the License.
 //
 
-use std::{sync::Arc, time::Duration};
+use std::{cell::RefCell, sync::Arc, time::Duration};
 use arcstr::ArcStr;
 use imbl::{vector, Vector};
 use lazy_static::lazy_static;
+use nanoid::nanoid;
 use rustc_hash::{FxHashMap, FxHashSet};
 use serde::{Deserialize, Serialize};
-use crate::{model::{Field, Func, Graph, Prototype, SPath, SELF_STR_KEYWORD, SUPER_STR_KEYWORD}, runtime::{instruction::{Instruction, Instructions}, instructions::call::FuncCall, proc::{ProcEnv, Process}, Error, Type, Val, Variable, WakeRef}};
+use crate::{model::{DataRef, Field, Func, Graph, NodeRef, Prototype, SPath, CANCELLED_FUNC_ATTR, GET_FUNC_ATTR, ON_CHANGE_FIELD_ATTR, SELF_STR_KEYWORD, SET_FUNC_ATTR, SUPER_STR_KEYWORD}, runtime::{bytecode::Depth, instruction::{Instruction, Instructions}, instructions::{call::FuncCall, computed::compute_field, ops::{binary_op, overload_op, Op}}, proc::{ProcEnv, Process, SettleMode}, Error, Num, Priority, Type, Val, Variable, WakeRef}};
 
 pub mod call;
 pub mod block;
@@ -38,6 +39,8 @@ pub mod map;
 pub mod ret;
 pub mod func;
 pub mod nullcheck;
+pub mod computed;
+pub mod fmt;
 
 
 // static instructions for efficiency
@@ -47,6 +50,7 @@ lazy_static! {
     pub static ref AWAIT: Arc<dyn Instruction> = Arc::new(Base::CtrlAwait);
     pub static ref NOOP: Arc<dyn Instruction> = Arc::new(Base::CtrlNoOp);
     pub static ref EXIT: Arc<dyn Instruction> = Arc::new(Base::CtrlExit);
+    pub static ref CANCELLED: Arc<dyn Instruction> = Arc::new(Base::CtrlCancelled);
 
     pub static ref PUSH_SELF: Arc<dyn Instruction> = Arc::new(Base::PushSelf);
     pub static ref POP_SELF: Arc<dyn Instruction> = Arc::new(Base::PopSelf);
@@ -102,6 +106,46 @@ lazy_static! {
 }
 
 
+thread_local! {
+    static TAG_SCOPE: RefCell<Option<TagScope>> = const { RefCell::new(None) };
+}
+
+
+/// Tags created while compiling a program.
+/// The prefix is random (created with the first tag), so it is unique to the program.
+struct TagScope {
+    prefix: Option<ArcStr>,
+    next: u32,
+}
+
+
+/// Create a unique control flow tag.
+/// Tags are scoped to the program being compiled (a random program prefix & a counter), so programs compiled
+/// after a process is restored from a checkpoint never reuse the tags of the programs it is still running.
+pub fn unique_tag() -> ArcStr {
+    TAG_SCOPE.with(|scope| {
+        if let Some(scope) = scope.borrow_mut().as_mut() {
+            let prefix = scope.prefix.get_or_insert_with(|| nanoid!(8).into());
+            scope.next += 1;
+            return format!("@{prefix}.{}", scope.next).into();
+        }
+        format!("@{}", nanoid!(10)).into()
+    })
+}
+
+
+/// Run a program compile with its own tag scope (compiles within a compile share the outer scope).
+pub(crate) fn tag_scope<R>(compile: impl FnOnce() -> R) -> R {
+    if TAG_SCOPE.with(|scope| scope.borrow().is_some()) {
+        return compile();
+    }
+    TAG_SCOPE.with(|scope| *scope.borrow_mut() = Some(TagScope { prefix: None, next: 0 }));
+    let res = compile();
+    TAG_SCOPE.with(|scope| *scope.borrow_mut() = None);
+    res
+}
+
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ConsumeStack {
     Dont,
@@ -264,6 +308,21 @@ pub enum Base {
     PopSelfUntilDepth(usize),
     PopCallUntilDepth(usize),
     PopRetValidUntilDepth(usize),
+
+    // Set the priority of the process being spawned - added at the end for rev-compatability
+    SpawnPriority(Priority),
+
+    // Promise control (cancel, await with a deadline, and await a group) - added at the end for rev-compatability
+    CtrlCancel,
+    CtrlAwaitTimeout(Duration),
+    CtrlSettle(SettleMode),
+
+    // Loop back-jump & restoring stacks to the depths captured by the frame - added at the end for rev-compatability
+    CtrlLoopBack(ArcStr),
+    RestoreDepth(Depth, usize),
+
+    // Call the #[cancelled] functions of a cancelled process before it unwinds - added at the end for rev-compatability
+    CtrlCancelled,
 }
 #[typetag::serde(name = "Base")]
 impl Instruction for Base {
@@ -280,7 +339,37 @@ impl Instruction for Base {
             Self::CtrlExit => {}, // Nothing here...
             Self::CtrlAwaitCast(_) => {}, // Nothing here...
             Self::CtrlAwaitError(err) => { return Err(err.clone()); },
+            Self::CtrlCancel => {}, // Nothing here...
+            Self::CtrlAwaitTimeout(_) => {}, // Nothing here...
+            Self::CtrlSettle(_) => {}, // Nothing here...
+            Self::CtrlLoopBack(_) => {}, // Nothing here... used by instructions...
+            Self::RestoreDepth(..) => {}, // Nothing here... uses the frame
             Self::CtrlNoOp => {}, // Does nothing
+            Self::CtrlCancelled => {
+                // #[dropped]-style cleanup, called on the object this process was running on when it was cancelled
+                let self_ptr = env.self_ptr();
+                let mut objects = vec![self_ptr.clone()];
+                objects.append(&mut Prototype::prototype_nodes(graph, &self_ptr, true));
+
+                let mut attrs = FxHashSet::default();
+                attrs.insert(CANCELLED_FUNC_ATTR.to_string());
+                let attrs = Some(attrs);
+
+                let mut names = FxHashSet::default();
+                let mut instructions = Instructions::default();
+                for obj in objects {
+                    for func in Func::functions(graph, &obj, &attrs, false) {
+                        if let Some(name) = func.data_name(graph) {
+                            if names.insert(name) {
+                                instructions.append(&field_func_call(func, self_ptr.clone(), None, Some(env.stack.len())).instructions);
+                            }
+                        }
+                    }
+                }
+                if !names.is_empty() {
+                    return Ok(Some(instructions));
+                }
+            },
 
             /*****************************************************************************
              * Sleep.
@@ -351,16 +440,21 @@ impl Instruction for Base {
                 if let Some(var) = env.stack.pop() {
                     if let Some(func) = var.try_func() {
                         env.call_stack.push(func);
+                        env.call_frames.push((env.table.scopes.len(), env.loop_stack.len()));
                         return Ok(None);
                     }
                 }
                 return Err(Error::CallStackError);
             },
-            Self::PopCall => { env.call_stack.pop(); },
+            Self::PopCall => {
+                env.call_stack.pop();
+                env.call_frames.pop();
+            },
             Self::PopCallUntilDepth(depth) => {
                 while env.call_stack.len() > *depth {
                     env.call_stack.pop();
                 }
+                env.call_frames.truncate(*depth);
             },
             
             Self::PushNew => {
@@ -421,7 +515,7 @@ impl Instruction for Base {
             
             Self::Spawn((async_ins, ty)) => {
                 // Creates a new PID every time here, avoiding a lot of issues...
-                let mut proc = Process::from(async_ins.clone());
+                let mut proc = Process::from(Instructions::program(async_ins.compiled()));
                 let pid = proc.env.pid.clone();
 
                 proc.env = env.clone(); // clone this environment
@@ -430,8 +524,16 @@ impl Instruction for Base {
                 proc.env.return_stack.clear();
                 proc.env.ret_valid_stack.clear();
                 proc.env.try_stack.clear();
+                proc.env.error_stack.clear();
+                proc.env.error_objs.clear();
+                proc.env.finally_stack.clear();
+                proc.env.finally_running.clear();
+                proc.env.computed_reads.clear();
+                proc.env.fuel = None; // each process gets its own budget
+                proc.env.usage = Default::default();
                 proc.env.spawn = None;
                 proc.env.pid = pid.clone();
+                proc.env.parent = Some(env.pid.clone());
 
                 let mut promise_type = ty.clone();
                 loop {
@@ -449,6 +551,11 @@ impl Instruction for Base {
                 env.stack.push(Variable::val(Val::Promise(pid, promise_type)));
                 // up to the caller to add the suspend to actually spawn (don't want this ins replaced)
             },
+            Self::SpawnPriority(priority) => {
+                if let Some(proc) = &mut env.spawn {
+                    proc.env.priority = *priority;
+                }
+            },
             
             /*****************************************************************************
              * Variables.
@@ -469,7 +576,7 @@ impl Instruction for Base {
                     if !vtype.empty() {
                         var.vtype = Some(vtype.clone());
                     }
-                    env.table.insert(name, var);
+                    env.table.insert(name.clone(), var);
                 } else {
                     return Err(Error::StackError);
                 }
@@ -482,7 +589,7 @@ impl Instruction for Base {
                     if !vtype.empty() {
                         var.vtype = Some(vtype.clone());
                     }
-                    env.table.insert(name, var);
+                    env.table.insert(name.clone(), var);
                 } else {
                     return Err(Error::StackError);
                 }
@@ -532,32 +639,21 @@ impl Instruction for Base {
                 if *stack {
                     if let Some(var) = env.stack.pop() {
                         if let Some(obj) = var.try_obj() {
-                            if let Some(field_ref) = Field::field_from_path(graph, &name, Some(obj.clone())) {
-                                if let Some(field) = graph.get_stof_data::<Field>(&field_ref) {
-                                    if field.is_private() {
-                                        let self_ptr = env.self_ptr();
-                                        let field_nodes = field_ref.data_nodes(&graph);
-                                        if !field_nodes.contains(&self_ptr) {
-                                            env.stack.push(Variable::val(Val::Null));
-                                            return Ok(None);
-                                        }
-                                    }
-                                    env.stack.push(field.value.stack_var(*by_ref));
-                                    return Ok(None);
-                                }
-                            } else if let Some(node) = SPath::node(&graph, &name, Some(obj.clone())) {
-                                env.stack.push(Variable::val(Val::Obj(node)));
-                                return Ok(None);
-                            } else if let Some(func) = Func::func_from_path(graph, &name, Some(obj)) {
-                                env.stack.push(Variable::val(Val::Fn(func)));
-                                return Ok(None);
-                            }
+                            return load_path(obj, name, *by_ref, env, graph);
                         }
                     }
                     env.stack.push(Variable::val(Val::Null));
                     return Ok(None);
                 }
 
+                // Local variable fast path (most common load)
+                if !name.contains('.') {
+                    if let Some(var) = env.table.get(name) {
+                        env.stack.push(var.stack_var(*by_ref));
+                        return Ok(None);
+                    }
+                }
+
                 let mut split_path = name.split('.').collect::<Vec<_>>();
                 let context;
                 if split_path[0] == SELF_STR_KEYWORD.as_str() {
@@ -607,18 +703,9 @@ impl Instruction for Base {
                 } else {
                     // Global case
                     if let Some(field_ref) = Field::field_from_path(graph, &name, None) {
-                        if let Some(field) = graph.get_stof_data::<Field>(&field_ref) {
-                            if field.is_private() {
-                                let self_ptr = env.self_ptr();
-                                let field_nodes = field_ref.data_nodes(&graph);
-                                if !field_nodes.contains(&self_ptr) {
-                                    env.stack.push(Variable::val(Val::Null));
-                                    return Ok(None);
-                                }
-                            }
-                            env.stack.push(field.value.stack_var(*by_ref));
-                            return Ok(None);
-                        }
+                        return load_field(&field_ref, *by_ref, env, graph);
+                    } else if let Some((obj, getter)) = Field::accessor(graph, &name, None, GET_FUNC_ATTR.as_str()) {
+                        return Ok(Some(field_func_call(getter, obj, None, None)));
                     } else if let Some(node) = SPath::node(&graph, &name, None) {
                         env.stack.push(Variable::val(Val::Obj(node)));
                         return Ok(None);
@@ -639,26 +726,7 @@ impl Instruction for Base {
                 // Else, the context needs to be an object to continue the lookup!
                 let name = split_path.join(".");
                 if let Some(obj) = context.try_obj() {
-                    if let Some(field_ref) = Field::field_from_path(graph, &name, Some(obj.clone())) {
-                        if let Some(field) = graph.get_stof_data::<Field>(&field_ref) {
-                            if field.is_private() {
-                                let self_ptr = env.self_ptr();
-                                let field_nodes = field_ref.data_nodes(&graph);
-                                if !field_nodes.contains(&self_ptr) {
-                                    env.stack.push(Variable::val(Val::Null));
-                                    return Ok(None);
-                                }
-                            }
-                            env.stack.push(field.value.stack_var(*by_ref));
-                            return Ok(None);
-                        }
-                    } else if let Some(node) = SPath::node(&graph, &name, Some(obj.clone())) {
-                        env.stack.push(Variable::val(Val::Obj(node)));
-                        return Ok(None);
-                    } else if let Some(func) = Func::func_from_path(graph, &name, Some(obj)) {
-                        env.stack.push(Variable::val(Val::Fn(func)));
-                        return Ok(None);
-                    }
+                    return load_path(obj, &name, *by_ref, env, graph);
                 }
                 env.stack.push(Variable::val(Val::Null));
                 return Ok(None);
@@ -742,6 +810,7 @@ impl Instruction for Base {
                                 fvar = Some(field.value.clone());
                             }
                             if let Some(mut fvar) = fvar {
+                                graph.quota_set(&field_ref, &var.val.read())?;
                                 fvar.set(&var, graph, Some(env.self_ptr()))?;
 
                                 if let Some(field) = graph.get_mut_stof_data::<Field>(&field_ref) {
@@ -751,7 +820,12 @@ impl Instruction for Base {
                             if let Some(field) = field_ref.data_mut(graph) {
                                 field.invalidate_value();
                             }
-                            return Ok(None);
+                            graph.invalidate_dependents(&field_ref);
+                            return Ok(field_changed(&field_ref, env, graph));
+                        } else if let Some((obj, setter)) = Field::accessor(graph, &name, Some(self_ptr.clone()), SET_FUNC_ATTR.as_str()) {
+                            return Ok(Some(field_func_call(setter, obj, Some(var), Some(env.stack.len()))));
+                        } else if Field::accessor(graph, &name, Some(self_ptr.clone()), GET_FUNC_ATTR.as_str()).is_some() {
+                            return Err(Error::FieldReadOnlySet); // getter without a setter
                         } else {
                             let mut path = SPath::from(name);
                             let field_name = path.path.pop().unwrap();
@@ -812,6 +886,7 @@ impl Instruction for Base {
                                 fvar = Some(field.value.clone());
                             }
                             if let Some(mut fvar) = fvar {
+                                graph.quota_set(&field_ref, &var.val.read())?;
                                 fvar.set(&var, graph, context.clone())?;
                                 
                                 if let Some(field) = graph.get_mut_stof_data::<Field>(&field_ref) {
@@ -821,7 +896,13 @@ impl Instruction for Base {
                             if let Some(field) = field_ref.data_mut(graph) {
                                 field.invalidate_value();
                             }
-                            return Ok(None);
+                            graph.invalidate_dependents(&field_ref);
+                            return Ok(field_changed(&field_ref, env, graph));
+                        }
+                        if let Some((obj, setter)) = Field::accessor(graph, &path.join("."), context.clone(), SET_FUNC_ATTR.as_str()) {
+                            return Ok(Some(field_func_call(setter, obj, Some(var), Some(env.stack.len()))));
+                        } else if Field::accessor(graph, &path.join("."), context.clone(), GET_FUNC_ATTR.as_str()).is_some() {
+                            return Err(Error::FieldReadOnlySet); // getter without a setter
                         }
 
                         let field_name = path.path.pop().unwrap();
@@ -967,158 +1048,46 @@ impl Instruction for Base {
                 }
             },
             Self::GreaterThan => {
-                let lhs = env.stack.pop();
-                let rhs = env.stack.pop();
-                if let Some(lhs) = lhs {
-                    if let Some(rhs) = rhs {
-                        env.stack.push(lhs.gt(&rhs, graph)?);
-                    } else {
-                        return Err(Error::GreaterThan);
-                    }
-                } else {
-                    return Err(Error::GreaterThan);
-                }
+                return binary_op(&Op::Greater, env, graph);
             },
             Self::GreaterOrEq => {
-                let lhs = env.stack.pop();
-                let rhs = env.stack.pop();
-                if let Some(lhs) = lhs {
-                    if let Some(rhs) = rhs {
-                        env.stack.push(lhs.gte(&rhs, graph)?);
-                    } else {
-                        return Err(Error::GreaterOrEq);
-                    }
-                } else {
-                    return Err(Error::GreaterOrEq);
-                }
+                return binary_op(&Op::GreaterOrEq, env, graph);
             },
             Self::LessThan => {
-                let lhs = env.stack.pop();
-                let rhs = env.stack.pop();
-                if let Some(lhs) = lhs {
-                    if let Some(rhs) = rhs {
-                        env.stack.push(lhs.lt(&rhs, graph)?);
-                    } else {
-                        return Err(Error::LessThan);
-                    }
-                } else {
-                    return Err(Error::LessThan);
-                }
+                return binary_op(&Op::Less, env, graph);
             },
             Self::LessOrEq => {
-                let lhs = env.stack.pop();
-                let rhs = env.stack.pop();
-                if let Some(lhs) = lhs {
-                    if let Some(rhs) = rhs {
-                        env.stack.push(lhs.lte(&rhs, graph)?);
-                    } else {
-                        return Err(Error::LessOrEq);
-                    }
-                } else {
-                    return Err(Error::LessOrEq);
-                }
+                return binary_op(&Op::LessOrEq, env, graph);
             },
             Self::Eq => {
-                let lhs = env.stack.pop();
-                let rhs = env.stack.pop();
-                if let Some(lhs) = lhs {
-                    if let Some(rhs) = rhs {
-                        env.stack.push(lhs.equal(&rhs)?);
-                    } else {
-                        return Err(Error::Eq);
-                    }
-                } else {
-                    return Err(Error::Eq);
-                }
+                return binary_op(&Op::Eq, env, graph);
             },
             Self::Neq => {
-                let lhs = env.stack.pop();
-                let rhs = env.stack.pop();
-                if let Some(lhs) = lhs {
-                    if let Some(rhs) = rhs {
-                        env.stack.push(lhs.not_equal(&rhs)?);
-                    } else {
-                        return Err(Error::Eq);
-                    }
-                } else {
-                    return Err(Error::Eq);
-                }
+                return binary_op(&Op::Neq, env, graph);
             },
             Self::Add => {
-                let lhs = env.stack.pop();
-                let rhs = env.stack.pop();
-                if let Some(lhs) = lhs {
-                    if let Some(rhs) = rhs {
-                        lhs.add(rhs, graph)?;
-                        env.stack.push(lhs);
-                    } else {
-                        return Err(Error::Add);
-                    }
-                } else {
-                    return Err(Error::Add);
-                }
+                return binary_op(&Op::Add, env, graph);
             },
             Self::Sub => {
-                let lhs = env.stack.pop();
-                let rhs = env.stack.pop();
-                if let Some(lhs) = lhs {
-                    if let Some(rhs) = rhs {
-                        lhs.sub(rhs, graph)?;
-                        env.stack.push(lhs);
-                    } else {
-                        return Err(Error::Sub);
-                    }
-                } else {
-                    return Err(Error::Sub);
-                }
+                return binary_op(&Op::Sub, env, graph);
             },
             Self::Mul => {
-                let lhs = env.stack.pop();
-                let rhs = env.stack.pop();
-                if let Some(lhs) = lhs {
-                    if let Some(rhs) = rhs {
-                        lhs.mul(rhs, graph)?;
-                        env.stack.push(lhs);
-                    } else {
-                        return Err(Error::Mul);
-                    }
-                } else {
-                    return Err(Error::Mul);
-                }
+                return binary_op(&Op::Mul, env, graph);
             },
             Self::Div => {
-                let lhs = env.stack.pop();
-                let rhs = env.stack.pop();
-                if let Some(lhs) = lhs {
-                    if let Some(rhs) = rhs {
-                        lhs.div(rhs, graph)?;
-                        env.stack.push(lhs);
-                    } else {
-                        return Err(Error::Div);
-                    }
-                } else {
-                    return Err(Error::Div);
-                }
+                return binary_op(&Op::Div, env, graph);
             },
             Self::Mod => {
-                let lhs = env.stack.pop();
-                let rhs = env.stack.pop();
-                if let Some(lhs) = lhs {
-                    if let Some(rhs) = rhs {
-                        lhs.rem(rhs, graph)?;
-                        env.stack.push(lhs);
-                    } else {
-                        return Err(Error::Mod);
-                    }
-                } else {
-                    return Err(Error::Mod);
-                }
+                return binary_op(&Op::Mod, env, graph);
             },
             Self::AND => {
                 let lhs = env.stack.pop();
                 let rhs = env.stack.pop();
                 if let Some(lhs) = lhs {
                     if let Some(rhs) = rhs {
+                        if let Some(overload) = overload_op(&Op::BAND, &lhs, &rhs, graph) {
+                            return Ok(Some(overload));
+                        }
                         lhs.bit_and(rhs)?;
                         env.stack.push(lhs);
                     } else {
@@ -1133,6 +1102,9 @@ impl Instruction for Base {
                 let rhs = env.stack.pop();
                 if let Some(lhs) = lhs {
                     if let Some(rhs) = rhs {
+                        if let Some(overload) = overload_op(&Op::BOR, &lhs, &rhs, graph) {
+                            return Ok(Some(overload));
+                        }
                         lhs.bit_or(rhs)?;
                         env.stack.push(lhs);
                     } else {
@@ -1147,6 +1119,9 @@ impl Instruction for Base {
                 let rhs = env.stack.pop();
                 if let Some(lhs) = lhs {
                     if let Some(rhs) = rhs {
+                        if let Some(overload) = overload_op(&Op::BXOR, &lhs, &rhs, graph) {
+                            return Ok(Some(overload));
+                        }
                         lhs.bit_xor(rhs)?;
                         env.stack.push(lhs);
                     } else {
@@ -1161,7 +1136,13 @@ impl Instruction for Base {
                 let rhs = env.stack.pop();
                 if let Some(lhs) = lhs {
                     if let Some(rhs) = rhs {
+                        if let Some(overload) = overload_op(&Op::BSHL, &lhs, &rhs, graph) {
+                            return Ok(Some(overload));
+                        }
                         lhs.bit_shl(rhs)?;
+                        if let Val::Num(Num::BigInt(big)) = &*lhs.val.read() {
+                            graph.quota_grow(big.bits().div_ceil(8) as usize, 0)?;
+                        }
                         env.stack.push(lhs);
                     } else {
                         return Err(Error::SHL);
@@ -1175,6 +1156,9 @@ impl Instruction for Base {
                 let rhs = env.stack.pop();
                 if let Some(lhs) = lhs {
                     if let Some(rhs) = rhs {
+                        if let Some(overload) = overload_op(&Op::BSHR, &lhs, &rhs, graph) {
+                            return Ok(Some(overload));
+                        }
                         lhs.bit_shr(rhs)?;
                         env.stack.push(lhs);
                     } else {
@@ -1188,3 +1172,85 @@ impl Instruction for Base {
         Ok(None)
     }
 }
+
+
+/// Call a function on an object for a field (accessors & change hooks).
+/// If a stack count is given, the stack is restored to it after the call (setters & hooks don't produce a value).
+fn field_func_call(func: DataRef, obj: NodeRef, arg: Option<Variable>, stack_count: Option<usize>) -> Instructions {
+    let mut args = vector![];
+    if let Some(arg) = arg {
+        args.push_back(Arc::new(Base::Variable(arg)) as Arc<dyn Instruction>);
+    }
+    let mut instructions = Instructions::default();
+    instructions.push(Arc::new(FuncCall {
+        func: Some(func),
+        search: None,
+        stack: false,
+        as_ref: false,
+        cnull: false,
+        args,
+        oself: Some(Arc::new(Base::Literal(Val::Obj(obj)))),
+    }));
+    if let Some(count) = stack_count {
+        instructions.push(Arc::new(Base::PopUntilStackCount(count)));
+    }
+    instructions
+}
+
+
+/// Load what a path from an object refers to onto the stack (null if nothing).
+/// In order: a field, a field getter, a child object, or a function.
+pub(crate) fn load_path(obj: NodeRef, path: &str, by_ref: bool, env: &mut ProcEnv, graph: &mut Graph) -> Result<Option<Instructions>, Error> {
+    if let Some(field_ref) = Field::field_from_path(graph, path, Some(obj.clone())) {
+        return load_field(&field_ref, by_ref, env, graph);
+    } else if let Some((obj, getter)) = Field::accessor(graph, path, Some(obj.clone()), GET_FUNC_ATTR.as_str()) {
+        return Ok(Some(field_func_call(getter, obj, None, None)));
+    } else if let Some(node) = SPath::node(graph, path, Some(obj.clone())) {
+        env.stack.push(Variable::val(Val::Obj(node)));
+        return Ok(None);
+    } else if let Some(func) = Func::func_from_path(graph, path, Some(obj)) {
+        env.stack.push(Variable::val(Val::Fn(func)));
+        return Ok(None);
+    }
+    env.stack.push(Variable::val(Val::Null));
+    Ok(None)
+}
+
+
+/// Load a field onto the stack (recording the read for computed fields).
+/// Computed fields that are out of date get recomputed first.
+fn load_field(field_ref: &DataRef, by_ref: bool, env: &mut ProcEnv, graph: &Graph) -> Result<Option<Instructions>, Error> {
+    if let Some(field) = graph.get_stof_data::<Field>(field_ref) {
+        if field.is_private() {
+            let self_ptr = env.self_ptr();
+            let field_nodes = field_ref.data_nodes(&graph);
+            if !field_nodes.contains(&self_ptr) {
+                env.stack.push(Variable::val(Val::Null));
+                return Ok(None);
+            }
+        }
+        if let Some((_, _, reads)) = env.computed_reads.last_mut() {
+            reads.insert(field_ref.clone());
+        }
+        if let Some(instructions) = compute_field(field_ref, env, graph)? {
+            return Ok(Some(instructions));
+        }
+        env.stack.push(field.value.stack_var(by_ref));
+        return Ok(None);
+    }
+    env.stack.push(Variable::val(Val::Null));
+    Ok(None)
+}
+
+
+/// A field was set, so call its on change function (if any).
+fn field_changed(field_ref: &DataRef, env: &ProcEnv, graph: &Graph) -> Option<Instructions> {
+    if let Some(field) = graph.get_stof_data::<Field>(field_ref) {
+        for node in field_ref.data_nodes(graph) {
+            if let Some(func) = field.attribute_func(graph, &node, ON_CHANGE_FIELD_ATTR.as_str()) {
+                return Some(field_func_call(func, node, None, Some(env.stack.len())));
+            }
+        }
+    }
+    None
+}
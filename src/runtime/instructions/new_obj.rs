@@ -34,7 +34,7 @@ impl Instruction for NewObjIns {
             // Special syntax for creating a root object instead of a sub-object
             // Name will be re-assigned when using SetVariable Ex. MyRoot = new root {};
             let name = SId::default();
-            let nref = graph.insert_root(name);
+            let nref = graph.try_insert_root(name)?;
             env.stack.push(Variable::val(Val::Obj(nref)));
             return Ok(None);
         }
@@ -53,7 +53,7 @@ impl Instruction for NewObjIns {
 
         let id = SId::default();
         let name = id.clone();
-        let nref = graph.insert_node_id(name, id, parent, false);
+        let nref = graph.try_insert_node_id(name, id, parent, false)?;
         env.stack.push(Variable::val(Val::Obj(nref)));
         Ok(None)
     }
@@ -16,9 +16,8 @@
 
 use std::sync::Arc;
 use arcstr::ArcStr;
-use nanoid::nanoid;
 use serde::{Deserialize, Serialize};
-use crate::{model::Graph, runtime::{instruction::{Instruction, Instructions}, instructions::{Base, ConsumeStack, DUPLICATE, IS_NULL, POP_STACK}, proc::ProcEnv, Error}};
+use crate::{model::Graph, runtime::{instruction::{Instruction, Instructions}, instructions::{unique_tag, Base, ConsumeStack, DUPLICATE, IS_NULL, POP_STACK}, optimizer::Optimizer, proc::ProcEnv, Error}};
 
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,12 +29,16 @@ pub struct NullcheckIns {
 #[typetag::serde(name = "NullcheckIns")]
 impl Instruction for NullcheckIns {
     fn exec(&self, _env: &mut ProcEnv, _graph: &mut Graph) -> Result<Option<Instructions>, Error> {
+        Ok(self.compile())
+    }
+
+    fn compile(&self) -> Option<Instructions> {
         let mut instructions = Instructions::default();
 
         instructions.push(self.ins.clone());
 
         // Duplicate the value, check if its null, and go to end if not
-        let end_tag: ArcStr = nanoid!(10).into();
+        let end_tag: ArcStr = unique_tag();
         instructions.push(DUPLICATE.clone());
         instructions.push(IS_NULL.clone());
         instructions.push(Arc::new(Base::CtrlForwardToIfNotTruthy(end_tag.clone(), ConsumeStack::Consume)));
@@ -46,6 +49,18 @@ impl Instruction for NullcheckIns {
         
         instructions.push(Arc::new(Base::Tag(end_tag)));
 
-        Ok(Some(instructions))
+        Some(instructions)
+    }
+
+    fn optimize(&self, optimizer: &mut Optimizer) -> Option<Arc<dyn Instruction>> {
+        let ins = optimizer.expr(&self.ins);
+        let ifnull = optimizer.expr(&self.ifnull);
+        if let Some(val) = Optimizer::constant(&ins) {
+            if val.null() {
+                return Some(ifnull);
+            }
+            return Some(ins);
+        }
+        Some(Arc::new(Self { ins, ifnull }))
     }
 }
@@ -16,9 +16,9 @@
 
 use std::sync::Arc;
 use arcstr::ArcStr;
-use nanoid::nanoid;
+use imbl::vector;
 use serde::{Deserialize, Serialize};
-use crate::{model::Graph, runtime::{instruction::{Instruction, Instructions}, instructions::{Base, ConsumeStack, ADD, BIT_AND, BIT_OR, BIT_SHIFT_LEFT, BIT_SHIFT_RIGHT, BIT_XOR, DIVIDE, EQUAL, GREATER_THAN, GREATER_THAN_OR_EQ, LESS_THAN, LESS_THAN_OR_EQ, MODULUS, MULTIPLY, NOT_EQUAL, SUBTRACT, TRUTHY}, proc::ProcEnv, Error}};
+use crate::{model::{CMP_FUNC_ATTR, DataRef, EQ_FUNC_ATTR, Graph, NodeRef, OP_FUNC_ATTR, Prototype}, runtime::{instruction::{Instruction, Instructions}, optimizer::Optimizer, instructions::{call::FuncCall, unique_tag, Base, ConsumeStack, ADD, BIT_AND, BIT_OR, BIT_SHIFT_LEFT, BIT_SHIFT_RIGHT, BIT_XOR, DIVIDE, EQUAL, GREATER_THAN, GREATER_THAN_OR_EQ, LESS_THAN, LESS_THAN_OR_EQ, MODULUS, MULTIPLY, NOT_EQUAL, NOT_TRUTHY, SUBTRACT, TRUTHY}, proc::ProcEnv, Error, Num, Val, Variable}};
 
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -46,6 +46,223 @@ pub enum Op {
     BSHL,
     BSHR,
 }
+impl Op {
+    /// Operator symbol, as used by #[op('..')] overloads.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Self::And => "&&",
+            Self::Or => "||",
+            Self::Add => "+",
+            Self::Sub => "-",
+            Self::Mul => "*",
+            Self::Div => "/",
+            Self::Mod => "%",
+            Self::Greater => ">",
+            Self::Less => "<",
+            Self::GreaterOrEq => ">=",
+            Self::LessOrEq => "<=",
+            Self::Eq => "==",
+            Self::Neq => "!=",
+            Self::BAND => "&",
+            Self::BOR => "|",
+            Self::BXOR => "^",
+            Self::BSHL => "<<",
+            Self::BSHR => ">>",
+        }
+    }
+
+    /// Error for this op when its operands are missing.
+    fn error(&self) -> Error {
+        match self {
+            Self::And |
+            Self::Or => Error::Truthy,
+            Self::Add => Error::Add,
+            Self::Sub => Error::Sub,
+            Self::Mul => Error::Mul,
+            Self::Div => Error::Div,
+            Self::Mod => Error::Mod,
+            Self::Greater => Error::GreaterThan,
+            Self::Less => Error::LessThan,
+            Self::GreaterOrEq => Error::GreaterOrEq,
+            Self::LessOrEq => Error::LessOrEq,
+            Self::Eq |
+            Self::Neq => Error::Eq,
+            Self::BAND => Error::AND,
+            Self::BOR => Error::OR,
+            Self::BXOR => Error::XOR,
+            Self::BSHL => Error::SHL,
+            Self::BSHR => Error::SHR,
+        }
+    }
+
+    /// Comparison instruction for this op, if it is an ordering op.
+    fn ordering_ins(&self) -> Option<Arc<dyn Instruction>> {
+        match self {
+            Self::Greater => Some(GREATER_THAN.clone()),
+            Self::Less => Some(LESS_THAN.clone()),
+            Self::GreaterOrEq => Some(GREATER_THAN_OR_EQ.clone()),
+            Self::LessOrEq => Some(LESS_THAN_OR_EQ.clone()),
+            _ => None,
+        }
+    }
+
+    /// Op with the operands swapped (a < b == b > a).
+    fn swapped(&self) -> Self {
+        match self {
+            Self::Greater => Self::Less,
+            Self::Less => Self::Greater,
+            Self::GreaterOrEq => Self::LessOrEq,
+            Self::LessOrEq => Self::GreaterOrEq,
+            op => op.clone(),
+        }
+    }
+}
+
+
+/// Operator overloading for prototype objects.
+/// If an operand is an object whose prototype defines an #[op('..')], #[eq], or #[cmp] function,
+/// returns the instructions that replace the base operation. Otherwise, the base operation is used.
+///
+/// Arithmetic and bitwise ops dispatch on the lhs only. Equality and ordering will also try the rhs.
+pub(crate) fn overload_op(op: &Op, lhs: &Variable, rhs: &Variable, graph: &Graph) -> Option<Instructions> {
+    let lhs_obj = lhs.try_obj();
+    let rhs_obj = rhs.try_obj();
+    if lhs_obj.is_none() && rhs_obj.is_none() { return None; }
+    let symbol = Val::Str(op.symbol().into());
+
+    if let Some(obj) = &lhs_obj {
+        if let Some(func) = Prototype::attribute_func(graph, obj, &OP_FUNC_ATTR, Some(&symbol)) {
+            return Some(overload_call(func, obj, rhs).into());
+        }
+    }
+    match op {
+        Op::Eq | Op::Neq => {
+            let mut call = None;
+            if let Some(obj) = &lhs_obj {
+                if let Some(func) = overload_eq_func(graph, obj) {
+                    call = Some(overload_call(func, obj, rhs));
+                }
+            }
+            if call.is_none() {
+                if let Some(obj) = &rhs_obj {
+                    if let Some(func) = overload_eq_func(graph, obj) {
+                        call = Some(overload_call(func, obj, lhs));
+                    }
+                }
+            }
+            if let Some(call) = call {
+                let mut instructions = Instructions::from(call);
+                if let Op::Neq = op {
+                    instructions.push(NOT_TRUTHY.clone());
+                } else {
+                    instructions.push(TRUTHY.clone());
+                }
+                return Some(instructions);
+            }
+        },
+        Op::Greater | Op::Less | Op::GreaterOrEq | Op::LessOrEq => {
+            if let Some(obj) = &lhs_obj {
+                if let Some(func) = Prototype::attribute_func(graph, obj, &CMP_FUNC_ATTR, None) {
+                    return Some(overload_cmp(op, func, obj, rhs));
+                }
+            }
+            if let Some(obj) = &rhs_obj {
+                let swapped = op.swapped();
+                let symbol = Val::Str(swapped.symbol().into());
+                if let Some(func) = Prototype::attribute_func(graph, obj, &OP_FUNC_ATTR, Some(&symbol)) {
+                    return Some(overload_call(func, obj, lhs).into());
+                }
+                if let Some(func) = Prototype::attribute_func(graph, obj, &CMP_FUNC_ATTR, None) {
+                    return Some(overload_cmp(&swapped, func, obj, lhs));
+                }
+            }
+        },
+        _ => {}
+    }
+    None
+}
+
+/// Arithmetic or comparison on the top two values of the stack (lhs on top), calling an operator overload if one applies.
+/// Shared by the base instructions and the opcodes programs lower them to.
+pub(crate) fn binary_op(op: &Op, env: &mut ProcEnv, graph: &mut Graph) -> Result<Option<Instructions>, Error> {
+    let lhs = env.stack.pop();
+    let rhs = env.stack.pop();
+    if let Some(lhs) = lhs {
+        if let Some(rhs) = rhs {
+            if let Some(overload) = overload_op(op, &lhs, &rhs, graph) {
+                return Ok(Some(overload));
+            }
+            match op {
+                Op::Add => lhs.add(rhs, graph)?,
+                Op::Sub => lhs.sub(rhs, graph)?,
+                Op::Mul => lhs.mul(rhs, graph)?,
+                Op::Div => lhs.div(rhs, graph)?,
+                Op::Mod => lhs.rem(rhs, graph)?,
+                Op::Greater => {
+                    env.stack.push(lhs.gt(&rhs, graph)?);
+                    return Ok(None);
+                },
+                Op::Less => {
+                    env.stack.push(lhs.lt(&rhs, graph)?);
+                    return Ok(None);
+                },
+                Op::GreaterOrEq => {
+                    env.stack.push(lhs.gte(&rhs, graph)?);
+                    return Ok(None);
+                },
+                Op::LessOrEq => {
+                    env.stack.push(lhs.lte(&rhs, graph)?);
+                    return Ok(None);
+                },
+                Op::Eq => {
+                    env.stack.push(lhs.equal(&rhs)?);
+                    return Ok(None);
+                },
+                Op::Neq => {
+                    env.stack.push(lhs.not_equal(&rhs)?);
+                    return Ok(None);
+                },
+                _ => return Err(op.error()), // logical & bitwise ops have their own instructions
+            }
+            env.stack.push(lhs);
+            return Ok(None);
+        }
+    }
+    Err(op.error())
+}
+
+
+/// Equality function for an object (#[eq] or #[op('==')]).
+fn overload_eq_func(graph: &Graph, obj: &NodeRef) -> Option<DataRef> {
+    if let Some(func) = Prototype::attribute_func(graph, obj, &EQ_FUNC_ATTR, None) {
+        return Some(func);
+    }
+    Prototype::attribute_func(graph, obj, &OP_FUNC_ATTR, Some(&Val::Str("==".into())))
+}
+
+/// Call an overload function with obj as self and other as the only argument.
+fn overload_call(func: DataRef, obj: &NodeRef, other: &Variable) -> Arc<dyn Instruction> {
+    Arc::new(FuncCall {
+        func: Some(func),
+        search: None,
+        stack: false,
+        as_ref: false,
+        cnull: false,
+        args: vector![Arc::new(Base::Variable(other.clone())) as Arc<dyn Instruction>],
+        oself: Some(Arc::new(Base::Literal(Val::Obj(obj.clone())))),
+    })
+}
+
+/// Compare the result of a #[cmp] function with zero using the given ordering op.
+fn overload_cmp(op: &Op, func: DataRef, obj: &NodeRef, other: &Variable) -> Instructions {
+    let mut instructions = Instructions::default();
+    instructions.push(Arc::new(Base::Literal(Val::Num(Num::Int(0))))); // rhs of the comparison
+    instructions.push(overload_call(func, obj, other));
+    if let Some(ins) = op.ordering_ins() {
+        instructions.push(ins);
+    }
+    instructions
+}
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 /// Value operations.
@@ -57,6 +274,10 @@ pub struct OpIns {
 #[typetag::serde(name = "OpIns")]
 impl Instruction for OpIns {
     fn exec(&self, _env: &mut ProcEnv, _graph: &mut Graph) -> Result<Option<Instructions>, Error> {
+        Ok(self.compile())
+    }
+
+    fn compile(&self) -> Option<Instructions> {
         let mut instructions = Instructions::default();
         instructions.push(self.rhs.clone());
         instructions.push(self.lhs.clone()); // lhs first, so reversed
@@ -84,7 +305,7 @@ impl Instruction for OpIns {
                 instructions.pop();
                 instructions.push(self.lhs.clone());
 
-                let end_tag: ArcStr = nanoid!(11).into();
+                let end_tag: ArcStr = unique_tag();
                 instructions.push(TRUTHY.clone()); // put truthy onto stack
                 instructions.push(Arc::new(Base::CtrlForwardToIfNotTruthy(end_tag.clone(), ConsumeStack::IfTrue)));
                 instructions.push(self.rhs.clone());
@@ -96,7 +317,7 @@ impl Instruction for OpIns {
                 instructions.pop();
                 instructions.push(self.lhs.clone());
 
-                let end_tag: ArcStr = nanoid!(11).into();
+                let end_tag: ArcStr = unique_tag();
                 instructions.push(TRUTHY.clone()); // put truthy onto stack
                 instructions.push(Arc::new(Base::CtrlForwardToIfTruthy(end_tag.clone(), ConsumeStack::IfTrue)));
                 instructions.push(self.rhs.clone());
@@ -104,7 +325,19 @@ impl Instruction for OpIns {
                 instructions.push(Arc::new(Base::Tag(end_tag)));
             },
         }
-        Ok(Some(instructions))
+        Some(instructions)
+    }
+
+    fn optimize(&self, optimizer: &mut Optimizer) -> Option<Arc<dyn Instruction>> {
+        let ins = Arc::new(Self {
+            lhs: optimizer.expr(&self.lhs),
+            rhs: optimizer.expr(&self.rhs),
+            op: self.op.clone(),
+        });
+        if Optimizer::constant(&ins.lhs).is_some() && Optimizer::constant(&ins.rhs).is_some() {
+            return Some(optimizer.fold(ins));
+        }
+        Some(ins)
     }
 }
 
@@ -16,7 +16,7 @@
 
 use std::sync::Arc;
 use serde::{Deserialize, Serialize};
-use crate::{model::Graph, runtime::{instruction::{Instruction, Instructions}, instructions::FN_RETURN, proc::ProcEnv, Error}};
+use crate::{model::Graph, runtime::{instruction::{Instruction, Instructions}, instructions::{call::FuncCall, FN_RETURN}, optimizer::Optimizer, proc::ProcEnv, Error}};
 
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,6 +27,10 @@ pub struct RetIns {
 #[typetag::serde(name = "RetIns")]
 impl Instruction for RetIns {
     fn exec(&self, _env: &mut ProcEnv, _graph: &mut Graph) -> Result<Option<Instructions>, Error> {
+        Ok(self.compile())
+    }
+
+    fn compile(&self) -> Option<Instructions> {
         let mut instructions = Instructions::default();
         
         if let Some(ins) = &self.expr {
@@ -34,6 +38,19 @@ impl Instruction for RetIns {
         }
 
         instructions.push(FN_RETURN.clone());
-        Ok(Some(instructions))
+        Some(instructions)
+    }
+
+    fn optimize(&self, optimizer: &mut Optimizer) -> Option<Arc<dyn Instruction>> {
+        if let Some(ins) = &self.expr {
+            let expr = optimizer.expr(ins);
+            if let Some(call) = expr.as_dyn_any().downcast_ref::<FuncCall>() {
+                if let Some(tail_call) = optimizer.tail_call(call) {
+                    return Some(Arc::new(Self { expr: Some(tail_call) }));
+                }
+            }
+            return Some(Arc::new(Self { expr: Some(expr) }));
+        }
+        None
     }
 }
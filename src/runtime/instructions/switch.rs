@@ -17,10 +17,9 @@
 use std::sync::Arc;
 use arcstr::ArcStr;
 use imbl::Vector;
-use nanoid::nanoid;
 use rustc_hash::FxHashMap;
 use serde::{Deserialize, Serialize};
-use crate::{model::Graph, runtime::{instruction::{Instruction, Instructions}, instructions::Base, proc::ProcEnv, Error, Val}};
+use crate::{model::Graph, runtime::{instruction::{Instruction, Instructions}, instructions::{unique_tag, Base}, optimizer::Optimizer, proc::ProcEnv, Error, Val}};
 
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -32,20 +31,24 @@ pub struct SwitchIns {
 #[typetag::serde(name = "SwitchIns")]
 impl Instruction for SwitchIns {
     fn exec(&self, _env: &mut ProcEnv, _graph: &mut Graph) -> Result<Option<Instructions>, Error> {
+        Ok(self.compile())
+    }
+
+    fn compile(&self) -> Option<Instructions> {
         let mut table = FxHashMap::default();
         let mut default = None;
 
-        let end_tag: ArcStr = nanoid!(12).into();
+        let end_tag: ArcStr = unique_tag();
         let mut table_instructions = Instructions::default();
         for (v, ins) in &self.map {
-            let tag: ArcStr = nanoid!(12).into();
+            let tag: ArcStr = unique_tag();
             table_instructions.push(Arc::new(Base::Tag(tag.clone())));
             table_instructions.push(ins.clone());
             table_instructions.push(Arc::new(Base::CtrlForwardTo(end_tag.clone())));
             table.insert(v.clone(), tag);
         }
         if let Some(def) = &self.def {
-            let tag: ArcStr = nanoid!(12).into();
+            let tag: ArcStr = unique_tag();
             table_instructions.push(Arc::new(Base::Tag(tag.clone())));
             table_instructions.append(def);
             default = Some(tag);
@@ -56,9 +59,20 @@ impl Instruction for SwitchIns {
             let mut instructions = Instructions::default();
             instructions.push(Arc::new(Base::CtrlJumpTable(table, default, end_tag)));
             instructions.append(&table_instructions.instructions);
-            return Ok(Some(instructions));
+            return Some(instructions);
+        }
+        None
+    }
+
+    fn optimize(&self, optimizer: &mut Optimizer) -> Option<Arc<dyn Instruction>> {
+        let mut map = FxHashMap::default();
+        for (val, ins) in &self.map {
+            map.insert(val.clone(), optimizer.optimize(ins));
         }
-        Ok(None)
+        Some(Arc::new(Self {
+            map,
+            def: self.def.as_ref().map(|def| optimizer.optimize_all(def)),
+        }))
     }
 }
 
@@ -17,9 +17,9 @@
 use std::sync::Arc;
 use arcstr::ArcStr;
 use imbl::Vector;
-use nanoid::nanoid;
+use rustc_hash::FxHashSet;
 use serde::{Deserialize, Serialize};
-use crate::{model::Graph, runtime::{instruction::{Instruction, Instructions}, instructions::Base, proc::ProcEnv, Error}};
+use crate::{model::{DataRef, Field, Graph, NodeRef, SId}, runtime::{bytecode::{CachedProgram, Depth, Frame, Locals}, instruction::{Instruction, Instructions}, instructions::{unique_tag, Base, BREAK_LOOP, CONTINUE_LOOP, FN_RETURN, POP_SYMBOL_SCOPE, PUSH_SYMBOL_SCOPE}, optimizer::Optimizer, proc::ProcEnv, Error, Type, Val, ValRef, Variable}};
 
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -28,50 +28,470 @@ pub struct TryCatchIns {
     pub try_ins: Vector<Arc<dyn Instruction>>,
     pub err_ins: Vector<Arc<dyn Instruction>>,
     pub catch_ins: Vector<Arc<dyn Instruction>>,
+
+    #[serde(default)]
+    pub finally_ins: Option<Vector<Arc<dyn Instruction>>>,
+
+    /// Catch type can bind a runtime error as an error object (dropped when the statement is left, if unreferenced).
+    #[serde(default)]
+    pub error_obj: bool,
+
+    /// Compiled once, then entered in a new frame every time the statement runs.
+    #[serde(skip)]
+    pub program: CachedProgram,
 }
-#[typetag::serde(name = "TryCatchIns")]
-impl Instruction for TryCatchIns {
-    fn exec(&self, env: &mut ProcEnv, _graph: &mut Graph) -> Result<Option<Instructions>, Error> {
-        let catch_tag: ArcStr = nanoid!(10).into();
-        let end_tag: ArcStr = nanoid!(10).into();
-        let size = env.stack.len();
-        let try_depth = env.try_stack.len();
+impl TryCatchIns {
+    /// Instructions for this statement, restoring the process stacks relative to the frame it runs in.
+    fn expand(&self) -> Instructions {
+        let catch_tag: ArcStr = unique_tag();
+        let end_tag: ArcStr = unique_tag();
 
-        let call_depth = env.call_stack.len();
-        let return_depth = env.return_stack.len();
-        let self_depth = env.self_stack.len();
-        let ret_valid_depth = env.ret_valid_stack.len();
+        // With a finally block, every way out of the try & catch goes through the finally tag first
+        let mut finally_tag = None;
+        let mut finally_err_tag = None;
+        let mut exit_tag = end_tag.clone();
+        if self.finally_ins.is_some() || self.error_obj {
+            let tag: ArcStr = unique_tag();
+            finally_tag = Some(tag.clone());
+            finally_err_tag = Some(unique_tag());
+            exit_tag = tag;
+        }
+
+        // Truncate any loop/scope/call/return/self/ret-valid entries left over from an in-flight call
+        // that got unwound by the error rather than completing normally.
+        let unwind = [
+            Arc::new(Base::RestoreDepth(Depth::Loop, 0)) as Arc<dyn Instruction>,
+            Arc::new(Base::RestoreDepth(Depth::Scope, 0)),
+            Arc::new(Base::RestoreDepth(Depth::Try, 0)),
+            Arc::new(Base::RestoreDepth(Depth::Call, 0)),
+            Arc::new(Base::RestoreDepth(Depth::Return, 0)),
+            Arc::new(Base::RestoreDepth(Depth::SelfStack, 0)),
+            Arc::new(Base::RestoreDepth(Depth::RetValid, 0)),
+            Arc::new(Base::RestoreDepth(Depth::Finally, finally_tag.is_some() as usize)),
+            Arc::new(Base::RestoreDepth(Depth::FinallyRunning, 0)),
+            Arc::new(Base::RestoreDepth(Depth::ErrorObj, 0)),
+        ];
 
         let mut instructions = Instructions::default();
+        if let Some(tag) = &finally_tag {
+            instructions.push(Arc::new(TryIns::PushFinally(tag.clone())));
+        }
         instructions.push(Arc::new(Base::Try(catch_tag.clone()))); // Go here when theres an error & inc try count
         instructions.append(&self.try_ins);
-        instructions.push(Arc::new(Base::PopTryUntilDepth(try_depth)));
-        instructions.push(Arc::new(Base::CtrlForwardTo(end_tag.clone())));
+        instructions.push(Arc::new(Base::RestoreDepth(Depth::Try, 0)));
+        instructions.push(Arc::new(Base::CtrlForwardTo(exit_tag.clone())));
         
         instructions.push(Arc::new(Base::Tag(catch_tag))); // now theres an error on the stack!
-        instructions.push(Arc::new(Base::PopTryUntilDepth(try_depth))); // should be redundant!
-
-        // Truncate any call/return/self/ret-valid entries left over from an in-flight call
-        // that got unwound by the error rather than completing normally.
-        instructions.push(Arc::new(Base::PopCallUntilDepth(call_depth)));
-        instructions.push(Arc::new(Base::PopReturnUntilDepth(return_depth)));
-        instructions.push(Arc::new(Base::PopSelfUntilDepth(self_depth)));
-        instructions.push(Arc::new(Base::PopRetValidUntilDepth(ret_valid_depth)));
+        for ins in &unwind { instructions.push(ins.clone()); }
+        if let Some(tag) = &finally_err_tag {
+            instructions.push(Arc::new(Base::Try(tag.clone()))); // errors in the catch still run the finally block
+        }
         
+        instructions.push(PUSH_SYMBOL_SCOPE.clone()); // the error variable is scoped to the catch
         instructions.append(&self.err_ins);
-        instructions.push(Arc::new(Base::PopUntilStackCount(size)));
+        instructions.push(Arc::new(Base::RestoreDepth(Depth::Error, 0)));
+        instructions.push(Arc::new(Base::RestoreDepth(Depth::Stack, 0)));
         instructions.append(&self.catch_ins);
+        instructions.push(POP_SYMBOL_SCOPE.clone());
+
+        if let (Some(finally_tag), Some(finally_err_tag)) = (finally_tag, finally_err_tag) {
+            instructions.push(Arc::new(Base::RestoreDepth(Depth::Try, 0)));
+            instructions.push(Arc::new(Base::CtrlForwardTo(finally_tag.clone())));
+
+            // An error escaped the catch, so hold onto it and rethrow after the finally block
+            instructions.push(Arc::new(Base::Tag(finally_err_tag)));
+            for ins in &unwind { instructions.push(ins.clone()); }
+            instructions.push(Arc::new(TryIns::FinallyError));
+            instructions.push(Arc::new(Base::RestoreDepth(Depth::Error, 0)));
+            instructions.push(Arc::new(Base::RestoreDepth(Depth::Stack, 0)));
+
+            instructions.push(Arc::new(Base::Tag(finally_tag)));
+            instructions.push(Arc::new(Base::RestoreDepth(Depth::Loop, 0)));
+            instructions.push(Arc::new(Base::RestoreDepth(Depth::Scope, 0)));
+            instructions.push(Arc::new(Base::RestoreDepth(Depth::Try, 0)));
+            instructions.push(Arc::new(TryIns::FinallyStart));
+            instructions.push(Arc::new(Base::RestoreDepth(Depth::ErrorObj, 0)));
+            if let Some(finally_ins) = &self.finally_ins {
+                instructions.append(finally_ins);
+            }
+            instructions.push(Arc::new(TryIns::FinallyEnd));
+        } else {
+            instructions.push(Arc::new(Base::RestoreDepth(Depth::ErrorObj, 0)));
+        }
 
         instructions.push(Arc::new(Base::Tag(end_tag)));
+        instructions
+    }
+}
+#[typetag::serde(name = "TryCatchIns")]
+impl Instruction for TryCatchIns {
+    fn exec(&self, env: &mut ProcEnv, _graph: &mut Graph) -> Result<Option<Instructions>, Error> {
+        let program = self.program.get(|| self.expand());
+        let mut instructions = Instructions::default();
+        instructions.frames.push(Frame::enter(program, env));
         Ok(Some(instructions))
     }
+
+    fn precompile(&self, locals: &Locals) {
+        self.program.precompile(locals, || self.expand());
+    }
+
+    fn optimize(&self, optimizer: &mut Optimizer) -> Option<Arc<dyn Instruction>> {
+        // Returns within a try or catch go through the try-catch stacks, so they are not tail calls
+        Some(Arc::new(Self {
+            try_ins: optimizer.nontail_all(&self.try_ins),
+            err_ins: self.err_ins.clone(),
+            catch_ins: optimizer.nontail_all(&self.catch_ins),
+            finally_ins: self.finally_ins.as_ref().map(|finally_ins| optimizer.nontail_all(finally_ins)),
+            error_obj: self.error_obj,
+            program: Default::default(),
+        }))
+    }
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// How a try statement was left, resumed after its finally block runs.
+pub enum Completion {
+    Return,
+    Break,
+    Continue,
+    Throw(Error),
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Finally frame.
+/// Pushed when entering a try statement that has a finally block.
+pub struct FinallyFrame {
+    pub tag: ArcStr,
+    pub return_depth: usize,
+    pub loop_depth: usize,
+    pub pending: Option<Completion>,
+}
+
+
+/// Redirect a return/break/continue to the finally block of the try statement it is leaving (if any).
+/// Returns the tag to go forwards to instead of the normal destination.
+pub(crate) fn finally_redirect(env: &mut ProcEnv, completion: Completion) -> Option<ArcStr> {
+    if let Some(frame) = env.finally_stack.last_mut() {
+        if frame.return_depth == env.return_stack.len() {
+            let leaves_try = match completion {
+                Completion::Return => !env.return_stack.is_empty(),
+                Completion::Break |
+                Completion::Continue => !env.loop_stack.is_empty() && frame.loop_depth == env.loop_stack.len(),
+                Completion::Throw(_) => true,
+            };
+            if leaves_try {
+                frame.pending = Some(completion);
+                return Some(frame.tag.clone());
+            }
+        }
+    }
+    None
+}
+
+
+/// Value given to a catch block for an error (also the "error" of a rejected settled promise).
+/// Thrown values are passed through as-is, while runtime errors are given as their message.
+pub(crate) fn error_value(error: &Error) -> Val {
+    match error {
+        Error::Thrown(val) => val.clone(),
+        _ => Val::Str(error.to_string().into()),
+    }
+}
+
+
+/// Error object for a runtime error caught as an obj, "Error", or by its kind.
+/// Has "kind", "message", "trace" (call stack function paths), and "location" (function path) fields.
+/// The node is dropped when the try statement that caught it is left, unless it is still referenced (see TryIns::DropErrorObjs).
+fn error_obj(error: &Error, call_stack: &[DataRef], env: &mut ProcEnv, graph: &mut Graph) -> Val {
+    let mut trace = Vector::default();
+    for func in call_stack {
+        let mut path = func.data_any_path(graph, ".");
+        if let Some(name) = func.data_name(graph) {
+            if !path.is_empty() { path.push('.'); }
+            path.push_str(name.as_ref());
+        }
+        trace.push_back(ValRef::new(Val::Str(path.into())));
+    }
+    let mut location = Val::Null;
+    if let Some(last) = trace.last() {
+        location = last.read().clone();
+    }
+
+    let id = SId::default();
+    let nref = match graph.try_insert_node_id(id.clone(), id, env.self_stack.last().cloned(), false) {
+        Ok(nref) => nref,
+        Err(_) => return error_value(error), // over a node quota, so catch the error as a value instead
+    };
+    let fields = [
+        ("kind", Val::Str(error.kind().into())),
+        ("message", Val::Str(error.to_string().into())),
+        ("trace", Val::List(trace)),
+        ("location", location),
+    ];
+    for (name, val) in fields {
+        graph.insert_stof_data(&nref, name, Box::new(Field::new(Variable::val(val), None)), None);
+    }
+    env.error_objs.push(nref.clone());
+    Val::Obj(nref)
+}
+
+
+/// Remove error objects that are still referenced when their try statement is left.
+/// Looks at the process stack, locals, errors being thrown, and graph fields.
+/// Referenced error objects are kept as ordinary objects (dropped like any other object).
+fn retain_unreferenced(objs: &mut FxHashSet<NodeRef>, env: &ProcEnv, graph: &Graph) {
+    for var in &env.stack {
+        unmark_referenced(&var.val.read(), objs);
+    }
+    for local in &env.table.locals {
+        if let Some(var) = &local.var {
+            unmark_referenced(&var.val.read(), objs);
+        }
+    }
+    for (error, _) in &env.error_stack {
+        if let Error::Thrown(val) = error {
+            unmark_referenced(val, objs);
+        }
+    }
+    for frame in env.finally_stack.iter().chain(env.finally_running.iter()) {
+        if let Some(Completion::Throw(Error::Thrown(val))) = &frame.pending {
+            unmark_referenced(val, objs);
+        }
+    }
+    for dref in graph.data.keys() {
+        if objs.is_empty() { return; }
+        if let Some(field) = graph.get_stof_data::<Field>(dref) {
+            unmark_referenced(&field.value.val.read(), objs);
+        }
+    }
+}
+
+
+/// Remove every object a value references from a set of objects.
+fn unmark_referenced(val: &Val, objs: &mut FxHashSet<NodeRef>) {
+    if objs.is_empty() { return; }
+    match val {
+        Val::Obj(nref) => {
+            objs.remove(nref);
+        },
+        Val::List(vals) |
+        Val::Tup(vals) => {
+            for val in vals {
+                unmark_referenced(&val.read(), objs);
+            }
+        },
+        Val::Map(map) => {
+            for (key, val) in map {
+                unmark_referenced(&key.read(), objs);
+                unmark_referenced(&val.read(), objs);
+            }
+        },
+        Val::Set(set) => {
+            for val in set {
+                unmark_referenced(&val.read(), objs);
+            }
+        },
+        _ => {}
+    }
+}
+
+
+/// Can this catch type bind a runtime error as an error object?
+pub(crate) fn catches_error_obj(ty: &Type) -> bool {
+    match ty {
+        Type::Obj(_) => true,
+        Type::Union(types) => types.iter().any(catches_error_obj),
+        Type::NotNull(ty) => catches_error_obj(ty),
+        _ => false,
+    }
+}
+
+
+/// Value of a field directly on an object (used for error objects).
+fn error_field(graph: &Graph, obj: &NodeRef, name: &str) -> Option<Val> {
+    if let Some(node) = obj.node(graph) {
+        if let Some(dref) = node.data.get(name) {
+            if let Some(field) = graph.get_stof_data::<Field>(dref) {
+                return Some(field.value.get());
+            }
+        }
+    }
+    None
+}
+
+
+/// Value to bind in a catch block for a caught error value and catch type.
+/// Untyped, "unknown", and "str" catches get the error value (the message for runtime errors),
+/// while "obj", "Error", and error kind catches get an error object for runtime errors.
+/// Returns None if the error does not match the type (it will be rethrown).
+fn catch_value(val: &Val, error: Option<&(Error, Vec<DataRef>)>, ty: &Type, env: &mut ProcEnv, graph: &mut Graph) -> Option<Val> {
+    match ty {
+        Type::Unknown => Some(val.clone()),
+        Type::Str => {
+            let mut val = val.clone();
+            if val.cast(ty, graph, Some(env.self_ptr())).is_ok() {
+                return Some(val);
+            }
+            None
+        },
+        Type::Union(types) => {
+            for ty in types {
+                if let Some(val) = catch_value(val, error, ty, env, graph) {
+                    return Some(val);
+                }
+            }
+            None
+        },
+        Type::NotNull(ty) => {
+            if val.null() { return None; }
+            catch_value(val, error, ty, env, graph)
+        },
+        Type::Obj(name) => {
+            if let Some(obj) = val.try_obj() {
+                // User error prototypes
+                let mut proto = ty.clone();
+                proto.obj_to_proto(graph, Some(env.self_ptr()));
+                if let Type::Obj(proto) = &proto {
+                    if proto.node_exists(graph) && val.instance_of(proto, graph).unwrap_or(false) {
+                        return Some(val.clone());
+                    }
+                }
+
+                // Error kinds
+                if let Some(Val::Str(kind)) = error_field(graph, &obj, "kind") {
+                    if kind.as_str() == name.as_ref() {
+                        return Some(val.clone());
+                    }
+                }
+
+                if ty.empty() || val.is_type(ty, graph) {
+                    return Some(val.clone());
+                }
+            } else if let Some((error, call_stack)) = error {
+                if !matches!(error, Error::Thrown(_)) && (name.as_ref() == "obj" || name.as_ref() == "Error" || name.as_ref() == error.kind()) {
+                    return Some(error_obj(error, call_stack, env, graph));
+                }
+            }
+            None
+        },
+        _ => {
+            if val.obj() { return None; }
+            let mut val = val.clone();
+            if val.cast(ty, graph, Some(env.self_ptr())).is_ok() {
+                return Some(val);
+            }
+            None
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Try catch & finally helper instructions.
+pub enum TryIns {
+    /// Match the caught error (on the stack) to a type, rethrowing it if it doesn't match.
+    Catch(Type),
+    Rethrow,
+    PopErrorUntilDepth(usize),
+    DropErrorObjs(usize),
+
+    PushFinally(ArcStr),
+    PopFinallyUntilDepth(usize, usize),
+    FinallyError,
+    FinallyStart,
+    FinallyEnd,
+}
+#[typetag::serde(name = "TryIns")]
+impl Instruction for TryIns {
+    fn exec(&self, env: &mut ProcEnv, graph: &mut Graph) -> Result<Option<Instructions>, Error> {
+        match self {
+            Self::Catch(ty) => {
+                if let Some(var) = env.stack.pop() {
+                    let val = var.get();
+                    let error = env.error_stack.pop();
+                    if let Some(bound) = catch_value(&val, error.as_ref(), ty, env, graph) {
+                        env.stack.push(Variable::val(bound));
+                    } else if let Some((error, _)) = error {
+                        return Err(error);
+                    } else {
+                        return Err(Error::Thrown(val));
+                    }
+                } else {
+                    return Err(Error::StackError);
+                }
+            },
+            Self::Rethrow => {
+                let val = env.stack.pop().map(|var| var.get()).unwrap_or(Val::Null);
+                if let Some((error, _)) = env.error_stack.pop() {
+                    return Err(error);
+                }
+                return Err(Error::Thrown(val));
+            },
+            Self::PopErrorUntilDepth(depth) => {
+                env.error_stack.truncate(*depth);
+            },
+            Self::DropErrorObjs(depth) => {
+                if env.error_objs.len() > *depth {
+                    let mut objs: FxHashSet<NodeRef> = env.error_objs.drain(*depth..).collect();
+                    retain_unreferenced(&mut objs, env, graph);
+                    for nref in objs {
+                        graph.remove_node(&nref, false);
+                    }
+                }
+            },
+            Self::PushFinally(tag) => {
+                env.finally_stack.push(FinallyFrame {
+                    tag: tag.clone(),
+                    return_depth: env.return_stack.len(),
+                    loop_depth: env.loop_stack.len(),
+                    pending: None,
+                });
+            },
+            Self::PopFinallyUntilDepth(depth, running_depth) => {
+                env.finally_stack.truncate(*depth);
+                env.finally_running.truncate(*running_depth);
+            },
+            Self::FinallyError => {
+                env.stack.pop();
+                if let Some((error, _)) = env.error_stack.pop() {
+                    if let Some(frame) = env.finally_stack.last_mut() {
+                        frame.pending = Some(Completion::Throw(error));
+                    }
+                }
+            },
+            Self::FinallyStart => {
+                if let Some(frame) = env.finally_stack.pop() {
+                    env.finally_running.push(frame);
+                }
+            },
+            Self::FinallyEnd => {
+                if let Some(frame) = env.finally_running.pop() {
+                    if let Some(pending) = frame.pending {
+                        let mut instructions = Instructions::default();
+                        match pending {
+                            Completion::Return => instructions.push(FN_RETURN.clone()),
+                            Completion::Break => instructions.push(BREAK_LOOP.clone()),
+                            Completion::Continue => instructions.push(CONTINUE_LOOP.clone()),
+                            Completion::Throw(error) => return Err(error),
+                        }
+                        return Ok(Some(instructions));
+                    }
+                }
+            },
+        }
+        Ok(None)
+    }
 }
 
 
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
-    use crate::{model::Graph, runtime::{instructions::{trycatch::TryCatchIns, Base}, Num, Runtime, Val}};
+    use imbl::vector;
+    use crate::{model::Graph, runtime::{instructions::{trycatch::{TryCatchIns, TryIns}, Base}, Error, Num, Runtime, Type, Val}};
 
 
     #[test]
@@ -84,4 +504,23 @@ mod tests {
         let res = Runtime::eval(&mut graph, Arc::new(try_catch)).unwrap();
         assert_eq!(res, 42.2.into());
     }
+
+    #[test]
+    fn finally_rethrows() {
+        let mut try_catch = TryCatchIns::default();
+        try_catch.try_ins.push_back(Arc::new(Base::Cast(Type::Str))); // nothing on the stack
+        try_catch.err_ins.push_back(Arc::new(TryIns::Rethrow));
+        try_catch.finally_ins = Some(vector![Arc::new(Base::Literal(Val::Num(Num::Int(3)))) as _]);
+
+        let mut graph = Graph::default();
+        let res = Runtime::eval(&mut graph, Arc::new(try_catch));
+        assert_eq!(res, Err(Error::CastStackError));
+    }
+
+    #[test]
+    fn error_kinds() {
+        assert_eq!(Error::FuncDne("missing".into()).kind(), "FuncDne");
+        assert_eq!(Error::CastStackError.kind(), "CastStackError");
+        assert_eq!(Error::Thrown(Val::Null).kind(), "Thrown");
+    }
 }
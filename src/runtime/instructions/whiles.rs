@@ -17,9 +17,8 @@
 use std::sync::Arc;
 use arcstr::ArcStr;
 use imbl::Vector;
-use nanoid::nanoid;
 use serde::{Deserialize, Serialize};
-use crate::{model::Graph, runtime::{instruction::{Instruction, Instructions}, instructions::{Base, ConsumeStack, PUSH_SYMBOL_SCOPE, TRUTHY}, proc::ProcEnv, Error}};
+use crate::{model::Graph, runtime::{bytecode::{CachedProgram, Depth, Frame, Locals}, instruction::{Instruction, Instructions}, instructions::{block::Block, unique_tag, Base, ConsumeStack, PUSH_SYMBOL_SCOPE, TRUTHY}, optimizer::Optimizer, proc::ProcEnv, Error}};
 
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,24 +34,21 @@ pub struct WhileIns {
     // added control flow stuff for flexibility
     pub declare: Option<Arc<dyn Instruction>>,
     pub inc: Option<Arc<dyn Instruction>>,
+
+    // compiled once, then entered in a new frame every time the loop runs
+    #[serde(skip)]
+    pub program: CachedProgram,
 }
-#[typetag::serde(name = "WhileIns")]
-impl Instruction for WhileIns {
-    fn exec(&self, env: &mut ProcEnv, _graph: &mut Graph) -> Result<Option<Instructions>, Error> {
+impl WhileIns {
+    /// Instructions for this loop, restoring the loop & scope stacks relative to the frame it runs in.
+    fn expand(&self) -> Instructions {
         // Create a tag for this loop (control statements)
-        let mut tag: ArcStr = nanoid!(10).into();
-        if let Some(ctag) = &self.tag {
-            tag = ctag.clone();
-        }
+        let tag: ArcStr = self.tag.clone().unwrap_or_else(unique_tag);
 
         // Create break and continue tags
         let continue_tag: ArcStr = format!("{}_con", &tag).into();
         let break_tag: ArcStr = format!("{}_brk", &tag).into();
 
-        // Record scopes for poping at end
-        let scope_count = env.table.scopes.len();
-        let while_count = env.loop_stack.len();
-
         let mut instructions = Instructions::default();
         instructions.push(Arc::new(Base::PushLoop(tag)));
 
@@ -60,8 +56,8 @@ impl Instruction for WhileIns {
             instructions.push(declare.clone());
         }
         
-        let top_tag: ArcStr = nanoid!(10).into();
-        let end_tag: ArcStr = nanoid!(10).into();
+        let top_tag: ArcStr = unique_tag();
+        let end_tag: ArcStr = unique_tag();
 
         instructions.push(Arc::new(Base::Tag(top_tag.clone())));
         {
@@ -75,9 +71,9 @@ impl Instruction for WhileIns {
             instructions.append(&self.ins);
 
             // Continue statements will go to here
-            instructions.push(Arc::new(Base::Tag(continue_tag.clone())));
-            instructions.push(Arc::new(Base::PopLoopUntilDepth(while_count + 1)));
-            instructions.push(Arc::new(Base::PopSymbolScopeUntilDepth(scope_count + 1))); // take loop count into consideration
+            instructions.push(Arc::new(Base::Tag(continue_tag)));
+            instructions.push(Arc::new(Base::RestoreDepth(Depth::Loop, 1)));
+            instructions.push(Arc::new(Base::RestoreDepth(Depth::Scope, 1))); // take loop count into consideration
 
             // If we have an inc expr, do that now before we start the loop again
             if let Some(inc) = &self.inc {
@@ -85,25 +81,48 @@ impl Instruction for WhileIns {
             }
 
             // Go back to the top in our special loopy way
-            instructions.push(Arc::new(Base::CtrlLoopBackTo {
-                top_tag,
-                test: self.test.clone(),
-                end_tag: end_tag.clone(),
-                ins: self.ins.clone(),
-                continue_tag,
-                loop_count: while_count,
-                scope_count,
-                inc: self.inc.clone()
-            }));
+            instructions.push(Arc::new(Base::CtrlLoopBack(top_tag)));
         }
 
         // Break statements will go here, as well as our jump if not truthy
         instructions.push(Arc::new(Base::Tag(break_tag)));
         instructions.push(Arc::new(Base::Tag(end_tag)));
-        instructions.push(Arc::new(Base::PopLoopUntilDepth(while_count)));
-        instructions.push(Arc::new(Base::PopSymbolScopeUntilDepth(scope_count)));
+        instructions.push(Arc::new(Base::RestoreDepth(Depth::Loop, 0)));
+        instructions.push(Arc::new(Base::RestoreDepth(Depth::Scope, 0)));
+        instructions
+    }
+}
+#[typetag::serde(name = "WhileIns")]
+impl Instruction for WhileIns {
+    fn exec(&self, env: &mut ProcEnv, _graph: &mut Graph) -> Result<Option<Instructions>, Error> {
+        let program = self.program.get(|| self.expand());
+        let mut instructions = Instructions::default();
+        instructions.frames.push(Frame::enter(program, env));
         Ok(Some(instructions))
     }
+
+    fn precompile(&self, locals: &Locals) {
+        self.program.precompile(locals, || self.expand());
+    }
+
+    fn optimize(&self, optimizer: &mut Optimizer) -> Option<Arc<dyn Instruction>> {
+        let test = optimizer.expr(&self.test);
+        if self.declare.is_none() {
+            if let Some(val) = Optimizer::constant(&test) {
+                if !val.truthy() {
+                    return Some(Arc::new(Block::default())); // never runs
+                }
+            }
+        }
+        Some(Arc::new(Self {
+            tag: self.tag.clone(),
+            test,
+            ins: optimizer.optimize_all(&self.ins),
+            declare: self.declare.as_ref().map(|declare| optimizer.expr(declare)),
+            inc: self.inc.as_ref().map(|inc| optimizer.expr(inc)),
+            program: Default::default(),
+        }))
+    }
 }
 
 
@@ -146,6 +165,7 @@ mod tests {
             ins: Default::default(),
             declare: Some(Arc::new(declare)),
             inc: Some(Arc::new(inc_block)),
+            program: Default::default(),
         };
         //while_loop.ins.push_back(Arc::new(Base::CtrlForwardTo(literal!("continue"))));
         while_loop.ins.push_back(Arc::new(OpIns {
@@ -18,10 +18,25 @@
 pub mod runtime;
 pub use runtime::*;
 
+#[cfg(not(feature = "js"))]
+pub mod executor;
+#[cfg(not(feature = "js"))]
+pub use executor::*;
+
 pub mod proc;
+
+pub mod scheduler;
+pub use scheduler::*;
+
+pub mod checkpoint;
+pub use checkpoint::*;
+
 pub mod table;
 pub mod instruction;
 pub mod instructions;
+pub mod bytecode;
+pub mod optimizer;
+pub mod ordering;
 
 pub mod error;
 pub use error::*;
@@ -44,5 +59,8 @@ pub use variable::*;
 pub mod units;
 pub use units::*;
 
+pub mod fmtspec;
+pub use fmtspec::*;
+
 pub mod waker;
 pub use waker::*;
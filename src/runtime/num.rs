@@ -14,17 +14,26 @@
 // limitations under the License.
 //
 
-use std::hash::Hash;
+use std::{hash::Hash, sync::Arc};
+use num_bigint::BigInt;
+use num_traits::{FromPrimitive, Signed, ToPrimitive, Zero};
+use rust_decimal::{Decimal, RoundingStrategy};
 use serde::{Deserialize, Serialize};
 use crate::runtime::{Error, NumT, Units};
 
 
-#[derive(Debug, Clone, Serialize, Deserialize, Copy)]
+/// Largest bigint pow can create, in bits (1 Mib).
+pub const MAX_BIGINT_BITS: u64 = 1 << 20;
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// Number.
 pub enum Num {
     Int(i64),
     Float(f64),
     Units(f64, Units),
+    Decimal(Decimal),
+    BigInt(Arc<BigInt>),
 }
 impl Default for Num {
     fn default() -> Self {
@@ -47,11 +56,23 @@ impl Hash for Num {
                 if val.signum() < 0. { sign = 2; }
                 NumHash(sign, val.trunc() as u64, (val.fract() * 1000000.) as u64).hash(state)
             },
+            Self::Decimal(val) => val.normalize().hash(state),
+            Self::BigInt(val) => {
+                // same hash as an equal int
+                if let Some(val) = val.to_i64() { val.hash(state) }
+                else { val.hash(state) }
+            },
         }
     }
 }
 impl PartialEq for Num {
     fn eq(&self, other: &Self) -> bool {
+        if let Some((a, b)) = self.bigint_operands(other) {
+            return a == b;
+        }
+        if let Some((a, b)) = self.decimal_operands(other) {
+            return a == b;
+        }
         match self {
             Self::Int(val) => {
                 match other {
@@ -77,7 +98,8 @@ impl PartialEq for Num {
                             }
                         }
                         *val as f64 == *oval
-                    }
+                    },
+                    Self::Decimal(_) | Self::BigInt(_) => *self == other.float_num(),
                 }
             },
             Self::Float(val) => {
@@ -104,7 +126,8 @@ impl PartialEq for Num {
                             }
                         }
                         *val == *oval
-                    }
+                    },
+                    Self::Decimal(_) | Self::BigInt(_) => *self == other.float_num(),
                 }
             },
             Self::Units(val, units) => {
@@ -159,9 +182,11 @@ impl PartialEq for Num {
                             }
                         }
                         *val == *oval
-                    }
+                    },
+                    Self::Decimal(_) | Self::BigInt(_) => *self == other.float_num(),
                 }
-            }
+            },
+            Self::Decimal(_) | Self::BigInt(_) => self.float_num() == *other,
         }
     }
 }
@@ -188,7 +213,31 @@ impl From<i8> for Num {
 }
 impl From<i128> for Num {
     fn from(value: i128) -> Self {
-        Self::Int(value as i64)
+        match i64::try_from(value) {
+            Ok(value) => Self::Int(value),
+            Err(_) => Self::from(BigInt::from(value)),
+        }
+    }
+}
+impl From<u64> for Num {
+    fn from(value: u64) -> Self {
+        match i64::try_from(value) {
+            Ok(value) => Self::Int(value),
+            Err(_) => Self::from(BigInt::from(value)),
+        }
+    }
+}
+impl From<u128> for Num {
+    fn from(value: u128) -> Self {
+        match i64::try_from(value) {
+            Ok(value) => Self::Int(value),
+            Err(_) => Self::from(BigInt::from(value)),
+        }
+    }
+}
+impl From<BigInt> for Num {
+    fn from(value: BigInt) -> Self {
+        Self::BigInt(Arc::new(value))
     }
 }
 impl From<f32> for Num {
@@ -201,6 +250,11 @@ impl From<f64> for Num {
         Self::Float(value)
     }
 }
+impl From<Decimal> for Num {
+    fn from(value: Decimal) -> Self {
+        Self::Decimal(value)
+    }
+}
 impl From<(i32, Units)> for Num {
     fn from(value: (i32, Units)) -> Self {
         Self::Units(value.0 as f64, value.1)
@@ -233,6 +287,8 @@ impl Num {
             Self::Float(_) => NumT::Float,
             Self::Int(_) => NumT::Int,
             Self::Units(_, _) => NumT::Float,
+            Self::Decimal(_) => NumT::Decimal,
+            Self::BigInt(_) => NumT::BigInt,
         }
     }
 
@@ -242,6 +298,8 @@ impl Num {
             Self::Float(_) => NumT::Float,
             Self::Int(_) => NumT::Int,
             Self::Units(_, units) => NumT::Units(*units),
+            Self::Decimal(_) => NumT::Decimal,
+            Self::BigInt(_) => NumT::BigInt,
         }
     }
 
@@ -250,7 +308,9 @@ impl Num {
         match self {
             Self::Int(val) => format!("{val}"),
             Self::Float(val) => format!("{val}"),
-            Self::Units(val, units) => format!("{val}{}", units.to_string())
+            Self::Units(val, units) => format!("{val}{}", units.to_string()),
+            Self::Decimal(val) => val.to_string(),
+            Self::BigInt(val) => val.to_string(),
         }
     }
 
@@ -259,7 +319,9 @@ impl Num {
         match self {
             Self::Int(val) => format!("Int({val})"),
             Self::Float(val) => format!("Float({val})"),
-            Self::Units(val, units) => format!("Units({val}, {:?})", units)
+            Self::Units(val, units) => format!("Units({val}, {:?})", units),
+            Self::Decimal(val) => format!("Decimal({val})"),
+            Self::BigInt(val) => format!("BigInt({val})"),
         }
     }
 
@@ -268,7 +330,9 @@ impl Num {
         match self {
             Self::Int(v) => *v != 0,
             Self::Float(v) => v.round() != 0.,
-            Self::Units(v, _) => v.round() != 0.
+            Self::Units(v, _) => v.round() != 0.,
+            Self::Decimal(v) => !v.round().is_zero(),
+            Self::BigInt(v) => !v.is_zero(),
         }
     }
 
@@ -366,7 +430,9 @@ impl Num {
         match self {
             Self::Int(v) => *v,
             Self::Float(v) => *v as i64,
-            Self::Units(v, _) => *v as i64
+            Self::Units(v, _) => *v as i64,
+            Self::Decimal(v) => v.trunc().to_i64().unwrap_or(if v.is_sign_negative() { i64::MIN } else { i64::MAX }),
+            Self::BigInt(v) => v.to_i64().unwrap_or(if v.is_negative() { i64::MIN } else { i64::MAX }),
         }
     }
 
@@ -397,6 +463,69 @@ impl Num {
                 }
                 *val
             },
+            Self::Decimal(_) |
+            Self::BigInt(_) => {
+                let val = self.float_num().float(None);
+                if let Some(units) = units {
+                    if let Ok(val) = Units::convert(val, units, units) {
+                        return val;
+                    }
+                }
+                val
+            },
+        }
+    }
+
+    /// Decimal value of this number, if it can be represented as one (no NaN or infinity).
+    pub fn decimal(&self) -> Option<Decimal> {
+        match self {
+            Self::Int(val) => Some(Decimal::from(*val)),
+            // Shortest float repr, so 0.1 is 0.1 and not the nearest binary fraction
+            Self::Float(val) |
+            Self::Units(val, _) => val.to_string().parse().ok().or_else(|| Decimal::from_f64(*val)),
+            Self::Decimal(val) => Some(*val),
+            Self::BigInt(val) => Decimal::from_str_exact(&val.to_string()).ok(),
+        }
+    }
+
+    /// Big integer value of this number (truncated), if it has one (no NaN or infinity).
+    pub fn bigint(&self) -> Option<BigInt> {
+        match self {
+            Self::Int(val) => Some(BigInt::from(*val)),
+            Self::Float(val) |
+            Self::Units(val, _) => BigInt::from_f64(val.trunc()),
+            Self::Decimal(val) => val.trunc().to_string().parse().ok(),
+            Self::BigInt(val) => Some(val.as_ref().clone()),
+        }
+    }
+
+    /// Big integer operands for a binary operation between a big integer and an int or big integer.
+    fn bigint_operands(&self, other: &Self) -> Option<(BigInt, BigInt)> {
+        match (self, other) {
+            (Self::BigInt(_), Self::Int(_) | Self::BigInt(_)) |
+            (Self::Int(_), Self::BigInt(_)) => Some((self.bigint()?, other.bigint()?)),
+            _ => None,
+        }
+    }
+
+    /// Decimal operands for a binary operation between a decimal and an int, float, or decimal.
+    /// Units win over decimals, so a decimal with units (or an unrepresentable float) has no decimal operands.
+    fn decimal_operands(&self, other: &Self) -> Option<(Decimal, Decimal)> {
+        match (self, other) {
+            (Self::Decimal(_), Self::Units(..)) |
+            (Self::Units(..), Self::Decimal(_)) => None,
+            (Self::Decimal(_), _) |
+            (_, Self::Decimal(_)) => Some((self.decimal()?, other.decimal()?)),
+            _ => None,
+        }
+    }
+
+    /// Float version of a decimal or big integer (other numbers are unchanged).
+    fn float_num(&self) -> Self {
+        match self {
+            Self::Decimal(val) => Self::Float(val.to_f64().unwrap_or_default()),
+            Self::BigInt(val) => Self::Float(val.to_f64().unwrap_or(f64::NAN)),
+            _ => self.clone(),
         }
     }
 
@@ -407,6 +536,8 @@ impl Num {
                 match target {
                     NumT::Int => Self::Int(*val as i64),
                     NumT::Float => Self::Float(*val as f64),
+                    NumT::Decimal => Self::Decimal(Decimal::from(*val)),
+                    NumT::BigInt => Self::from(BigInt::from(*val)),
                     NumT::Units(ounits) => {
                         if let Ok(v) = Units::convert(*val as f64, ounits, ounits) {
                             Self::Units(v, ounits)
@@ -420,6 +551,8 @@ impl Num {
                 match target {
                     NumT::Int => Self::Int(*val as i64),
                     NumT::Float => Self::Float(*val as f64),
+                    NumT::Decimal => self.decimal().map_or(self.clone(), Self::Decimal),
+                    NumT::BigInt => self.bigint().map_or(self.clone(), Self::from),
                     NumT::Units(ounits) => {
                         if let Ok(v) = Units::convert(*val, ounits, ounits) {
                             Self::Units(v, ounits)
@@ -433,6 +566,8 @@ impl Num {
                 match target {
                     NumT::Int => Self::Int(*val as i64),
                     NumT::Float => Self::Float(*val as f64),
+                    NumT::Decimal => self.decimal().map_or(self.clone(), Self::Decimal),
+                    NumT::BigInt => self.bigint().map_or(self.clone(), Self::from),
                     NumT::Units(ounits) => {
                         // Try casting directly to ounits
                         if let Ok(value) = Units::convert(*val, *units, ounits) {
@@ -449,12 +584,34 @@ impl Num {
                         Self::Float(*val)
                     },
                 }
-            }
+            },
+            Self::Decimal(_) => {
+                match target {
+                    NumT::Int => Self::Int(self.int()),
+                    NumT::Decimal => self.clone(),
+                    NumT::BigInt => self.bigint().map_or(self.clone(), Self::from),
+                    _ => self.float_num().cast(target),
+                }
+            },
+            Self::BigInt(_) => {
+                match target {
+                    NumT::Int => Self::Int(self.int()),
+                    NumT::BigInt => self.clone(),
+                    NumT::Decimal => self.decimal().map_or(self.float_num(), Self::Decimal),
+                    _ => self.float_num().cast(target),
+                }
+            },
         }
     }
 
     /// Greater than another number?
     pub fn gt(&self, other: &Self) -> bool {
+        if let Some((a, b)) = self.bigint_operands(other) {
+            return a > b;
+        }
+        if let Some((a, b)) = self.decimal_operands(other) {
+            return a > b;
+        }
         match self {
             Self::Int(val) => {
                 match other {
@@ -473,6 +630,7 @@ impl Num {
                         }
                         *val as f64 > *oval
                     },
+                    Self::Decimal(_) | Self::BigInt(_) => self.gt(&other.float_num()),
                 }
             },
             Self::Float(val) => {
@@ -492,6 +650,7 @@ impl Num {
                         }
                         *val > *oval
                     },
+                    Self::Decimal(_) | Self::BigInt(_) => self.gt(&other.float_num()),
                 }
             },
             Self::Units(val, units) => {
@@ -535,13 +694,21 @@ impl Num {
                         }
                         *val > *oval
                     },
+                    Self::Decimal(_) | Self::BigInt(_) => self.gt(&other.float_num()),
                 }
             },
+            Self::Decimal(_) | Self::BigInt(_) => self.float_num().gt(other),
         }
     }
 
     /// Less than another number?
     pub fn lt(&self, other: &Self) -> bool {
+        if let Some((a, b)) = self.bigint_operands(other) {
+            return a < b;
+        }
+        if let Some((a, b)) = self.decimal_operands(other) {
+            return a < b;
+        }
         match self {
             Self::Int(val) => {
                 match other {
@@ -560,6 +727,7 @@ impl Num {
                         }
                         (*val as f64) < *oval
                     },
+                    Self::Decimal(_) | Self::BigInt(_) => self.lt(&other.float_num()),
                 }
             },
             Self::Float(val) => {
@@ -579,6 +747,7 @@ impl Num {
                         }
                         *val < *oval
                     },
+                    Self::Decimal(_) | Self::BigInt(_) => self.lt(&other.float_num()),
                 }
             },
             Self::Units(val, units) => {
@@ -622,18 +791,26 @@ impl Num {
                         }
                         *val < *oval
                     },
+                    Self::Decimal(_) | Self::BigInt(_) => self.lt(&other.float_num()),
                 }
             },
+            Self::Decimal(_) | Self::BigInt(_) => self.float_num().lt(other),
         }
     }
 
     /// Add two numbers together.
-    pub fn add(&self, other: &Self) -> Self {
-        match self {
+    pub fn add(&self, other: &Self) -> Result<Self, Error> {
+        if let Some((a, b)) = self.bigint_operands(other) {
+            return Ok(Self::from(a + b));
+        }
+        if let Some((a, b)) = self.decimal_operands(other) {
+            return a.checked_add(b).map(Self::Decimal).ok_or_else(|| Error::NumOverflow(format!("{a} + {b}")));
+        }
+        Ok(match self {
             Self::Int(val) => {
                 match other {
                     Self::Int(bval) => {
-                        Self::Int(*val + *bval)
+                        return val.checked_add(*bval).map(Self::Int).ok_or_else(|| Error::NumOverflow(format!("{val} + {bval}")));
                     },
                     Self::Float(bval) => {
                         Self::Float(*val as f64 + *bval)
@@ -647,7 +824,8 @@ impl Num {
                             }
                         }
                         Self::Units(res, base)
-                    }
+                    },
+                    Self::Decimal(_) | Self::BigInt(_) => return self.add(&other.float_num()),
                 }
             },
             Self::Float(val) => {
@@ -667,7 +845,8 @@ impl Num {
                             }
                         }
                         Self::Units(res, base)
-                    }
+                    },
+                    Self::Decimal(_) | Self::BigInt(_) => return self.add(&other.float_num()),
                 }
             },
             Self::Units(val, units) => {
@@ -703,26 +882,34 @@ impl Num {
                                     res = a + b;
                                 }
                                 if base.is_undefined() {
-                                    return Self::Float(res);
+                                    return Ok(Self::Float(res));
                                 }
-                                return Self::Units(res, base);
+                                return Ok(Self::Units(res, base));
                             }
                         }
                         // No units anymore...
                         Self::Float(res)
-                    }
+                    },
+                    Self::Decimal(_) | Self::BigInt(_) => return self.add(&other.float_num()),
                 }
-            }
-        }
+            },
+            Self::Decimal(_) | Self::BigInt(_) => return self.float_num().add(other),
+        })
     }
 
     /// Subtract two number.
-    pub fn sub(&self, other: &Self) -> Self {
-        match self {
+    pub fn sub(&self, other: &Self) -> Result<Self, Error> {
+        if let Some((a, b)) = self.bigint_operands(other) {
+            return Ok(Self::from(a - b));
+        }
+        if let Some((a, b)) = self.decimal_operands(other) {
+            return a.checked_sub(b).map(Self::Decimal).ok_or_else(|| Error::NumOverflow(format!("{a} - {b}")));
+        }
+        Ok(match self {
             Self::Int(val) => {
                 match other {
                     Self::Int(bval) => {
-                        Self::Int(*val - *bval)
+                        return val.checked_sub(*bval).map(Self::Int).ok_or_else(|| Error::NumOverflow(format!("{val} - {bval}")));
                     },
                     Self::Float(bval) => {
                         Self::Float(*val as f64 - *bval)
@@ -736,7 +923,8 @@ impl Num {
                             }
                         }
                         Self::Units(res, base)
-                    }
+                    },
+                    Self::Decimal(_) | Self::BigInt(_) => return self.sub(&other.float_num()),
                 }
             },
             Self::Float(val) => {
@@ -756,7 +944,8 @@ impl Num {
                             }
                         }
                         Self::Units(res, base)
-                    }
+                    },
+                    Self::Decimal(_) | Self::BigInt(_) => return self.sub(&other.float_num()),
                 }
             },
             Self::Units(val, units) => {
@@ -792,26 +981,34 @@ impl Num {
                                     res = a - b;
                                 }
                                 if base.is_undefined() {
-                                    return Self::Float(res);
+                                    return Ok(Self::Float(res));
                                 }
-                                return Self::Units(res, base);
+                                return Ok(Self::Units(res, base));
                             }
                         }
                         // No units anymore...
                         Self::Float(res)
-                    }
+                    },
+                    Self::Decimal(_) | Self::BigInt(_) => return self.sub(&other.float_num()),
                 }
-            }
-        }
+            },
+            Self::Decimal(_) | Self::BigInt(_) => return self.float_num().sub(other),
+        })
     }
 
     /// Multiply two numbers.
-    pub fn mul(&self, other: &Self) -> Self {
-        match self {
+    pub fn mul(&self, other: &Self) -> Result<Self, Error> {
+        if let Some((a, b)) = self.bigint_operands(other) {
+            return Ok(Self::from(a * b));
+        }
+        if let Some((a, b)) = self.decimal_operands(other) {
+            return a.checked_mul(b).map(Self::Decimal).ok_or_else(|| Error::NumOverflow(format!("{a} * {b}")));
+        }
+        Ok(match self {
             Self::Int(val) => {
                 match other {
                     Self::Int(bval) => {
-                        Self::Int(*val * *bval)
+                        return val.checked_mul(*bval).map(Self::Int).ok_or_else(|| Error::NumOverflow(format!("{val} * {bval}")));
                     },
                     Self::Float(bval) => {
                         Self::Float(*val as f64 * *bval)
@@ -825,7 +1022,8 @@ impl Num {
                             }
                         }
                         Self::Units(res, base)
-                    }
+                    },
+                    Self::Decimal(_) | Self::BigInt(_) => return self.mul(&other.float_num()),
                 }
             },
             Self::Float(val) => {
@@ -845,7 +1043,8 @@ impl Num {
                             }
                         }
                         Self::Units(res, base)
-                    }
+                    },
+                    Self::Decimal(_) | Self::BigInt(_) => return self.mul(&other.float_num()),
                 }
             },
             Self::Units(val, units) => {
@@ -881,26 +1080,43 @@ impl Num {
                                     res = a * b;
                                 }
                                 if base.is_undefined() {
-                                    return Self::Float(res);
+                                    return Ok(Self::Float(res));
                                 }
-                                return Self::Units(res, base);
+                                return Ok(Self::Units(res, base));
                             }
                         }
                         // No units anymore...
                         Self::Float(res)
-                    }
+                    },
+                    Self::Decimal(_) | Self::BigInt(_) => return self.mul(&other.float_num()),
                 }
-            }
-        }
+            },
+            Self::Decimal(_) | Self::BigInt(_) => return self.float_num().mul(other),
+        })
     }
 
     /// Divide two numbers.
-    pub fn div(&self, other: &Self) -> Self {
-        match self {
+    pub fn div(&self, other: &Self) -> Result<Self, Error> {
+        if let Some((a, b)) = self.bigint_operands(other) {
+            if b.is_zero() {
+                return Err(Error::NumDivideByZero);
+            }
+            return Ok(Self::from(a / b));
+        }
+        if let Some((a, b)) = self.decimal_operands(other) {
+            if b.is_zero() {
+                return Err(Error::NumDivideByZero);
+            }
+            return a.checked_div(b).map(Self::Decimal).ok_or_else(|| Error::NumOverflow(format!("{a} / {b}")));
+        }
+        Ok(match self {
             Self::Int(val) => {
                 match other {
                     Self::Int(bval) => {
-                        Self::Int(*val / *bval)
+                        if *bval == 0 {
+                            return Err(Error::NumDivideByZero);
+                        }
+                        return val.checked_div(*bval).map(Self::Int).ok_or_else(|| Error::NumOverflow(format!("{val} / {bval}")));
                     },
                     Self::Float(bval) => {
                         Self::Float(*val as f64 / *bval)
@@ -914,7 +1130,8 @@ impl Num {
                             }
                         }
                         Self::Units(res, base)
-                    }
+                    },
+                    Self::Decimal(_) | Self::BigInt(_) => return self.div(&other.float_num()),
                 }
             },
             Self::Float(val) => {
@@ -934,7 +1151,8 @@ impl Num {
                             }
                         }
                         Self::Units(res, base)
-                    }
+                    },
+                    Self::Decimal(_) | Self::BigInt(_) => return self.div(&other.float_num()),
                 }
             },
             Self::Units(val, units) => {
@@ -970,26 +1188,43 @@ impl Num {
                                     res = a / b;
                                 }
                                 if base.is_undefined() {
-                                    return Self::Float(res);
+                                    return Ok(Self::Float(res));
                                 }
-                                return Self::Units(res, base);
+                                return Ok(Self::Units(res, base));
                             }
                         }
                         // No units anymore...
                         Self::Float(res)
-                    }
+                    },
+                    Self::Decimal(_) | Self::BigInt(_) => return self.div(&other.float_num()),
                 }
-            }
-        }
+            },
+            Self::Decimal(_) | Self::BigInt(_) => return self.float_num().div(other),
+        })
     }
 
     /// Rem (mod) between two numbers.
-    pub fn rem(&self, other: &Self) -> Self {
-        match self {
+    pub fn rem(&self, other: &Self) -> Result<Self, Error> {
+        if let Some((a, b)) = self.bigint_operands(other) {
+            if b.is_zero() {
+                return Err(Error::NumDivideByZero);
+            }
+            return Ok(Self::from(a % b));
+        }
+        if let Some((a, b)) = self.decimal_operands(other) {
+            if b.is_zero() {
+                return Err(Error::NumDivideByZero);
+            }
+            return a.checked_rem(b).map(Self::Decimal).ok_or_else(|| Error::NumOverflow(format!("{a} % {b}")));
+        }
+        Ok(match self {
             Self::Int(val) => {
                 match other {
                     Self::Int(bval) => {
-                        Self::Int(*val % *bval)
+                        if *bval == 0 {
+                            return Err(Error::NumDivideByZero);
+                        }
+                        return val.checked_rem(*bval).map(Self::Int).ok_or_else(|| Error::NumOverflow(format!("{val} % {bval}")));
                     },
                     Self::Float(bval) => {
                         Self::Float(*val as f64 % *bval)
@@ -1003,7 +1238,8 @@ impl Num {
                             }
                         }
                         Self::Units(res, base)
-                    }
+                    },
+                    Self::Decimal(_) | Self::BigInt(_) => return self.rem(&other.float_num()),
                 }
             },
             Self::Float(val) => {
@@ -1023,7 +1259,8 @@ impl Num {
                             }
                         }
                         Self::Units(res, base)
-                    }
+                    },
+                    Self::Decimal(_) | Self::BigInt(_) => return self.rem(&other.float_num()),
                 }
             },
             Self::Units(val, units) => {
@@ -1059,17 +1296,19 @@ impl Num {
                                     res = a % b;
                                 }
                                 if base.is_undefined() {
-                                    return Self::Float(res);
+                                    return Ok(Self::Float(res));
                                 }
-                                return Self::Units(res, base);
+                                return Ok(Self::Units(res, base));
                             }
                         }
                         // No units anymore...
                         Self::Float(res)
-                    }
+                    },
+                    Self::Decimal(_) | Self::BigInt(_) => return self.rem(&other.float_num()),
                 }
-            }
-        }
+            },
+            Self::Decimal(_) | Self::BigInt(_) => return self.float_num().rem(other),
+        })
     }
 
 
@@ -1078,13 +1317,17 @@ impl Num {
      *****************************************************************************/
     
     /// Bitwise and operation.
-    pub fn bit_and(&self, other: &Self) -> Self {
-        match self {
+    pub fn bit_and(&self, other: &Self) -> Result<Self, Error> {
+        if let Some((a, b)) = self.bigint_operands(other) {
+            return Ok(Self::from(a & b));
+        }
+        Ok(match self {
             Self::Int(val) => {
                 match other {
                     Self::Int(oval) => Self::Int(*val & *oval),
                     Self::Float(oval) => Self::Int(*val & *oval as i64),
                     Self::Units(oval, _) => Self::Int(*val & *oval as i64),
+                    Self::Decimal(_) | Self::BigInt(_) => return self.bit_and(&other.float_num()),
                 }
             },
             Self::Float(val) => {
@@ -1092,6 +1335,7 @@ impl Num {
                     Self::Int(oval) => Self::Int((*val as i64) & *oval),
                     Self::Float(oval) => Self::Int((*val as i64) & *oval as i64),
                     Self::Units(oval, _) => Self::Int((*val as i64) & *oval as i64),
+                    Self::Decimal(_) | Self::BigInt(_) => return self.bit_and(&other.float_num()),
                 }
             },
             Self::Units(val, units) => {
@@ -1099,19 +1343,25 @@ impl Num {
                     Self::Int(oval) => Self::Units(((*val as i64) & *oval) as f64, *units),
                     Self::Float(oval) => Self::Units(((*val as i64) & *oval as i64) as f64, *units),
                     Self::Units(oval, _) => Self::Units(((*val as i64) & *oval as i64) as f64, *units),
+                    Self::Decimal(_) | Self::BigInt(_) => return self.bit_and(&other.float_num()),
                 }
             },
-        }
+            Self::Decimal(_) | Self::BigInt(_) => return self.float_num().bit_and(other),
+        })
     }
 
     /// Bitwise or operation.
-    pub fn bit_or(&self, other: &Self) -> Self {
-        match self {
+    pub fn bit_or(&self, other: &Self) -> Result<Self, Error> {
+        if let Some((a, b)) = self.bigint_operands(other) {
+            return Ok(Self::from(a | b));
+        }
+        Ok(match self {
             Self::Int(val) => {
                 match other {
                     Self::Int(oval) => Self::Int(*val | *oval),
                     Self::Float(oval) => Self::Int(*val | *oval as i64),
                     Self::Units(oval, _) => Self::Int(*val | *oval as i64),
+                    Self::Decimal(_) | Self::BigInt(_) => return self.bit_or(&other.float_num()),
                 }
             },
             Self::Float(val) => {
@@ -1119,6 +1369,7 @@ impl Num {
                     Self::Int(oval) => Self::Int((*val as i64) | *oval),
                     Self::Float(oval) => Self::Int((*val as i64) | *oval as i64),
                     Self::Units(oval, _) => Self::Int((*val as i64) | *oval as i64),
+                    Self::Decimal(_) | Self::BigInt(_) => return self.bit_or(&other.float_num()),
                 }
             },
             Self::Units(val, units) => {
@@ -1126,19 +1377,25 @@ impl Num {
                     Self::Int(oval) => Self::Units(((*val as i64) | *oval) as f64, *units),
                     Self::Float(oval) => Self::Units(((*val as i64) | *oval as i64) as f64, *units),
                     Self::Units(oval, _) => Self::Units(((*val as i64) | *oval as i64) as f64, *units),
+                    Self::Decimal(_) | Self::BigInt(_) => return self.bit_or(&other.float_num()),
                 }
             },
-        }
+            Self::Decimal(_) | Self::BigInt(_) => return self.float_num().bit_or(other),
+        })
     }
 
     /// Bitwise xor operation.
-    pub fn bit_xor(&self, other: &Self) -> Self {
-        match self {
+    pub fn bit_xor(&self, other: &Self) -> Result<Self, Error> {
+        if let Some((a, b)) = self.bigint_operands(other) {
+            return Ok(Self::from(a ^ b));
+        }
+        Ok(match self {
             Self::Int(val) => {
                 match other {
                     Self::Int(oval) => Self::Int(*val ^ *oval),
                     Self::Float(oval) => Self::Int(*val ^ *oval as i64),
                     Self::Units(oval, _) => Self::Int(*val ^ *oval as i64),
+                    Self::Decimal(_) | Self::BigInt(_) => return self.bit_xor(&other.float_num()),
                 }
             },
             Self::Float(val) => {
@@ -1146,6 +1403,7 @@ impl Num {
                     Self::Int(oval) => Self::Int((*val as i64) ^ *oval),
                     Self::Float(oval) => Self::Int((*val as i64) ^ *oval as i64),
                     Self::Units(oval, _) => Self::Int((*val as i64) ^ *oval as i64),
+                    Self::Decimal(_) | Self::BigInt(_) => return self.bit_xor(&other.float_num()),
                 }
             },
             Self::Units(val, units) => {
@@ -1153,46 +1411,57 @@ impl Num {
                     Self::Int(oval) => Self::Units(((*val as i64) ^ *oval) as f64, *units),
                     Self::Float(oval) => Self::Units(((*val as i64) ^ *oval as i64) as f64, *units),
                     Self::Units(oval, _) => Self::Units(((*val as i64) ^ *oval as i64) as f64, *units),
+                    Self::Decimal(_) | Self::BigInt(_) => return self.bit_xor(&other.float_num()),
                 }
             },
-        }
+            Self::Decimal(_) | Self::BigInt(_) => return self.float_num().bit_xor(other),
+        })
     }
 
     /// Bitwise shift left operation.
-    pub fn bit_shl(&self, other: &Self) -> Self {
-        match self {
-            Self::Int(val) => {
-                match other {
-                    Self::Int(oval) => Self::Int(*val << *oval),
-                    Self::Float(oval) => Self::Int(*val << *oval as i64),
-                    Self::Units(oval, _) => Self::Int(*val << *oval as i64),
-                }
-            },
-            Self::Float(val) => {
-                match other {
-                    Self::Int(oval) => Self::Int((*val as i64) << *oval),
-                    Self::Float(oval) => Self::Int((*val as i64) << *oval as i64),
-                    Self::Units(oval, _) => Self::Int((*val as i64) << *oval as i64),
-                }
-            },
-            Self::Units(val, units) => {
-                match other {
-                    Self::Int(oval) => Self::Units(((*val as i64) << *oval) as f64, *units),
-                    Self::Float(oval) => Self::Units(((*val as i64) << *oval as i64) as f64, *units),
-                    Self::Units(oval, _) => Self::Units(((*val as i64) << *oval as i64) as f64, *units),
-                }
-            },
+    pub fn bit_shl(&self, other: &Self) -> Result<Self, Error> {
+        let shift = other.int();
+        if let Self::BigInt(val) = self {
+            let shift = usize::try_from(shift).map_err(|_| Error::NumOverflow(format!("{val} << {shift}")))?;
+            if val.bits().saturating_add(shift as u64) > MAX_BIGINT_BITS {
+                return Err(Error::NumOverflow(format!("{val} << {shift} is larger than {MAX_BIGINT_BITS} bits")));
+            }
+            return Ok(Self::from(val.as_ref() << shift));
+        }
+        if !(0..64).contains(&shift) {
+            return Err(Error::NumOverflow(format!("{} << {shift}", self.print())));
         }
+        // checked: bits shifted out (or into the sign bit) are an overflow
+        let shl = |val: i64| {
+            val.checked_shl(shift as u32)
+                .filter(|res| res >> shift == val)
+                .ok_or_else(|| Error::NumOverflow(format!("{val} << {shift}")))
+        };
+        Ok(match self {
+            Self::Int(val) => Self::Int(shl(*val)?),
+            Self::Float(val) => Self::Int(shl(*val as i64)?),
+            Self::Units(val, units) => Self::Units(shl(*val as i64)? as f64, *units),
+            Self::Decimal(_) | Self::BigInt(_) => return self.float_num().bit_shl(other),
+        })
     }
 
     /// Bitwise shift right operation.
-    pub fn bit_shr(&self, other: &Self) -> Self {
-        match self {
+    pub fn bit_shr(&self, other: &Self) -> Result<Self, Error> {
+        let shift = other.int();
+        if let Self::BigInt(val) = self {
+            let shift = usize::try_from(shift).map_err(|_| Error::NumOverflow(format!("{val} >> {shift}")))?;
+            return Ok(Self::from(val.as_ref() >> shift));
+        }
+        if !(0..64).contains(&shift) {
+            return Err(Error::NumOverflow(format!("{} >> {shift}", self.print())));
+        }
+        Ok(match self {
             Self::Int(val) => {
                 match other {
                     Self::Int(oval) => Self::Int(*val >> *oval),
                     Self::Float(oval) => Self::Int(*val >> *oval as i64),
                     Self::Units(oval, _) => Self::Int(*val >> *oval as i64),
+                    Self::Decimal(_) | Self::BigInt(_) => return self.bit_shr(&other.float_num()),
                 }
             },
             Self::Float(val) => {
@@ -1200,6 +1469,7 @@ impl Num {
                     Self::Int(oval) => Self::Int((*val as i64) >> *oval),
                     Self::Float(oval) => Self::Int((*val as i64) >> *oval as i64),
                     Self::Units(oval, _) => Self::Int((*val as i64) >> *oval as i64),
+                    Self::Decimal(_) | Self::BigInt(_) => return self.bit_shr(&other.float_num()),
                 }
             },
             Self::Units(val, units) => {
@@ -1207,9 +1477,11 @@ impl Num {
                     Self::Int(oval) => Self::Units(((*val as i64) >> *oval) as f64, *units),
                     Self::Float(oval) => Self::Units(((*val as i64) >> *oval as i64) as f64, *units),
                     Self::Units(oval, _) => Self::Units(((*val as i64) >> *oval as i64) as f64, *units),
+                    Self::Decimal(_) | Self::BigInt(_) => return self.bit_shr(&other.float_num()),
                 }
             },
-        }
+            Self::Decimal(_) | Self::BigInt(_) => return self.float_num().bit_shr(other),
+        })
     }
 
 
@@ -1224,10 +1496,16 @@ impl Num {
                 *v = v.abs();
             },
             Self::Int(v) => {
-                *v = v.abs();
+                *v = v.checked_abs().ok_or_else(|| Error::NumOverflow(format!("abs({v})")))?;
             },
             Self::Units(v, _) => {
                 *v = v.abs();
+            },
+            Self::Decimal(v) => {
+                *v = v.abs();
+            },
+            Self::BigInt(v) => {
+                *v = Arc::new(v.abs());
             }
         }
         Ok(())
@@ -1245,6 +1523,11 @@ impl Num {
             },
             Self::Units(v, _) => {
                 *v = v.sqrt();
+            },
+            Self::Decimal(_) |
+            Self::BigInt(_) => {
+                *self = self.float_num();
+                return self.sqrt();
             }
         }
         Ok(())
@@ -1262,6 +1545,11 @@ impl Num {
             },
             Self::Units(v, _) => {
                 *v = v.cbrt();
+            },
+            Self::Decimal(_) |
+            Self::BigInt(_) => {
+                *self = self.float_num();
+                return self.cbrt();
             }
         }
         Ok(())
@@ -1278,6 +1566,12 @@ impl Num {
             },
             Self::Units(v, _) => {
                 *v = v.floor();
+            },
+            Self::Decimal(v) => {
+                *v = v.floor();
+            },
+            Self::BigInt(_v) => {
+                // nothing to do here
             }
         }
         Ok(())
@@ -1294,6 +1588,12 @@ impl Num {
             },
             Self::Units(v, _) => {
                 *v = v.ceil();
+            },
+            Self::Decimal(v) => {
+                *v = v.ceil();
+            },
+            Self::BigInt(_v) => {
+                // nothing to do here
             }
         }
         Ok(())
@@ -1310,6 +1610,12 @@ impl Num {
             },
             Self::Units(v, _) => {
                 *v = v.trunc();
+            },
+            Self::Decimal(v) => {
+                *v = v.trunc();
+            },
+            Self::BigInt(_v) => {
+                // nothing to do here
             }
         }
         Ok(())
@@ -1326,6 +1632,12 @@ impl Num {
             },
             Self::Units(v, _) => {
                 *v = v.fract();
+            },
+            Self::Decimal(v) => {
+                *v = v.fract();
+            },
+            Self::BigInt(v) => {
+                *v = Arc::new(BigInt::zero()); // no fractional part of an integer
             }
         }
         Ok(())
@@ -1342,6 +1654,12 @@ impl Num {
             },
             Self::Units(v, _) => {
                 *v = v.signum();
+            },
+            Self::Decimal(v) => {
+                *v = v.signum();
+            },
+            Self::BigInt(v) => {
+                *v = Arc::new(v.signum());
             }
         }
         Ok(())
@@ -1358,6 +1676,11 @@ impl Num {
             },
             Self::Units(v, _) => {
                 *v = v.exp();
+            },
+            Self::Decimal(_) |
+            Self::BigInt(_) => {
+                *self = self.float_num();
+                return self.exp();
             }
         }
         Ok(())
@@ -1374,6 +1697,11 @@ impl Num {
             },
             Self::Units(v, _) => {
                 *v = v.exp2();
+            },
+            Self::Decimal(_) |
+            Self::BigInt(_) => {
+                *self = self.float_num();
+                return self.exp2();
             }
         }
         Ok(())
@@ -1390,6 +1718,10 @@ impl Num {
             },
             Self::Units(v, _) => {
                 v.is_nan()
+            },
+            Self::Decimal(_) |
+            Self::BigInt(_) => {
+                false
             }
         }
     }
@@ -1405,6 +1737,10 @@ impl Num {
             },
             Self::Units(v, _) => {
                 v.is_infinite()
+            },
+            Self::Decimal(_) |
+            Self::BigInt(_) => {
+                false
             }
         }
     }
@@ -1420,6 +1756,11 @@ impl Num {
             },
             Self::Units(v, _) => {
                 *v = v.ln();
+            },
+            Self::Decimal(_) |
+            Self::BigInt(_) => {
+                *self = self.float_num();
+                return self.ln();
             }
         }
         Ok(())
@@ -1441,6 +1782,11 @@ impl Num {
                     *v = v.sin();
                 }
                 *self = Self::Float(*v);
+            },
+            Self::Decimal(_) |
+            Self::BigInt(_) => {
+                *self = self.float_num();
+                return self.sin();
             }
         }
         Ok(())
@@ -1462,6 +1808,11 @@ impl Num {
                     *v = v.cos();
                 }
                 *self = Self::Float(*v);
+            },
+            Self::Decimal(_) |
+            Self::BigInt(_) => {
+                *self = self.float_num();
+                return self.cos();
             }
         }
         Ok(())
@@ -1483,6 +1834,11 @@ impl Num {
                     *v = v.tan();
                 }
                 *self = Self::Float(*v);
+            },
+            Self::Decimal(_) |
+            Self::BigInt(_) => {
+                *self = self.float_num();
+                return self.tan();
             }
         }
         Ok(())
@@ -1500,6 +1856,11 @@ impl Num {
             },
             Self::Units(v, _) => {
                 rad = v.asin();
+            },
+            Self::Decimal(_) |
+            Self::BigInt(_) => {
+                *self = self.float_num();
+                return self.asin();
             }
         }
         *self = Self::Units(rad, Units::Radians);
@@ -1518,6 +1879,11 @@ impl Num {
             },
             Self::Units(v, _) => {
                 rad = v.acos();
+            },
+            Self::Decimal(_) |
+            Self::BigInt(_) => {
+                *self = self.float_num();
+                return self.acos();
             }
         }
         *self = Self::Units(rad, Units::Radians);
@@ -1536,6 +1902,11 @@ impl Num {
             },
             Self::Units(v, _) => {
                 rad = v.atan();
+            },
+            Self::Decimal(_) |
+            Self::BigInt(_) => {
+                *self = self.float_num();
+                return self.atan();
             }
         }
         *self = Self::Units(rad, Units::Radians);
@@ -1553,6 +1924,11 @@ impl Num {
             },
             Self::Units(v, _) => {
                 *v = v.sinh();
+            },
+            Self::Decimal(_) |
+            Self::BigInt(_) => {
+                *self = self.float_num();
+                return self.sinh();
             }
         }
         Ok(())
@@ -1569,6 +1945,11 @@ impl Num {
             },
             Self::Units(v, _) => {
                 *v = v.cosh();
+            },
+            Self::Decimal(_) |
+            Self::BigInt(_) => {
+                *self = self.float_num();
+                return self.cosh();
             }
         }
         Ok(())
@@ -1585,6 +1966,11 @@ impl Num {
             },
             Self::Units(v, _) => {
                 *v = v.tanh();
+            },
+            Self::Decimal(_) |
+            Self::BigInt(_) => {
+                *self = self.float_num();
+                return self.tanh();
             }
         }
         Ok(())
@@ -1601,6 +1987,11 @@ impl Num {
             },
             Self::Units(v, _) => {
                 *v = v.asinh();
+            },
+            Self::Decimal(_) |
+            Self::BigInt(_) => {
+                *self = self.float_num();
+                return self.asinh();
             }
         }
         Ok(())
@@ -1617,6 +2008,11 @@ impl Num {
             },
             Self::Units(v, _) => {
                 *v = v.acosh();
+            },
+            Self::Decimal(_) |
+            Self::BigInt(_) => {
+                *self = self.float_num();
+                return self.acosh();
             }
         }
         Ok(())
@@ -1633,6 +2029,11 @@ impl Num {
             },
             Self::Units(v, _) => {
                 *v = v.atanh();
+            },
+            Self::Decimal(_) |
+            Self::BigInt(_) => {
+                *self = self.float_num();
+                return self.atanh();
             }
         }
         Ok(())
@@ -1649,13 +2050,39 @@ impl Num {
             },
             Self::Units(v, _) => {
                 *v = v.round();
+            },
+            Self::Decimal(v) => {
+                *v = v.round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero);
+            },
+            Self::BigInt(_v) => {
+                // rounded
             }
         }
         Ok(())
     }
 
-    /// Round 2.
-    pub fn round2(&mut self, digits: &Self) -> Result<(), Error> {
+    /// Round 2, with an optional rounding mode.
+    /// Without a mode, halfway values round away from zero. With one, floats are rounded as decimals (2.675 -> 2.68).
+    pub fn round2(&mut self, digits: &Self, mode: Option<RoundingStrategy>) -> Result<(), Error> {
+        let places = digits.int().clamp(0, 28) as u32;
+        if let Some(mode) = mode {
+            match &mut *self {
+                Self::Float(v) |
+                Self::Units(v, _) => {
+                    if let Some(dec) = Self::Float(*v).decimal() {
+                        *v = dec.round_dp_with_strategy(places, mode).to_f64().unwrap_or(*v);
+                    }
+                },
+                Self::Int(_) |
+                Self::BigInt(_) => {
+                    // rounded
+                },
+                Self::Decimal(v) => {
+                    *v = v.round_dp_with_strategy(places, mode);
+                }
+            }
+            return Ok(());
+        }
         match &mut *self {
             Self::Float(v) => {
                 let digits = digits.int();
@@ -1669,7 +2096,8 @@ impl Num {
                     *v = v.round();
                 }
             },
-            Self::Int(_v) => {
+            Self::Int(_) |
+            Self::BigInt(_) => {
                 // rounded
             },
             Self::Units(v, _) => {
@@ -1683,6 +2111,9 @@ impl Num {
                 } else {
                     *v = v.round();
                 }
+            },
+            Self::Decimal(v) => {
+                *v = v.round_dp_with_strategy(places, RoundingStrategy::MidpointAwayFromZero);
             }
         }
         Ok(())
@@ -1699,6 +2130,24 @@ impl Num {
             },
             Self::Units(v, _) => {
                 *v = v.powf(to.float(None));
+            },
+            Self::BigInt(v) => {
+                // exact for whole exponents
+                if let Self::Int(exp) = to {
+                    if let Ok(exp) = u32::try_from(*exp) {
+                        if v.bits().saturating_mul(exp as u64) > MAX_BIGINT_BITS {
+                            return Err(Error::NumOverflow(format!("{v} ** {exp} is larger than {MAX_BIGINT_BITS} bits")));
+                        }
+                        *v = Arc::new(v.as_ref().pow(exp));
+                        return Ok(());
+                    }
+                }
+                *self = self.float_num();
+                return self.pow(to);
+            },
+            Self::Decimal(_) => {
+                *self = self.float_num();
+                return self.pow(to);
             }
         }
         Ok(())
@@ -1715,6 +2164,11 @@ impl Num {
             },
             Self::Units(v, _) => {
                 *v = v.log(base.float(None));
+            },
+            Self::Decimal(_) |
+            Self::BigInt(_) => {
+                *self = self.float_num();
+                return self.log(base);
             }
         }
         Ok(())
@@ -1732,6 +2186,12 @@ impl Num {
             },
             Self::Units(v, _) => {
                 rad = v.atan2(base.float(None));
+            },
+            Self::Decimal(v) => {
+                rad = v.to_f64().unwrap_or_default().atan2(base.float(None));
+            },
+            Self::BigInt(v) => {
+                rad = v.to_f64().unwrap_or(f64::NAN).atan2(base.float(None));
             }
         }
         *self = Self::Units(rad, Units::Radians);
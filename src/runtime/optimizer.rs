@@ -0,0 +1,253 @@
+//
+// Copyright 2025 Formata, Inc. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use std::sync::Arc;
+use arcstr::ArcStr;
+use imbl::Vector;
+use crate::{model::{Func, Graph, SId, ABSTRACT_FUNC_ATTR, ASYNC_FUNC_ATTR, SELF_STR_KEYWORD, UNSELF_FUNC_ATTR}, runtime::{instruction::Instruction, instructions::{call::{FuncCall, NamedArg, TailCallIns}, ret::RetIns, Base}, Runtime, Val}};
+
+
+/// Instruction optimizer.
+/// Runs over parsed functions when the profile asks for it (prod).
+///
+/// Folds constant expressions (including unit conversions), removes branches with constant conditions,
+/// marks trivial getters to be inlined at call sites, and turns self-recursive tail calls into loops.
+/// Instructions opt in by implementing `Instruction::optimize`.
+pub struct Optimizer<'a> {
+    pub graph: &'a mut Graph,
+
+    /// Name of the function being optimized.
+    pub func_name: String,
+
+    /// Tag at the top of the function body, set once a tail call is turned into a loop.
+    pub top_tag: Option<ArcStr>,
+
+    /// Is a return at the current position a return from the function?
+    /// Not the case in expressions (block expressions return at the block) or in try-catch statements.
+    pub tail: bool,
+}
+impl<'a> Optimizer<'a> {
+    /// Optimize a parsed function in place.
+    pub fn optimize_func(graph: &'a mut Graph, name: &str, func: &mut Func) {
+        let mut optimizer = Self {
+            graph,
+            func_name: name.to_string(),
+            top_tag: None,
+            tail: true,
+        };
+        for param in func.params.iter_mut() {
+            if let Some(default) = &param.default {
+                param.default = Some(optimizer.expr(default));
+            }
+        }
+
+        let mut instructions = optimizer.optimize_all(&func.instructions);
+        if let Some(tag) = optimizer.top_tag.take() {
+            instructions.push_front(Arc::new(Base::Tag(tag)));
+        }
+        func.instructions = instructions;
+        func.inline = Self::getter_body(func);
+    }
+
+    /// Optimize an instruction.
+    pub fn optimize(&mut self, ins: &Arc<dyn Instruction>) -> Arc<dyn Instruction> {
+        ins.optimize(self).unwrap_or_else(|| ins.clone())
+    }
+
+    /// Optimize a list of statements.
+    pub fn optimize_all(&mut self, instructions: &Vector<Arc<dyn Instruction>>) -> Vector<Arc<dyn Instruction>> {
+        instructions.iter().map(|ins| self.optimize(ins)).collect()
+    }
+
+    /// Optimize an expression.
+    /// Returns within an expression do not return from the function.
+    pub fn expr(&mut self, ins: &Arc<dyn Instruction>) -> Arc<dyn Instruction> {
+        let tail = self.tail;
+        self.tail = false;
+        let res = self.optimize(ins);
+        self.tail = tail;
+        res
+    }
+
+    /// Optimize a list of statements that are not in a tail position (Ex. try-catch).
+    pub fn nontail_all(&mut self, instructions: &Vector<Arc<dyn Instruction>>) -> Vector<Arc<dyn Instruction>> {
+        let tail = self.tail;
+        self.tail = false;
+        let res = self.optimize_all(instructions);
+        self.tail = tail;
+        res
+    }
+
+    /// Constant value of an instruction.
+    /// Only literals that are safe to share between executions (scalars) are constants.
+    pub fn constant(ins: &Arc<dyn Instruction>) -> Option<&Val> {
+        if let Some(Base::Literal(val)) = ins.as_dyn_any().downcast_ref::<Base>() {
+            match val {
+                Val::Null |
+                Val::Bool(_) |
+                Val::Num(_) |
+                Val::Str(_) |
+                Val::Ver(..) |
+                Val::Time(..) => return Some(val),
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Evaluate a constant expression, replacing it with a literal.
+    /// Expressions that error are left alone, so that the error happens at runtime as it would have.
+    pub fn fold(&mut self, ins: Arc<dyn Instruction>) -> Arc<dyn Instruction> {
+        if let Ok(val) = Runtime::eval(self.graph, ins.clone()) {
+            let literal: Arc<dyn Instruction> = Arc::new(Base::Literal(val));
+            if Self::constant(&literal).is_some() {
+                return literal;
+            }
+        }
+        ins
+    }
+
+    /// Returned call to the function being optimized (by name or on self), in a tail position.
+    /// These get checked at runtime (the name could resolve to another function) and loop instead of calling.
+    pub fn tail_call(&mut self, call: &FuncCall) -> Option<Arc<dyn Instruction>> {
+        if !self.tail || call.func.is_some() || call.stack || call.cnull || call.oself.is_some() {
+            return None;
+        }
+        if let Some(search) = &call.search {
+            let self_path = format!("{}.{}", SELF_STR_KEYWORD.as_str(), &self.func_name);
+            if search.as_str() == self.func_name || search.as_str() == self_path {
+                if call.args.iter().any(|arg| arg.as_dyn_any().is::<NamedArg>()) {
+                    return None;
+                }
+                let tag = self.top_tag.get_or_insert_with(|| SId::default().as_ref().into()).clone();
+                return Some(Arc::new(TailCallIns {
+                    call: call.clone(),
+                    tag,
+                }));
+            }
+        }
+        None
+    }
+
+    /// Body of a trivial getter, inlined at call sites instead of setting up a call.
+    /// Trivial getters have no parameters and return a constant or a field on self.
+    fn getter_body(func: &Func) -> Option<Arc<dyn Instruction>> {
+        if !func.params.is_empty() || func.return_type.empty() || func.instructions.len() != 3 {
+            return None;
+        }
+        for attr in [&ASYNC_FUNC_ATTR, &ABSTRACT_FUNC_ATTR, &UNSELF_FUNC_ATTR] {
+            if func.attributes.contains_key(attr.as_str()) {
+                return None;
+            }
+        }
+        if !matches!(func.instructions[0].as_dyn_any().downcast_ref::<Base>(), Some(Base::PushSymbolScope)) ||
+            !matches!(func.instructions[2].as_dyn_any().downcast_ref::<Base>(), Some(Base::PopSymbolScope)) {
+            return None;
+        }
+        if let Some(ret) = func.instructions[1].as_dyn_any().downcast_ref::<RetIns>() {
+            if let Some(expr) = &ret.expr {
+                if Self::constant(expr).is_some() {
+                    return Some(expr.clone());
+                }
+                if let Some(Base::LoadVariable(path, false, false)) = expr.as_dyn_any().downcast_ref::<Base>() {
+                    if let Some(field) = path.strip_prefix("self.") {
+                        if !field.is_empty() && !field.contains('?') {
+                            return Some(expr.clone());
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{model::{Func, Graph, Profile}, runtime::{instructions::Base, Val}};
+
+    fn prod_graph(stof: &str) -> Graph {
+        let mut graph = Graph::default();
+        graph.parse_stof_src(stof, None, Profile::prod()).expect("failed to parse");
+        graph
+    }
+
+    #[test]
+    fn fold_constants() {
+        let mut graph = prod_graph(r#"
+            fn calc() -> float { return 2 * 3 + 4; }
+            fn dist() -> m { return 1km + 500m; }
+            fn text() -> str { return 'a' + 'b'; }
+        "#);
+        for (name, val) in [("calc", Val::from(10)), ("dist", Val::from(1500)), ("text", Val::from("ab"))] {
+            let func = Func::func_from_path(&graph, &format!("root.{name}"), None).unwrap();
+            let func = graph.get_stof_data::<Func>(&func).unwrap();
+            assert!(func.inline.is_some(), "{name} should be a constant");
+            let res = graph.call(&format!("root.{name}"), None, vec![]).unwrap();
+            assert_eq!(res, val);
+        }
+    }
+
+    #[test]
+    fn dead_branches() {
+        let mut graph = prod_graph(r#"
+            fn branch() -> str {
+                if (1 > 2) { return 'dead'; }
+                else if (true) { return 'live'; }
+                return 'after';
+            }
+        "#);
+        let dref = Func::func_from_path(&graph, "root.branch", None).unwrap();
+        let func = graph.get_stof_data::<Func>(&dref).unwrap();
+        for ins in &func.instructions {
+            assert!(ins.as_dyn_any().downcast_ref::<crate::runtime::instructions::ifs::IfIns>().is_none());
+        }
+        assert_eq!(graph.call("root.branch", None, vec![]).unwrap(), "live".into());
+    }
+
+    #[test]
+    fn tail_recursion() {
+        let mut graph = prod_graph(r#"
+            fn sum(n: int, acc: int = 0) -> int {
+                if (n < 1) return acc;
+                return self.sum(n - 1, acc + n);
+            }
+        "#);
+        let dref = Func::func_from_path(&graph, "root.sum", None).unwrap();
+        let func = graph.get_stof_data::<Func>(&dref).unwrap();
+        assert!(matches!(func.instructions[0].as_dyn_any().downcast_ref::<Base>(), Some(Base::Tag(_))));
+
+        // deeper than the max call stack depth
+        let res = graph.call("root.sum", None, vec![Val::from(50_000)]).unwrap();
+        assert_eq!(res, Val::from(1_250_025_000i64));
+    }
+
+    #[test]
+    fn inline_getters() {
+        let mut graph = prod_graph(r#"
+            name: 'stof'
+            fn getname() -> str { return self.name; }
+            fn test() -> str {
+                self.name = 'formata';
+                return self.getname();
+            }
+        "#);
+        let dref = Func::func_from_path(&graph, "root.getname", None).unwrap();
+        assert!(graph.get_stof_data::<Func>(&dref).unwrap().inline.is_some());
+        assert_eq!(graph.call("root.test", None, vec![]).unwrap(), "formata".into());
+    }
+}
@@ -0,0 +1,96 @@
+//
+// Copyright 2025 Formata, Inc. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use std::{cell::RefCell, cmp::Ordering, ops::Deref, sync::Arc};
+use imbl::{vector, Vector};
+use crate::{model::{CMP_FUNC_ATTR, DataRef, Graph, Prototype}, runtime::{instruction::Instruction, instructions::{call::FuncCall, Base}, proc::ProcEnv, Error, Val, ValRef, Variable}};
+
+
+/// Sort a list with a comparison that can call functions in the graph.
+/// Comparisons run on a synchronous env, and the first error stops the sort (remaining comparisons are equal) and is returned.
+pub(crate) fn sort_values(list: &mut Vector<ValRef<Val>>, env: &ProcEnv, graph: &mut Graph, cmp: impl FnMut(&ValRef<Val>, &ValRef<Val>, &mut ProcEnv, &mut Graph) -> Result<Ordering, Error>) -> Result<(), Error> {
+    // imbl sorts with an Fn comparison, so the mutable state lives in a cell
+    let state = RefCell::new((env.sync_env(), graph, cmp, None));
+    list.sort_by(|a, b| {
+        let mut state = state.borrow_mut();
+        let (env, graph, cmp, error) = &mut *state;
+        if error.is_some() {
+            return Ordering::Equal;
+        }
+        cmp(a, b, env, graph).unwrap_or_else(|err| {
+            *error = Some(err);
+            Ordering::Equal
+        })
+    });
+    let (.., error) = state.into_inner();
+    match error {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
+}
+
+
+/// Compare two values, letting prototype objects with a #[cmp] function order themselves.
+/// Ord for Val (sets and map keys) cannot see the graph, so objects there are ordered by ID instead.
+pub(crate) fn custom_ordering(lhs: &ValRef<Val>, rhs: &ValRef<Val>, env: &mut ProcEnv, graph: &mut Graph) -> Result<Ordering, Error> {
+    let lobj = lhs.read().try_obj();
+    if let Some(obj) = lobj {
+        if let Some(func) = Prototype::attribute_func(graph, &obj, &CMP_FUNC_ATTR, None) {
+            return call_cmp(func, Val::Obj(obj), rhs, env, graph);
+        }
+    }
+    let robj = rhs.read().try_obj();
+    if let Some(obj) = robj {
+        if let Some(func) = Prototype::attribute_func(graph, &obj, &CMP_FUNC_ATTR, None) {
+            return call_cmp(func, Val::Obj(obj), lhs, env, graph).map(Ordering::reverse);
+        }
+    }
+    Ok(lhs.cmp(rhs))
+}
+
+
+/// Call a #[cmp] function on an object with another value as the argument.
+fn call_cmp(func: DataRef, obj: Val, other: &ValRef<Val>, env: &mut ProcEnv, graph: &mut Graph) -> Result<Ordering, Error> {
+    let ins: Arc<dyn Instruction> = Arc::new(FuncCall {
+        func: Some(func),
+        search: None,
+        stack: false,
+        as_ref: false,
+        cnull: false,
+        args: vector![
+            Arc::new(Base::Variable(Variable::refval(other.clone()))) as Arc<dyn Instruction>,
+        ],
+        oself: Some(Arc::new(Base::Literal(obj))),
+    });
+    sync_ordering(ins, env, graph)
+}
+
+
+/// Synchronously execute a comparison function call, interpreting the int result as an ordering.
+/// Used by sorting, where the comparison has to complete within the sort itself.
+pub(crate) fn sync_ordering(ins: Arc<dyn Instruction>, env: &mut ProcEnv, graph: &mut Graph) -> Result<Ordering, Error> {
+    if let Some(mut func_ins) = ins.exec(env, graph)? {
+        func_ins.exec(env, graph, -1)?;
+        if let Some(ret) = env.stack.pop() {
+            if let Val::Num(num) = ret.val.read().deref() {
+                let i = num.int();
+                if i > 0 { return Ok(Ordering::Greater); }
+                if i < 0 { return Ok(Ordering::Less); }
+            }
+        }
+    }
+    Ok(Ordering::Equal)
+}
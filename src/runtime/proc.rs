@@ -17,7 +17,10 @@
 use std::{sync::Arc, time::Duration};
 use arcstr::ArcStr;
 use colored::Colorize;
-use crate::{model::{DataRef, Func, Graph, NodeRef, SId}, runtime::{instruction::{Instruction, Instructions}, table::SymbolTable, Error, Variable, WakeRef, Waker}};
+use rustc_hash::FxHashSet;
+use serde::{Deserialize, Serialize};
+use web_time::{SystemTime, UNIX_EPOCH};
+use crate::{model::{DataRef, Func, Graph, NodeRef, Quota, SandboxPolicy, SId, Usage}, runtime::{instruction::{Instruction, Instructions}, instructions::trycatch::FinallyFrame, table::SymbolTable, Error, Priority, Variable, WakeRef, Waker}};
 
 
 #[derive(Debug)]
@@ -31,18 +34,115 @@ pub enum ProcRes {
     SleepFor(Duration),
     Sleep(WakeRef),
     Exit(Option<SId>),
+    Cancel(SId),
+    WaitFor(SId, Duration),
+    Settle(Settle),
 }
 
 
-#[derive(Clone, Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// How a process waits on a group of promises.
+pub enum SettleMode {
+    /// First promise to complete or error.
+    Race,
+    /// First promise to complete (errors only if every promise errors).
+    Any,
+    /// Every promise, completed or errored.
+    All,
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Group of promises a process is waiting on.
+/// Values that are not promises are already settled.
+pub struct Settle {
+    pub mode: SettleMode,
+    pub pending: Vec<Option<SId>>,
+    pub results: Vec<Option<Result<Variable, Error>>>,
+    /// Indices in the order they settled (values that are not promises first).
+    pub order: Vec<usize>,
+}
+impl Settle {
+    /// Create a settle group from values (promises or otherwise).
+    pub fn new(mode: SettleMode, values: Vec<Variable>) -> Self {
+        let mut pending = Vec::with_capacity(values.len());
+        let mut results = Vec::with_capacity(values.len());
+        let mut order = Vec::new();
+        for var in values {
+            if let Some((pid, _)) = var.try_promise() {
+                pending.push(Some(pid));
+                results.push(None);
+            } else {
+                order.push(results.len());
+                pending.push(None);
+                results.push(Some(Ok(var)));
+            }
+        }
+        Self { mode, pending, results, order }
+    }
+
+    /// Settle a promise in this group.
+    pub fn settle(&mut self, index: usize, result: Result<Variable, Error>) {
+        self.pending[index] = None;
+        self.results[index] = Some(result);
+        self.order.push(index);
+    }
+
+    /// Outcome of this group, if settled.
+    /// Race and Any give the winning result (first to settle), All gives every result (in order).
+    pub fn outcome(&self) -> Option<Result<Vec<Result<Variable, Error>>, Error>> {
+        match self.mode {
+            SettleMode::Race => {
+                if let Some(Some(result)) = self.order.first().map(|index| &self.results[*index]) {
+                    return Some(result.clone().map(|var| vec![Ok(var)]));
+                }
+                if self.results.is_empty() {
+                    return Some(Ok(vec![]));
+                }
+                None
+            },
+            SettleMode::Any => {
+                let mut errors = Vec::new();
+                for result in self.order.iter().map(|index| &self.results[*index]) {
+                    match result {
+                        Some(Ok(var)) => return Some(Ok(vec![Ok(var.clone())])),
+                        Some(Err(error)) => errors.push(error.clone()),
+                        None => {},
+                    }
+                }
+                if errors.len() == self.results.len() {
+                    return Some(Err(Error::AllRejected(errors)));
+                }
+                None
+            },
+            SettleMode::All => {
+                if self.pending.iter().any(|pid| pid.is_some()) {
+                    return None;
+                }
+                Some(Ok(self.results.iter().flatten().cloned().collect()))
+            },
+        }
+    }
+}
+
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 /// Process Env.
 pub struct ProcEnv {
     pub pid: SId,
+    pub parent: Option<SId>, // process that spawned this one
+    #[serde(skip)]
     pub start_time: Option<web_time::Instant>,
     pub max_execution_time: Option<web_time::Duration>,
+    pub fuel: Option<u64>, // remaining instruction budget
+    pub quota: Quota,
+    pub usage: Usage,
+    pub sandbox: Option<Arc<SandboxPolicy>>,
+    pub priority: Priority,
     pub self_stack: Vec<NodeRef>,
     pub max_call_stack_depth: usize,
     pub call_stack: Vec<DataRef>,
+    pub call_frames: Vec<(usize, usize)>, // symbol scope & loop depths at each call (tail calls)
     pub new_stack: Vec<NodeRef>,
     pub max_stack_size: usize,
     pub stack: Vec<Variable>,
@@ -51,23 +151,38 @@ pub struct ProcEnv {
     pub return_stack: Vec<ArcStr>,
     pub ret_valid_stack: Vec<usize>,
     pub try_stack: Vec<ArcStr>,
+    pub error_stack: Vec<(Error, Vec<DataRef>)>, // caught errors & the call stack where they happened
+    #[serde(default)]
+    pub error_objs: Vec<NodeRef>, // error objects created for catch blocks (dropped when the try is left, if unreferenced)
+    pub finally_stack: Vec<FinallyFrame>,
+    pub finally_running: Vec<FinallyFrame>,
+    pub computed_reads: Vec<(DataRef, usize, FxHashSet<DataRef>)>,
     pub yield_enabled: bool,
 
     // Setting this will put the process into a waiting mode
+    #[serde(skip)]
     pub spawn: Option<Box<Process>>,
 
     #[cfg(feature = "tokio")]
+    #[serde(skip)]
     pub tokio_runtime: Option<tokio::runtime::Handle>,
 }
 impl Default for ProcEnv {
     fn default() -> Self {
         Self {
             pid: Default::default(),
+            parent: None,
             start_time: None,
             max_execution_time: Some(Duration::from_secs(120)),
+            fuel: None,
+            quota: Default::default(),
+            usage: Default::default(),
+            sandbox: None,
+            priority: Default::default(),
             self_stack: Default::default(),
             max_call_stack_depth: 10_000,
             call_stack: Default::default(),
+            call_frames: Default::default(),
             new_stack: Default::default(),
             max_stack_size: 100_000,
             stack: Default::default(),
@@ -76,6 +191,11 @@ impl Default for ProcEnv {
             return_stack: Default::default(),
             ret_valid_stack: Default::default(),
             try_stack: Default::default(),
+            error_stack: Default::default(),
+            error_objs: Default::default(),
+            finally_stack: Default::default(),
+            finally_running: Default::default(),
+            computed_reads: Default::default(),
             spawn: None,
             yield_enabled: true,
 
@@ -85,6 +205,29 @@ impl Default for ProcEnv {
     }
 }
 impl ProcEnv {
+    /// Env for synchronous function calls made in the middle of an instruction (sort/ordering comparisons).
+    /// Keeps this process's identity and limits, but gets its own stacks and cannot yield.
+    pub fn sync_env(&self) -> Self {
+        Self {
+            pid: self.pid.clone(),
+            parent: self.parent.clone(),
+            start_time: self.start_time,
+            max_execution_time: self.max_execution_time,
+            fuel: self.fuel,
+            quota: self.quota.clone(),
+            sandbox: self.sandbox.clone(),
+            priority: self.priority.clone(),
+            self_stack: self.self_stack.clone(),
+            max_call_stack_depth: self.max_call_stack_depth,
+            max_stack_size: self.max_stack_size,
+            yield_enabled: false,
+
+            #[cfg(feature = "tokio")]
+            tokio_runtime: self.tokio_runtime.clone(),
+            ..Default::default()
+        }
+    }
+
     // Get the current self ptr.
     pub fn self_ptr(&self) -> NodeRef {
         self.self_stack.last().unwrap().clone()
@@ -136,7 +279,7 @@ impl ProcEnv {
 }
 
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 /// Process.
 pub struct Process {
     pub env: ProcEnv,
@@ -144,7 +287,33 @@ pub struct Process {
     pub result: Option<Variable>,
     pub error: Option<Error>,
     pub waiting: Option<SId>,
+    #[serde(default)]
+    #[serde(deserialize_with = "deserialize_deadline")]
+    #[serde(serialize_with = "serialize_deadline")]
+    pub deadline: Option<web_time::Instant>, // when to stop waiting (see Promise.timeout)
+    pub settle: Option<Settle>,
+    pub cancelled: bool,
+}
+/// Deadlines are serialized as wall-clock times (duration since the Unix Epoch), so they keep the same time on resume.
+fn serialize_deadline<S>(deadline: &Option<web_time::Instant>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer {
+    let at = deadline.map(|deadline| {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        now + deadline.saturating_duration_since(web_time::Instant::now())
+    });
+    at.serialize(serializer)
 }
+fn deserialize_deadline<'de, D>(deserializer: D) -> Result<Option<web_time::Instant>, D::Error>
+    where
+        D: serde::Deserializer<'de> {
+    let at: Option<Duration> = Deserialize::deserialize(deserializer)?;
+    Ok(at.map(|at| {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        web_time::Instant::now() + at.saturating_sub(now)
+    }))
+}
+
 impl From<Instructions> for Process {
     fn from(value: Instructions) -> Self {
         Self {
@@ -16,15 +16,18 @@
 
 #[cfg(feature = "js")]
 use std::cell::RefCell;
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 use web_time::{SystemTime, UNIX_EPOCH};
 use colored::Colorize;
-use imbl::Vector;
+use imbl::{OrdMap, Vector};
 use rustc_hash::{FxHashMap, FxHashSet};
-use crate::{model::{DataRef, Func, Graph, SId}, runtime::{instruction::Instruction, instructions::{call::FuncCall, Base}, proc::{ProcRes, Process}, Error, Val, Waker}};
+use bytes::Bytes;
+use crate::{model::{DataRef, Func, Graph, SandboxPolicy, SId}, runtime::{Checkpoint, CheckpointState, instruction::Instruction, instructions::{call::FuncCall, trycatch::error_value, Base, CANCELLED}, proc::{ProcRes, Process, SettleMode}, Error, Scheduler, SchedulerMetrics, Val, ValRef, Variable, WakeRef, Waker}};
 
 #[cfg(feature = "tokio")]
 use parking_lot::RwLock;
+#[cfg(feature = "tokio")]
+use crate::runtime::WAKE_NOTIFY;
 
 #[cfg(feature = "tokio")]
 use lazy_static::lazy_static;
@@ -37,16 +40,20 @@ lazy_static! {
 
 /// Runtime.
 pub struct Runtime {
-    running: Vec<Process>, // TODO: split into high-priority and low-priority based on size to minimize mean running time?
+    running: Scheduler,
     waiting: FxHashMap<SId, Process>,
     pub done: FxHashMap<SId, Process>,
     pub errored: FxHashMap<SId, Process>,
+    pub cancelled: FxHashMap<SId, Option<SId>>, // cancelled process IDs & the processes that spawned them (until awaited)
 
     sleeping: FxHashMap<SId, Process>,
     wakers: Vec<Waker>,
 
-    pub done_callback: Option<Box<dyn FnMut(&Graph, &Process)->bool>>,
-    pub err_callback: Option<Box<dyn FnMut(&Graph, &Process)->bool>>,
+    pub done_callback: Option<Box<dyn FnMut(&Graph, &Process)->bool + Send>>,
+    pub err_callback: Option<Box<dyn FnMut(&Graph, &Process)->bool + Send>>,
+
+    /// Sandbox policy for processes run by this runtime (in addition to the graph's policy).
+    pub sandbox: Option<Arc<SandboxPolicy>>,
 
     #[cfg(feature = "tokio")]
     /// Optional tokio runtime handle (default exists, but still optional for flexibility & best practice in lib development).
@@ -61,10 +68,12 @@ impl Default for Runtime {
             waiting: Default::default(),
             done: Default::default(),
             errored: Default::default(),
+            cancelled: Default::default(),
             sleeping: Default::default(),
             wakers: Default::default(),
             done_callback: Default::default(),
             err_callback: Default::default(),
+            sandbox: None,
             tokio_runtime: Default::default(),
         };
 
@@ -95,10 +104,12 @@ impl Default for Runtime {
             waiting: Default::default(),
             done: Default::default(),
             errored: Default::default(),
+            cancelled: Default::default(),
             sleeping: Default::default(),
             wakers: Default::default(),
             done_callback: Default::default(),
             err_callback: Default::default(),
+            sandbox: None,
         }
     }
 }
@@ -112,6 +123,17 @@ impl Runtime {
         if proc.env.self_stack.is_empty() {
             proc.env.self_stack.push(graph.ensure_main_root());
         }
+
+        // graph limits apply to processes without their own
+        if proc.env.fuel.is_none() {
+            proc.env.fuel = graph.limits.fuel;
+        }
+        if proc.env.quota.is_empty() {
+            proc.env.quota = graph.limits.process;
+        }
+        if proc.env.sandbox.is_none() {
+            proc.env.sandbox = self.sandbox.clone();
+        }
         
         self.running.push(proc);
         id
@@ -119,272 +141,174 @@ impl Runtime {
 
     #[inline]
     /// Remove a process from running and return it.
-    fn remove_running(&mut self, id: &SId) -> Process {
-        let mut i: usize = 0;
-        for proc in &self.running {
-            if &proc.env.pid == id {
-                break;
-            }
-            i += 1;
-        }
-        self.running.swap_remove(i)
+    fn remove_running(&mut self, id: &SId) -> Option<Process> {
+        self.running.remove(id)
     }
 
     #[inline(always)]
     /// Move from running to done.
     fn move_running_to_done(&mut self, graph: &Graph, id: &SId) {
-        let proc = self.remove_running(id);
-        if let Some(cb) = &mut self.done_callback {
-            if cb(graph, &proc) {
-                self.done.insert(id.clone(), proc);
+        if let Some(proc) = self.remove_running(id) {
+            if let Some(cb) = &mut self.done_callback {
+                if cb(graph, &proc) {
+                    self.done.insert(id.clone(), proc);
+                } else {
+                    self.errored.insert(id.clone(), proc);
+                }
             } else {
-                self.errored.insert(id.clone(), proc);
+                self.done.insert(id.clone(), proc);
             }
-        } else {
-            self.done.insert(id.clone(), proc);
         }
     }
 
     #[inline(always)]
     /// Move from running to waiting.
     fn move_running_to_waiting(&mut self, id: &SId) {
-        let proc = self.remove_running(id);
-        self.waiting.insert(id.clone(), proc);
+        if let Some(proc) = self.remove_running(id) {
+            self.waiting.insert(id.clone(), proc);
+        }
     }
 
     #[inline(always)]
     /// Move from running to errored.
     fn move_running_to_error(&mut self, graph: &Graph, id: &SId) {
-        let proc = self.remove_running(id);
-        if let Some(cb) = &mut self.err_callback {
-            if cb(graph, &proc) {
-                self.errored.insert(id.clone(), proc);
+        if let Some(proc) = self.remove_running(id) {
+            if proc.cancelled && matches!(proc.error, Some(Error::Cancelled)) {
+                // cancellation is not a failure to report (the unwound process is dropped, keeping its ID for awaits)
+                self.cancelled.insert(id.clone(), proc.env.parent);
+            } else if let Some(cb) = &mut self.err_callback {
+                if cb(graph, &proc) {
+                    self.errored.insert(id.clone(), proc);
+                } else {
+                    self.done.insert(id.clone(), proc);
+                }
             } else {
-                self.done.insert(id.clone(), proc);
+                self.errored.insert(id.clone(), proc);
             }
-        } else {
-            self.errored.insert(id.clone(), proc);
         }
     }
 
     #[inline(always)]
     /// Move from running to sleeping.
     fn move_running_to_sleeping(&mut self, id: &SId) {
-        let proc = self.remove_running(id);
-        self.sleeping.insert(id.clone(), proc);
+        if let Some(proc) = self.remove_running(id) {
+            self.sleeping.insert(id.clone(), proc);
+        }
     }
 
     /// Run to completion.
     pub fn run_to_complete(&mut self, graph: &mut Graph) {
-        let mut to_done = Vec::new();
-        let mut to_wait = Vec::new();
-        let mut to_err = Vec::new();
-        let mut to_run = Vec::new();
-        let mut to_spawn = Vec::new();
-        let mut to_sleep = Vec::new();
-        let mut to_exit = Vec::new();
-        while !self.running.is_empty() || !self.sleeping.is_empty() {
-            // Check to see if any sleeping processes need to be woken up first
-            if !self.sleeping.is_empty() {
-                let mut to_wake = Vec::new();
-                let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
-                self.wakers.retain(|waker| {
-                    let woken = waker.woken(&now);
-                    if woken { to_wake.push(waker.pid.clone()); }
-                    !woken
-                });
-                for id in to_wake {
-                    if let Some(proc) = self.sleeping.remove(&id) {
-                        self.running.push(proc);
-                    }
-                }
-            }
-
-            // any limit < 1 will progress the process as much as possible per process until yield
-            // kept at 0 for now, but might change with future optimization or priority scheduling
-            let limit: i32 = 0;
-            let yield_enabled = !self.sleeping.is_empty() || self.running.len() > 1;
-            /* if !self.sleeping.is_empty() || self.running.len() > 1 {
-                let len = (self.sleeping.len() + self.running.len()) as i32;
-                limit = i32::max(10, 500 / len);
-            } */
-
-            for proc in self.running.iter_mut() {
-
-                #[cfg(feature = "tokio")]
-                {
-                    // make sure each process has a handle to the correct runtime if needed
-                    if self.tokio_runtime.is_some() {
-                        proc.env.tokio_runtime = self.tokio_runtime.clone();
-                    } else {
-                        proc.env.tokio_runtime = None;
-                    }
-                }
+        while self.run_single_step(graph) {}
+    }
 
-                proc.env.yield_enabled = yield_enabled;
-                match proc.progress(graph, limit) {
-                    Ok(state) => {
-                        match state {
-                            ProcRes::Exit(pid) => {
-                                if let Some(pid) = pid {
-                                    to_exit.push(pid);
-                                } else {
-                                    to_exit.push(proc.env.pid.clone());
-                                }
-                            },
-                            ProcRes::Wait(pid) => {
-                                proc.waiting = Some(pid);
-                                to_wait.push(proc.env.pid.clone());
-                            },
-                            ProcRes::Sleep(wref) => {
-                                to_sleep.push((proc.env.pid.clone(), proc.waker_ref(wref)));
-                            },
-                            ProcRes::SleepFor(dur) => {
-                                let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
-                                to_sleep.push((proc.env.pid.clone(), proc.waker_time(now + dur)));
-                            },
-                            ProcRes::Trace(n) => {
-                                let trace = proc.trace(&graph, n);
-                                println!("{trace}");
-                            },
-                            ProcRes::Peek(n) => {
-                                let trace = proc.peek(&graph, n);
-                                println!("{trace}");
-                            },
-                            ProcRes::More => {
-                                if let Some(spawn) = proc.env.spawn.take() {
-                                    // this is only set via the Spawn instruction, which creates a new PID each time
-                                    // therefore, don't have to worry about collisions here
-                                    to_spawn.push(spawn);
-                                }
-                            },
-                            ProcRes::Done => {
-                                if let Some(var) = proc.env.stack.pop() {
-                                    proc.result = Some(var);
-                                }
-                                to_done.push(proc.env.pid.clone());
-                            },
-                        }
-                    },
-                    Err(error) => {
-                        proc.error = Some(error);
-                        to_err.push(proc.env.pid.clone());
-                    }
-                }
+    /// Run until every process is either complete or asleep (Ex. waiting on I/O).
+    /// Returns true if there are sleeping processes left to run once woken (see Runtime::ready).
+    pub fn run_until_blocked(&mut self, graph: &mut Graph) -> bool {
+        while self.run_single_step(graph) {
+            if self.running.is_empty() {
+                return true;
             }
+        }
+        false
+    }
 
-            if !to_done.is_empty() {
-                for id in to_done.drain(..) {
-                    self.move_running_to_done(&graph, &id);
+    /// Is there work ready to run (a running process, a woken process, or a timed out process)?
+    pub fn ready(&self) -> bool {
+        if !self.running.is_empty() {
+            return true;
+        }
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        if self.wakers.iter().any(|waker| waker.woken(&now)) {
+            return true;
+        }
+        let instant = web_time::Instant::now();
+        if self.waiting.values().any(|proc| proc.deadline.is_some_and(|deadline| instant >= deadline)) {
+            return true;
+        }
+        self.waiting.values().chain(self.sleeping.values()).any(|proc| {
+            if let Some(max) = &proc.env.max_execution_time {
+                if let Some(start) = &proc.env.start_time {
+                    return &start.elapsed() > max;
                 }
             }
+            false
+        })
+    }
 
-            if !to_wait.is_empty() {
-                for id in to_wait.drain(..) {
-                    self.move_running_to_waiting(&id);
-                }
+    /// Time until the next timed wake up (a sleep ending or a process timing out), if any.
+    pub fn next_wake(&self) -> Option<Duration> {
+        let mut next: Option<Duration> = None;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        for waker in &self.wakers {
+            if let Some(at) = &waker.at {
+                let until = at.saturating_sub(now);
+                next = Some(next.map_or(until, |next| next.min(until)));
             }
-
-            if !to_err.is_empty() {
-                for id in to_err.drain(..) {
-                    self.move_running_to_error(&graph, &id);
+        }
+        for proc in self.waiting.values().chain(self.sleeping.values()) {
+            if let Some(max) = &proc.env.max_execution_time {
+                if let Some(start) = &proc.env.start_time {
+                    let until = max.saturating_sub(start.elapsed());
+                    next = Some(next.map_or(until, |next| next.min(until)));
                 }
             }
-
-            if !to_spawn.is_empty() {
-                for proc in to_spawn.drain(..) {
-                    self.push_running_proc(*proc, graph);
-                }
+            if let Some(deadline) = &proc.deadline {
+                let until = deadline.saturating_duration_since(web_time::Instant::now());
+                next = Some(next.map_or(until, |next| next.min(until)));
             }
+        }
+        next
+    }
 
-            if !to_sleep.is_empty() {
-                for (id, waker) in to_sleep.drain(..) {
-                    self.move_running_to_sleeping(&id);
-                    self.wakers.push(waker);
-                }
-            }
+    #[cfg(feature = "tokio")]
+    /// Run to completion on the host's tokio runtime (background tasks, like HTTP requests, are spawned on it too).
+    /// Yields to the host executor between steps, and when every process is asleep, parks until
+    /// a process is woken (see wake) or a timer is due, instead of polling.
+    ///
+    /// Dropping the future cancels every process in this runtime.
+    pub async fn run_async(&mut self, graph: &mut Graph) {
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            self.tokio_runtime = Some(handle);
+        }
 
-            for (id, waiting_proc) in &mut self.waiting {
-                if let Some(wait_id) = &waiting_proc.waiting {
-                    if let Some(done_proc) = self.done.remove(wait_id) {
-                        // If the completed process has a result, push that to the waiting processes stack
-                        if let Some(res) = done_proc.result {
-                            waiting_proc.env.stack.push(res);
-                        }
-                        to_run.push(id.clone());
-                    } else if let Some(error_proc) = self.errored.remove(wait_id) {
-                        // Propagate the error back to the awaiting process, so that it can optionally handle it itself
-                        println!("{} {}{}{}{}{}\n{}", "await error".red().bold(), "(".dimmed(), waiting_proc.env.pid.as_ref().dimmed().purple(), " waiting on ".dimmed(), error_proc.env.pid.as_ref().dimmed().cyan(), ")".dimmed(), error_proc.trace(&graph, 20));
-                        if let Some(error) = error_proc.error {
-                            waiting_proc.instructions.instructions.push_front(Arc::new(Base::CtrlAwaitError(Error::AwaitError(Box::new(error)))));
-                        }
-                        to_run.push(id.clone());
-                    } else if let Some(max) = &waiting_proc.env.max_execution_time {
-                        if let Some(start) = &waiting_proc.env.start_time {
-                            if &start.elapsed() > max {
-                                // If the waiting process has outlived its ttl, then error
-                                println!("{} {}{}{}{}{}", "await timeout error".red().bold(), "(".dimmed(), waiting_proc.env.pid.as_ref().dimmed().purple(), " waiting on ".dimmed(), wait_id.as_ref().dimmed().cyan(), ")".dimmed());
-                                waiting_proc.instructions.instructions.push_front(Arc::new(Base::CtrlAwaitError(Error::ExecutionTimeout)));
-                                to_run.push(id.clone());
-                            }
-                        }
-                    }
+        const YIELD_INTERVAL: Duration = Duration::from_millis(10);
+        let mut last_yield = web_time::Instant::now();
+        while self.run_single_step(graph) {
+            if self.ready() {
+                if last_yield.elapsed() >= YIELD_INTERVAL {
+                    tokio::task::yield_now().await;
+                    last_yield = web_time::Instant::now();
                 }
+                continue;
             }
 
-            for (id, sleeping_proc) in &mut self.sleeping {
-                if let Some(max) = &sleeping_proc.env.max_execution_time {
-                    if let Some(start) = &sleeping_proc.env.start_time {
-                        if &start.elapsed() > max {
-                            // If the sleeping process has outlived its ttl, then error
-                            println!("{} {}{}{}", "sleep timeout error".red().bold(), "(".dimmed(), sleeping_proc.env.pid.as_ref().dimmed().purple(), ")".dimmed());
-                            sleeping_proc.instructions.instructions.push_front(Arc::new(Base::CtrlAwaitError(Error::ExecutionTimeout)));
-                            to_run.push(id.clone());
-                        }
+            // register for wake notifications before checking again, so that a wake can't be missed
+            let notified = WAKE_NOTIFY.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            if !self.ready() {
+                if let Some(until) = self.next_wake() {
+                    tokio::select! {
+                        _ = &mut notified => {},
+                        _ = tokio::time::sleep(until) => {},
                     }
+                } else {
+                    notified.await;
                 }
             }
+            last_yield = web_time::Instant::now();
+        }
+    }
 
-            if !to_run.is_empty() {
-                for id in to_run.drain(..) {
-                    if let Some(mut proc) = self.waiting.remove(&id) {
-                        proc.waiting = None;
-                        self.running.push(proc);
-                    } else if let Some(mut proc) = self.sleeping.remove(&id) {
-                        proc.waiting = None;
-                        self.running.push(proc);
-                    }
-                }
-            }
+    /// Scheduler metrics (run queue depths, wait times, etc.).
+    pub fn metrics(&self) -> SchedulerMetrics {
+        self.running.metrics()
+    }
 
-            if !to_exit.is_empty() {
-                for id in to_exit.drain(..) {
-                    if let Some(proc) = self.waiting.remove(&id) {
-                        if let Some(cb) = &mut self.done_callback {
-                            if cb(graph, &proc) {
-                                self.done.insert(id, proc);
-                            } else {
-                                self.errored.insert(id, proc);
-                            }
-                        } else {
-                            self.done.insert(id, proc);
-                        }
-                    } else if let Some(proc) = self.sleeping.remove(&id) {
-                        if let Some(cb) = &mut self.done_callback {
-                            if cb(graph, &proc) {
-                                self.done.insert(id, proc);
-                            } else {
-                                self.errored.insert(id, proc);
-                            }
-                        } else {
-                            self.done.insert(id, proc);
-                        }
-                    } else {
-                        self.move_running_to_done(graph, &id);
-                    }
-                }
-            }
-        }
+    /// Set the number of instructions a normal priority process runs before giving others a turn.
+    /// A value less than 1 lets each process run until it yields on its own.
+    pub fn set_time_slice(&mut self, instructions: i32) {
+        self.running.time_slice = instructions;
     }
 
     /// Clear this runtime completely.
@@ -393,6 +317,7 @@ impl Runtime {
         self.waiting.clear();
         self.done.clear();
         self.errored.clear();
+        self.cancelled.clear();
     }
 
 
@@ -400,10 +325,9 @@ impl Runtime {
      * Singular & asynchronous.
      *****************************************************************************/
     
-    /// Run a single step of this runtime.
+    /// Run a single step (scheduling round) of this runtime.
+    /// Every running process gets a time slice, in priority order (see Scheduler).
     /// Returns true if there is another step to run.
-    /// N.B: Do not alter this function - change run_to_complete and copy changes here.
-    /// Make sure the limit is greater than 0 so that the step yields before complete if only one proc.
     pub fn run_single_step(&mut self, graph: &mut Graph) -> bool {
         let mut to_done = Vec::new();
         let mut to_wait = Vec::new();
@@ -412,6 +336,8 @@ impl Runtime {
         let mut to_spawn = Vec::new();
         let mut to_sleep = Vec::new();
         let mut to_exit = Vec::new();
+        let mut to_cancel = Vec::new();
+        let mut to_timeout = Vec::new();
         if !self.running.is_empty() || !self.sleeping.is_empty() {
             // Check to see if any sleeping processes need to be woken up first
             if !self.sleeping.is_empty() {
@@ -419,27 +345,32 @@ impl Runtime {
                 let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
                 self.wakers.retain(|waker| {
                     let woken = waker.woken(&now);
-                    if woken { to_wake.push(waker.pid.clone()); }
+                    if woken { to_wake.push((waker.at.unwrap_or(now), waker.pid.clone())); }
                     !woken
                 });
-                for id in to_wake {
+                // run in the order they were due to wake (a busy runtime can find several overdue at once)
+                to_wake.sort_by_key(|(at, _)| *at);
+                for (_, id) in to_wake {
                     if let Some(proc) = self.sleeping.remove(&id) {
                         self.running.push(proc);
                     }
                 }
             }
 
-            // any limit < 1 will progress the process as much as possible per process
-            // kept at 0 for now, but might change with future optimization or priority scheduling
-            let limit: i32 = 0;
+            // time slices only matter when there are other processes to run
+            // any limit < 1 will progress the process as much as possible until it yields
             let yield_enabled = !self.sleeping.is_empty() || self.running.len() > 1;
-            /* let mut limit: i32 = 100;
-            if !self.sleeping.is_empty() || self.running.len() > 1 {
-                let len = (self.sleeping.len() + self.running.len()) as i32;
-                limit = i32::max(10, 500 / len);
-            } */
-
-            for proc in self.running.iter_mut() {
+            let time_slice = if yield_enabled { self.running.time_slice } else { 0 };
+            let mut to_requeue = Vec::new();
+            let round = self.running.round();
+            for id in &round {
+                let proc;
+                if let Some(running) = self.running.get_mut(id) {
+                    proc = running;
+                } else {
+                    continue;
+                }
+                let limit = proc.env.priority.slice(time_slice);
 
                 #[cfg(feature = "tokio")]
                 {
@@ -457,6 +388,9 @@ impl Runtime {
                         match state {
                             ProcRes::Exit(pid) => {
                                 if let Some(pid) = pid {
+                                    if pid != proc.env.pid {
+                                        to_requeue.push(proc.env.pid.clone());
+                                    }
                                     to_exit.push(pid);
                                 } else {
                                     to_exit.push(proc.env.pid.clone());
@@ -466,6 +400,19 @@ impl Runtime {
                                 proc.waiting = Some(pid);
                                 to_wait.push(proc.env.pid.clone());
                             },
+                            ProcRes::WaitFor(pid, dur) => {
+                                proc.waiting = Some(pid);
+                                proc.deadline = Some(web_time::Instant::now() + dur);
+                                to_wait.push(proc.env.pid.clone());
+                            },
+                            ProcRes::Settle(settle) => {
+                                proc.settle = Some(settle);
+                                to_wait.push(proc.env.pid.clone());
+                            },
+                            ProcRes::Cancel(pid) => {
+                                to_requeue.push(proc.env.pid.clone());
+                                to_cancel.push((proc.env.pid.clone(), pid));
+                            },
                             ProcRes::Sleep(wref) => {
                                 to_sleep.push((proc.env.pid.clone(), proc.waker_ref(wref)));
                             },
@@ -476,12 +423,15 @@ impl Runtime {
                             ProcRes::Trace(n) => {
                                 let trace = proc.trace(&graph, n);
                                 println!("{trace}");
+                                to_requeue.push(proc.env.pid.clone());
                             },
                             ProcRes::Peek(n) => {
                                 let trace = proc.peek(&graph, n);
                                 println!("{trace}");
+                                to_requeue.push(proc.env.pid.clone());
                             },
                             ProcRes::More => {
+                                to_requeue.push(proc.env.pid.clone());
                                 if let Some(spawn) = proc.env.spawn.take() {
                                     // this is only set via the Spawn instruction, which creates a new PID each time
                                     // therefore, don't have to worry about collisions here
@@ -502,6 +452,9 @@ impl Runtime {
                     }
                 }
             }
+            for id in to_requeue {
+                self.running.enqueue(id);
+            }
 
             if !to_done.is_empty() {
                 for id in to_done.drain(..) {
@@ -535,7 +488,48 @@ impl Runtime {
             }
 
             for (id, waiting_proc) in &mut self.waiting {
-                if let Some(wait_id) = &waiting_proc.waiting {
+                if let Some(settle) = &mut waiting_proc.settle {
+                    // settle in the order the processes finished (the order they ran this round), so the first to finish wins a race
+                    let mut indices = (0..settle.pending.len()).collect::<Vec<_>>();
+                    indices.sort_by_key(|index| settle.pending[*index].as_ref().and_then(|pid| round.iter().position(|id| id == pid)));
+                    for index in indices {
+                        if let Some(pid) = settle.pending[index].clone() {
+                            if let Some(done_proc) = self.done.remove(&pid) {
+                                settle.settle(index, Ok(done_proc.result.unwrap_or(Variable::val(Val::Null))));
+                            } else if let Some(error_proc) = self.errored.remove(&pid) {
+                                settle.settle(index, Err(error_proc.error.unwrap_or(Error::NotImplemented)));
+                            } else if self.cancelled.remove(&pid).is_some() {
+                                settle.settle(index, Err(Error::Cancelled));
+                            }
+                        }
+                    }
+                    let mode = settle.mode;
+                    if let Some(outcome) = settle.outcome() {
+                        waiting_proc.settle = None;
+                        match outcome {
+                            Ok(mut results) => {
+                                if mode == SettleMode::All {
+                                    let mut list = Vector::default();
+                                    for result in results {
+                                        list.push_back(ValRef::new(settled_value(result)));
+                                    }
+                                    waiting_proc.env.stack.push(Variable::val(Val::List(list)));
+                                } else if let Some(result) = results.pop() {
+                                    match result {
+                                        Ok(var) => waiting_proc.env.stack.push(var),
+                                        Err(error) => waiting_proc.instructions.push_front(Arc::new(Base::CtrlAwaitError(Error::AwaitError(Box::new(error))))),
+                                    }
+                                } else {
+                                    waiting_proc.env.stack.push(Variable::val(Val::Null));
+                                }
+                            },
+                            Err(error) => {
+                                waiting_proc.instructions.push_front(Arc::new(Base::CtrlAwaitError(error)));
+                            },
+                        }
+                        to_run.push(id.clone());
+                    }
+                } else if let Some(wait_id) = &waiting_proc.waiting {
                     if let Some(done_proc) = self.done.remove(wait_id) {
                         // If the completed process has a result, push that to the waiting processes stack
                         if let Some(res) = done_proc.result {
@@ -546,15 +540,23 @@ impl Runtime {
                         // Propagate the error back to the awaiting process, so that it can optionally handle it itself
                         println!("{} {}{}{}{}{}\n{}", "await error".red().bold(), "(".dimmed(), waiting_proc.env.pid.as_ref().dimmed().purple(), " waiting on ".dimmed(), error_proc.env.pid.as_ref().dimmed().cyan(), ")".dimmed(), error_proc.trace(&graph, 20));
                         if let Some(error) = error_proc.error {
-                            waiting_proc.instructions.instructions.push_front(Arc::new(Base::CtrlAwaitError(Error::AwaitError(Box::new(error)))));
+                            waiting_proc.instructions.push_front(Arc::new(Base::CtrlAwaitError(Error::AwaitError(Box::new(error)))));
                         }
                         to_run.push(id.clone());
+                    } else if self.cancelled.remove(wait_id).is_some() {
+                        waiting_proc.instructions.push_front(Arc::new(Base::CtrlAwaitError(Error::AwaitError(Box::new(Error::Cancelled)))));
+                        to_run.push(id.clone());
+                    } else if waiting_proc.deadline.is_some_and(|deadline| web_time::Instant::now() >= deadline) {
+                        // Stopped waiting on a process that ran out of time (it gets cancelled)
+                        waiting_proc.instructions.push_front(Arc::new(Base::CtrlAwaitError(Error::TimedOut)));
+                        to_timeout.push(wait_id.clone());
+                        to_run.push(id.clone());
                     } else if let Some(max) = &waiting_proc.env.max_execution_time {
                         if let Some(start) = &waiting_proc.env.start_time {
                             if &start.elapsed() > max {
                                 // If the waiting process has outlived its ttl, then error
                                 println!("{} {}{}{}{}{}", "await timeout error".red().bold(), "(".dimmed(), waiting_proc.env.pid.as_ref().dimmed().purple(), " waiting on ".dimmed(), wait_id.as_ref().dimmed().cyan(), ")".dimmed());
-                                waiting_proc.instructions.instructions.push_front(Arc::new(Base::CtrlAwaitError(Error::ExecutionTimeout)));
+                                waiting_proc.instructions.push_front(Arc::new(Base::CtrlAwaitError(Error::ExecutionTimeout)));
                                 to_run.push(id.clone());
                             }
                         }
@@ -568,7 +570,7 @@ impl Runtime {
                         if &start.elapsed() > max {
                             // If the sleeping process has outlived its ttl, then error
                             println!("{} {}{}{}", "sleep timeout error".red().bold(), "(".dimmed(), sleeping_proc.env.pid.as_ref().dimmed().purple(), ")".dimmed());
-                            sleeping_proc.instructions.instructions.push_front(Arc::new(Base::CtrlAwaitError(Error::ExecutionTimeout)));
+                            sleeping_proc.instructions.push_front(Arc::new(Base::CtrlAwaitError(Error::ExecutionTimeout)));
                             to_run.push(id.clone());
                         }
                     }
@@ -579,6 +581,8 @@ impl Runtime {
                 for id in to_run.drain(..) {
                     if let Some(mut proc) = self.waiting.remove(&id) {
                         proc.waiting = None;
+                        proc.deadline = None;
+                        proc.settle = None;
                         self.running.push(proc);
                     } else if let Some(mut proc) = self.sleeping.remove(&id) {
                         proc.waiting = None;
@@ -614,11 +618,173 @@ impl Runtime {
                     }
                 }
             }
+
+            for id in to_timeout {
+                self.cancel(&id);
+            }
+
+            for (id, pid) in to_cancel {
+                let cancelled = self.cancel(&pid);
+                if let Some(proc) = self.running.get_mut(&id) {
+                    proc.env.stack.push(Variable::val(Val::Bool(cancelled)));
+                }
+            }
+
+            if self.running.is_empty() && self.sleeping.is_empty() && self.waiting.is_empty() {
+                // nothing is left to await spawned processes that were cancelled (top-level processes are kept for the host)
+                self.cancelled.retain(|_, parent| parent.is_none());
+            }
         }
 
         !self.running.is_empty() || !self.sleeping.is_empty()
     }
 
+    /// Cancel a process, along with the processes it spawned that have not completed.
+    /// Cancelled processes call the #[cancelled] functions on their current self object, then unwind with a Cancelled error (finally blocks still run).
+    /// Returns false if the process is not running (Ex. already complete or cancelled).
+    pub fn cancel(&mut self, pid: &SId) -> bool {
+        // index the spawned processes by their parent, so that the whole tree is found in one pass
+        let mut children: FxHashMap<&SId, Vec<&SId>> = FxHashMap::default();
+        for proc in self.running.iter().chain(self.waiting.values()).chain(self.sleeping.values()) {
+            if let Some(parent) = &proc.env.parent {
+                children.entry(parent).or_default().push(&proc.env.pid);
+            }
+        }
+        let mut cancel = vec![pid.clone()];
+        let mut seen = FxHashSet::default();
+        seen.insert(pid.clone());
+        let mut index = 0;
+        while index < cancel.len() {
+            if let Some(spawned) = children.get(&cancel[index]) {
+                for child in spawned {
+                    if seen.insert((*child).clone()) {
+                        cancel.push((*child).clone());
+                    }
+                }
+            }
+            index += 1;
+        }
+
+        let mut cancelled = false;
+        let mut sleepers = FxHashSet::default();
+        for id in cancel {
+            let mut proc;
+            if let Some(waiting) = self.waiting.remove(&id) {
+                proc = waiting;
+            } else if let Some(sleeping) = self.sleeping.remove(&id) {
+                proc = sleeping;
+                sleepers.insert(id.clone());
+            } else if let Some(running) = self.running.get_mut(&id) {
+                if !running.cancelled {
+                    running.cancelled = true;
+                    running.instructions.push_front(Arc::new(Base::CtrlAwaitError(Error::Cancelled)));
+                    running.instructions.push_front(CANCELLED.clone());
+                    cancelled = cancelled || &id == pid;
+                }
+                continue;
+            } else {
+                continue;
+            }
+            proc.waiting = None;
+            proc.deadline = None;
+            proc.settle = None;
+            proc.cancelled = true;
+            proc.instructions.push_front(Arc::new(Base::CtrlAwaitError(Error::Cancelled)));
+            proc.instructions.push_front(CANCELLED.clone());
+            self.running.push(proc);
+            cancelled = cancelled || &id == pid;
+        }
+        if !sleepers.is_empty() {
+            self.wakers.retain(|waker| !sleepers.contains(&waker.pid));
+        }
+        cancelled
+    }
+
+    /// Checkpoint a process (running, waiting, or sleeping) to bytes, so that it can be resumed later (see Runtime::resume).
+    /// The process keeps going in this runtime, so checkpoint processes that are about to sleep or wait for a long time.
+    ///
+    /// The process references the graph it is running on, so persist the graph as well (Ex. "bstf").
+    pub fn checkpoint(&self, pid: &SId) -> Result<Bytes, Error> {
+        let checkpoint;
+        if let Some(proc) = self.running.get(pid) {
+            checkpoint = Checkpoint { state: CheckpointState::Running, proc: proc.clone() };
+        } else if let Some(proc) = self.waiting.get(pid) {
+            checkpoint = Checkpoint { state: CheckpointState::Waiting, proc: proc.clone() };
+        } else if let Some(proc) = self.sleeping.get(pid) {
+            let at = self.wakers.iter().find(|waker| &waker.pid == pid).and_then(|waker| waker.at);
+            checkpoint = Checkpoint { state: CheckpointState::Sleeping(at), proc: proc.clone() };
+        } else {
+            return Err(Error::CheckpointProcDne(pid.to_string()));
+        }
+        checkpoint.to_bytes()
+    }
+
+    /// Resume a checkpointed process (see Runtime::checkpoint) in this runtime, returning its PID.
+    /// Timed sleeps and await deadlines keep their original wall-clock times and the process gets a fresh execution time budget.
+    ///
+    /// Resume the processes it awaits first, otherwise the await throws a CheckpointProcDne error.
+    /// Processes asleep on an external event (Ex. an HTTP response) stay asleep until the host wakes them (see Runtime::wake_ref).
+    pub fn resume(&mut self, bytes: impl AsRef<[u8]>, graph: &mut Graph) -> Result<SId, Error> {
+        let mut checkpoint = Checkpoint::from_bytes(bytes.as_ref())?;
+        let pid = checkpoint.proc.env.pid.clone();
+        if self.running.contains(&pid) || self.waiting.contains_key(&pid) || self.sleeping.contains_key(&pid) {
+            return Err(Error::CheckpointPidExists(pid.to_string()));
+        }
+
+        checkpoint.proc.env.start_time = None;
+        let mut state = checkpoint.state;
+        if let CheckpointState::Waiting = state {
+            let proc = &mut checkpoint.proc;
+            if let Some(settle) = &mut proc.settle {
+                for index in 0..settle.pending.len() {
+                    if let Some(wait_id) = settle.pending[index].clone() {
+                        if !self.contains(&wait_id) {
+                            settle.settle(index, Err(Error::CheckpointProcDne(wait_id.to_string())));
+                        }
+                    }
+                }
+            } else if let Some(wait_id) = proc.waiting.clone() {
+                if !self.contains(&wait_id) {
+                    // nothing here will complete the awaited process, so the await throws instead of waiting forever
+                    proc.waiting = None;
+                    proc.deadline = None;
+                    proc.instructions.push_front(Arc::new(Base::CtrlAwaitError(Error::AwaitError(Box::new(Error::CheckpointProcDne(wait_id.to_string()))))));
+                    state = CheckpointState::Running;
+                }
+            }
+        }
+        let waker = match &state {
+            CheckpointState::Sleeping(Some(at)) => checkpoint.proc.waker_time(*at),
+            _ => checkpoint.proc.waker_ref(Default::default()),
+        };
+        self.push_running_proc(checkpoint.proc, graph);
+        match state {
+            CheckpointState::Waiting => {
+                self.move_running_to_waiting(&pid);
+            },
+            CheckpointState::Sleeping(_) => {
+                self.move_running_to_sleeping(&pid);
+                self.wakers.push(waker);
+            },
+            CheckpointState::Running => {},
+        }
+        Ok(pid)
+    }
+
+    /// Does this runtime know about a process (running, waiting, sleeping, or complete)?
+    fn contains(&self, pid: &SId) -> bool {
+        self.running.contains(pid) || self.waiting.contains_key(pid) || self.sleeping.contains_key(pid) ||
+            self.done.contains_key(pid) || self.errored.contains_key(pid) || self.cancelled.contains_key(pid)
+    }
+
+    /// Wake reference for a process asleep on an external event, if any (Ex. a resumed checkpoint).
+    /// The host reconnects the event by calling "wake" with it once the event happens.
+    pub fn wake_ref(&self, pid: &SId) -> Option<WakeRef> {
+        self.wakers.iter()
+            .find(|waker| &waker.pid == pid && waker.at.is_none())
+            .map(|waker| waker.with.clone())
+    }
+
     #[cfg(feature = "js")]
     /// Single step async.
     pub async fn async_single_step_with_gate(&mut self, graph: &RefCell<Graph>, yield_to_outer: bool, acquire: &js_sys::Function, release: &js_sys::Function) -> bool {
@@ -760,8 +926,8 @@ impl Runtime {
 
         let mut output = String::from("");
         for (_, success) in &rt.done {
-            if success.env.call_stack.len() < 1 && success.instructions.executed.len() > 0 {
-                let func = success.instructions.executed[0].clone();
+            if success.env.call_stack.len() < 1 && success.instructions.first.is_some() {
+                let func = success.instructions.first_executed().unwrap();
                 if let Some(func) = func.as_dyn_any().downcast_ref::<Base>() {
                     match func {
                         Base::Literal(val) => {
@@ -863,8 +1029,8 @@ impl Runtime {
 
         let mut output = String::from("");
         for (_, success) in &rt.done {
-            if success.env.call_stack.len() < 1 && success.instructions.executed.len() > 0 {
-                let func = success.instructions.executed[0].clone();
+            if success.env.call_stack.len() < 1 && success.instructions.first.is_some() {
+                let func = success.instructions.first_executed().unwrap();
                 if let Some(func) = func.as_dyn_any().downcast_ref::<Base>() {
                     match func {
                         Base::Literal(val) => {
@@ -969,8 +1135,8 @@ impl Runtime {
         rt.run_to_complete(graph);
         let mut output = String::from("");
         for (_, success) in &rt.done {
-            if success.env.call_stack.len() < 1 && success.instructions.executed.len() > 0 {
-                let func = success.instructions.executed[0].clone();
+            if success.env.call_stack.len() < 1 && success.instructions.first.is_some() {
+                let func = success.instructions.first_executed().unwrap();
                 if let Some(func) = func.as_dyn_any().downcast_ref::<Base>() {
                     match func {
                         Base::Literal(val) => {
@@ -1072,8 +1238,8 @@ impl Runtime {
         // Create and set callbacks for printing successes and failures
         rt.done_callback = Some(Box::new(|graph, success| {
             // if this is top-level and executed something, print out a success message
-            if success.env.call_stack.len() < 1 && success.instructions.executed.len() > 0 {
-                let func = success.instructions.executed[0].clone();
+            if success.env.call_stack.len() < 1 && success.instructions.first.is_some() {
+                let func = success.instructions.first_executed().unwrap();
                 if let Some(func) = func.as_dyn_any().downcast_ref::<Base>() {
                     match func {
                         Base::Literal(val) => {
@@ -1108,7 +1274,8 @@ impl Runtime {
         }));
         rt.err_callback = Some(Box::new(|graph, errored| {
             // if this is top-level and executed something, print out an error message
-            if errored.env.call_stack.len() > 0 {
+            // spawned processes give their errors to whoever awaits them instead
+            if errored.env.call_stack.len() > 0 && errored.env.parent.is_none() {
                 let func_ref = errored.env.call_stack.first().unwrap();
                 if let Some(name) = func_ref.data_name(graph) {
                     if let Some(func) = graph.get_stof_data::<Func>(&func_ref) {
@@ -1154,8 +1321,8 @@ impl Runtime {
                     if let Some(_err) = &failure.error {
                         err_str = failure.trace(graph, 10); // contains the error
                     }
-                } else if failure.env.call_stack.len() < 1 && failure.instructions.executed.len() > 0 {
-                    let func = failure.instructions.executed[0].clone();
+                } else if failure.env.call_stack.len() < 1 && failure.instructions.first.is_some() {
+                    let func = failure.instructions.first_executed().unwrap();
                     if let Some(func) = func.as_dyn_any().downcast_ref::<Base>() {
                         match func {
                             Base::Literal(val) => {
@@ -1295,6 +1462,8 @@ impl Runtime {
             } else {
                 Err(Error::NotImplemented)
             }
+        } else if runtime.cancelled.contains_key(&pid) {
+            Err(Error::Cancelled)
         } else {
             Err(Error::NotImplemented)
         }
@@ -1337,6 +1506,8 @@ impl Runtime {
             } else {
                 Err(Error::NotImplemented)
             }
+        } else if runtime.cancelled.contains_key(&pid) {
+            Err(Error::Cancelled)
         } else {
             Err(Error::NotImplemented)
         }
@@ -1346,21 +1517,30 @@ impl Runtime {
     /// Evaluate a single instruction.
     /// Creates a new runtime and process just for this (lightweight).
     /// Use this while parsing if needed.
+    ///
+    /// With tokio, the runtime is driven by the host executor (see Runtime::run_async).
+    /// Dropping the future cancels the evaluation.
     pub async fn async_eval(graph: &mut Graph, instruction: Arc<dyn Instruction>) -> Result<Val, Error> {
         let mut runtime = Self::default();
         let proc = Process::from(instruction);
         let pid = proc.env.pid.clone();
         
         runtime.push_running_proc(proc, graph);
-        
-        const YIELD_INTERVAL_MS: u64 = 20;
-        let mut yield_to_outer = false;
-        let mut last_yield = web_time::Instant::now();
-        while runtime.async_single_step(graph, yield_to_outer).await {
-            yield_to_outer = false;
-            if last_yield.elapsed().as_millis() as u64 >= YIELD_INTERVAL_MS {
-                yield_to_outer = true;
-                last_yield = web_time::Instant::now();
+
+        #[cfg(feature = "tokio")]
+        runtime.run_async(graph).await;
+
+        #[cfg(not(feature = "tokio"))]
+        {
+            const YIELD_INTERVAL_MS: u64 = 20;
+            let mut yield_to_outer = false;
+            let mut last_yield = web_time::Instant::now();
+            while runtime.async_single_step(graph, yield_to_outer).await {
+                yield_to_outer = false;
+                if last_yield.elapsed().as_millis() as u64 >= YIELD_INTERVAL_MS {
+                    yield_to_outer = true;
+                    last_yield = web_time::Instant::now();
+                }
             }
         }
 
@@ -1376,6 +1556,8 @@ impl Runtime {
             } else {
                 Err(Error::NotImplemented)
             }
+        } else if runtime.cancelled.contains_key(&pid) {
+            Err(Error::Cancelled)
         } else {
             Err(Error::NotImplemented)
         }
@@ -1388,3 +1570,87 @@ impl Runtime {
         *tokio_handle = Some(runtime);
     }
 }
+
+
+/// Value for a settled promise (see Promise.all_settled).
+/// Ex. { 'status': 'fulfilled', 'value': 42 } or { 'status': 'rejected', 'error': <error> }.
+fn settled_value(result: Result<Variable, Error>) -> Val {
+    let mut map = OrdMap::default();
+    match result {
+        Ok(var) => {
+            map.insert(ValRef::new(Val::Str("status".into())), ValRef::new(Val::Str("fulfilled".into())));
+            map.insert(ValRef::new(Val::Str("value".into())), ValRef::new(var.get()));
+        },
+        Err(error) => {
+            map.insert(ValRef::new(Val::Str("status".into())), ValRef::new(Val::Str("rejected".into())));
+            map.insert(ValRef::new(Val::Str("error".into())), ValRef::new(error_value(&error)));
+        },
+    }
+    Val::Map(map)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+    use crate::{model::{Graph, Profile}, runtime::{instruction::Instruction, instructions::call::FuncCall, proc::Process, Runtime, Val}};
+
+    #[test]
+    fn race_overdue_sleepers() {
+        let mut graph = Graph::default();
+        graph.parse_stof_src(r#"
+            fn race() -> str {
+                return await Promise.race([async { sleep(30ms); 'slow' }, async { sleep(10ms); 'fast' }]);
+            }
+        "#, None, Profile::test()).unwrap();
+
+        let mut runtime = Runtime::default();
+        let pid = runtime.push_running_proc(Process::from(Arc::new(FuncCall {
+            as_ref: false,
+            cnull: false,
+            stack: false,
+            func: None,
+            search: Some("root.race".into()),
+            args: Default::default(),
+            oself: None,
+        }) as Arc<dyn Instruction>), &mut graph);
+        while runtime.ready() {
+            runtime.run_single_step(&mut graph);
+        }
+
+        // a busy host can find both sleepers overdue at once, and the one due first still wins
+        std::thread::sleep(Duration::from_millis(50));
+        runtime.run_to_complete(&mut graph);
+        let res = runtime.done.remove(&pid).and_then(|proc| proc.result).map(|var| var.get());
+        assert_eq!(res, Some(Val::from("fast")));
+    }
+
+    #[test]
+    fn prune_cancelled() {
+        let mut graph = Graph::default();
+        graph.parse_stof_src(r#"
+            fn cancel() -> bool {
+                const promise = async { sleep(10s); };
+                sleep(5ms);
+                return promise.cancel();
+            }
+        "#, None, Profile::test()).unwrap();
+
+        let mut runtime = Runtime::default();
+        let pid = runtime.push_running_proc(Process::from(Arc::new(FuncCall {
+            as_ref: false,
+            cnull: false,
+            stack: false,
+            func: None,
+            search: Some("root.cancel".into()),
+            args: Default::default(),
+            oself: None,
+        }) as Arc<dyn Instruction>), &mut graph);
+        runtime.run_to_complete(&mut graph);
+
+        // nothing is left to await the cancelled process, so its ID is not kept around
+        let res = runtime.done.remove(&pid).and_then(|proc| proc.result).map(|var| var.get());
+        assert_eq!(res, Some(Val::Bool(true)));
+        assert!(runtime.cancelled.is_empty());
+    }
+}
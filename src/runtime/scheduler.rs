@@ -0,0 +1,329 @@
+//
+// Copyright 2025 Formata, Inc. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+
+use std::collections::VecDeque;
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use web_time::{Duration, Instant};
+use crate::{model::SId, runtime::{proc::Process, Val}};
+
+
+/// Priority attribute for async functions (Ex. #[priority('high')] async fn work() {}).
+/// The value of the async attribute also works (Ex. #[async('low')]).
+pub const PRIORITY_FUNC_ATTR: &str = "priority";
+
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+/// Process priority.
+pub enum Priority {
+    High = 0,
+    #[default]
+    Normal = 1,
+    Low = 2,
+}
+impl Priority {
+    /// All priorities, in the order they run.
+    pub const ALL: [Priority; 3] = [Priority::High, Priority::Normal, Priority::Low];
+
+    /// Priority from a value ("high", "normal", or "low").
+    pub fn from_val(val: &Val) -> Option<Self> {
+        match val {
+            Val::Str(name) => {
+                match name.to_lowercase().as_str() {
+                    "high" => Some(Self::High),
+                    "normal" => Some(Self::Normal),
+                    "low" => Some(Self::Low),
+                    _ => None,
+                }
+            },
+            _ => None,
+        }
+    }
+
+    /// Instruction limit for a time slice at this priority, given the normal priority time slice.
+    /// High priority processes get 4x the normal slice, low priority get a 1/4.
+    pub fn slice(&self, time_slice: i32) -> i32 {
+        if time_slice < 1 {
+            return 0;
+        }
+        match self {
+            Self::High => time_slice.saturating_mul(4),
+            Self::Normal => time_slice,
+            Self::Low => i32::max(1, time_slice / 4),
+        }
+    }
+
+    /// Priority from function attributes, if set.
+    pub fn from_attributes(attributes: &FxHashMap<String, Val>) -> Option<Self> {
+        for attr in [PRIORITY_FUNC_ATTR, "async"] {
+            if let Some(val) = attributes.get(attr) {
+                if let Some(priority) = Self::from_val(val) {
+                    return Some(priority);
+                }
+            }
+        }
+        None
+    }
+}
+
+
+#[derive(Debug, Clone, Default, PartialEq)]
+/// Scheduler metrics, indexed by priority (Priority as usize).
+pub struct SchedulerMetrics {
+    /// Runnable processes waiting in each run queue.
+    pub queue_depth: [usize; 3],
+
+    /// Most runnable processes queued at once (all priorities).
+    pub max_queue_depth: usize,
+
+    /// Time slices given to processes.
+    pub slices: u64,
+
+    /// Number of times a process waited in each run queue.
+    pub waits: [u64; 3],
+
+    /// Total time processes waited in each run queue before running.
+    pub total_wait: [Duration; 3],
+
+    /// Longest time a process waited in each run queue before running.
+    pub max_wait: [Duration; 3],
+}
+impl SchedulerMetrics {
+    /// Mean time processes of a priority wait in the run queue.
+    pub fn mean_wait(&self, priority: Priority) -> Duration {
+        let index = priority as usize;
+        if self.waits[index] < 1 {
+            return Duration::ZERO;
+        }
+        self.total_wait[index] / self.waits[index] as u32
+    }
+}
+
+
+/// Run queues for a runtime.
+///
+/// Running processes are stored by ID, with a FIFO run queue for each priority.
+/// Every runnable process gets a time slice each round, so lower priorities are never starved:
+/// higher priorities run first in a round and get longer slices.
+#[derive(Debug)]
+pub struct Scheduler {
+    procs: FxHashMap<SId, Process>,
+    queues: [VecDeque<SId>; 3],
+    /// Queued processes and when they were queued.
+    queued: FxHashMap<SId, Instant>,
+
+    /// Instructions a normal priority process runs before yielding to others (< 1 runs until the process yields itself).
+    /// See Priority::slice.
+    pub time_slice: i32,
+
+    metrics: SchedulerMetrics,
+}
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self {
+            procs: Default::default(),
+            queues: Default::default(),
+            queued: Default::default(),
+            time_slice: 2_000,
+            metrics: Default::default(),
+        }
+    }
+}
+impl Scheduler {
+    #[inline]
+    /// Number of running processes.
+    pub fn len(&self) -> usize {
+        self.procs.len()
+    }
+
+    #[inline]
+    /// No running processes?
+    pub fn is_empty(&self) -> bool {
+        self.procs.is_empty()
+    }
+
+    #[inline]
+    /// Is a process running?
+    pub fn contains(&self, id: &SId) -> bool {
+        self.procs.contains_key(id)
+    }
+
+    #[inline]
+    /// Get a running process.
+    pub fn get(&self, id: &SId) -> Option<&Process> {
+        self.procs.get(id)
+    }
+
+    #[inline]
+    /// Get a running process (mutable).
+    pub fn get_mut(&mut self, id: &SId) -> Option<&mut Process> {
+        self.procs.get_mut(id)
+    }
+
+    /// Iterate over the running processes.
+    pub fn iter(&self) -> impl Iterator<Item = &Process> {
+        self.procs.values()
+    }
+
+    /// Add a running process to the back of its run queue.
+    pub fn push(&mut self, proc: Process) {
+        let id = proc.env.pid.clone();
+        self.procs.insert(id.clone(), proc);
+        self.enqueue(id);
+    }
+
+    /// Remove a running process.
+    pub fn remove(&mut self, id: &SId) -> Option<Process> {
+        self.queued.remove(id); // left in its queue and skipped
+        self.procs.remove(id)
+    }
+
+    /// Put a running process back into its run queue (after its time slice).
+    pub fn enqueue(&mut self, id: SId) {
+        if self.queued.contains_key(&id) { return; }
+        if let Some(proc) = self.procs.get(&id) {
+            let index = proc.env.priority as usize;
+            self.queues[index].push_back(id.clone());
+            self.queued.insert(id, Instant::now());
+            self.metrics.max_queue_depth = self.metrics.max_queue_depth.max(self.queued.len());
+        }
+    }
+
+    /// Processes to run this round, in priority order.
+    /// Processes run this round are no longer queued (see enqueue).
+    pub fn round(&mut self) -> Vec<SId> {
+        let mut round = Vec::with_capacity(self.queued.len());
+        let now = Instant::now();
+        for index in 0..self.queues.len() {
+            while let Some(id) = self.queues[index].pop_front() {
+                if let Some(queued_at) = self.queued.remove(&id) {
+                    let wait = now.saturating_duration_since(queued_at);
+                    self.metrics.waits[index] += 1;
+                    self.metrics.total_wait[index] += wait;
+                    if wait > self.metrics.max_wait[index] {
+                        self.metrics.max_wait[index] = wait;
+                    }
+                    round.push(id);
+                }
+            }
+        }
+        self.metrics.slices += round.len() as u64;
+        round
+    }
+
+    /// Current scheduler metrics.
+    pub fn metrics(&self) -> SchedulerMetrics {
+        let mut metrics = self.metrics.clone();
+        for (index, queue) in self.queues.iter().enumerate() {
+            metrics.queue_depth[index] = queue.iter().filter(|id| self.queued.contains_key(*id)).count();
+        }
+        metrics
+    }
+
+    /// Remove all processes.
+    pub fn clear(&mut self) {
+        self.procs.clear();
+        self.queued.clear();
+        for queue in &mut self.queues {
+            queue.clear();
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::{model::{Graph, Profile}, runtime::{instruction::Instruction, instructions::call::FuncCall, proc::Process, Priority, Runtime}};
+
+    fn call(search: &str, priority: Priority) -> Process {
+        let ins: Arc<dyn Instruction> = Arc::new(FuncCall {
+            as_ref: false,
+            cnull: false,
+            stack: false,
+            func: None,
+            search: Some(search.into()),
+            args: Default::default(),
+            oself: None,
+        });
+        let mut proc = Process::from(ins);
+        proc.env.priority = priority;
+        proc
+    }
+
+    #[test]
+    fn time_slices_interleave() {
+        let mut graph = Graph::default();
+        graph.parse_stof_src(r#"
+            order: []
+            fn busy() { for (const i in 100_000) {} self.order.push_back('busy'); }
+            fn quick() { self.order.push_back('quick'); }
+        "#, None, Profile::test()).unwrap();
+
+        // the quick process doesn't wait for the busy one to finish, even though it was added after it
+        let mut runtime = Runtime::default();
+        runtime.push_running_proc(call("root.busy", Priority::Normal), &mut graph);
+        runtime.push_running_proc(call("root.quick", Priority::Normal), &mut graph);
+        runtime.run_to_complete(&mut graph);
+        assert_eq!(graph.field_value("order", None).unwrap().print(&graph), "[\"quick\", \"busy\"]");
+        assert!(runtime.metrics().slices > 2);
+    }
+
+    #[test]
+    fn priority_order_without_starvation() {
+        let mut graph = Graph::default();
+        graph.parse_stof_src(r#"
+            order: []
+            fn work(name: str) { for (const i in 20_000) {} self.order.push_back(name); }
+            fn low() { self.work('low'); }
+            fn normal() { self.work('normal'); }
+            fn high() { self.work('high'); }
+        "#, None, Profile::test()).unwrap();
+
+        let mut runtime = Runtime::default();
+        runtime.push_running_proc(call("root.low", Priority::Low), &mut graph);
+        runtime.push_running_proc(call("root.normal", Priority::Normal), &mut graph);
+        runtime.push_running_proc(call("root.high", Priority::High), &mut graph);
+        runtime.run_to_complete(&mut graph);
+        assert_eq!(graph.field_value("order", None).unwrap().print(&graph), "[\"high\", \"normal\", \"low\"]");
+
+        let metrics = runtime.metrics();
+        for priority in Priority::ALL {
+            assert!(metrics.waits[priority as usize] > 0); // every priority got to run
+        }
+        assert_eq!(metrics.queue_depth, [0, 0, 0]);
+        assert_eq!(metrics.max_queue_depth, 3);
+    }
+
+    #[test]
+    fn priority_attribute() {
+        let mut graph = Graph::default();
+        graph.parse_stof_src(r#"
+            order: []
+            fn work(name: str) { for (const i in 20_000) {} self.order.push_back(name); }
+            #[priority('low')] async fn low() { for (const i in 20_000) {} self.order.push_back('low'); }
+            #[async('high')] fn high() { for (const i in 20_000) {} self.order.push_back('high'); }
+            fn main() -> list {
+                const a = self.low();
+                const b = self.high();
+                await a;
+                await b;
+                return self.order;
+            }
+        "#, None, Profile::test()).unwrap();
+        assert_eq!(graph.call("root.main", None, vec![]).unwrap().print(&graph), "[\"high\", \"low\"]");
+    }
+}
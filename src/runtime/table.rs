@@ -14,58 +14,115 @@
 // limitations under the License.
 //
 
-use rustc_hash::FxHashMap;
+use arcstr::ArcStr;
 use serde::{Deserialize, Serialize};
 use crate::{model::{DataRef, Graph, NodeRef}, runtime::{Error, Variable}};
 
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 /// Symbol table for a process.
+/// Locals are stored in slots (innermost last), with each scope starting at a slot index.
+/// Compiled programs address locals by slot relative to their frame, falling back to names.
 pub struct SymbolTable {
-    pub scopes: Vec<Scope>,
+    pub scopes: Vec<usize>,
+    pub locals: Vec<Local>,
 }
 impl SymbolTable {
     #[inline(always)]
     /// Push a new scope to this table.
     pub fn push(&mut self) {
-        self.scopes.push(Scope::default());
+        self.scopes.push(self.locals.len());
     }
 
     #[inline(always)]
     /// Clear this table.
     pub fn clear(&mut self) {
         self.scopes.clear();
+        self.locals.clear();
     }
 
-    #[inline(always)]
+    #[inline]
     /// Pop a scope from this table.
     pub fn pop(&mut self) -> bool {
-        self.scopes.pop().is_some()
+        if let Some(start) = self.scopes.pop() {
+            while self.locals.len() > start {
+                if let Some(local) = self.locals.pop() {
+                    self.unshadow(&local);
+                }
+            }
+            true
+        } else {
+            false
+        }
     }
 
-    #[inline(always)]
+    #[inline]
+    /// Index of the closest local with a name (that hasn't been dropped).
+    fn find(&self, name: &str, from: usize) -> Option<usize> {
+        for index in (from..self.locals.len()).rev() {
+            let local = &self.locals[index];
+            if local.var.is_some() && local.name.as_str() == name {
+                return Some(index);
+            }
+        }
+        None
+    }
+
+    #[inline]
+    /// A local is being removed, so the local it shadows is visible again.
+    fn unshadow(&mut self, local: &Local) {
+        if let Some(index) = local.shadows {
+            if let Some(shadowed) = self.locals.get_mut(index) {
+                shadowed.shadowed = false;
+            }
+        }
+    }
+
+    #[inline]
+    /// Drop the local at an index, leaving its slot in place.
+    fn drop_index(&mut self, index: usize) -> Option<Variable> {
+        let local = &mut self.locals[index];
+        let var = local.var.take();
+        local.shadowed = false;
+        let shadows = local.shadows.take();
+        if let Some(index) = shadows {
+            if let Some(shadowed) = self.locals.get_mut(index) {
+                shadowed.shadowed = false;
+            }
+        }
+        var
+    }
+
+    #[inline]
     /// Can declare variable in the current scope?
     /// True if the name doesn't collide with a current var in the scope.
     pub fn can_declare(&self, name: impl AsRef<str>) -> bool {
-        self.scopes.is_empty() || !self.scopes.last().unwrap().has(name)
+        let start = self.scopes.last().copied().unwrap_or_default();
+        self.find(name.as_ref(), start).is_none()
     }
 
-    #[inline]
     /// Insert a variable into this scope.
     /// Will create a new scope if one doesn't exist.
-    pub fn insert(&mut self, name: impl ToString, var: Variable) {
+    pub fn insert(&mut self, name: ArcStr, var: Variable) {
         if self.scopes.is_empty() { self.push(); }
-        self.scopes.last_mut().unwrap().insert(name, var);
+        let start = self.scopes.last().copied().unwrap_or_default();
+        let mut shadows = None;
+        if let Some(index) = self.find(&name, 0) {
+            if index >= start {
+                self.locals[index].var = Some(var); // already in this scope
+                return;
+            }
+            self.locals[index].shadowed = true;
+            shadows = Some(index);
+        }
+        self.locals.push(Local { name, var: Some(var), shadowed: false, shadows });
     }
 
     /// Remove a variable from this symbol table.
     /// Will only drop one if multiple exist (closest).
     pub fn drop_var(&mut self, name: impl AsRef<str>) -> Option<Variable> {
-        let name = name.as_ref();
-        for scope in self.scopes.iter_mut().rev() {
-            if let Some(var) = scope.remove(name) {
-                return Some(var);
-            }
+        if let Some(index) = self.find(name.as_ref(), 0) {
+            return self.drop_index(index);
         }
         None
     }
@@ -73,11 +130,8 @@ impl SymbolTable {
     /// Get a variable from this symbol table.
     /// Will find the closest if it exists.
     pub fn get(&self, name: impl AsRef<str>) -> Option<&Variable> {
-        let name = name.as_ref();
-        for scope in self.scopes.iter().rev() {
-            if let Some(var) = scope.get(name) {
-                return Some(var);
-            }
+        if let Some(index) = self.find(name.as_ref(), 0) {
+            return self.locals[index].var.as_ref();
         }
         None
     }
@@ -85,9 +139,9 @@ impl SymbolTable {
     /// Set an existing variable in this symbol table.
     /// Will return an error if the var exists but is const.
     pub fn set(&mut self, name: impl AsRef<str>, var: &Variable, graph: &mut Graph, context: Option<NodeRef>) -> Result<bool, Error> {
-        let name = name.as_ref();
-        for scope in self.scopes.iter_mut().rev() {
-            if scope.set(name, var, graph, context.clone())? {
+        if let Some(index) = self.find(name.as_ref(), 0) {
+            if let Some(svar) = &mut self.locals[index].var {
+                svar.set(var, graph, context)?;
                 return Ok(true);
             }
         }
@@ -95,95 +149,76 @@ impl SymbolTable {
     }
 
     #[inline]
-    /// Garbage collect variables in this symbol table that reference a node.
-    pub fn gc_node(&mut self, node: &NodeRef) {
-        for scope in &mut self.scopes {
-            scope.gc_node(node);
+    /// Local in a slot, if it is the closest variable with this name (what a lookup by name would find).
+    fn slot_index(&self, slot: usize, name: &ArcStr) -> Option<usize> {
+        if let Some(local) = self.locals.get(slot) {
+            if !local.shadowed && local.var.is_some() && (ArcStr::ptr_eq(&local.name, name) || local.name == *name) {
+                return Some(slot);
+            }
         }
+        None
     }
 
     #[inline]
-    /// Garbage collect variables in this symbol table that reference data.
-    pub fn gc_data(&mut self, data: &DataRef) {
-        for scope in &mut self.scopes {
-            scope.gc_data(data);
+    /// Get a variable by slot.
+    /// None if the slot doesn't hold the closest variable with this name (look it up by name instead).
+    pub fn slot(&self, slot: usize, name: &ArcStr) -> Option<&Variable> {
+        if let Some(index) = self.slot_index(slot, name) {
+            return self.locals[index].var.as_ref();
         }
-    }
-}
-
-
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
-/// Symbol table scope.
-pub struct Scope {
-    variables: FxHashMap<String, Variable>,
-}
-impl Scope {
-    #[inline(always)]
-    /// Has a variable with this name?
-    pub fn has(&self, name: impl AsRef<str>) -> bool {
-        self.variables.contains_key(name.as_ref())
-    }
-    
-    #[inline(always)]
-    /// Insert a variable.
-    pub fn insert(&mut self, name: impl ToString, var: Variable) {
-        self.variables.insert(name.to_string(), var);
-    }
-
-    #[inline(always)]
-    /// Remove a variable.
-    pub fn remove(&mut self, name: impl AsRef<str>) -> Option<Variable> {
-        self.variables.remove(name.as_ref())
-    }
-
-    #[inline(always)]
-    /// Get a variable.
-    pub fn get(&self, name: impl AsRef<str>) -> Option<&Variable> {
-        self.variables.get(name.as_ref())
-    }
-
-    #[inline(always)]
-    /// Get a mutable variable.
-    pub fn get_mut(&mut self, name: impl AsRef<str>) -> Option<&mut Variable> {
-        self.variables.get_mut(name.as_ref())
+        None
     }
 
     #[inline]
-    /// Set a variable.
-    pub fn set(&mut self, name: impl AsRef<str>, var: &Variable, graph: &mut Graph, context: Option<NodeRef>) -> Result<bool, Error> {
-        if let Some(svar) = self.get_mut(name) {
-            svar.set(var, graph, context)?;
-            Ok(true)
-        } else {
-            Ok(false)
+    /// Set a variable by slot.
+    /// Returns false if the slot doesn't hold the closest variable with this name (set it by name instead).
+    pub fn set_slot(&mut self, slot: usize, name: &ArcStr, var: &Variable, graph: &mut Graph, context: Option<NodeRef>) -> Result<bool, Error> {
+        if let Some(index) = self.slot_index(slot, name) {
+            if let Some(svar) = &mut self.locals[index].var {
+                svar.set(var, graph, context)?;
+                return Ok(true);
+            }
         }
+        Ok(false)
     }
 
     /// Garbage collect variables in this symbol table that reference a node.
     pub fn gc_node(&mut self, node: &NodeRef) {
-        let mut to_remove = Vec::new();
-        for (name, var) in &self.variables {
-            if let Some(nref) = var.try_obj() {
-                if &nref == node {
-                    to_remove.push(name.clone());
+        for index in 0..self.locals.len() {
+            if let Some(var) = &self.locals[index].var {
+                if let Some(nref) = var.try_obj() {
+                    if &nref == node {
+                        self.drop_index(index);
+                    }
                 }
             }
         }
-        for name in to_remove {
-            self.variables.remove(&name);
-        }
     }
 
     /// Garbage collect variables in this symbol table that reference data.
     pub fn gc_data(&mut self, data: &DataRef) {
-        let mut to_remove = Vec::new();
-        for (name, var) in &self.variables {
-            if var.is_data_ref(data) {
-                to_remove.push(name.clone());
+        for index in 0..self.locals.len() {
+            if let Some(var) = &self.locals[index].var {
+                if var.is_data_ref(data) {
+                    self.drop_index(index);
+                }
             }
         }
-        for name in to_remove {
-            self.variables.remove(&name);
-        }
     }
 }
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Symbol table local (slot).
+pub struct Local {
+    pub name: ArcStr,
+
+    /// None once dropped (the slot stays, so later slots keep their index).
+    pub var: Option<Variable>,
+
+    /// A closer local has the same name.
+    pub shadowed: bool,
+
+    /// Index of the local this one shadows.
+    pub shadows: Option<usize>,
+}
@@ -34,10 +34,13 @@ const FUNC: ArcStr = literal!("fn");
 pub(super) const DATA: ArcStr = literal!("data");
 pub(super) const OBJ: ArcStr = literal!("obj");
 const VER: ArcStr = literal!("ver");
+const TIME: ArcStr = literal!("time");
 const STR: ArcStr = literal!("str");
 const PROMPT: ArcStr = literal!("prompt");
 const INT: ArcStr = literal!("int");
 const FLOAT: ArcStr = literal!("float");
+const DECIMAL: ArcStr = literal!("decimal");
+const BIGINT: ArcStr = literal!("bigint");
 
 
 #[derive(Debug, Clone, Deserialize, Serialize, Default, Hash)]
@@ -71,6 +74,9 @@ pub enum Type {
 
     Unknown,
     Union(Vector<Self>),
+
+    // Added at the end for rev-compatability
+    Time,
 }
 impl PartialEq for Type {
     fn eq(&self, other: &Self) -> bool {
@@ -166,6 +172,12 @@ impl PartialEq for Type {
                     _ => false,
                 }
             },
+            Self::Time => {
+                match other {
+                    Self::Time => true,
+                    _ => false,
+                }
+            },
             Self::Obj(t) => {
                 match other {
                     Self::Obj(ot) => t.eq(ot),
@@ -327,6 +339,7 @@ impl Type {
             Self::NotNull(t) => t.type_of(),
             Self::Num(num) => num.type_of(),
             Self::Ver => VER,
+            Self::Time => TIME,
             Self::Str => STR,
             Self::Prompt => PROMPT,
             Self::Tup(vals) => {
@@ -380,6 +393,7 @@ impl Type {
             Self::NotNull(t) => t.rt_type_of(graph),
             Self::Num(num) => num.type_of(),
             Self::Ver => VER,
+            Self::Time => TIME,
             Self::Str => STR,
             Self::Prompt => PROMPT,
             Self::Tup(vals) => {
@@ -440,6 +454,7 @@ impl Type {
             Self::Obj(_) => literal!("Obj"),
             Self::Promise(_) => literal!("Promise"),
             Self::Ver => literal!("Ver"),
+            Self::Time => literal!("Time"),
             Self::Tup(_) => literal!("Tup"),
             Self::NotNull(t) => t.gen_lib_name(),
         }
@@ -462,6 +477,8 @@ impl ToString for Type {
 pub enum NumT {
     Int,
     Float,
+    Decimal,
+    BigInt,
     Units(Units),
 }
 impl PartialEq for NumT {
@@ -479,6 +496,18 @@ impl PartialEq for NumT {
                     _ => false,
                 }
             },
+            Self::Decimal => {
+                match other {
+                    Self::Decimal => true,
+                    _ => false,
+                }
+            },
+            Self::BigInt => {
+                match other {
+                    Self::BigInt => true,
+                    _ => false,
+                }
+            },
             Self::Units(units) => {
                 match other {
                     Self::Float => true,
@@ -497,6 +526,8 @@ impl NumT {
         match self {
             Self::Float => FLOAT,
             Self::Int => INT,
+            Self::Decimal => DECIMAL,
+            Self::BigInt => BIGINT,
             Self::Units(units) => units.to_string(),
         }
     }
@@ -22,8 +22,11 @@ use std::{cmp::Ordering, hash::{Hash, Hasher}, ops::{Deref, DerefMut}, sync::Arc
 use arcstr::{literal, ArcStr};
 use bytes::Bytes;
 use imbl::{vector, OrdMap, OrdSet, Vector};
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use crate::{model::{export::json_value_from_node, Data, DataRef, Field, Func, Graph, Node, NodeRef, Prototype, SId}, parser::{number::number, semver::parse_semver_alone}, runtime::{Error, Num, NumT, Prompt, Type, Units, DATA, OBJ}};
+use crate::{model::{add_growth, export::json_value_from_node, time::instant_to_string, Data, DataRef, Field, Func, Graph, Node, NodeRef, Prototype, SId}, parser::{datetime::parse_time_alone, number::number, semver::parse_semver_alone}, runtime::{Error, Num, NumT, Prompt, Type, Units, DATA, OBJ}};
 
 
 /// Value reference (value, by reference?).
@@ -118,6 +121,9 @@ pub enum Val {
     Tup(Vector<ValRef<Self>>),
     Map(OrdMap<ValRef<Self>, ValRef<Self>>),
     Set(OrdSet<ValRef<Self>>),
+
+    // Instant in time (ms since the unix epoch) with an optional IANA time zone (UTC if none) - added at the end for rev-compatability
+    Time(i64, Option<ArcStr>),
 }
 
 impl From<&char> for Val {
@@ -152,12 +158,12 @@ impl From<u32> for Val {
 }
 impl From<u64> for Val {
     fn from(value: u64) -> Self {
-        Self::Num(Num::Int(value as i64))
+        Self::Num(Num::from(value)) // bigint if it does not fit in an int
     }
 }
 impl From<u128> for Val {
     fn from(value: u128) -> Self {
-        Self::Num(Num::Int(value as i64))
+        Self::Num(Num::from(value)) // bigint if it does not fit in an int
     }
 }
 impl From<i8> for Val {
@@ -182,7 +188,7 @@ impl From<i64> for Val {
 }
 impl From<i128> for Val {
     fn from(value: i128) -> Self {
-        Self::Num(Num::Int(value as i64))
+        Self::Num(Num::from(value)) // bigint if it does not fit in an int
     }
 }
 impl From<f32> for Val {
@@ -282,7 +288,7 @@ impl Ord for Val {
             },
             Self::Obj(nref) => {
                 match other {
-                    Self::Obj(oref) => nref.cmp(oref),
+                    Self::Obj(oref) => nref.cmp(oref), // by ID (#[cmp] needs the graph, so only sorting uses it)
                     Self::Void |
                     Self::Bool(_) |
                     Self::Num(_) |
@@ -430,6 +436,32 @@ impl Ord for Val {
                     _ => Ordering::Less,
                 }
             },
+            Self::Time(ms, zone) => {
+                match other {
+                    Self::Time(oms, ozone) => {
+                        let mut cmp = ms.cmp(oms);
+                        if cmp == Ordering::Equal {
+                            cmp = zone.cmp(ozone);
+                        }
+                        cmp
+                    },
+                    Self::Void |
+                    Self::Bool(_) |
+                    Self::Num(_) |
+                    Self::Str(_) |
+                    Self::Prompt(_) |
+                    Self::Obj(_) |
+                    Self::Fn(_) |
+                    Self::List(_) |
+                    Self::Tup(_) |
+                    Self::Blob(_) |
+                    Self::Set(_) |
+                    Self::Map(_) |
+                    Self::Data(_) |
+                    Self::Ver(..) => Ordering::Greater,
+                    _ => Ordering::Less,
+                }
+            },
             Self::Promise(id, _) => {
                 match other {
                     Self::Promise(pid, _) => id.cmp(pid),
@@ -445,6 +477,7 @@ impl Ord for Val {
                     Self::Blob(_) |
                     Self::Set(_) |
                     Self::Ver(..) |
+                    Self::Time(..) |
                     Self::Map(_) => Ordering::Greater,
                     _ => Ordering::Less,
                 }
@@ -560,6 +593,12 @@ impl PartialEq for Val {
                     _ => false,
                 }
             },
+            Self::Time(ms, zone) => {
+                match other {
+                    Self::Time(oms, ozone) => ms == oms && zone == ozone,
+                    _ => false,
+                }
+            },
             Self::Promise(id, _) => {
                 match other {
                     Self::Promise(pid, _) => id == pid,
@@ -605,7 +644,8 @@ impl ToString for Val {
                     res.push_str(&format!("+{build}"));
                 }
                 res
-            }
+            },
+            Self::Time(ms, zone) => instant_to_string(*ms, zone.as_ref()),
         }
     }
 }
@@ -725,6 +765,15 @@ impl Val {
         }
     }
 
+    #[inline(always)]
+    /// Is time value?
+    pub fn time(&self) -> bool {
+        match self {
+            Self::Time(..) => true,
+            _ => false,
+        }
+    }
+
     #[inline(always)]
     /// Is obj value?
     pub fn obj(&self) -> bool {
@@ -876,6 +925,7 @@ impl Val {
             Self::Void |
             Self::Num(_) |
             Self::Ver(..) |
+            Self::Time(..) |
             Self::Blob(_) |
             Self::Promise(..) => true,
             
@@ -1021,7 +1071,13 @@ impl Val {
                     attributes,
                     dirty: Default::default(),
                 };
-                let obj = graph.insert_stof_node(node, parent.clone());
+                let obj = match graph.try_insert_stof_node(node, parent.clone()) {
+                    Ok(nref) => nref,
+                    Err(error) => {
+                        graph.refuse(error); // raised after the instruction
+                        return Self::Null;
+                    },
+                };
 
                 // Deep copy all children
                 for child in children {
@@ -1105,6 +1161,7 @@ impl Val {
             Self::Obj(_) => Type::Obj(SId::from(&OBJ)),
             Self::Fn(_) => Type::Fn,
             Self::Ver(..) => Type::Ver,
+            Self::Time(..) => Type::Time,
             Self::List(_) => Type::List,
             Self::Tup(vals) => {
                 let mut types = vector![];
@@ -1275,6 +1332,8 @@ impl Val {
                         match num {
                             NumT::Int => *self = Self::Num(Num::Int(v)),
                             NumT::Float => *self = Self::Num(Num::Float(v as f64)),
+                            NumT::Decimal => *self = Self::Num(Num::Decimal(v.into())),
+                            NumT::BigInt => *self = Self::Num(Num::from(BigInt::from(v))),
                             NumT::Units(units) => *self = Self::Num(Num::Units(v as f64, *units)),
                         }
                         Ok(())
@@ -1337,9 +1396,18 @@ impl Val {
                         Ok(())
                     },
                     Type::Num(numt) => {
+                        if let (NumT::Int, Num::BigInt(big)) = (numt, &num) {
+                            if big.to_i64().is_none() {
+                                return Err(Error::NumOverflow(format!("{big} as int")));
+                            }
+                        }
                         *self = Self::Num(num.cast(*numt));
                         Ok(())
                     },
+                    Type::Time => {
+                        *self = Self::Time(num.float(Some(Units::Milliseconds)) as i64, None);
+                        Ok(())
+                    },
                     _ => Err(Error::NotImplemented)
                 }
             },
@@ -1377,7 +1445,28 @@ impl Val {
                             Err(Error::CastVal(self.spec_type(graph), target.clone()))
                         }
                     },
-                    Type::Num(_) => {
+                    Type::Time => {
+                        if let Some(time) = parse_time_alone(&val) {
+                            *self = time;
+                            Ok(())
+                        } else {
+                            Err(Error::CastVal(self.spec_type(graph), target.clone()))
+                        }
+                    },
+                    Type::Num(numt) => {
+                        if let NumT::Decimal = numt {
+                            // exact, without a trip through a float
+                            if let Ok(dec) = Decimal::from_str_exact(val.trim()) {
+                                *self = Self::Num(Num::Decimal(dec));
+                                return Ok(());
+                            }
+                        }
+                        if let NumT::BigInt = numt {
+                            if let Ok(big) = val.trim().parse::<BigInt>() {
+                                *self = Self::Num(Num::from(big));
+                                return Ok(());
+                            }
+                        }
                         match number(&val) {
                             Ok((rest, mut res)) => {
                                 if rest.len() > 0 {
@@ -1465,6 +1554,19 @@ impl Val {
                     _ => Err(Error::NotImplemented)
                 }
             },
+            Self::Time(ms, _) => {
+                match target {
+                    Type::Str => {
+                        *self = Self::Str(self.to_string().into());
+                        Ok(())
+                    },
+                    Type::Num(numt) => {
+                        *self = Self::Num(Num::Units(*ms as f64, Units::Milliseconds).cast(*numt));
+                        Ok(())
+                    },
+                    _ => Err(Error::NotImplemented)
+                }
+            },
             Self::Tup(values) => {
                 match target {
                     Type::Tup(types) => {
@@ -1573,65 +1675,7 @@ impl Val {
                     graph.insert_stof_data(&obj, "__proto__", Box::new(Prototype { node: proto_id.clone() }), None);
                 
                     // Perform field initializations and checks for the type
-                    let obj_fields = Field::fields(graph, &obj);
-                    'proto_loop: for (fname, fref) in Field::fields(graph, &proto_id) {
-                        let mut field_value = None;
-                        let mut field_attributes = None;
-                        if let Some(field) = graph.get_stof_data::<Field>(&fref) {
-                            if field.attributes.contains_key("type_ignore") {
-                                continue 'proto_loop;
-                            }
-                            field_value = Some(field.value.clone()); // shallow
-                            field_attributes = Some(field.attributes.clone());
-                        }
-                        if let Some(field_value) = field_value {
-                            if let Some(existing) = obj_fields.get(&fname) {
-                                let mut existing_value = None;
-                                if let Some(field) = graph.get_stof_data::<Field>(existing) {
-                                    existing_value = Some(field.value.clone());
-                                }
-                                if let Some(existing_value) = existing_value {
-                                    let field_type;
-                                    if let Some(ty) = &field_value.vtype {
-                                        field_type = ty.clone();
-                                    } else {
-                                        field_type = field_value.spec_type(graph);
-                                    }
-                                    if existing_value.spec_type(graph) != field_type {
-                                        existing_value.cast(&field_type, graph, context.clone())?;
-                                    } else if let Some(field_obj) = field_value.try_obj() {
-                                        if let Some(existing_obj) = existing_value.try_obj() {
-                                            let target = Type::Obj(field_obj);
-                                            Self::Obj(existing_obj).cast(&target, graph, context.clone())?;
-                                        }
-                                    }
-                                    if let Some(field) = graph.get_mut_stof_data::<Field>(existing) {
-                                        field.value = existing_value;
-                                        if let Some(attrs) = field_attributes {
-                                            for (k, v) in attrs {
-                                                if !field.attributes.contains_key(&k) {
-                                                    field.attributes.insert(k, v);
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
-                            } else {
-                                let copied = field_value.deep_copy(graph, context.clone());
-                                
-                                // if copied is an object, cast it too & move it to the new object
-                                if let Some(copy) = copied.try_obj() {
-                                    graph.move_node(&copy, &obj);
-
-                                    let target = Type::Obj(field_value.try_obj().unwrap());
-                                    Self::Obj(copy).cast(&target, graph, context.clone())?;
-                                }
-
-                                let field = Field::new(copied, field_attributes);
-                                graph.insert_stof_data(&obj, &fname, Box::new(field), None);
-                            }
-                        }
-                    }
+                    Self::init_prototype_fields(&obj, &proto_id, graph, context)?;
                 }
                 Ok(())
             },
@@ -1648,6 +1692,92 @@ impl Val {
         }
     }
 
+    /// Initialize the fields of an object from a prototype, casting existing fields to the prototype field types.
+    fn init_prototype_fields(obj: &NodeRef, proto_id: &NodeRef, graph: &mut Graph, context: Option<NodeRef>) -> Result<(), Error> {
+        let obj_fields = Field::fields(graph, obj);
+        'proto_loop: for (fname, fref) in Field::fields(graph, proto_id) {
+            let mut field_value = None;
+            let mut field_attributes = None;
+            if let Some(field) = graph.get_stof_data::<Field>(&fref) {
+                if field.attributes.contains_key("type_ignore") {
+                    continue 'proto_loop;
+                }
+                field_value = Some(field.value.clone()); // shallow
+                field_attributes = Some(field.attributes.clone());
+            }
+            if let Some(field_value) = field_value {
+                if let Some(existing) = obj_fields.get(&fname) {
+                    let mut existing_value = None;
+                    if let Some(field) = graph.get_stof_data::<Field>(existing) {
+                        existing_value = Some(field.value.clone());
+                    }
+                    if let Some(existing_value) = existing_value {
+                        let field_type;
+                        if let Some(ty) = &field_value.vtype {
+                            field_type = ty.clone();
+                        } else {
+                            field_type = field_value.spec_type(graph);
+                        }
+                        if existing_value.spec_type(graph) != field_type {
+                            existing_value.cast(&field_type, graph, context.clone())?;
+                        } else if let Some(field_obj) = field_value.try_obj() {
+                            if let Some(existing_obj) = existing_value.try_obj() {
+                                let target = Type::Obj(field_obj);
+                                Self::Obj(existing_obj).cast(&target, graph, context.clone())?;
+                            }
+                        }
+                        if let Some(field) = graph.get_mut_stof_data::<Field>(existing) {
+                            field.value = existing_value;
+                            if let Some(attrs) = field_attributes {
+                                for (k, v) in attrs {
+                                    if !field.attributes.contains_key(&k) {
+                                        field.attributes.insert(k, v);
+                                    }
+                                }
+                            }
+                        }
+                    }
+                } else {
+                    let copied = field_value.deep_copy(graph, context.clone());
+                    
+                    // if copied is an object, cast it too & move it to the new object
+                    if let Some(copy) = copied.try_obj() {
+                        graph.move_node(&copy, obj);
+
+                        let target = Type::Obj(field_value.try_obj().unwrap());
+                        Self::Obj(copy).cast(&target, graph, context.clone())?;
+                    }
+
+                    let field = Field::new(copied, field_attributes);
+                    graph.insert_stof_data(obj, &fname, Box::new(field), None);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Add a prototype to this object without removing the prototypes it already has (mixin).
+    /// Used for multiple #[extends] types and #[implements] interfaces.
+    pub fn mixin(&self, target: &Type, graph: &mut Graph, context: Option<NodeRef>) -> Result<(), Error> {
+        if let Some(obj) = self.try_obj() {
+            let mut target = target.clone();
+            target.obj_to_proto(graph, context.clone());
+            if let Type::Obj(proto_id) = &target {
+                if !proto_id.node_exists(graph) {
+                    return Err(Error::ObjectCastProtoDne);
+                }
+                if !self.instance_of(proto_id, graph)? {
+                    let name = format!("__proto__{}", proto_id.as_ref());
+                    graph.insert_stof_data(&obj, &name, Box::new(Prototype { node: proto_id.clone() }), None);
+                    Self::init_prototype_fields(&obj, proto_id, graph, context)?;
+                }
+                return Ok(());
+            }
+            return Err(Error::CastVal(self.spec_type(graph), target));
+        }
+        Err(Error::CastVal(self.spec_type(graph), target.clone()))
+    }
+
     /// Library name for this value.
     pub fn lib_name(&self, graph: &Graph) -> ArcStr {
         if let Some(dref) = self.try_data() {
@@ -1692,6 +1822,7 @@ impl Val {
             Self::Str(_) |
             Self::Prompt(_) |
             Self::Ver(..) |
+            Self::Time(..) |
             Self::Promise(..) |
             Self::Bool(_) => {
                 if self != other {
@@ -1800,6 +1931,14 @@ impl Val {
                     _ => {}
                 }
             },
+            Self::Time(ms, _) => {
+                match other {
+                    Self::Time(oms, _) => {
+                        return Ok((ms == oms).into());
+                    },
+                    _ => {}
+                }
+            },
             Self::Fn(dref) =>{
                 match other {
                     Self::Data(odref) => {
@@ -1863,6 +2002,9 @@ impl Val {
                     Self::Bool(ov) => {
                         Ok(Self::Bool(val.int() > *ov as i64))
                     },
+                    Self::Time(ms, _) => {
+                        Ok(Self::Bool(val.float(Some(Units::Milliseconds)) > *ms as f64))
+                    },
                     _ => Ok(Self::Bool(false))
                 }
             },
@@ -1935,6 +2077,13 @@ impl Val {
                     _ => Ok(Self::Bool(false)),
                 }
             },
+            Self::Time(ms, _) => {
+                match other {
+                    Self::Time(oms, _) => Ok(Self::Bool(ms > oms)),
+                    Self::Num(num) => Ok(Self::Bool((*ms as f64) > num.float(Some(Units::Milliseconds)))),
+                    _ => Ok(Self::Bool(false)),
+                }
+            },
         }
     }
 
@@ -1974,6 +2123,9 @@ impl Val {
                     Self::Bool(ov) => {
                         Ok(Self::Bool(val.int() < *ov as i64))
                     },
+                    Self::Time(ms, _) => {
+                        Ok(Self::Bool(val.float(Some(Units::Milliseconds)) < *ms as f64))
+                    },
                     _ => Ok(Self::Bool(false))
                 }
             },
@@ -2046,6 +2198,13 @@ impl Val {
                     _ => Ok(Self::Bool(false)),
                 }
             },
+            Self::Time(ms, _) => {
+                match other {
+                    Self::Time(oms, _) => Ok(Self::Bool(ms < oms)),
+                    Self::Num(num) => Ok(Self::Bool((*ms as f64) < num.float(Some(Units::Milliseconds)))),
+                    _ => Ok(Self::Bool(false)),
+                }
+            },
         }
     }
 
@@ -2071,6 +2230,10 @@ impl Val {
     /// Add a value to this value.
     pub fn add(&mut self, other: Self, graph: &mut Graph) -> Result<(), Error> {
         if other.empty() { return Ok(()); }
+        if graph.quotas_enabled() {
+            let (bytes, len) = add_growth(self, &other);
+            graph.quota_grow(bytes, len)?;
+        }
         match &mut *self {
             Self::Null |
             Self::Void => {
@@ -2100,7 +2263,7 @@ impl Val {
                     Self::Num(num) => {
                         let res;
                         if *val {
-                            res = num.add(&Num::Int(1));
+                            res = num.add(&Num::Int(1))?;
                         } else {
                             res = num;
                         }
@@ -2142,7 +2305,7 @@ impl Val {
             Self::Num(val) => {
                 match other {
                     Self::Num(other) => {
-                        *val = val.add(&other);
+                        *val = val.add(&other)?;
                         Ok(())
                     },
                     Self::Str(other) => {
@@ -2164,10 +2327,29 @@ impl Val {
                     },
                     Self::Bool(other) => {
                         if other {
-                            *val = val.add(&Num::Int(1));
+                            *val = val.add(&Num::Int(1))?;
                         }
                         Ok(())
                     },
+                    Self::Time(ms, zone) => {
+                        // duration + instant = instant
+                        *self = Self::Time(ms + val.float(Some(Units::Milliseconds)) as i64, zone);
+                        Ok(())
+                    },
+                    _ => Err(Error::NotImplemented)
+                }
+            },
+            Self::Time(ms, _) => {
+                match other {
+                    Self::Num(num) => {
+                        // instant + duration = instant (unit-less numbers are ms)
+                        *ms += num.float(Some(Units::Milliseconds)) as i64;
+                        Ok(())
+                    },
+                    Self::Str(other) => {
+                        *self = Self::Str(format!("{}{other}", self.to_string()).into());
+                        Ok(())
+                    },
                     _ => Err(Error::NotImplemented)
                 }
             },
@@ -2242,9 +2424,9 @@ impl Val {
                     Self::Num(num) => {
                         let res;
                         if *val {
-                            res = Num::Int(1).sub(&num);
+                            res = Num::Int(1).sub(&num)?;
                         } else {
-                            res = num.mul(&Num::Int(-1));
+                            res = num.mul(&Num::Int(-1))?;
                         }
                         *self = Self::Num(res);
                         Ok(())
@@ -2267,7 +2449,7 @@ impl Val {
             Self::Num(val) => {
                 match other {
                     Self::Num(other) => {
-                        *self = Self::Num(val.sub(&other));
+                        *self = Self::Num(val.sub(&other)?);
                         Ok(())
                     },
                     Self::Str(other) => {
@@ -2285,13 +2467,28 @@ impl Val {
                     },
                     Self::Bool(other) => {
                         if other {
-                            *val = val.sub(&Num::Int(1));
+                            *val = val.sub(&Num::Int(1))?;
                         }
                         Ok(())
                     },
                     _ => Err(Error::NotImplemented)
                 }
             },
+            Self::Time(ms, _) => {
+                match other {
+                    Self::Time(oms, _) => {
+                        // instant - instant = duration
+                        *self = Self::Num(Num::Units((*ms - oms) as f64, Units::Milliseconds));
+                        Ok(())
+                    },
+                    Self::Num(num) => {
+                        // instant - duration = instant (unit-less numbers are ms)
+                        *ms -= num.float(Some(Units::Milliseconds)) as i64;
+                        Ok(())
+                    },
+                    _ => Err(Error::NotImplemented)
+                }
+            },
             Self::Map(map) => {
                 match other {
                     Self::Map(other) => {
@@ -2364,7 +2561,7 @@ impl Val {
             Self::Num(val) => {
                 match other {
                     Self::Num(other) => {
-                        *val = val.mul(&other);
+                        *val = val.mul(&other)?;
                         Ok(())
                     },
                     Self::Str(other) => {
@@ -2382,9 +2579,9 @@ impl Val {
                     },
                     Self::Bool(other) => {
                         if other {
-                            *val = val.mul(&Num::Int(1));
+                            *val = val.mul(&Num::Int(1))?;
                         } else {
-                            *val = val.mul(&Num::Int(0)); // keep units, etc.
+                            *val = val.mul(&Num::Int(0))?; // keep units, etc.
                         }
                         Ok(())
                     },
@@ -2477,7 +2674,7 @@ impl Val {
             Self::Num(val) => {
                 match other {
                     Self::Num(other) => {
-                        *val = val.div(&other);
+                        *val = val.div(&other)?;
                         Ok(())
                     },
                     Self::Str(other) => {
@@ -2582,7 +2779,7 @@ impl Val {
             Self::Num(val) => {
                 match other {
                     Self::Num(other) => {
-                        *val = val.rem(&other);
+                        *val = val.rem(&other)?;
                         Ok(())
                     },
                     Self::Str(other) => {
@@ -2655,7 +2852,7 @@ impl Val {
             Self::Num(num) => {
                 match other {
                     Self::Num(onum) => {
-                        *num = num.bit_and(&onum);
+                        *num = num.bit_and(&onum)?;
                         return Ok(());
                     },
                     _ => {}
@@ -2672,7 +2869,7 @@ impl Val {
             Self::Num(num) => {
                 match other {
                     Self::Num(onum) => {
-                        *num = num.bit_or(&onum);
+                        *num = num.bit_or(&onum)?;
                         return Ok(());
                     },
                     _ => {}
@@ -2689,7 +2886,7 @@ impl Val {
             Self::Num(num) => {
                 match other {
                     Self::Num(onum) => {
-                        *num = num.bit_xor(&onum);
+                        *num = num.bit_xor(&onum)?;
                         return Ok(());
                     },
                     _ => {}
@@ -2706,7 +2903,7 @@ impl Val {
             Self::Num(num) => {
                 match other {
                     Self::Num(onum) => {
-                        *num = num.bit_shl(&onum);
+                        *num = num.bit_shl(&onum)?;
                         return Ok(());
                     },
                     _ => {}
@@ -2723,7 +2920,7 @@ impl Val {
             Self::Num(num) => {
                 match other {
                     Self::Num(onum) => {
-                        *num = num.bit_shr(&onum);
+                        *num = num.bit_shr(&onum)?;
                         return Ok(());
                     },
                     _ => {}
@@ -2749,6 +2946,7 @@ impl Val {
             Self::Prompt(_) |
             Self::Bool(_) |
             Self::Ver(..) |
+            Self::Time(..) |
             Self::Fn(_) |
             Self::Blob(_) => self.to_string(),
             Self::Promise(..) |
@@ -2826,7 +3024,7 @@ impl Val {
                 format!("({})", res)
             },
             Self::Obj(nref) => {
-                let value = json_value_from_node(graph, nref);
+                let value = json_value_from_node(graph, nref, false);
                 if let Ok(str) = serde_json::to_string(&value) {
                     str
                 } else {
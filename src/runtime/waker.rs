@@ -14,10 +14,35 @@
 // limitations under the License.
 //
 
-use std::{ops::DerefMut, sync::Arc, time::Duration};
+use std::{ops::DerefMut, sync::{Arc, Weak}, time::Duration};
+use lazy_static::lazy_static;
 use parking_lot::RwLock;
 use crate::model::SId;
 
+#[cfg(feature = "tokio")]
+lazy_static! {
+    /// Notified every time a process is woken.
+    /// Async runtime drivers park on this instead of polling sleeping processes (see Runtime::run_async).
+    pub(crate) static ref WAKE_NOTIFY: tokio::sync::Notify = tokio::sync::Notify::new();
+}
+
+#[cfg(not(feature = "js"))]
+/// Wake listener, called every time a process is woken.
+pub(crate) type WakeListener = Arc<dyn Fn() + Send + Sync>;
+
+lazy_static! {
+    /// Listeners called every time a process is woken (Ex. executor workers with parked documents).
+    static ref WAKE_LISTENERS: RwLock<Vec<Weak<dyn Fn() + Send + Sync>>> = Default::default();
+}
+
+#[cfg(not(feature = "js"))]
+/// Call a listener every time a process is woken, for as long as the listener is alive.
+pub(crate) fn on_wake(listener: &WakeListener) {
+    let mut listeners = WAKE_LISTENERS.write();
+    listeners.retain(|listener| listener.strong_count() > 0);
+    listeners.push(Arc::downgrade(listener));
+}
+
 
 /// Wake reference.
 /// Will wake when value is true.
@@ -34,10 +59,21 @@ pub type WakeRef = Arc<RwLock<bool>>;
 /// have the runtime in a separate thread so that it can make progress
 /// independantly from this runtime loop.
 pub fn wake(wref: &WakeRef) -> bool {
-    let mut val = wref.write();
-    let val = val.deref_mut();
-    let res = !*val;
-    *val = true;
+    let res;
+    {
+        let mut val = wref.write();
+        let val = val.deref_mut();
+        res = !*val;
+        *val = true;
+    }
+
+    #[cfg(feature = "tokio")]
+    WAKE_NOTIFY.notify_waiters();
+    for listener in WAKE_LISTENERS.read().iter() {
+        if let Some(listener) = listener.upgrade() {
+            listener();
+        }
+    }
     res
 }
 
@@ -73,9 +109,7 @@ impl Waker {
     pub fn waker(&self) -> Box<dyn Fn()> {
         let clone = self.with.clone();
         let func = move || {
-            let mut val = clone.write();
-            let val = val.deref_mut();
-            *val = true;
+            wake(&clone);
         };
         Box::new(func)
     }